@@ -0,0 +1,74 @@
+/* C API крейта data_system_project (функция ffi), см. src/ffi.rs.
+ * Перегенерация: cbindgen --config cbindgen.toml --output include/columnar.h */
+
+#ifndef COLUMNAR_H
+#define COLUMNAR_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define COLUMNAR_OK 0
+
+#define COLUMNAR_ERR_NULL_POINTER 1
+
+#define COLUMNAR_ERR_IO 2
+
+#define COLUMNAR_ERR_CORRUPT 3
+
+#define COLUMNAR_ERR_DECOMPRESSION 4
+
+#define COLUMNAR_ERR_INVALID_INPUT 5
+
+#define COLUMNAR_ERR_LOCKED 6
+
+#define COLUMNAR_ERR_OUT_OF_RANGE 7
+
+/* Строка NULL или удалена; значение не записано */
+#define COLUMNAR_ERR_NULL_VALUE 8
+
+/* Буфер меньше данных; нужный размер записан в out_len */
+#define COLUMNAR_ERR_BUFFER_TOO_SMALL 9
+
+#define COLUMNAR_ERR_PANIC 10
+
+#define COLUMNAR_ERR_OTHER 11
+
+/* Колонка значений i32, открытая из C */
+typedef struct ColumnarColumn ColumnarColumn;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/* Открывает колонку i32 и записывает её в *out; освобождать — columnar_column_free */
+int32_t columnar_column_open(const char *path, ColumnarColumn **out);
+
+/* Число строк вместе с NULL и удалёнными */
+int32_t columnar_column_len(const ColumnarColumn *column, uint64_t *out);
+
+int32_t columnar_column_get_i32(const ColumnarColumn *column, uint64_t idx, int32_t *out);
+
+/* Копирует распакованные значения (little-endian) в buffer и пишет их размер в
+ * *out_len. Если capacity не хватает, ничего не копируется, а в *out_len
+ * оказывается нужный размер: так его можно узнать вызовом с buffer = NULL и
+ * capacity = 0 */
+int32_t columnar_column_decompress(const ColumnarColumn *column,
+                                   uint8_t *buffer,
+                                   size_t capacity,
+                                   size_t *out_len);
+
+/* NULL допустим и ничего не делает */
+void columnar_column_free(ColumnarColumn *column);
+
+/* Сообщение последней ошибки в этом потоке или NULL, если последний вызов был
+ * успешным. Строка принадлежит библиотеке и живёт до следующего вызова в потоке */
+const char *columnar_last_error_message(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* COLUMNAR_H */
@@ -0,0 +1,122 @@
+use std::sync::atomic::Ordering;
+use crate::backing::Backing;
+use crate::error::Result;
+use crate::storage::Column;
+use crate::types::ColumnType;
+
+// Характер доступа к отображённому файлу колонки; передаётся ядру через madvise.
+// На платформах без madvise подсказки ничего не делают
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessPattern {
+    // Поведение ядра по умолчанию
+    Normal,
+    // Сканы: агрессивное упреждающее чтение, прочитанные страницы вытесняются первыми
+    Sequential,
+    // Точечные чтения: упреждающее чтение только занимает память
+    Random,
+    // Загрузить файл в page cache заранее
+    WillNeed,
+    // Страницы больше не нужны; следующее обращение перечитает их с диска
+    DontNeed,
+}
+
+impl AccessPattern {
+    // WillNeed и DontNeed — разовые действия, а не режим доступа
+    fn is_persistent(self) -> bool {
+        matches!(self, AccessPattern::Normal | AccessPattern::Sequential | AccessPattern::Random)
+    }
+
+    pub(crate) fn tag(self) -> u8 {
+        self as u8
+    }
+
+    fn from_tag(tag: u8) -> Self {
+        match tag {
+            1 => AccessPattern::Sequential,
+            2 => AccessPattern::Random,
+            _ => AccessPattern::Normal,
+        }
+    }
+}
+
+impl<T: ColumnType> Column<T> {
+    // Normal, Sequential и Random запоминаются: проходы вроде decompress_parallel
+    // временно включают Sequential и затем возвращают запомненный режим
+    pub fn advise(&self, pattern: AccessPattern) -> Result<()> {
+        apply(&self.backing, pattern)?;
+        if pattern.is_persistent() {
+            self.access_pattern.store(pattern.tag(), Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    pub fn access_pattern(&self) -> AccessPattern {
+        AccessPattern::from_tag(self.access_pattern.load(Ordering::Relaxed))
+    }
+
+    // Подсказка только ускоряет проход, поэтому её ошибка не прерывает чтение
+    pub(crate) fn with_access_pattern<R>(&self, pattern: AccessPattern, pass: impl FnOnce() -> R) -> R {
+        let _ = apply(&self.backing, pattern);
+        let result = pass();
+        let _ = apply(&self.backing, self.access_pattern());
+        result
+    }
+}
+
+// Для колонки в памяти подсказывать некому
+#[cfg(unix)]
+fn apply(backing: &Backing, pattern: AccessPattern) -> Result<()> {
+    use memmap2::Advice;
+    let Backing::Mapped(mmap) = backing else {
+        return Ok(());
+    };
+    let advice = match pattern {
+        AccessPattern::Normal => Advice::Normal,
+        AccessPattern::Sequential => Advice::Sequential,
+        AccessPattern::Random => Advice::Random,
+        AccessPattern::WillNeed => Advice::WillNeed,
+        AccessPattern::DontNeed => Advice::DontNeed,
+    };
+    mmap.advise(advice)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn apply(_: &Backing, _: AccessPattern) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Codec, ColumnBuilder};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_every_access_pattern_on_real_column() {
+        let values: Vec<i32> = (0..300_000).map(|i| i % 777).collect();
+        for codec in [Codec::None, Codec::Lz4] {
+            let mut builder = ColumnBuilder::from_i32("advice".to_string(), &values);
+            builder.compress_with(codec).unwrap();
+            let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+            assert_eq!(column.access_pattern(), AccessPattern::Normal);
+
+            for pattern in [
+                AccessPattern::Sequential,
+                AccessPattern::Random,
+                AccessPattern::WillNeed,
+                AccessPattern::DontNeed,
+                AccessPattern::Normal,
+            ] {
+                column.advise(pattern).unwrap();
+                assert_eq!(column.get_value(123_456), Some(123_456 % 777), "{:?}", pattern);
+            }
+            column.advise(AccessPattern::Random).unwrap();
+            column.advise(AccessPattern::DontNeed).unwrap();
+            // Разовое действие не меняет режим, а проход восстанавливает его после себя
+            assert_eq!(column.access_pattern(), AccessPattern::Random);
+            assert_eq!(column.decompress_parallel().unwrap().len(), values.len() * 4);
+            assert_eq!(column.access_pattern(), AccessPattern::Random);
+        }
+    }
+}
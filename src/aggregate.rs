@@ -0,0 +1,262 @@
+use crate::bools::bit_is_set;
+use crate::error::{invalid_input, Result};
+use crate::compute::Selection;
+use crate::par::*;
+use crate::storage::Column;
+use crate::types::ColumnType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Agg {
+    Sum,
+    Min,
+    Max,
+    Avg,
+    Count,
+}
+
+// Результат агрегации. Min/Max/Avg пусты, если не нашлось ни одного значения
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggValue<T: ColumnType = i32> {
+    Sum(T::Sum),
+    Min(Option<T>),
+    Max(Option<T>),
+    Avg(Option<f64>),
+    Count(u64),
+}
+
+// Агрегации считаются по чанкам параллельно и пропускают NULL и удалённые строки. selection, если задан,
+// ограничивает агрегацию отобранными строками и должен иметь длину колонки.
+// NaN пропускается в min/max, но входит в сумму и среднее по правилам f64
+impl<T: ColumnType> Column<T> {
+    pub fn aggregate(&self, agg: Agg, selection: Option<&Selection>) -> Result<AggValue<T>> {
+        Ok(match agg {
+            Agg::Sum => AggValue::Sum(self.sum(selection)?),
+            Agg::Min => AggValue::Min(self.min_value(selection)?),
+            Agg::Max => AggValue::Max(self.max_value(selection)?),
+            Agg::Avg => AggValue::Avg(self.avg(selection)?),
+            Agg::Count => AggValue::Count(self.count(selection)?),
+        })
+    }
+
+    // Сумма в расширенном типе: i32 суммируется в i64, i64 — в i128
+    pub fn sum(&self, selection: Option<&Selection>) -> Result<T::Sum> {
+        self.check_selection(selection)?;
+        let live = self.live_selection(selection);
+        let selection = live.as_deref();
+        let partial: Vec<T::Sum> = (0..self.chunks().len())
+            .into_par_iter()
+            .map(|idx| self.fold_chunk(idx, selection, T::Sum::default(), |sum, value| sum + value.widen()))
+            .collect::<Result<_>>()?;
+        // Складываем в порядке чанков, чтобы сумма f64 не зависела от планировщика
+        Ok(partial.into_iter().fold(T::Sum::default(), |sum, part| sum + part))
+    }
+
+    // Число значений, отличных от NULL
+    pub fn count(&self, selection: Option<&Selection>) -> Result<u64> {
+        self.check_selection(selection)?;
+        let live = self.live_selection(selection);
+        let selection = live.as_deref();
+        let partial: Vec<u64> = (0..self.chunks().len())
+            .into_par_iter()
+            .map(|idx| {
+                let chunk = &self.chunks()[idx];
+                let selected = self.selected_rows(idx, selection);
+                // Без NULL число значений известно без распаковки
+                if chunk.null_count == 0 || selected == 0 {
+                    return Ok(selected);
+                }
+                if selected == chunk.row_count() {
+                    return Ok(chunk.row_count() - chunk.null_count);
+                }
+                self.fold_chunk(idx, selection, 0, |count, _| count + 1)
+            })
+            .collect::<Result<_>>()?;
+        Ok(partial.into_iter().sum())
+    }
+
+    pub fn avg(&self, selection: Option<&Selection>) -> Result<Option<f64>> {
+        let count = self.count(selection)?;
+        if count == 0 {
+            return Ok(None);
+        }
+        Ok(Some(T::sum_to_f64(self.sum(selection)?) / count as f64))
+    }
+
+    pub fn min_value(&self, selection: Option<&Selection>) -> Result<Option<T>> {
+        self.extreme(selection, false)
+    }
+
+    pub fn max_value(&self, selection: Option<&Selection>) -> Result<Option<T>> {
+        self.extreme(selection, true)
+    }
+
+    // Чанки перебираются в порядке zone map: как только граница очередного чанка
+    // не лучше найденного значения, остальные чанки не распаковываются. Полностью
+    // отобранный чанк берёт значение прямо из zone map
+    fn extreme(&self, selection: Option<&Selection>, max: bool) -> Result<Option<T>> {
+        self.check_selection(selection)?;
+        let live = self.live_selection(selection);
+        let selection = live.as_deref();
+        let chunks = self.chunks();
+        let bound = |idx: usize| if max { chunks[idx].max } else { chunks[idx].min };
+        let better = |a: &T, b: &T| if max { a.total_cmp(b).is_gt() } else { a.total_cmp(b).is_lt() };
+
+        // У чанка без значений (только NULL или NaN) min остаётся больше max
+        let mut order: Vec<usize> = (0..chunks.len())
+            .filter(|idx| chunks[*idx].min.total_cmp(&chunks[*idx].max).is_le())
+            .filter(|idx| self.selected_rows(*idx, selection) > 0)
+            .collect();
+        order.sort_by(|a, b| {
+            let ordering = bound(*a).total_cmp(&bound(*b));
+            if max { ordering.reverse() } else { ordering }
+        });
+
+        let mut best: Option<T> = None;
+        for idx in order {
+            if best.is_some_and(|best| !better(&bound(idx), &best)) {
+                break;
+            }
+            let candidate = if self.selected_rows(idx, selection) == chunks[idx].row_count() {
+                Some(bound(idx))
+            } else {
+                self.fold_chunk(idx, selection, None, |best: Option<T>, value| {
+                    if value.is_nan() || best.is_some_and(|best| !better(&value, &best)) {
+                        best
+                    } else {
+                        Some(value)
+                    }
+                })?
+            };
+            if let Some(candidate) = candidate {
+                if best.is_none_or(|best| better(&candidate, &best)) {
+                    best = Some(candidate);
+                }
+            }
+        }
+        Ok(best)
+    }
+
+    fn check_selection(&self, selection: Option<&Selection>) -> Result<()> {
+        match selection {
+            Some(bitmap) if bitmap.len() != self.len() => Err(invalid_input(format!(
+                "битовая карта из {} строк для колонки из {} строк",
+                bitmap.len(),
+                self.len()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    // Отобранных строк в чанке, включая NULL
+    fn selected_rows(&self, idx: usize, selection: Option<&Selection>) -> u64 {
+        let chunk = &self.chunks()[idx];
+        match selection {
+            None => chunk.row_count(),
+            Some(bitmap) => bitmap.count_range(chunk.first_row as usize..chunk.end_row() as usize) as u64,
+        }
+    }
+
+    // Свёртка отобранных значений чанка, отличных от NULL
+    fn fold_chunk<A>(&self, idx: usize, selection: Option<&Selection>, init: A, f: impl Fn(A, T) -> A) -> Result<A> {
+        if self.selected_rows(idx, selection) == 0 {
+            return Ok(init);
+        }
+        let first_row = self.chunks()[idx].first_row as usize;
+        let values = self.chunk_values(idx)?;
+        let validity = self.chunk_validity(idx)?;
+        Ok(values
+            .chunks_exact(T::WIDTH)
+            .enumerate()
+            .filter(|(row, _)| selection.is_none_or(|bitmap| bitmap.get(first_row + row)))
+            .filter(|(row, _)| validity.as_ref().is_none_or(|bits| bit_is_set(bits, *row)))
+            .fold(init, |acc, (_, value)| f(acc, T::read_le(value))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Codec, ColumnBuilder, ColumnarError, Predicate};
+    use tempfile::NamedTempFile;
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_aggregates_match_naive_computation() {
+        let values: Vec<i32> = (0..30_000).map(|i| ((i * 7919) % 20_011) - 10_000).collect();
+        let mut builder = ColumnBuilder::from_i32("agg".to_string(), &values);
+        builder.compress_with(Codec::zstd()).unwrap();
+        builder.set_chunk_rows(4096);
+        let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+
+        let sum: i64 = values.iter().map(|v| *v as i64).sum();
+        assert_eq!(column.aggregate(Agg::Sum, None).unwrap(), AggValue::Sum(sum));
+        assert_eq!(column.aggregate(Agg::Count, None).unwrap(), AggValue::Count(30_000));
+        assert_eq!(column.aggregate(Agg::Avg, None).unwrap(), AggValue::Avg(Some(sum as f64 / 30_000.0)));
+        let frames = column.frames_decoded();
+        // Без отбора min/max берутся из zone maps
+        assert_eq!(column.aggregate(Agg::Min, None).unwrap(), AggValue::Min(values.iter().min().copied()));
+        assert_eq!(column.aggregate(Agg::Max, None).unwrap(), AggValue::Max(values.iter().max().copied()));
+        assert_eq!(column.frames_decoded(), frames);
+
+        let selection = column.filter(Predicate::Between(-500, 1500)).unwrap();
+        let selected: Vec<i32> = values.iter().copied().filter(|v| (-500..=1500).contains(v)).collect();
+        assert_eq!(column.sum(Some(&selection)).unwrap(), selected.iter().map(|v| *v as i64).sum::<i64>());
+        assert_eq!(column.count(Some(&selection)).unwrap(), selected.len() as u64);
+        assert_eq!(column.min_value(Some(&selection)).unwrap(), selected.iter().min().copied());
+        assert_eq!(column.max_value(Some(&selection)).unwrap(), selected.iter().max().copied());
+
+        // Пустой отбор и отбор неверной длины
+        let nothing = column.filter(Predicate::Gt(i32::MAX)).unwrap();
+        assert_eq!(column.aggregate(Agg::Avg, Some(&nothing)).unwrap(), AggValue::Avg(None));
+        assert_eq!(column.aggregate(Agg::Min, Some(&nothing)).unwrap(), AggValue::Min(None));
+        assert_eq!(column.sum(Some(&nothing)).unwrap(), 0);
+        let err = column.sum(Some(&Selection::new(5))).unwrap_err();
+        assert!(matches!(err, ColumnarError::InvalidInput(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn test_sum_widens_past_i32_overflow() {
+        let values = vec![i32::MAX; 10];
+        let column = ColumnBuilder::from_i32("big".to_string(), &values)
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        // Сумма в i32 обернулась бы
+        assert_eq!(values.iter().fold(0i32, |acc, v| acc.wrapping_add(*v)), -10);
+        assert_eq!(column.sum(None).unwrap(), i32::MAX as i64 * 10);
+
+        let wide = ColumnBuilder::from_i64("wide".to_string(), &[i64::MAX, i64::MAX, 1])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert_eq!(wide.sum(None).unwrap(), i64::MAX as i128 * 2 + 1);
+    }
+
+    #[test]
+    fn test_aggregates_skip_nulls_and_nan() {
+        let values = [Some(4), None, Some(-2), None, Some(10)];
+        let column = ColumnBuilder::from_nullable("n".to_string(), &values)
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert_eq!(column.count(None).unwrap(), 3);
+        assert_eq!(column.sum(None).unwrap(), 12i64);
+        assert_eq!(column.avg(None).unwrap(), Some(4.0));
+        let mut selection = Selection::new(5);
+        selection.set(1);
+        selection.set(4);
+        assert_eq!(column.count(Some(&selection)).unwrap(), 1);
+        assert_eq!(column.min_value(Some(&selection)).unwrap(), Some(10));
+
+        let all_null = ColumnBuilder::<i32>::from_nullable("z".to_string(), &[None, None])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert_eq!(all_null.max_value(None).unwrap(), None);
+        assert_eq!(all_null.count(None).unwrap(), 0);
+
+        let floats = ColumnBuilder::from_f64("f".to_string(), &[1.5, f64::NAN, -3.0])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert_eq!(floats.min_value(None).unwrap(), Some(-3.0));
+        assert_eq!(floats.max_value(None).unwrap(), Some(1.5));
+        assert!(floats.sum(None).unwrap().is_nan());
+        assert_eq!(floats.count(None).unwrap(), 3);
+    }
+}
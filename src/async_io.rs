@@ -0,0 +1,156 @@
+use std::{
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+    thread::Result as ThreadResult,
+};
+use crate::error::Result;
+use crate::storage::{Column, ColumnBuilder};
+use crate::types::ColumnType;
+use crate::writer::BuildOptions;
+
+// Асинхронные build и open для сервисов на async-рантайме. Запись файла, fsync,
+// отображение в память и сжатие выполняются синхронным кодом в отдельном потоке,
+// а future только ждёт его, поэтому поток рантайма не блокируется. Future не
+// привязаны к конкретному рантайму: их можно ждать в tokio, async-std или любом
+// другом исполнителе
+impl<T: ColumnType> ColumnBuilder<T> {
+    pub async fn build_async(self, path: &Path) -> Result<Column<T>> {
+        self.build_async_with(path, BuildOptions::default()).await
+    }
+
+    pub async fn build_async_with(self, path: &Path, options: BuildOptions) -> Result<Column<T>> {
+        let path = path.to_path_buf();
+        blocking(move || self.build_with(&path, options)).await
+    }
+}
+
+impl<T: ColumnType> Column<T> {
+    pub async fn open_async(path: &Path) -> Result<Column<T>> {
+        let path = path.to_path_buf();
+        blocking(move || Column::open(&path)).await
+    }
+}
+
+// Результат потока и задача, которую он разбудит
+struct Shared<R> {
+    result: Option<ThreadResult<Result<R>>>,
+    waker: Option<Waker>,
+}
+
+struct Blocking<R> {
+    shared: Arc<Mutex<Shared<R>>>,
+}
+
+// Выполняет work в отдельном потоке. Паника в work продолжается у ожидающего future
+fn blocking<R, F>(work: F) -> Blocking<R>
+where
+    R: Send + 'static,
+    F: FnOnce() -> Result<R> + Send + 'static,
+{
+    let shared = Arc::new(Mutex::new(Shared { result: None, waker: None }));
+    let worker = shared.clone();
+    let spawned = thread::Builder::new().name("columnar-blocking".to_string()).spawn(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(work));
+        let waker = {
+            let mut shared = worker.lock().unwrap();
+            shared.result = Some(result);
+            shared.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    });
+    if let Err(e) = spawned {
+        shared.lock().unwrap().result = Some(Ok(Err(e.into())));
+    }
+    Blocking { shared }
+}
+
+impl<R> Future for Blocking<R> {
+    type Output = Result<R>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<R>> {
+        let mut shared = self.shared.lock().unwrap();
+        match shared.result.take() {
+            Some(Ok(result)) => Poll::Ready(result),
+            Some(Err(payload)) => {
+                drop(shared);
+                panic::resume_unwind(payload)
+            }
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+    use crate::{Codec, ColumnarError, Predicate};
+
+    // Однопоточный исполнитель: паркует поток до пробуждения
+    struct Unpark(thread::Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn test_build_async_and_open_async_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let values: Vec<i64> = (0..100_000).map(|i| i * 13 % 9973).collect();
+        let mut builder = ColumnBuilder::from_values("v".to_string(), &values);
+        builder.set_chunk_rows(8192);
+        builder.compress_with(Codec::Zstd { level: 3 }).unwrap();
+
+        // Future можно отдать многопоточному рантайму
+        fn assert_send<F: Future + Send>(future: F) -> F {
+            future
+        }
+        let built = block_on(assert_send(builder.build_async(&path))).unwrap();
+        assert_eq!(built.values().unwrap(), values);
+        let column = block_on(assert_send(Column::<i64>::open_async(&path))).unwrap();
+        assert_eq!(column.name, "v");
+        assert_eq!(column.values().unwrap(), values);
+        assert_eq!(column.stats(), built.stats());
+        assert_eq!(column.filter(Predicate::Eq(42)).unwrap(), built.filter(Predicate::Eq(42)).unwrap());
+
+        let synced = ColumnBuilder::from_values("s".to_string(), &values[..10]);
+        let synced = block_on(synced.build_async_with(&dir.path().join("s.col"), BuildOptions { sync: true })).unwrap();
+        assert_eq!(synced.len(), 10);
+    }
+
+    #[test]
+    fn test_open_async_reports_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = block_on(Column::<i32>::open_async(&dir.path().join("missing.col")));
+        assert!(matches!(missing, Err(ColumnarError::Io(_))));
+
+        let path = dir.path().join("v.col");
+        block_on(ColumnBuilder::from_values("v".to_string(), &[1i32, 2, 3]).build_async(&path)).unwrap();
+        assert!(matches!(block_on(Column::<i64>::open_async(&path)), Err(ColumnarError::Corrupt { .. })));
+    }
+}
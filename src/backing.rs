@@ -0,0 +1,169 @@
+use std::{
+    borrow::Cow,
+    fs::File,
+    ops::{Deref, Range},
+    sync::{Arc, OnceLock},
+};
+use memmap2::Mmap;
+use crate::error::{corrupt, Result};
+use crate::read_at::ReadAt;
+
+// Байты колонки: отображённый в память файл, буфер, собранный без файловой системы,
+// или внешний источник. Чтения чанков идут через read и берут у источника только
+// нужный диапазон; срез всего файла через Deref скачивает его целиком при первом
+// обращении
+#[derive(Debug, Clone)]
+pub enum Backing {
+    Mapped(Arc<Mmap>),
+    Memory(Arc<Vec<u8>>),
+    Remote(Arc<RemoteBytes>),
+}
+
+// Файл колонки у источника ReadAt и его копия, если весь файл уже понадобился
+#[derive(Debug)]
+pub struct RemoteBytes {
+    source: Arc<dyn ReadAt>,
+    len: u64,
+    whole: OnceLock<Vec<u8>>,
+}
+
+impl Backing {
+    pub(crate) fn map(file: &File) -> Result<Self> {
+        // Файлы колонок не меняются на месте: дозапись только дописывает байты в конец
+        let mmap = unsafe { Mmap::map(file)? };
+        Ok(Backing::Mapped(Arc::new(mmap)))
+    }
+
+    pub(crate) fn remote(source: Arc<dyn ReadAt>, len: u64) -> Self {
+        Backing::Remote(Arc::new(RemoteBytes { source, len, whole: OnceLock::new() }))
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        matches!(self, Backing::Memory(_))
+    }
+
+    pub fn is_remote(&self) -> bool {
+        matches!(self, Backing::Remote(_))
+    }
+
+    // Длина файла; у источника не требует чтения
+    pub fn len(&self) -> usize {
+        match self {
+            Backing::Remote(remote) => remote.len as usize,
+            _ => self.deref().len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Байты range: срез для файла и буфера, один запрос диапазона для источника
+    pub(crate) fn read(&self, range: Range<usize>) -> Result<Cow<'_, [u8]>> {
+        let bytes = match self {
+            Backing::Remote(remote) if remote.whole.get().is_none() => {
+                if range.start > range.end || range.end as u64 > remote.len {
+                    return Err(corrupt(format!("диапазон {:?} вне файла колонки", range)));
+                }
+                let bytes = remote.source.read_at(range.start as u64, range.len())?;
+                if bytes.len() != range.len() {
+                    return Err(corrupt(format!(
+                        "источник вернул {} байт вместо {}",
+                        bytes.len(),
+                        range.len()
+                    )));
+                }
+                return Ok(Cow::Owned(bytes));
+            }
+            _ => self.whole()?,
+        };
+        bytes.get(range.clone()).map(Cow::Borrowed).ok_or_else(|| corrupt(format!("диапазон {:?} вне файла колонки", range)))
+    }
+
+    // Весь файл; у источника скачивается один раз
+    pub(crate) fn whole(&self) -> Result<&[u8]> {
+        match self {
+            Backing::Mapped(mmap) => Ok(mmap),
+            Backing::Memory(bytes) => Ok(bytes),
+            Backing::Remote(remote) => {
+                if let Some(bytes) = remote.whole.get() {
+                    return Ok(bytes);
+                }
+                let bytes = remote.source.read_at(0, remote.len as usize)?;
+                if bytes.len() as u64 != remote.len {
+                    return Err(corrupt(format!("источник вернул {} байт вместо {}", bytes.len(), remote.len)));
+                }
+                Ok(remote.whole.get_or_init(|| bytes))
+            }
+        }
+    }
+}
+
+// Ошибку чтения источника срез вернуть не может; пути, которым нужна ошибка,
+// читают через read и whole
+impl Deref for Backing {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.whole().unwrap_or_else(|e| panic!("файл колонки недоступен у источника: {}", e))
+    }
+}
+
+// Распакованные значения колонки. Несжатые данные без битовых карт отдаются срезом
+// backing без копирования, остальное собирается в отдельный буфер
+#[derive(Debug, Clone)]
+pub enum ColumnData {
+    Slice { backing: Backing, range: Range<usize> },
+    Owned(Vec<u8>),
+}
+
+impl ColumnData {
+    pub fn is_zero_copy(&self) -> bool {
+        matches!(self, ColumnData::Slice { .. })
+    }
+
+    // Длина без обращения к байтам: у среза внешнего источника Deref скачал бы файл
+    pub fn len(&self) -> usize {
+        match self {
+            ColumnData::Slice { range, .. } => range.len(),
+            ColumnData::Owned(bytes) => bytes.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        match self {
+            ColumnData::Slice { backing, range } => backing[range].to_vec(),
+            ColumnData::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl Deref for ColumnData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            ColumnData::Slice { backing, range } => &backing[range.clone()],
+            ColumnData::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl From<Vec<u8>> for ColumnData {
+    fn from(bytes: Vec<u8>) -> Self {
+        ColumnData::Owned(bytes)
+    }
+}
+
+// Сравниваются байты, а не способ хранения
+impl PartialEq for ColumnData {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for ColumnData {}
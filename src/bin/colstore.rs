@@ -0,0 +1,625 @@
+// colstore — просмотр и преобразование файлов колонок из командной строки. Пользуется
+// только публичным API библиотеки, поэтому заодно проверяет формат снаружи крейта.
+//
+// Коды выхода: 0 — успех, 1 — verify нашёл расхождения, 2 — неверные аргументы,
+// 3 — ошибка библиотеки (ввод-вывод, повреждённый файл и т.п.). С --json результат
+// печатается одной строкой JSON, а текст ошибок по-прежнему идёт в stderr
+use std::{
+    fmt::Display,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+use data_system_project::{
+    BoolColumn, Column, ColumnMeta, ColumnSummary, ColumnType, ColumnarError, CsvOptions, DataType, Field,
+    FileReadAt, ReadAt, Schema, StringColumn, Table, TableColumn, VerifyOptions, VerifyReport,
+};
+
+const USAGE: &str = "\
+использование:
+  colstore inspect <файл> [--json]
+  colstore cat <файл> [--limit N] [--json]
+  colstore create <выход> --from-csv <csv> --type i32|i64|f64|str|bool [--column ИМЯ] [--json]
+  colstore verify <файл> [--quick] [--json]
+  colstore compact <файл> [--json]
+
+create без --column читает CSV без заголовка и берёт первую колонку; имя колонки —
+имя выходного файла без расширения. С --column первая строка CSV — заголовок";
+
+const EXIT_OK: u8 = 0;
+const EXIT_PROBLEMS: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_ERROR: u8 = 3;
+
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Library(ColumnarError),
+}
+
+impl From<ColumnarError> for CliError {
+    fn from(e: ColumnarError) -> Self {
+        CliError::Library(e)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Library(e.into())
+    }
+}
+
+type CliResult<T> = std::result::Result<T, CliError>;
+
+fn usage(message: impl Into<String>) -> CliError {
+    CliError::Usage(message.into())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut stdout = io::stdout().lock();
+    match run(&args, &mut stdout) {
+        Ok(code) => ExitCode::from(code),
+        Err(CliError::Usage(message)) => {
+            eprintln!("colstore: {}\n\n{}", message, USAGE);
+            ExitCode::from(EXIT_USAGE)
+        }
+        Err(CliError::Library(e)) => {
+            eprintln!("colstore: {}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+// Позиционные аргументы и флаги команды; флаги со значением перечислены в valued
+struct Args {
+    positional: Vec<String>,
+    flags: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    fn parse(args: &[String], switches: &[&str], valued: &[&str]) -> CliResult<Args> {
+        let mut parsed = Args { positional: Vec::new(), flags: Vec::new() };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg.clone());
+            } else if switches.contains(&arg.as_str()) {
+                parsed.flags.push((arg.clone(), None));
+            } else if valued.contains(&arg.as_str()) {
+                let value = args.next().ok_or_else(|| usage(format!("у {} нет значения", arg)))?;
+                parsed.flags.push((arg.clone(), Some(value.clone())));
+            } else {
+                return Err(usage(format!("неизвестный флаг {}", arg)));
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn has(&self, flag: &str) -> bool {
+        self.flags.iter().any(|(name, _)| name == flag)
+    }
+
+    fn value(&self, flag: &str) -> Option<&str> {
+        self.flags.iter().rev().find(|(name, _)| name == flag).and_then(|(_, value)| value.as_deref())
+    }
+
+    // Единственный позиционный аргумент — путь файла
+    fn path(&self) -> CliResult<PathBuf> {
+        match self.positional.as_slice() {
+            [path] => Ok(PathBuf::from(path)),
+            [] => Err(usage("не указан файл")),
+            _ => Err(usage(format!("лишние аргументы: {}", self.positional[1..].join(" ")))),
+        }
+    }
+}
+
+fn run(args: &[String], out: &mut dyn Write) -> CliResult<u8> {
+    let (command, rest) = args.split_first().ok_or_else(|| usage("не указана команда"))?;
+    match command.as_str() {
+        "inspect" => {
+            let args = Args::parse(rest, &["--json"], &[])?;
+            inspect(&args.path()?, args.has("--json"), out)
+        }
+        "cat" => {
+            let args = Args::parse(rest, &["--json"], &["--limit"])?;
+            let limit = match args.value("--limit") {
+                Some(limit) => Some(limit.parse().map_err(|_| usage(format!("--limit {} — не число строк", limit)))?),
+                None => None,
+            };
+            cat(&args.path()?, limit, args.has("--json"), out)
+        }
+        "create" => {
+            let args = Args::parse(rest, &["--json"], &["--from-csv", "--type", "--column"])?;
+            let csv = args.value("--from-csv").ok_or_else(|| usage("create требует --from-csv"))?;
+            let data_type = match args.value("--type") {
+                Some("i32") => DataType::Int32,
+                Some("i64") => DataType::Int64,
+                Some("f64") => DataType::Float64,
+                Some("str") => DataType::Utf8,
+                Some("bool") => DataType::Bool,
+                Some(other) => return Err(usage(format!("неизвестный тип {}", other))),
+                None => return Err(usage("create требует --type")),
+            };
+            create(&args.path()?, Path::new(csv), data_type, args.value("--column"), args.has("--json"), out)
+        }
+        "verify" => {
+            let args = Args::parse(rest, &["--json", "--quick"], &[])?;
+            verify(&args.path()?, VerifyOptions { quick: args.has("--quick") }, args.has("--json"), out)
+        }
+        "compact" => {
+            let args = Args::parse(rest, &["--json"], &[])?;
+            compact(&args.path()?, args.has("--json"), out)
+        }
+        "help" | "--help" | "-h" => {
+            writeln!(out, "{}", USAGE)?;
+            Ok(EXIT_OK)
+        }
+        other => Err(usage(format!("неизвестная команда {}", other))),
+    }
+}
+
+// Файл колонки любого типа; тип берётся из заголовка
+enum AnyColumn {
+    Int32(Column<i32>),
+    Int64(Column<i64>),
+    Float64(Column<f64>),
+    // Строковая колонка заметно больше остальных вариантов
+    Utf8(Box<StringColumn>),
+    Bool(BoolColumn),
+}
+
+impl AnyColumn {
+    fn open(path: &Path) -> CliResult<AnyColumn> {
+        let source = FileReadAt::open(path)?;
+        let data_type = ColumnMeta::read(&source as &dyn ReadAt)?.data_type()?;
+        Ok(match data_type {
+            DataType::Int32 => AnyColumn::Int32(Column::open(path)?),
+            DataType::Int64 => AnyColumn::Int64(Column::open(path)?),
+            DataType::Float64 => AnyColumn::Float64(Column::open(path)?),
+            DataType::Utf8 => AnyColumn::Utf8(Box::new(StringColumn::open(path)?)),
+            DataType::Bool => AnyColumn::Bool(BoolColumn::open(path)?),
+        })
+    }
+
+    fn describe(&self) -> ColumnSummary {
+        match self {
+            AnyColumn::Int32(column) => column.describe(),
+            AnyColumn::Int64(column) => column.describe(),
+            AnyColumn::Float64(column) => column.describe(),
+            AnyColumn::Utf8(column) => column.describe(),
+            AnyColumn::Bool(column) => column.describe(),
+        }
+    }
+
+    // Значения строк в виде JSON, пропуская удалённые; не больше limit
+    fn json_values(&self, limit: usize) -> CliResult<Vec<String>> {
+        match self {
+            AnyColumn::Int32(column) => numeric_values(column, limit),
+            AnyColumn::Int64(column) => numeric_values(column, limit),
+            AnyColumn::Float64(column) => numeric_values(column, limit),
+            AnyColumn::Utf8(column) => Ok((0..column.len().min(limit))
+                .map(|idx| json_string(column.get_str(idx).unwrap_or_default()))
+                .collect()),
+            AnyColumn::Bool(column) => Ok((0..column.len().min(limit))
+                .map(|idx| column.get_bool(idx).unwrap_or_default().to_string())
+                .collect()),
+        }
+    }
+}
+
+fn numeric_values<T: ColumnType + JsonNumber>(column: &Column<T>, limit: usize) -> CliResult<Vec<String>> {
+    let mut values = Vec::new();
+    for idx in 0..column.len() {
+        if values.len() == limit {
+            break;
+        }
+        if !column.is_deleted(idx) {
+            let value = column.try_get_nullable(idx)?.flatten();
+            values.push(value.map_or_else(|| "null".to_string(), |value| value.json()));
+        }
+    }
+    Ok(values)
+}
+
+// Число в JSON; NaN и бесконечности JSON не представляет, они пишутся строками
+trait JsonNumber {
+    fn json(&self) -> String;
+}
+
+impl JsonNumber for i32 {
+    fn json(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl JsonNumber for i64 {
+    fn json(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl JsonNumber for f64 {
+    fn json(&self) -> String {
+        if self.is_finite() {
+            format!("{:?}", self)
+        } else {
+            json_string(&self.to_string())
+        }
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Объект JSON из пар ключ — готовое значение JSON, в порядке добавления
+struct JsonObject(Vec<(&'static str, String)>);
+
+impl JsonObject {
+    fn new() -> Self {
+        JsonObject(Vec::new())
+    }
+
+    fn raw(&mut self, key: &'static str, value: impl Display) -> &mut Self {
+        self.0.push((key, value.to_string()));
+        self
+    }
+
+    fn string(&mut self, key: &'static str, value: &str) -> &mut Self {
+        self.0.push((key, json_string(value)));
+        self
+    }
+
+    fn finish(&self) -> String {
+        let fields: Vec<String> = self.0.iter().map(|(key, value)| format!("{}:{}", json_string(key), value)).collect();
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+// Строка сводки и её значение в JSON: числа как есть, строки в кавычках, нет — null
+struct Entry {
+    key: &'static str,
+    label: &'static str,
+    text: String,
+    json: String,
+}
+
+fn entries(path: &Path, column: &AnyColumn) -> Vec<Entry> {
+    let summary = column.describe();
+    let mut entries = Vec::new();
+    let mut push = |key, label, text: String, json: String| entries.push(Entry { key, label, text, json });
+    push("path", "файл", path.display().to_string(), json_string(&path.display().to_string()));
+    push("name", "колонка", summary.name.clone(), json_string(&summary.name));
+    push("type", "тип", format!("{:?}", summary.data_type), json_string(&format!("{:?}", summary.data_type)));
+    push("rows", "строк", summary.rows.to_string(), summary.rows.to_string());
+    push("deleted", "удалено", summary.deleted.to_string(), summary.deleted.to_string());
+    push("codec", "кодек", summary.codec.to_string(), json_string(&summary.codec.to_string()));
+    push("disk_bytes", "на диске, байт", summary.disk_bytes.to_string(), summary.disk_bytes.to_string());
+    push(
+        "uncompressed_bytes",
+        "без сжатия, байт",
+        summary.uncompressed_bytes.to_string(),
+        summary.uncompressed_bytes.to_string(),
+    );
+    let (min, max) = bounds(column);
+    push("min", "min", summary.min.clone().unwrap_or_else(|| "—".to_string()), min);
+    push("max", "max", summary.max.clone().unwrap_or_else(|| "—".to_string()), max);
+    push("null_count", "NULL", summary.null_count.to_string(), summary.null_count.to_string());
+    push("bloom", "bloom", yes_no(summary.has_bloom).to_string(), summary.has_bloom.to_string());
+    let numeric = match column {
+        AnyColumn::Int32(column) => Some(numeric_details(column)),
+        AnyColumn::Int64(column) => Some(numeric_details(column)),
+        AnyColumn::Float64(column) => Some(numeric_details(column)),
+        AnyColumn::Utf8(_) | AnyColumn::Bool(_) => None,
+    };
+    if let Some((encoding, chunks, distinct, sorted)) = numeric {
+        push("encoding", "кодирование", encoding.clone(), json_string(&encoding));
+        push("chunks", "чанков", chunks.to_string(), chunks.to_string());
+        push("distinct_estimate", "различных (оценка)", distinct.to_string(), distinct.to_string());
+        push("sorted", "отсортирована", yes_no(sorted).to_string(), sorted.to_string());
+    }
+    entries
+}
+
+fn numeric_details<T: ColumnType>(column: &Column<T>) -> (String, usize, u64, bool) {
+    let stats = column.stats();
+    (format!("{:?}", column.encoding), column.chunks().len(), stats.distinct_count, stats.is_sorted)
+}
+
+// min и max в JSON: у числовых колонок числа, у строковых строки, у пустых null
+fn bounds(column: &AnyColumn) -> (String, String) {
+    fn numeric<T: ColumnType + JsonNumber>(column: &Column<T>) -> (String, String) {
+        match column.describe().min {
+            Some(_) => (column.min.json(), column.max.json()),
+            None => ("null".to_string(), "null".to_string()),
+        }
+    }
+    match column {
+        AnyColumn::Int32(column) => numeric(column),
+        AnyColumn::Int64(column) => numeric(column),
+        AnyColumn::Float64(column) => numeric(column),
+        AnyColumn::Utf8(column) if column.is_empty() => ("null".to_string(), "null".to_string()),
+        AnyColumn::Utf8(column) => (json_string(&column.min), json_string(&column.max)),
+        AnyColumn::Bool(column) => {
+            let summary = column.describe();
+            (summary.min.unwrap_or_else(|| "null".to_string()), summary.max.unwrap_or_else(|| "null".to_string()))
+        }
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "да" } else { "нет" }
+}
+
+fn inspect(path: &Path, json: bool, out: &mut dyn Write) -> CliResult<u8> {
+    let column = AnyColumn::open(path)?;
+    let entries = entries(path, &column);
+    if json {
+        let mut object = JsonObject::new();
+        for entry in &entries {
+            object.raw(entry.key, &entry.json);
+        }
+        writeln!(out, "{}", object.finish())?;
+    } else {
+        let width = entries.iter().map(|entry| entry.label.chars().count()).max().unwrap_or(0);
+        for entry in &entries {
+            writeln!(out, "{:<width$}  {}", format!("{}:", entry.label), entry.text, width = width + 1)?;
+        }
+    }
+    Ok(EXIT_OK)
+}
+
+fn cat(path: &Path, limit: Option<usize>, json: bool, out: &mut dyn Write) -> CliResult<u8> {
+    let column = AnyColumn::open(path)?;
+    let values = column.json_values(limit.unwrap_or(usize::MAX))?;
+    if json {
+        writeln!(out, "[{}]", values.join(","))?;
+        return Ok(EXIT_OK);
+    }
+    // В тексте строки без кавычек, а NULL — словом NULL
+    match &column {
+        AnyColumn::Utf8(strings) => {
+            for idx in 0..values.len() {
+                writeln!(out, "{}", strings.get_str(idx).unwrap_or_default())?;
+            }
+        }
+        _ => {
+            for value in values {
+                writeln!(out, "{}", if value == "null" { "NULL" } else { value.trim_matches('"') })?;
+            }
+        }
+    }
+    Ok(EXIT_OK)
+}
+
+fn create(
+    path: &Path,
+    csv: &Path,
+    data_type: DataType,
+    column: Option<&str>,
+    json: bool,
+    out: &mut dyn Write,
+) -> CliResult<u8> {
+    let name = match column {
+        Some(name) => name.to_string(),
+        None => path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned()),
+    };
+    let schema = Schema::new(vec![Field::new(name.clone(), data_type, true)]);
+    let options = CsvOptions { has_header: column.is_some(), ..CsvOptions::default() };
+    let (table, _) = Table::from_csv(csv, &schema, options)?;
+    let created = table.get(&name).ok_or_else(|| usage(format!("в таблице нет колонки {}", name)))?;
+    // Файл колонки таблицы уже в формате колонки, его достаточно скопировать
+    let bytes: &[u8] = match created {
+        TableColumn::Int32(column) => &column.backing,
+        TableColumn::Int64(column) => &column.backing,
+        TableColumn::Float64(column) => &column.backing,
+        TableColumn::Utf8(column) => &column.mmap,
+        TableColumn::Bool(column) => &column.mmap,
+    };
+    fs::write(path, bytes)?;
+    let rows = AnyColumn::open(path)?.describe().rows;
+    if json {
+        let mut object = JsonObject::new();
+        object.string("path", &path.display().to_string()).string("name", &name).raw("rows", rows);
+        writeln!(out, "{}", object.finish())?;
+    } else {
+        writeln!(out, "{}: записано строк {}", path.display(), rows)?;
+    }
+    Ok(EXIT_OK)
+}
+
+fn verify(path: &Path, options: VerifyOptions, json: bool, out: &mut dyn Write) -> CliResult<u8> {
+    // Строковые и логические колонки сверяют контрольные суммы и структуру при open
+    let report = match AnyColumn::open(path)? {
+        AnyColumn::Int32(column) => column.verify_with(options)?,
+        AnyColumn::Int64(column) => column.verify_with(options)?,
+        AnyColumn::Float64(column) => column.verify_with(options)?,
+        AnyColumn::Utf8(_) | AnyColumn::Bool(_) => VerifyReport::default(),
+    };
+    let problems: Vec<String> = report.problems.iter().map(|problem| format!("{:?}", problem)).collect();
+    if json {
+        let list: Vec<String> = problems.iter().map(|problem| json_string(problem)).collect();
+        let mut object = JsonObject::new();
+        object
+            .raw("ok", report.is_ok())
+            .raw("chunks_checked", report.chunks_checked)
+            .raw("rows_checked", report.rows_checked)
+            .raw("problems", format!("[{}]", list.join(",")));
+        writeln!(out, "{}", object.finish())?;
+    } else {
+        for problem in &problems {
+            writeln!(out, "{}", problem)?;
+        }
+        writeln!(
+            out,
+            "{}: {}, чанков проверено {}, строк {}",
+            path.display(),
+            if report.is_ok() { "ошибок нет" } else { "найдены расхождения" },
+            report.chunks_checked,
+            report.rows_checked
+        )?;
+    }
+    Ok(if report.is_ok() { EXIT_OK } else { EXIT_PROBLEMS })
+}
+
+fn compact(path: &Path, json: bool, out: &mut dyn Write) -> CliResult<u8> {
+    let before = AnyColumn::open(path)?;
+    let (rows_before, bytes_before) = (before.describe().rows, before.describe().disk_bytes);
+    // compact пишет атомарно, поэтому результат можно сразу положить на место колонки.
+    // Строковые и логические колонки удалений не хранят, сжимать в них нечего
+    let after = match &before {
+        AnyColumn::Int32(column) => column.compact(path)?.describe(),
+        AnyColumn::Int64(column) => column.compact(path)?.describe(),
+        AnyColumn::Float64(column) => column.compact(path)?.describe(),
+        AnyColumn::Utf8(_) | AnyColumn::Bool(_) => before.describe(),
+    };
+    drop(before);
+    if json {
+        let mut object = JsonObject::new();
+        object
+            .string("path", &path.display().to_string())
+            .raw("rows_before", rows_before)
+            .raw("rows_after", after.rows)
+            .raw("bytes_before", bytes_before)
+            .raw("bytes_after", after.disk_bytes);
+        writeln!(out, "{}", object.finish())?;
+    } else {
+        writeln!(
+            out,
+            "{}: строк {} → {}, байт {} → {}",
+            path.display(),
+            rows_before,
+            after.rows,
+            bytes_before,
+            after.disk_bytes
+        )?;
+    }
+    Ok(EXIT_OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_system_project::ColumnBuilder;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/amount.col");
+
+    fn run_args(args: &[&str]) -> (CliResult<u8>, String) {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut out = Vec::new();
+        let result = run(&args, &mut out);
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_inspect_fixture_snapshot() {
+        let (code, text) = run_args(&["inspect", FIXTURE]);
+        assert_eq!(code.unwrap(), EXIT_OK);
+        let expected = format!(
+            "файл:                {}\n\
+             колонка:             amount\n\
+             тип:                 Int64\n\
+             строк:               1000\n\
+             удалено:             0\n\
+             кодек:               lz4\n\
+             на диске, байт:      11069\n\
+             без сжатия, байт:    8000\n\
+             min:                 0\n\
+             max:                 996\n\
+             NULL:                100\n\
+             bloom:               да\n\
+             кодирование:         Plain\n\
+             чанков:              4\n\
+             различных (оценка):  {}\n\
+             отсортирована:       нет\n",
+            FIXTURE,
+            Column::<i64>::open(Path::new(FIXTURE)).unwrap().stats().distinct_count
+        );
+        assert_eq!(text, expected);
+
+        let (code, json) = run_args(&["inspect", "--json", FIXTURE]);
+        assert_eq!(code.unwrap(), EXIT_OK);
+        assert!(json.starts_with(&format!("{{\"path\":{},\"name\":\"amount\",\"type\":\"Int64\",\"rows\":1000,", json_string(FIXTURE))));
+        assert!(json.contains(",\"codec\":\"lz4\",\"disk_bytes\":11069,\"uncompressed_bytes\":8000,\"min\":0,\"max\":996,"));
+        assert!(json.ends_with(",\"sorted\":false}\n"));
+    }
+
+    #[test]
+    fn test_cat_with_limit_and_json() {
+        let (code, text) = run_args(&["cat", FIXTURE, "--limit", "5"]);
+        assert_eq!(code.unwrap(), EXIT_OK);
+        assert_eq!(text, "0\n37\n74\nNULL\n148\n");
+        let (_, json) = run_args(&["cat", "--json", "--limit", "4", FIXTURE]);
+        assert_eq!(json, "[0,37,74,null]\n");
+    }
+
+    #[test]
+    fn test_create_verify_and_compact() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv = dir.path().join("in.csv");
+        fs::write(&csv, "id,label\n1,a\n2,\"b, c\"\n,d\n4,e\n").unwrap();
+        let out = dir.path().join("id.col");
+        let (code, text) = run_args(&["create", out.to_str().unwrap(), "--from-csv", csv.to_str().unwrap(), "--type", "i32", "--column", "id"]);
+        assert_eq!(code.unwrap(), EXIT_OK, "{}", text);
+        let column = Column::<i32>::open(&out).unwrap();
+        assert_eq!(column.nullable_values().unwrap(), [Some(1), Some(2), None, Some(4)]);
+
+        let labels = dir.path().join("labels.col");
+        let (code, _) = run_args(&["create", labels.to_str().unwrap(), "--from-csv", csv.to_str().unwrap(), "--type", "str", "--column", "label"]);
+        assert_eq!(code.unwrap(), EXIT_OK);
+        let (_, json) = run_args(&["cat", labels.to_str().unwrap(), "--json"]);
+        assert_eq!(json, "[\"a\",\"b, c\",\"d\",\"e\"]\n");
+
+        let (code, json) = run_args(&["verify", out.to_str().unwrap(), "--json"]);
+        assert_eq!(code.unwrap(), EXIT_OK);
+        assert_eq!(json, "{\"ok\":true,\"chunks_checked\":1,\"rows_checked\":4,\"problems\":[]}\n");
+
+        let mut column = column;
+        column.delete_rows(&[0, 2]).unwrap();
+        drop(column);
+        let (code, json) = run_args(&["compact", "--json", out.to_str().unwrap()]);
+        assert_eq!(code.unwrap(), EXIT_OK);
+        assert!(json.contains("\"rows_before\":4,\"rows_after\":2,"), "{}", json);
+        assert_eq!(Column::<i32>::open(&out).unwrap().values().unwrap(), [2, 4]);
+    }
+
+    #[test]
+    fn test_verify_reports_problems_with_exit_code() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let values: Vec<i64> = (0..5000).collect();
+        ColumnBuilder::from_values("v".to_string(), &values).build(&path).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[100] ^= 0xFF;
+        fs::write(&path, &bytes).unwrap();
+        let (code, text) = run_args(&["verify", path.to_str().unwrap(), "--quick"]);
+        assert_eq!(code.unwrap(), EXIT_PROBLEMS);
+        assert!(text.starts_with("Checksum { chunk: 0,"), "{}", text);
+    }
+
+    #[test]
+    fn test_usage_and_library_errors() {
+        assert!(matches!(run_args(&[]).0, Err(CliError::Usage(_))));
+        assert!(matches!(run_args(&["frobnicate"]).0, Err(CliError::Usage(_))));
+        assert!(matches!(run_args(&["cat", FIXTURE, "--limit", "many"]).0, Err(CliError::Usage(_))));
+        assert!(matches!(run_args(&["inspect", FIXTURE, "--verbose"]).0, Err(CliError::Usage(_))));
+        assert!(matches!(run_args(&["create", "x.col", "--from-csv", "in.csv"]).0, Err(CliError::Usage(_))));
+        assert!(matches!(run_args(&["inspect", "/nonexistent/v.col"]).0, Err(CliError::Library(ColumnarError::Io(_)))));
+    }
+}
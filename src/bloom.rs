@@ -0,0 +1,73 @@
+// Bloom-фильтр метаданных: с функцией bloom это bloomfilter::Bloom. Без неё
+// числовые колонки пишутся без фильтров, как f64, а фильтры уже записанных файлов
+// читаются и переписываются байт в байт, но не проверяются: check всегда true
+#[cfg(feature = "bloom")]
+pub(crate) use bloomfilter::Bloom;
+
+#[cfg(not(feature = "bloom"))]
+pub(crate) use stub::Bloom;
+
+#[cfg(not(feature = "bloom"))]
+mod stub {
+    use std::{fmt, marker::PhantomData};
+
+    pub struct Bloom<T: ?Sized> {
+        bitmap: Vec<u8>,
+        bits: u64,
+        k_num: u32,
+        sip_keys: [(u64, u64); 2],
+        _marker: PhantomData<fn(&T)>,
+    }
+
+    impl<T: ?Sized> Bloom<T> {
+        // Пустая заглушка, как у bloomfilter; в файл не пишется
+        pub fn new(bitmap_size: usize, _items_count: usize) -> Self {
+            Self::from_existing(&vec![0; bitmap_size], bitmap_size as u64 * 8, 1, [(0, 0); 2])
+        }
+
+        // Фильтр для записи насыщен: все биты выставлены, и библиотека bloomfilter
+        // при чтении такого файла тоже ответит «возможно» на любое значение. Так
+        // пишутся обязательные фильтры строковых колонок
+        pub fn new_for_fp_rate(_items_count: usize, _fp_rate: f64) -> Self {
+            Self::from_existing(&[0xFF], 8, 1, [(0, 0); 2])
+        }
+
+        pub fn from_existing(bitmap: &[u8], bits: u64, k_num: u32, sip_keys: [(u64, u64); 2]) -> Self {
+            Self { bitmap: bitmap.to_vec(), bits, k_num, sip_keys, _marker: PhantomData }
+        }
+
+        pub fn set(&mut self, _item: &T) {}
+
+        pub fn check(&self, _item: &T) -> bool {
+            true
+        }
+
+        pub fn bitmap(&self) -> Vec<u8> {
+            self.bitmap.clone()
+        }
+
+        pub fn number_of_bits(&self) -> u64 {
+            self.bits
+        }
+
+        pub fn number_of_hash_functions(&self) -> u32 {
+            self.k_num
+        }
+
+        pub fn sip_keys(&self) -> [(u64, u64); 2] {
+            self.sip_keys
+        }
+    }
+
+    impl<T: ?Sized> Clone for Bloom<T> {
+        fn clone(&self) -> Self {
+            Self::from_existing(&self.bitmap, self.bits, self.k_num, self.sip_keys)
+        }
+    }
+
+    impl<T: ?Sized> fmt::Debug for Bloom<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Bloom").field("bits", &self.bits).field("k_num", &self.k_num).finish()
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::Arc,
+};
+use memmap2::Mmap;
+use crate::codec::Codec;
+use crate::error::{corrupt, Result};
+use crate::encoding::Encoding;
+use crate::format::{
+    read_str, split_trailer, validate_sections, write_str, write_trailer, Buffer, ByteReader, Header,
+    Section, HEADER_SIZE,
+};
+use crate::types::type_name;
+
+// Тег булевой колонки в заголовке файла
+pub(crate) const BOOL_TAG: u8 = 4;
+
+// Булева колонка упаковывает 8 значений в байт, младший бит — первая строка.
+// Неиспользуемые биты последнего байта равны нулю.
+pub struct BoolColumnBuilder {
+    name: String,
+    bits: Vec<u8>,
+    rows: usize,
+    true_count: u64,
+    codec: Codec,
+}
+
+#[derive(Debug)]
+pub struct BoolColumn {
+    pub name: String,
+    pub mmap: Arc<Mmap>,
+    pub codec: Codec,
+    // Вместо min/max статистика хранит число истинных и ложных значений
+    pub true_count: u64,
+    pub false_count: u64,
+    rows: usize,
+    bits: Buffer,
+}
+
+struct BoolFooter {
+    name: String,
+    compression_level: i32,
+    true_count: u64,
+    bits: Section,
+}
+
+// Упаковка по 8 значений в байт; используется и для битовых карт валидности
+pub(crate) fn pack_bits(values: &[bool]) -> Vec<u8> {
+    let mut bits = vec![0u8; values.len().div_ceil(8)];
+    for (idx, _) in values.iter().enumerate().filter(|(_, v)| **v) {
+        bits[idx / 8] |= 1 << (idx % 8);
+    }
+    bits
+}
+
+pub(crate) fn bit_is_set(bits: &[u8], idx: usize) -> bool {
+    bits[idx / 8] & (1 << (idx % 8)) != 0
+}
+
+impl BoolColumnBuilder {
+    pub fn from_bools(name: String, values: &[bool]) -> Self {
+        Self {
+            name,
+            true_count: values.iter().filter(|v| **v).count() as u64,
+            bits: pack_bits(values),
+            rows: values.len(),
+            codec: Codec::None,
+        }
+    }
+
+    pub fn compress_with(&mut self, codec: Codec) -> Result<()> {
+        codec.validate()?;
+        self.codec = codec;
+        Ok(())
+    }
+
+    pub fn build(self, path: &Path) -> Result<BoolColumn> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let header = Header::new(self.codec.tag(), Encoding::Plain.tag(), BOOL_TAG, self.rows as u64);
+        file.write_all(&header.encode())?;
+        let bits = Section::write(&mut file, HEADER_SIZE as u64, &self.bits, self.codec)?;
+
+        let footer = BoolFooter {
+            name: self.name,
+            compression_level: self.codec.level(),
+            true_count: self.true_count,
+            bits,
+        };
+        file.write_all(&footer.encode())?;
+        file.into_inner().map_err(|e| e.into_error())?;
+
+        BoolColumn::open(path)
+    }
+}
+
+impl BoolColumn {
+    pub fn open(path: &Path) -> Result<BoolColumn> {
+        let file = File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        let header = Header::decode(&mmap)?;
+        if header.type_tag != BOOL_TAG {
+            return Err(corrupt(format!(
+                "колонка хранит значения {}, а открывается как {}",
+                type_name(header.type_tag), type_name(BOOL_TAG)
+            )));
+        }
+        let (data_end, meta) = split_trailer(&mmap)?;
+        let footer = BoolFooter::decode(meta)?;
+        let codec = Codec::from_parts(header.codec_tag, footer.compression_level)?;
+        validate_sections(&[footer.bits], data_end)?;
+        let rows = header.row_count as usize;
+        if footer.bits.raw_len != rows.div_ceil(8) as u64 || footer.true_count > rows as u64 {
+            return Err(corrupt("размер битовой карты не соответствует числу строк"));
+        }
+
+        let bits = footer.bits.load(&mmap, codec)?;
+        Ok(BoolColumn {
+            name: footer.name,
+            codec,
+            true_count: footer.true_count,
+            false_count: rows as u64 - footer.true_count,
+            rows,
+            bits,
+            mmap: Arc::new(mmap),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.codec.is_compressed()
+    }
+
+    fn bytes(&self) -> &[u8] {
+        self.bits.bytes(&self.mmap)
+    }
+
+    pub(crate) fn uncompressed_len(&self) -> u64 {
+        self.bytes().len() as u64
+    }
+
+    pub fn get_bool(&self, idx: usize) -> Option<bool> {
+        if idx >= self.rows {
+            return None;
+        }
+        Some(bit_is_set(self.bytes(), idx))
+    }
+
+    // Подсчёт по битовой карте; хвост последнего байта маскируется, а не считается нулевым
+    pub fn count_true(&self) -> u64 {
+        let bytes = self.bytes();
+        let full = self.rows / 8;
+        let mut count: u64 = bytes[..full].iter().map(|b| b.count_ones() as u64).sum();
+        let tail = self.rows % 8;
+        if tail > 0 {
+            count += (bytes[full] & ((1u8 << tail) - 1)).count_ones() as u64;
+        }
+        count
+    }
+}
+
+impl BoolFooter {
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_str(&mut out, &self.name);
+        out.extend_from_slice(&self.compression_level.to_le_bytes());
+        out.extend_from_slice(&self.true_count.to_le_bytes());
+        self.bits.encode(&mut out);
+        write_trailer(&mut out);
+        out
+    }
+
+    fn decode(meta: &[u8]) -> Result<BoolFooter> {
+        let mut r = ByteReader::new(meta);
+        Ok(BoolFooter {
+            name: read_str(&mut r)?,
+            compression_level: r.i32()?,
+            true_count: r.u64()?,
+            bits: Section::decode(&mut r)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_count_true_matches_naive_scan() {
+        // Длина не кратна 8, чтобы последний байт был неполным
+        let mut state = 42u64;
+        let values: Vec<bool> = (0..1_000_003)
+            .map(|_| {
+                state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                (state >> 33).is_multiple_of(3)
+            })
+            .collect();
+        let naive = values.iter().filter(|v| **v).count() as u64;
+
+        for codec in [Codec::None, Codec::zstd()] {
+            let mut builder = BoolColumnBuilder::from_bools("flags".to_string(), &values);
+            builder.compress_with(codec).unwrap();
+            let tmp_file = NamedTempFile::new().unwrap();
+            builder.build(tmp_file.path()).unwrap();
+
+            let column = BoolColumn::open(tmp_file.path()).unwrap();
+            assert_eq!(column.count_true(), naive, "{:?}", codec);
+            assert_eq!(column.true_count, naive);
+            assert_eq!(column.false_count, values.len() as u64 - naive);
+            for idx in [0, 7, 8, 9, 999_999, 1_000_000, 1_000_002] {
+                assert_eq!(column.get_bool(idx), Some(values[idx]), "индекс {}", idx);
+            }
+            assert_eq!(column.get_bool(values.len()), None);
+        }
+
+        // Восемь значений на байт
+        let tmp_file = NamedTempFile::new().unwrap();
+        let column = BoolColumnBuilder::from_bools("flags".to_string(), &values).build(tmp_file.path()).unwrap();
+        assert_eq!(column.bytes().len(), values.len().div_ceil(8));
+    }
+
+    #[test]
+    fn test_partial_byte_and_empty_column() {
+        let values = [true, true, false, true, true];
+        let column = BoolColumnBuilder::from_bools("short".to_string(), &values)
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert_eq!(column.len(), 5);
+        assert_eq!(column.count_true(), 4);
+        assert_eq!(column.get_bool(2), Some(false));
+        assert_eq!(column.get_bool(5), None);
+
+        let tmp_file = NamedTempFile::new().unwrap();
+        let empty = BoolColumnBuilder::from_bools("empty".to_string(), &[]).build(tmp_file.path()).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.count_true(), 0);
+        assert_eq!(empty.get_bool(0), None);
+        let err = crate::Column::<i32>::open(tmp_file.path()).unwrap_err();
+        assert!(err.to_string().contains("bool"), "{}", err);
+    }
+}
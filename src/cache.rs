@@ -1,97 +1,1386 @@
 use std::{
-    collections::{HashMap, HashSet},
-    sync::Arc,
-    time::Instant,
+    borrow::Borrow,
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt, fs,
+    hash::{Hash, Hasher},
+    io::Write,
+    num::NonZeroUsize,
+    path::Path,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
 };
+use crate::backing::ColumnData;
+use crate::error::{corrupt, invalid_input, ColumnarError, Result};
+use crate::format::{read_str, write_str, ByteReader};
+use crate::trace::{display, event};
 
-pub struct HybridCache {
-    lfu: lfu_cache::LfuCache<String, Arc<Vec<u8>>>,
-    lru: lru::LruCache<String, Arc<Vec<u8>>>,
-    lfu_keys: HashSet<String>,
-    access_stats: HashMap<String, (u64, Instant)>,
-    size: usize,
+// В чём считается ёмкость кэша
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Entries,
+    // Сумма длин значений
+    Bytes,
 }
 
-impl HybridCache {
-    pub fn new(size: usize) -> Self {
+// Где нашлось значение при чтении; имя уровня уходит только в трассировку
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tier {
+    Lfu,
+    Lru,
+    Miss,
+}
+
+impl Tier {
+    fn name(self) -> &'static str {
+        match self {
+            Tier::Lfu => "lfu",
+            Tier::Lru => "lru",
+            Tier::Miss => "none",
+        }
+    }
+}
+
+// Значение кэша: клонируется дёшево и знает свой размер для ёмкости в байтах.
+// Байты нужны и для снимка кэша, см. snapshot_to
+pub trait CacheValue: Clone {
+    fn as_bytes(&self) -> &[u8];
+
+    fn from_bytes(bytes: Vec<u8>) -> Self;
+
+    fn byte_len(&self) -> usize {
+        self.as_bytes().len()
+    }
+}
+
+impl CacheValue for Arc<ColumnData> {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        Arc::new(ColumnData::from(bytes))
+    }
+
+    fn byte_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl CacheValue for Arc<Vec<u8>> {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        Arc::new(bytes)
+    }
+}
+
+// Счётчики кэша с создания или последнего reset; bytes — текущий объём значений
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub lfu_hits: u64,
+    pub lru_hits: u64,
+    // Промахи get, включая устаревшие по сроку годности записи
+    pub misses: u64,
+    // Значения, попавшие в кэш; слишком большие для уровня не считаются
+    pub insertions: u64,
+    // Вытеснения из-за ёмкости; remove, clear и истёкший срок не считаются
+    pub evictions: u64,
+    pub bytes: u64,
+    // lookup, ответившие KnownAbsent; в misses они не входят
+    pub negative_hits: u64,
+    // Сумма стоимостей значений в кэше, см. insert_weighted
+    pub cost: u64,
+}
+
+// Настройки HybridCache::with_config. Ёмкость в записях; LFU получает
+// floor(capacity * lfu_fraction), LRU — остаток
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheConfig {
+    pub capacity: usize,
+    pub lfu_fraction: f64,
+    pub promote_threshold: u64,
+    // Каждые столько обращений (get и insert) все счётчики делятся пополам, чтобы
+    // давно популярные ключи не занимали LFU вечно. None — счётчики не стареют
+    pub decay_every: Option<u64>,
+}
+
+// Результат lookup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheLookup<V> {
+    Hit(V),
+    Miss,
+    // Ключ недавно отмечен отсутствующим через insert_negative
+    KnownAbsent,
+}
+
+// Почему значение покинуло кэш
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+    // Вытеснено ради ёмкости уровня
+    Capacity,
+    // remove, invalidate_prefix или clear
+    Explicit,
+    // Истёк срок годности
+    Expired,
+    // Ключ вставлен заново
+    Replaced,
+}
+
+// Ключ одного распакованного чанка колонки; column — имя колонки в кэше, например
+// Table::cache_key, чтобы одноимённые колонки разных таблиц не смешивались
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChunkKey {
+    pub column: Arc<str>,
+    pub chunk: usize,
+}
+
+impl fmt::Display for ChunkKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}#{}", self.column, self.chunk)
+    }
+}
+
+// Кэш распакованных чанков, общий для колонок, см. Column::set_chunk_cache.
+// Ёмкость удобнее задавать в байтах: with_byte_capacity
+pub type ChunkCache = ConcurrentHybridCache<ChunkKey, Arc<Vec<u8>>>;
+
+// Снимок кэша для тёплого перезапуска: SNAPSHOT_MAGIC, версия u16, число записей
+// u32, затем записи и crc32 всего предыдущего. Запись — ключ, уровень u8 (1 — LFU),
+// число обращений u64, стоимость u64 и флаг u8, за которым при 1 идут байты значения
+// с длиной u32. Записи LRU идут от давних к недавним, чтобы при восстановлении
+// сохранился их порядок
+const SNAPSHOT_MAGIC: &[u8; 8] = b"COLCACHE";
+const SNAPSHOT_VERSION: u16 = 1;
+
+struct SnapshotEntry {
+    key: String,
+    lfu: bool,
+    count: u64,
+    cost: u64,
+    value: Option<Vec<u8>>,
+}
+
+// Сколько самых давних ключей LRU сравнивается по стоимости при вытеснении
+const LRU_CANDIDATES: usize = 4;
+
+type EvictionListener<K, V> = Box<dyn FnMut(&K, &V, EvictionReason) + Send>;
+type SharedEvictionListener<K, V> = Arc<dyn Fn(&K, &V, EvictionReason) + Send + Sync>;
+
+// Ёмкость делится между LFU и LRU, по умолчанию поровну, и при нечётной ёмкости
+// лишняя единица достаётся LRU. Уровень нулевой ёмкости не используется: при доле
+// 0 кэш работает как LRU, при доле 1 — как LFU. Каждый уровень вытесняет свои
+// записи сам, когда превышает свою долю: LFU — по access_stats, LRU — по порядку lru.
+// Ключ — любой тип с Hash и Eq, поиск идёт по заимствованной форме (&str для
+// String), так что попадание ничего не выделяет. Display нужен для событий трассировки
+pub struct HybridCache<K = String, V = Arc<ColumnData>> {
+    lfu: HashMap<K, V>,
+    lru: lru::LruCache<K, V>,
+    // Число обращений и время последнего только для ключей, которые сейчас в кэше:
+    // промах записи не создаёт, а каждый путь удаления значения удаляет и её
+    access_stats: HashMap<K, (u64, Instant)>,
+    unit: Unit,
+    capacity: usize,
+    lfu_fraction: f64,
+    // Ёмкости уровней в единицах unit, в сумме — capacity
+    lfu_capacity: usize,
+    lru_capacity: usize,
+    lfu_bytes: usize,
+    lru_bytes: usize,
+    // Срок годности по умолчанию для insert; None — записи не устаревают
+    ttl: Option<Duration>,
+    // Момент устаревания записей, вставленных со сроком годности
+    expires_at: HashMap<K, Instant>,
+    clock: Arc<dyn Fn() -> Instant + Send + Sync>,
+    stats: CacheStats,
+    // Ключ с большим числом обращений переходит в LFU
+    promote_threshold: u64,
+    // Число pin для каждого закреплённого ключа
+    pins: HashMap<K, usize>,
+    // Стоимость повторного получения каждого значения в кэше, см. insert_weighted
+    costs: HashMap<K, u64>,
+    // Сумма costs: stats() не должен обходить весь кэш
+    total_cost: u64,
+    // Ключи, известные как отсутствующие, с моментом устаревания (None — бессрочно),
+    // и срок годности новых; None — отрицательный кэш не включён, см. set_negative_cache
+    negative: Option<(lru::LruCache<K, Option<Instant>>, Duration)>,
+    decay_every: Option<u64>,
+    // Обращения с последнего старения счётчиков
+    operations: u64,
+    listener: Option<EvictionListener<K, V>>,
+    // Вытесненные значения, о которых ещё не сообщили слушателю. Копятся, только
+    // если слушатель задан или сообщать будет ConcurrentHybridCache
+    evicted: Vec<(K, V, EvictionReason)>,
+    collect_evicted: bool,
+}
+
+impl<K: Hash + Eq + Clone + fmt::Display, V: CacheValue> HybridCache<K, V> {
+    // Ёмкость в записях независимо от их размера. Каждой половине нужен хотя бы один элемент
+    pub fn new(size: usize) -> Result<Self> {
+        if size < 2 {
+            return Err(ColumnarError::CacheConfig(format!("размер кэша должен быть не меньше 2, получено {}", size)));
+        }
+        Ok(Self::with_unit(Unit::Entries, size))
+    }
+
+    pub fn with_config(config: CacheConfig) -> Result<Self> {
+        check_fraction(config.lfu_fraction)?;
+        let mut cache = Self::with_unit(Unit::Entries, check_capacity(config.capacity)?);
+        cache.promote_threshold = config.promote_threshold;
+        cache.set_decay_every(config.decay_every)?;
+        cache.lfu_fraction = config.lfu_fraction;
+        cache.split();
+        Ok(cache)
+    }
+
+    // Ёмкость в байтах значений: для кэша значений очень разного размера. Значение
+    // больше доли своего уровня не кэшируется совсем, см. insert
+    pub fn with_byte_capacity(bytes: usize) -> Result<Self> {
+        if bytes < 2 {
+            return Err(ColumnarError::CacheConfig(format!("ёмкость кэша должна быть не меньше 2 байт, получено {}", bytes)));
+        }
+        Ok(Self::with_unit(Unit::Bytes, bytes))
+    }
+
+    fn with_unit(unit: Unit, capacity: usize) -> Self {
         Self {
-            lfu: lfu_cache::LfuCache::with_capacity(size / 2),
-            lru: lru::LruCache::new(std::num::NonZeroUsize::new(size / 2).unwrap()),
-            lfu_keys: HashSet::new(),
+            lfu: HashMap::new(),
+            lru: lru::LruCache::unbounded(),
             access_stats: HashMap::new(),
-            size,
+            unit,
+            capacity,
+            lfu_fraction: 0.5,
+            lfu_capacity: capacity / 2,
+            lru_capacity: capacity - capacity / 2,
+            lfu_bytes: 0,
+            lru_bytes: 0,
+            ttl: None,
+            expires_at: HashMap::new(),
+            clock: Arc::new(Instant::now),
+            stats: CacheStats::default(),
+            promote_threshold: 5,
+            pins: HashMap::new(),
+            costs: HashMap::new(),
+            total_cost: 0,
+            negative: None,
+            decay_every: None,
+            operations: 0,
+            listener: None,
+            evicted: Vec::new(),
+            collect_evicted: false,
         }
     }
 
-    pub fn get(&mut self, key: &str) -> Option<Arc<Vec<u8>>> {
-        let key_str = key.to_string();
-        let entry = self.access_stats.entry(key_str.clone()).or_insert((0, Instant::now()));
+    // Новая ёмкость в единицах кэша (записи или байты); лишнее вытесняется сразу
+    pub fn resize(&mut self, capacity: usize) -> Result<()> {
+        self.capacity = check_capacity(capacity)?;
+        self.split();
+        self.rebalance();
+        self.notify();
+        Ok(())
+    }
+
+    // Новая доля LFU от 0 до 1; лишнее в каждом уровне вытесняется сразу
+    pub fn set_lfu_fraction(&mut self, fraction: f64) -> Result<()> {
+        check_fraction(fraction)?;
+        self.lfu_fraction = fraction;
+        self.split();
+        self.rebalance();
+        self.notify();
+        Ok(())
+    }
+
+    fn split(&mut self) {
+        self.lfu_capacity = (self.capacity as f64 * self.lfu_fraction) as usize;
+        self.lru_capacity = self.capacity - self.lfu_capacity;
+    }
+
+    // Срок годности для следующих insert; на уже вставленные записи не влияет
+    pub fn set_ttl(&mut self, ttl: Option<Duration>) {
+        self.ttl = ttl;
+    }
+
+    // Сколько обращений (get и insert) нужно ключу, чтобы перейти из LRU в LFU:
+    // строго больше порога. По умолчанию 5
+    pub fn set_promote_threshold(&mut self, threshold: u64) {
+        self.promote_threshold = threshold;
+    }
+
+    // Период старения счётчиков, см. CacheConfig::decay_every
+    pub fn set_decay_every(&mut self, operations: Option<u64>) -> Result<()> {
+        if operations == Some(0) {
+            return Err(ColumnarError::CacheConfig("период старения счётчиков должен быть больше нуля".to_string()));
+        }
+        self.decay_every = operations;
+        self.operations = 0;
+        Ok(())
+    }
+
+    // Делит все счётчики пополам раз в decay_every обращений. Проход по всем ключам
+    // раз в период, при периоде не меньше ёмкости — O(1) на обращение в среднем
+    fn tick(&mut self) {
+        let Some(period) = self.decay_every else {
+            return;
+        };
+        self.operations += 1;
+        if self.operations >= period {
+            self.operations = 0;
+            for (count, _) in self.access_stats.values_mut() {
+                *count /= 2;
+            }
+        }
+    }
+
+    // Слушатель вызывается для каждого значения, покинувшего кэш, кроме слишком
+    // большого для уровня, которое в кэш и не попало. Он вызывается в конце операции,
+    // когда кэш уже согласован, а обратиться к кэшу из слушателя не даст заимствование
+    pub fn set_eviction_listener(&mut self, listener: impl FnMut(&K, &V, EvictionReason) + Send + 'static) {
+        self.listener = Some(Box::new(listener));
+    }
+
+    fn record(&mut self, key: K, value: V, reason: EvictionReason) {
+        if self.listener.is_some() || self.collect_evicted {
+            self.evicted.push((key, value, reason));
+        }
+    }
+
+    fn notify(&mut self) {
+        if let Some(listener) = self.listener.as_mut() {
+            for (key, value, reason) in self.evicted.drain(..) {
+                listener(&key, &value, reason);
+            }
+        }
+    }
+
+    // Источник времени для сроков годности, в тестах — управляемые часы
+    pub fn set_clock(&mut self, clock: impl Fn() -> Instant + Send + Sync + 'static) {
+        self.clock = Arc::new(clock);
+    }
+
+    // Число значений в обоих уровнях
+    pub fn len(&self) -> usize {
+        self.lfu.len() + self.lru.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Сумма длин значений в обоих уровнях
+    pub fn current_bytes(&self) -> usize {
+        self.lfu_bytes + self.lru_bytes
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats { bytes: self.current_bytes() as u64, cost: self.total_cost, ..self.stats }
+    }
+
+    // Обнуляет счётчики, содержимое кэша не меняется
+    pub fn reset(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    pub fn get<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + fmt::Display + ?Sized,
+    {
+        self.tick();
+        let hot = match self.access_stats.get_mut(key) {
+            Some(entry) => {
+                entry.0 += 1;
+                entry.1 = Instant::now();
+                entry.0 > self.promote_threshold
+            }
+            None => false,
+        };
+
+        // Устаревшая запись — промах, и место она больше не занимает
+        if self.is_expired(key) {
+            self.discard(key, EvictionReason::Expired);
+        }
+        let (value, tier) = if let Some(val) = self.lfu.get(key) {
+            (Some(val.clone()), Tier::Lfu)
+        } else {
+            let value = self.lru.get(key).cloned();
+            let tier = if value.is_some() { Tier::Lru } else { Tier::Miss };
+            (value, tier)
+        };
+        match tier {
+            Tier::Lfu => self.stats.lfu_hits += 1,
+            Tier::Lru => self.stats.lru_hits += 1,
+            Tier::Miss => self.stats.misses += 1,
+        }
+        // Часто читаемый ключ защищён от потока однократных вставок в LRU
+        if tier == Tier::Lru && hot && self.lfu_capacity > 0 {
+            self.promote(key);
+        }
+        event!("columnar::cache", "get", key = display(&key), hit = value.is_some(), tier = tier.name());
+        self.notify();
+        value
+    }
+
+    // Значение без учёта обращения: счётчики, порядок LRU и статистика не меняются,
+    // поэтому проверки для мониторинга не влияют на вытеснение. Устаревшая запись не
+    // возвращается, но и не удаляется
+    pub fn peek<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.is_expired(key) {
+            return None;
+        }
+        self.lfu.get(key).or_else(|| self.lru.peek(key)).cloned()
+    }
+
+    // Есть ли у ключа живое значение; как и peek, обращением не считается
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.contains(key) && !self.is_expired(key)
+    }
+
+    // Значение, которое больше ёмкости своего уровня, не кэшируется: оно вытеснило
+    // бы весь уровень и всё равно не поместилось бы. Прежнее значение ключа при этом
+    // тоже удаляется, чтобы get не вернул устаревшие данные
+    pub fn insert(&mut self, key: K, value: V) {
+        self.insert_with_ttl(key, value, self.ttl);
+    }
+
+    // Значение ключа или, если его нет, результат load, который тут же вставляется.
+    // Ошибка load возвращается как есть, и в кэше для ключа ничего не остаётся
+    pub fn get_or_insert_with<E>(&mut self, key: K, load: impl FnOnce() -> std::result::Result<V, E>) -> std::result::Result<V, E> {
+        if let Some(value) = self.get(&key) {
+            return Ok(value);
+        }
+        let value = load()?;
+        self.insert(key, value.clone());
+        Ok(value)
+    }
+
+    // insert со своим сроком годности вместо заданного set_ttl; None — без срока
+    pub fn insert_with_ttl(&mut self, key: K, value: V, ttl: Option<Duration>) {
+        let cost = value.byte_len() as u64;
+        self.insert_entry(key, value, ttl, cost);
+    }
+
+    // insert с явной стоимостью повторного получения значения, например для колонки
+    // с высоким уровнем zstd или с удалённого источника. По умолчанию стоимость равна
+    // длине значения. Оба уровня при вытеснении сравнивают стоимость на байт: LFU
+    // вытесняет ключ с наименьшим произведением числа обращений на неё, LRU — самый
+    // дешёвый из LRU_CANDIDATES самых давних ключей. При стоимостях по умолчанию
+    // вытеснение такое же, как без них
+    pub fn insert_weighted(&mut self, key: K, value: V, cost: u64) {
+        self.insert_entry(key, value, self.ttl, cost);
+    }
+
+    fn insert_entry(&mut self, key: K, value: V, ttl: Option<Duration>, cost: u64) {
+        self.tick();
+        self.forget_negative(&key);
+        let entry = self.access_stats.entry(key.clone()).or_insert((0, Instant::now()));
         entry.0 += 1;
         entry.1 = Instant::now();
 
-        if let Some(val) = self.lfu.get(&key_str) {
-            Some(val.clone())
+        let hot = (entry.0 > self.promote_threshold && self.lfu_capacity > 0) || self.lru_capacity == 0;
+        let tier = if hot { Tier::Lfu } else { Tier::Lru };
+        event!("columnar::cache", "insert", key = display(&key), tier = tier.name());
+        // Ключ, переходящий в LFU, не должен остаться копией в LRU
+        if let Some((old_key, replaced)) = self.take(&key) {
+            self.record(old_key, replaced, EvictionReason::Replaced);
+        }
+        let capacity = if hot { self.lfu_capacity } else { self.lru_capacity };
+        if self.weight(&value) > capacity {
+            self.discard(&key, EvictionReason::Replaced);
+            self.notify();
+            return;
+        }
+        self.stats.insertions += 1;
+        if let Some(deadline) = ttl.and_then(|ttl| self.deadline(ttl)) {
+            self.expires_at.insert(key.clone(), deadline);
+        }
+        self.set_cost(key.clone(), cost);
+        if hot {
+            self.lfu_bytes += value.byte_len();
+            self.lfu.insert(key, value);
         } else {
-            self.lru.get(&key_str).cloned()
+            self.lru_bytes += value.byte_len();
+            self.lru.put(key, value);
         }
-    }
 
-    pub fn insert(&mut self, key: String, value: Arc<Vec<u8>>) {
-    let entry = self.access_stats.entry(key.clone()).or_insert((0, Instant::now()));
-    entry.0 += 1;
-    entry.1 = Instant::now();
+        self.rebalance();
+        self.notify();
+    }
 
-    if entry.0 > 5 {
-        self.lfu.insert(key.clone(), value);
-        self.lfu_keys.insert(key);
-    } else {
-        self.lru.put(key, value);
+    fn promote<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Some((key, value)) = self.lru.pop_entry(key) {
+            self.lru_bytes -= value.byte_len();
+            self.lfu_bytes += value.byte_len();
+            self.lfu.insert(key, value);
+            self.rebalance();
+        }
     }
 
-    self.rebalance();
-}
+    // Включает отрицательный кэш: до capacity ключей, отмеченных insert_negative как
+    // отсутствующие, каждый на срок ttl. Он не занимает ёмкость значений и вытесняет
+    // свои записи сам, по давности. Прежние отрицательные записи отбрасываются
+    pub fn set_negative_cache(&mut self, capacity: usize, ttl: Duration) -> Result<()> {
+        let Some(capacity) = NonZeroUsize::new(capacity) else {
+            return Err(ColumnarError::CacheConfig("ёмкость отрицательного кэша должна быть больше нуля".to_string()));
+        };
+        self.negative = Some((lru::LruCache::new(capacity), ttl));
+        Ok(())
+    }
 
+    // Запоминает, что у ключа нет значения, например колонки нет в таблице, чтобы
+    // lookup отвечал KnownAbsent без дорогой попытки загрузки. Прежнее значение ключа
+    // удаляется. Любая следующая вставка ключа снимает отметку. Без set_negative_cache
+    // ничего не делает
+    pub fn insert_negative(&mut self, key: K) {
+        let Some((_, ttl)) = self.negative else {
+            return;
+        };
+        self.discard::<K>(&key, EvictionReason::Explicit);
+        let deadline = self.deadline(ttl);
+        if let Some((negative, _)) = self.negative.as_mut() {
+            negative.put(key, deadline);
+        }
+        self.notify();
+    }
 
-    fn rebalance(&mut self) {
-        while self.lfu.len() + self.lru.len() > self.size {
-            if self.lfu.len() > self.size / 2 {
-                if let Some(key_to_remove) = self.least_used_key_in_lfu() {
-                    self.lfu.remove(&key_to_remove);
-                    self.lfu_keys.remove(&key_to_remove);
-                    self.access_stats.remove(&key_to_remove);
-                } else {
-                    break;
+    // get, различающий промах и известное отсутствие. Отметка отсутствия обращением
+    // к ключу не считается; устаревшая отметка удаляется, и lookup становится обычным get
+    pub fn lookup<Q>(&mut self, key: &Q) -> CacheLookup<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + fmt::Display + ?Sized,
+    {
+        let now = (self.clock)();
+        if let Some((negative, _)) = self.negative.as_mut() {
+            match negative.get(key) {
+                Some(deadline) if deadline.is_none_or(|deadline| deadline > now) => {
+                    self.stats.negative_hits += 1;
+                    event!("columnar::cache", "get", key = display(&key), hit = false, tier = "negative");
+                    return CacheLookup::KnownAbsent;
                 }
-            } else {
-                if let Some((key, _)) = self.lru.pop_lru() {
-                    self.access_stats.remove(&key);
+                Some(_) => {
+                    negative.pop(key);
                 }
+                None => {}
+            }
+        }
+        match self.get(key) {
+            Some(value) => CacheLookup::Hit(value),
+            None => CacheLookup::Miss,
+        }
+    }
+
+    fn forget_negative<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Some((negative, _)) = self.negative.as_mut() {
+            negative.pop(key);
+        }
+    }
+
+    // Удаляет все устаревшие записи и возвращает их число
+    pub fn purge_expired(&mut self) -> usize {
+        let now = (self.clock)();
+        let expired: Vec<K> =
+            self.expires_at.iter().filter(|(_, deadline)| **deadline <= now).map(|(key, _)| key.clone()).collect();
+        for key in &expired {
+            self.discard(key, EvictionReason::Expired);
+        }
+        self.notify();
+        expired.len()
+    }
+
+    // Момент устаревания записи с таким сроком; None, если он не представим в Instant
+    // (например Duration::MAX): такая запись не устаревает
+    fn deadline(&self, ttl: Duration) -> Option<Instant> {
+        (self.clock)().checked_add(ttl)
+    }
+
+    fn is_expired<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.expires_at.get(key).is_some_and(|deadline| *deadline <= (self.clock)())
+    }
+
+    // Удаляет ключ из кэша вместе со статистикой обращений, чтобы следующая вставка
+    // считалась первой и не попала сразу в LFU. Устаревшая запись удаляется, но не
+    // возвращается, как и в get
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.forget_negative(key);
+        let expired = self.is_expired(key);
+        let reason = if expired { EvictionReason::Expired } else { EvictionReason::Explicit };
+        let value = self.discard(key, reason);
+        self.notify();
+        value.filter(|_| !expired)
+    }
+
+    // Закрепляет ключ: пока он закреплён, rebalance его не вытесняет, но его байты
+    // по-прежнему занимают ёмкость уровня, и уровень может временно её превысить.
+    // remove, clear, invalidate_prefix и истёкший срок удаляют и закреплённый ключ.
+    // Закреплений можно сделать несколько, каждому нужен свой unpin. false — ключа
+    // в кэше нет
+    pub fn pin<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Some(count) = self.pins.get_mut(key) {
+            *count += 1;
+            return true;
+        }
+        if !self.contains(key) {
+            return false;
+        }
+        // Ключ в кэше, значит, и в access_stats
+        let Some((owned, _)) = self.access_stats.get_key_value(key) else {
+            return false;
+        };
+        self.pins.insert(owned.clone(), 1);
+        true
+    }
+
+    // Снимает одно закрепление. Если уровень переполнен, ключ вытеснится при
+    // следующем rebalance, то есть при следующей вставке
+    pub fn unpin<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Some(count) = self.pins.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                self.pins.remove(key);
+            }
+        }
+    }
+
+    fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lfu.contains_key(key) || self.lru.contains(key)
+    }
+
+    // Удаляет все ключи, для которых matches вернул true, и возвращает их число
+    // Отрицательные записи таких ключей тоже удаляются, но в число не входят
+    pub fn invalidate_where(&mut self, mut matches: impl FnMut(&K) -> bool) -> usize {
+        if let Some((negative, _)) = self.negative.as_mut() {
+            let absent: Vec<K> = negative.iter().map(|(key, _)| key).filter(|key| matches(key)).cloned().collect();
+            for key in &absent {
+                negative.pop(key);
             }
         }
+        let keys: Vec<K> = self.lfu.keys().chain(self.lru.iter().map(|(key, _)| key)).filter(|key| matches(key)).cloned().collect();
+        for key in &keys {
+            self.discard(key, EvictionReason::Explicit);
+        }
+        self.notify();
+        keys.len()
+    }
+
+    // Пустой кэш с прежними настройками
+    pub fn clear(&mut self) {
+        if self.listener.is_some() || self.collect_evicted {
+            let lru = self.lru.iter().map(|(key, value)| (key.clone(), value.clone())).collect::<Vec<_>>();
+            for (key, value) in self.lfu.drain().chain(lru) {
+                self.evicted.push((key, value, EvictionReason::Explicit));
+            }
+        }
+        self.lfu.clear();
+        self.lru.clear();
+        self.access_stats.clear();
+        self.expires_at.clear();
+        self.pins.clear();
+        self.costs.clear();
+        self.total_cost = 0;
+        if let Some((negative, _)) = self.negative.as_mut() {
+            negative.clear();
+        }
+        self.lfu_bytes = 0;
+        self.lru_bytes = 0;
+        self.notify();
+    }
+
+    fn set_cost(&mut self, key: K, cost: u64) {
+        self.total_cost += cost;
+        if let Some(old) = self.costs.insert(key, cost) {
+            self.total_cost -= old;
+        }
+    }
+
+    fn forget_cost<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Some(cost) = self.costs.remove(key) {
+            self.total_cost -= cost;
+        }
+    }
+
+    // Удаляет ключ со всем, что о нём известно
+    fn discard<Q>(&mut self, key: &Q, reason: EvictionReason) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.access_stats.remove(key);
+        self.pins.remove(key);
+        let (key, value) = self.take(key)?;
+        self.record(key, value.clone(), reason);
+        Some(value)
+    }
+
+    // Убирает значение ключа из обоих уровней; статистика обращений и закрепления остаются
+    fn take<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.expires_at.remove(key);
+        self.forget_cost(key);
+        if let Some((key, value)) = self.lfu.remove_entry(key) {
+            self.lfu_bytes -= value.byte_len();
+            return Some((key, value));
+        }
+        let (key, value) = self.lru.pop_entry(key)?;
+        self.lru_bytes -= value.byte_len();
+        Some((key, value))
+    }
+
+    fn weight(&self, value: &V) -> usize {
+        match self.unit {
+            Unit::Entries => 1,
+            Unit::Bytes => value.byte_len(),
+        }
+    }
+
+    fn lfu_used(&self) -> usize {
+        match self.unit {
+            Unit::Entries => self.lfu.len(),
+            Unit::Bytes => self.lfu_bytes,
+        }
+    }
+
+    fn lru_used(&self) -> usize {
+        match self.unit {
+            Unit::Entries => self.lru.len(),
+            Unit::Bytes => self.lru_bytes,
+        }
+    }
+
+    fn rebalance(&mut self) {
+        while self.lfu_used() > self.lfu_capacity {
+            let Some(key_to_remove) = self.least_used_key_in_lfu() else {
+                break;
+            };
+            let Some((key, value)) = self.lfu.remove_entry(&key_to_remove) else {
+                break;
+            };
+            self.lfu_bytes -= value.byte_len();
+            self.stats.evictions += 1;
+            self.expires_at.remove(&key);
+            self.forget_cost(&key);
+            self.access_stats.remove(&key);
+            self.record(key, value, EvictionReason::Capacity);
+        }
+        while self.lru_used() > self.lru_capacity {
+            // Итератор lru идёт от недавних к давним; при равной стоимости min_by
+            // выбирает первый, то есть самый давний
+            let Some(key) = self
+                .lru
+                .iter()
+                .rev()
+                .filter(|(key, _)| !self.pins.contains_key(*key))
+                .take(LRU_CANDIDATES)
+                .min_by(|(a, x), (b, y)| self.cost_per_byte(a, x).total_cmp(&self.cost_per_byte(b, y)))
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            let Some(value) = self.lru.pop(&key) else {
+                break;
+            };
+            self.lru_bytes -= value.byte_len();
+            self.stats.evictions += 1;
+            self.expires_at.remove(&key);
+            self.forget_cost(&key);
+            self.access_stats.remove(&key);
+            self.record(key, value, EvictionReason::Capacity);
+        }
     }
 
-    fn least_used_key_in_lfu(&self) -> Option<String> {
-        self.lfu_keys
+    fn least_used_key_in_lfu(&self) -> Option<K> {
+        let score = |key: &K, value: &V| {
+            let (freq, time) = self.access_stats.get(key).copied().unwrap_or((0, Instant::now()));
+            (freq as f64 * self.cost_per_byte(key, value), time)
+        };
+        self.lfu
             .iter()
-            .min_by_key(|key| {
-                self.access_stats
-                    .get(*key)
-                    .map(|(freq, time)| (*freq, *time))
-                    .unwrap_or((0, Instant::now()))
+            .filter(|(key, _)| !self.pins.contains_key(*key))
+            .map(|(key, value)| (key, score(key, value)))
+            .min_by(|(_, a), (_, b)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+            .map(|(key, _)| key.clone())
+    }
+
+    // Стоимость на байт значения; у стоимости по умолчанию она равна 1
+    fn cost_per_byte(&self, key: &K, value: &V) -> f64 {
+        let len = value.byte_len() as u64;
+        self.costs.get(key).map_or(1.0, |cost| *cost as f64 / len.max(1) as f64)
+    }
+}
+
+impl<K: Hash + Eq + Clone + fmt::Display + Borrow<str>, V: CacheValue> HybridCache<K, V> {
+    // Удаляет все ключи с префиксом, например все чанки одной колонки, и возвращает
+    // число удалённых значений
+    pub fn invalidate_prefix(&mut self, prefix: &str) -> usize {
+        self.invalidate_where(|key| key.borrow().starts_with(prefix))
+    }
+}
+
+impl<K: Hash + Eq + Clone + fmt::Display + Borrow<str> + From<String>, V: CacheValue> HybridCache<K, V> {
+    // Записывает ключи, их уровни, счётчики обращений и стоимости в файл path, а при
+    // with_values и байты значений. Устаревшие записи не сохраняются, сроки годности
+    // тоже: моменты Instant после перезапуска не имеют смысла. Файл заменяется атомарно.
+    // Значение от 4 ГиБ в снимок не помещается: ошибка, и файл не меняется
+    pub fn snapshot_to(&self, path: &Path, with_values: bool) -> Result<()> {
+        write_snapshot(path, &self.snapshot_entries(with_values))
+    }
+
+    // Заполняет кэш из снимка snapshot_to: значение берётся из снимка, а если его там
+    // нет — у loader; ключ, для которого loader вернул None, пропускается. Счётчики
+    // обращений и уровни восстанавливаются, так что горячий ключ сразу попадает в LFU.
+    // Снимок проверяется целиком до изменения кэша: при повреждённом снимке или
+    // другой версии формата возвращается ошибка, а кэш остаётся как был, то есть
+    // холодным после перезапуска. Возвращает число восстановленных значений
+    pub fn restore_from(&mut self, path: &Path, loader: impl Fn(&str) -> Option<V>) -> Result<usize> {
+        let entries = read_snapshot(path)?;
+        Ok(self.restore_entries(entries, &loader))
+    }
+
+    fn snapshot_entries(&self, with_values: bool) -> Vec<SnapshotEntry> {
+        let lfu = self.lfu.iter().map(|entry| (entry, true));
+        let lru = self.lru.iter().rev().map(|entry| (entry, false));
+        lfu.chain(lru)
+            .filter(|((key, _), _)| !self.is_expired::<K>(key))
+            .map(|((key, value), lfu)| SnapshotEntry {
+                key: key.borrow().to_string(),
+                lfu,
+                count: self.access_stats.get::<K>(key).map_or(0, |(count, _)| *count),
+                cost: self.costs.get::<K>(key).copied().unwrap_or(value.byte_len() as u64),
+                value: with_values.then(|| value.as_bytes().to_vec()),
             })
-            .cloned()
+            .collect()
+    }
+
+    fn restore_entries(&mut self, entries: Vec<SnapshotEntry>, loader: &impl Fn(&str) -> Option<V>) -> usize {
+        let mut restored = 0;
+        for entry in entries {
+            let Some(value) = entry.value.map(V::from_bytes).or_else(|| loader(&entry.key)) else {
+                continue;
+            };
+            let lfu = (entry.lfu && self.lfu_capacity > 0) || self.lru_capacity == 0;
+            if self.weight(&value) > if lfu { self.lfu_capacity } else { self.lru_capacity } {
+                continue;
+            }
+            let key = K::from(entry.key);
+            self.forget_negative::<K>(&key);
+            if let Some((old_key, replaced)) = self.take::<K>(&key) {
+                self.record(old_key, replaced, EvictionReason::Replaced);
+            }
+            self.access_stats.insert(key.clone(), (entry.count, Instant::now()));
+            self.set_cost(key.clone(), entry.cost);
+            if let Some(deadline) = self.ttl.and_then(|ttl| self.deadline(ttl)) {
+                self.expires_at.insert(key.clone(), deadline);
+            }
+            self.stats.insertions += 1;
+            if lfu {
+                self.lfu_bytes += value.byte_len();
+                self.lfu.insert(key, value);
+            } else {
+                self.lru_bytes += value.byte_len();
+                self.lru.put(key, value);
+            }
+            restored += 1;
+        }
+        self.rebalance();
+        self.notify();
+        restored
+    }
+}
+
+fn write_snapshot(path: &Path, entries: &[SnapshotEntry]) -> Result<()> {
+    let mut out = Vec::new();
+    out.extend_from_slice(SNAPSHOT_MAGIC);
+    out.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+    out.extend_from_slice(&snapshot_len(entries.len(), "записей")?.to_le_bytes());
+    for entry in entries {
+        snapshot_len(entry.key.len(), "байт ключа")?;
+        write_str(&mut out, &entry.key);
+        out.push(entry.lfu as u8);
+        out.extend_from_slice(&entry.count.to_le_bytes());
+        out.extend_from_slice(&entry.cost.to_le_bytes());
+        match &entry.value {
+            Some(value) => {
+                out.push(1);
+                out.extend_from_slice(&snapshot_len(value.len(), "байт значения")?.to_le_bytes());
+                out.extend_from_slice(value);
+            }
+            None => out.push(0),
+        }
+    }
+    let checksum = crc32fast::hash(&out);
+    out.extend_from_slice(&checksum.to_le_bytes());
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut file = tempfile::Builder::new().prefix(".cache-").tempfile_in(dir)?;
+    file.write_all(&out)?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+// Длины в снимке хранятся в u32; снимок, который нельзя прочитать обратно, не пишется
+fn snapshot_len(len: usize, what: &str) -> Result<u32> {
+    u32::try_from(len).map_err(|_| invalid_input(format!("{} {} не помещается в снимок кэша", len, what)))
+}
+
+fn read_snapshot(path: &Path) -> Result<Vec<SnapshotEntry>> {
+    let bytes = fs::read(path)?;
+    let header = SNAPSHOT_MAGIC.len() + 2 + 4;
+    if bytes.len() < header + 4 || &bytes[..SNAPSHOT_MAGIC.len()] != SNAPSHOT_MAGIC {
+        return Err(corrupt(format!("{} не является снимком кэша", path.display())));
+    }
+    let (body, checksum) = bytes.split_at(bytes.len() - 4);
+    if crc32fast::hash(body) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+        return Err(corrupt("контрольная сумма снимка кэша не совпадает"));
+    }
+    let mut r = ByteReader::new(&body[SNAPSHOT_MAGIC.len()..]);
+    let version = u16::from_le_bytes(r.bytes(2)?.try_into().unwrap());
+    if version != SNAPSHOT_VERSION {
+        return Err(corrupt(format!("неподдерживаемая версия снимка кэша {}, ожидалась {}", version, SNAPSHOT_VERSION)));
+    }
+    let count = r.u32()?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let key = read_str(&mut r)?;
+        let lfu = r.bytes(1)?[0] != 0;
+        let count = r.u64()?;
+        let cost = r.u64()?;
+        let value = match r.bytes(1)?[0] {
+            0 => None,
+            _ => {
+                let len = r.u32()? as usize;
+                Some(r.bytes(len)?.to_vec())
+            }
+        };
+        entries.push(SnapshotEntry { key, lfu, count, cost, value });
+    }
+    if r.remaining() != 0 {
+        return Err(corrupt("в снимке кэша лишние байты после записей"));
+    }
+    Ok(entries)
+}
+
+// HybridCache для общего использования из нескольких потоков: ключи распределяются
+// по хешу между независимыми сегментами, у каждого свой замок, так что обращения к
+// разным сегментам не ждут друг друга. Ёмкость делится между сегментами поровну,
+// поэтому горячие ключи одного сегмента вытесняются раньше, чем в одном общем кэше
+pub struct ConcurrentHybridCache<K = String, V = Arc<ColumnData>> {
+    shards: Vec<Shard<K, V>>,
+    listener: Option<SharedEvictionListener<K, V>>,
+}
+
+struct Shard<K, V> {
+    cache: Mutex<HybridCache<K, V>>,
+    // Ключи, которые сейчас вычисляет get_or_insert_with
+    loading: Mutex<HashMap<K, Arc<Flight<V>>>>,
+}
+
+// Результат вычисления, которого ждут остальные потоки: None — ещё идёт,
+// Some(None) — вычисление не удалось
+struct Flight<V> {
+    result: Mutex<Option<Option<V>>>,
+    done: Condvar,
+}
+
+impl<V: Clone> Flight<V> {
+    fn new() -> Self {
+        Self { result: Mutex::new(None), done: Condvar::new() }
+    }
+
+    fn wait(&self) -> Option<V> {
+        let mut result = lock(&self.result);
+        while result.is_none() {
+            result = self.done.wait(result).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        result.clone().flatten()
     }
 }
 
+// Завершает вычисление при удалении, в том числе при ошибке или панике load,
+// чтобы ждущие потоки не зависли
+struct Leader<'a, K: Hash + Eq, V> {
+    shard: &'a Shard<K, V>,
+    key: &'a K,
+    flight: Arc<Flight<V>>,
+    value: Option<V>,
+}
+
+impl<K: Hash + Eq, V> Drop for Leader<'_, K, V> {
+    fn drop(&mut self) {
+        lock(&self.shard.loading).remove(self.key);
+        *lock(&self.flight.result) = Some(self.value.take());
+        self.flight.done.notify_all();
+    }
+}
+
+impl<K: Hash + Eq + Clone + fmt::Display, V: CacheValue> ConcurrentHybridCache<K, V> {
+    // Ёмкость в записях; каждому сегменту нужно хотя бы 2, как в HybridCache::new
+    pub fn new(capacity: usize, shards: usize) -> Result<Self> {
+        Self::build(capacity, shards, HybridCache::new)
+    }
+
+    pub fn with_config(config: CacheConfig, shards: usize) -> Result<Self> {
+        Self::build(config.capacity, shards, |capacity| HybridCache::with_config(CacheConfig { capacity, ..config }))
+    }
+
+    pub fn with_byte_capacity(bytes: usize, shards: usize) -> Result<Self> {
+        Self::build(bytes, shards, HybridCache::with_byte_capacity)
+    }
+
+    // Остаток от деления ёмкости достаётся первым сегментам
+    fn build(capacity: usize, shards: usize, make: impl Fn(usize) -> Result<HybridCache<K, V>>) -> Result<Self> {
+        if shards == 0 {
+            return Err(ColumnarError::CacheConfig("нужен хотя бы один сегмент кэша".to_string()));
+        }
+        let shards = (0..shards)
+            .map(|i| {
+                let cache = make(capacity / shards + usize::from(i < capacity % shards))?;
+                Ok(Shard { cache: Mutex::new(cache), loading: Mutex::new(HashMap::new()) })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { shards, listener: None })
+    }
+
+    // Слушатель вытеснений, как в HybridCache, но вызывается после освобождения
+    // замка сегмента, поэтому из него можно обращаться к этому же кэшу. Вызовы
+    // идут из разных потоков, отсюда Fn + Sync вместо FnMut
+    pub fn set_eviction_listener(&mut self, listener: impl Fn(&K, &V, EvictionReason) + Send + Sync + 'static) {
+        self.listener = Some(Arc::new(listener));
+        for shard in &mut self.shards {
+            shard.cache.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner()).collect_evicted = true;
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + fmt::Display + ?Sized,
+    {
+        self.with_shard(self.segment(key), |cache| cache.get(key))
+    }
+
+    pub fn peek<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        lock(&self.segment(key).cache).peek(key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        lock(&self.segment(key).cache).contains_key(key)
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        self.with_shard(self.segment(&key), |cache| cache.insert(key, value))
+    }
+
+    pub fn insert_weighted(&self, key: K, value: V, cost: u64) {
+        self.with_shard(self.segment(&key), |cache| cache.insert_weighted(key, value, cost))
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.with_shard(self.segment(key), |cache| cache.remove(key))
+    }
+
+    // Ёмкость делится между сегментами, как ёмкость значений; каждому нужна хотя бы одна запись
+    pub fn set_negative_cache(&mut self, capacity: usize, ttl: Duration) -> Result<()> {
+        let shards = self.shards.len();
+        for (i, shard) in self.shards.iter_mut().enumerate() {
+            let cache = shard.cache.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner());
+            cache.set_negative_cache(capacity / shards + usize::from(i < capacity % shards), ttl)?;
+        }
+        Ok(())
+    }
+
+    pub fn insert_negative(&self, key: K) {
+        self.with_shard(self.segment(&key), |cache| cache.insert_negative(key))
+    }
+
+    pub fn lookup<Q>(&self, key: &Q) -> CacheLookup<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + fmt::Display + ?Sized,
+    {
+        self.with_shard(self.segment(key), |cache| cache.lookup(key))
+    }
+
+    pub fn pin<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        lock(&self.segment(key).cache).pin(key)
+    }
+
+    pub fn unpin<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        lock(&self.segment(key).cache).unpin(key)
+    }
+
+    // Как HybridCache::get_or_insert_with, но load для ключа выполняет только один
+    // поток, а остальные ждут его результата без замка сегмента. Если load вернул
+    // ошибку, ждущие потоки не получают её, а пробуют снова, и один из них вычисляет
+    // значение сам
+    pub fn get_or_insert_with<E>(&self, key: K, load: impl FnOnce() -> std::result::Result<V, E>) -> std::result::Result<V, E> {
+        let shard = self.segment(&key);
+        let flight = loop {
+            let mut loading = lock(&shard.loading);
+            if let Some(flight) = loading.get(&key).cloned() {
+                drop(loading);
+                match flight.wait() {
+                    Some(value) => return Ok(value),
+                    None => continue,
+                }
+            }
+            // Проверка под замком loading: значение, вставленное только что
+            // завершившимся вычислением, уже видно
+            let (value, evicted) = {
+                let mut cache = lock(&shard.cache);
+                (cache.get(&key), std::mem::take(&mut cache.evicted))
+            };
+            let flight = value.is_none().then(|| {
+                let flight = Arc::new(Flight::new());
+                loading.insert(key.clone(), flight.clone());
+                flight
+            });
+            drop(loading);
+            self.notify(evicted);
+            match (value, flight) {
+                (Some(value), _) => return Ok(value),
+                (None, Some(flight)) => break flight,
+                (None, None) => continue,
+            }
+        };
+        let mut leader = Leader { shard, key: &key, flight, value: None };
+        let value = load()?;
+        self.with_shard(shard, |cache| cache.insert(key.clone(), value.clone()));
+        leader.value = Some(value.clone());
+        Ok(value)
+    }
+
+    // Сегменты проходятся по очереди, так что удаление не атомарно для всего кэша
+    pub fn invalidate_where(&self, matches: impl Fn(&K) -> bool) -> usize {
+        self.shards.iter().map(|shard| self.with_shard(shard, |cache| cache.invalidate_where(&matches))).sum()
+    }
+
+    pub fn clear(&self) {
+        self.shards.iter().for_each(|shard| self.with_shard(shard, |cache| cache.clear()));
+    }
+
+    pub fn len(&self) -> usize {
+        self.each_shard().map(|shard| shard.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn current_bytes(&self) -> usize {
+        self.each_shard().map(|shard| shard.current_bytes()).sum()
+    }
+
+    // Обнуляет счётчики всех сегментов
+    pub fn reset(&self) {
+        self.each_shard().for_each(|mut shard| shard.reset());
+    }
+
+    // Сумма счётчиков всех сегментов
+    pub fn stats(&self) -> CacheStats {
+        self.each_shard().fold(CacheStats::default(), |total, shard| {
+            let stats = shard.stats();
+            CacheStats {
+                lfu_hits: total.lfu_hits + stats.lfu_hits,
+                lru_hits: total.lru_hits + stats.lru_hits,
+                misses: total.misses + stats.misses,
+                insertions: total.insertions + stats.insertions,
+                evictions: total.evictions + stats.evictions,
+                bytes: total.bytes + stats.bytes,
+                negative_hits: total.negative_hits + stats.negative_hits,
+                cost: total.cost + stats.cost,
+            }
+        })
+    }
+
+    // Операция над сегментом; о вытеснениях слушатель узнаёт уже без замка
+    fn with_shard<R>(&self, shard: &Shard<K, V>, op: impl FnOnce(&mut HybridCache<K, V>) -> R) -> R {
+        let (result, evicted) = {
+            let mut cache = lock(&shard.cache);
+            let result = op(&mut cache);
+            (result, std::mem::take(&mut cache.evicted))
+        };
+        self.notify(evicted);
+        result
+    }
+
+    fn notify(&self, evicted: Vec<(K, V, EvictionReason)>) {
+        if let Some(listener) = &self.listener {
+            for (key, value, reason) in evicted {
+                listener(&key, &value, reason);
+            }
+        }
+    }
+
+    // Borrow гарантирует одинаковый хеш у ключа и его заимствованной формы
+    fn segment<Q: Hash + ?Sized>(&self, key: &Q) -> &Shard<K, V> {
+        &self.shards[self.segment_index(key)]
+    }
+
+    fn segment_index<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish() as usize % self.shards.len()
+    }
+
+    fn each_shard(&self) -> impl Iterator<Item = MutexGuard<'_, HybridCache<K, V>>> {
+        self.shards.iter().map(|shard| lock(&shard.cache))
+    }
+}
+
+impl<K: Hash + Eq + Clone + fmt::Display + Borrow<str>, V: CacheValue> ConcurrentHybridCache<K, V> {
+    pub fn invalidate_prefix(&self, prefix: &str) -> usize {
+        self.invalidate_where(|key| key.borrow().starts_with(prefix))
+    }
+}
+
+impl<K: Hash + Eq + Clone + fmt::Display + Borrow<str> + From<String>, V: CacheValue> ConcurrentHybridCache<K, V> {
+    // Один снимок всех сегментов, формат как у HybridCache::snapshot_to. Сегменты
+    // читаются по очереди, так что снимок не атомарен для всего кэша
+    pub fn snapshot_to(&self, path: &Path, with_values: bool) -> Result<()> {
+        let entries: Vec<SnapshotEntry> = self.each_shard().flat_map(|shard| shard.snapshot_entries(with_values)).collect();
+        write_snapshot(path, &entries)
+    }
+
+    // Как HybridCache::restore_from; записи распределяются по сегментам заново, так
+    // что число сегментов может отличаться от сохранённого кэша
+    pub fn restore_from(&self, path: &Path, loader: impl Fn(&str) -> Option<V>) -> Result<usize> {
+        let mut per_shard: Vec<Vec<SnapshotEntry>> = self.shards.iter().map(|_| Vec::new()).collect();
+        for entry in read_snapshot(path)? {
+            per_shard[self.segment_index(entry.key.as_str())].push(entry);
+        }
+        Ok(self.shards.iter().zip(per_shard).map(|(shard, entries)| self.with_shard(shard, |cache| cache.restore_entries(entries, &loader))).sum())
+    }
+}
+
+// Кэш только ускоряет чтение, поэтому после паники в другом потоке сегментом можно
+// продолжать пользоваться: операции над ним не оставляют его в недопустимом состоянии
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn check_capacity(capacity: usize) -> Result<usize> {
+    if capacity == 0 {
+        return Err(ColumnarError::CacheConfig("ёмкость кэша должна быть больше нуля".to_string()));
+    }
+    Ok(capacity)
+}
+
+fn check_fraction(fraction: f64) -> Result<()> {
+    if !(0.0..=1.0).contains(&fraction) {
+        return Err(ColumnarError::CacheConfig(format!("доля LFU должна быть от 0 до 1, получено {}", fraction)));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::Arc;
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+        sync::Arc,
+    };
+
+    // Считает выделения памяти текущего потока, чтобы проверить, что попадание в
+    // кэш ничего не выделяет
+    struct CountingAlloc;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+    #[cfg(not(feature = "log"))]
+    fn allocations_in(op: impl FnOnce()) -> usize {
+        let before = ALLOCATIONS.with(Cell::get);
+        op();
+        ALLOCATIONS.with(Cell::get) - before
+    }
 
     #[test]
     fn test_hybrid_cache_behavior() {
-        let mut cache = HybridCache::new(10);
-        let test_data = Arc::new(vec![1u8, 2, 3, 4]);
+        let mut cache = HybridCache::new(10).unwrap();
+        let test_data = Arc::new(ColumnData::from(vec![1u8, 2, 3, 4]));
         
         // Добавляем часто используемый элемент (6 раз)
         for _ in 0..6 {
@@ -119,4 +1408,704 @@ mod tests {
         // Редкий элемент мог вытесниться
         println!("Cache state: {:?}", cache.access_stats);
     }
+
+    #[test]
+    fn test_cache_tiers_sum_to_size() {
+        for size in [0, 1] {
+            assert!(matches!(HybridCache::<String>::new(size), Err(ColumnarError::CacheConfig(_))), "{}", size);
+        }
+        let value = Arc::new(ColumnData::from(vec![0u8; 4]));
+        for (size, lfu, lru) in [(2, 1, 1), (3, 1, 2), (7, 3, 4)] {
+            let mut cache = HybridCache::new(size).unwrap();
+            assert_eq!((cache.lfu_capacity, cache.lru_capacity), (lfu, lru));
+            cache.set_promote_threshold(0);
+            for i in 0..10 {
+                cache.insert(format!("hot_{}", i), value.clone());
+            }
+            cache.set_promote_threshold(u64::MAX);
+            for i in 0..10 {
+                cache.insert(format!("cold_{}", i), value.clone());
+            }
+            assert_eq!((cache.lfu.len(), cache.lru.len()), (lfu, lru), "{}", size);
+        }
+    }
+
+    #[test]
+    fn test_byte_capacity_is_never_exceeded() {
+        let mut cache = HybridCache::with_byte_capacity(1000).unwrap();
+        let value = |len: usize| Arc::new(ColumnData::from(vec![7u8; len]));
+        // Горячий ключ в LFU и поток значений от 1 до 400 байт
+        for _ in 0..6 {
+            cache.insert("hot".to_string(), value(300));
+        }
+        assert_eq!(cache.current_bytes(), 300);
+        for i in 0..500usize {
+            cache.insert(format!("item_{}", i), value(1 + i * 7919 % 400));
+            assert!(cache.current_bytes() <= 1000, "{}", cache.current_bytes());
+            assert!(cache.lfu_bytes <= 500 && cache.lru_bytes <= 500);
+            assert_eq!(cache.current_bytes(), cache.lfu.values().chain(cache.lru.iter().map(|(_, v)| v)).map(|v| v.len()).sum::<usize>());
+        }
+        assert!(cache.get("hot").is_some());
+
+        // Значение больше уровня не кэшируется и убирает прежнее значение ключа
+        cache.insert("big".to_string(), value(10));
+        assert!(cache.get("big").is_some());
+        cache.insert("big".to_string(), value(501));
+        assert!(cache.get("big").is_none());
+        assert!(cache.current_bytes() <= 1000);
+
+        assert!(matches!(HybridCache::<String>::with_byte_capacity(1), Err(ColumnarError::CacheConfig(_))));
+    }
+
+    #[test]
+    fn test_promoted_key_leaves_lru() {
+        let mut cache = HybridCache::with_byte_capacity(100).unwrap();
+        for _ in 0..6 {
+            cache.insert("k".to_string(), Arc::new(ColumnData::from(vec![1u8; 10])));
+        }
+        assert_eq!((cache.lfu.len(), cache.lru.len(), cache.current_bytes()), (1, 0, 10));
+    }
+
+    #[test]
+    fn test_ttl_expires_entries_by_injected_clock() {
+        use std::sync::Mutex;
+        let now = Arc::new(Mutex::new(Instant::now()));
+        let advance = |secs: u64| *now.lock().unwrap() += Duration::from_secs(secs);
+        let mut cache = HybridCache::new(10).unwrap();
+        let clock = now.clone();
+        cache.set_clock(move || *clock.lock().unwrap());
+        cache.set_ttl(Some(Duration::from_secs(60)));
+        let value = Arc::new(ColumnData::from(vec![1u8, 2, 3]));
+
+        cache.insert("short".to_string(), value.clone());
+        cache.insert_with_ttl("long".to_string(), value.clone(), Some(Duration::from_secs(600)));
+        cache.insert_with_ttl("forever".to_string(), value.clone(), None);
+        advance(59);
+        assert!(cache.get("short").is_some());
+        advance(1);
+        assert!(cache.get("short").is_none());
+        assert!(cache.get("long").is_some());
+        assert_eq!(cache.current_bytes(), 6);
+
+        // Повторная вставка начинает срок заново
+        cache.insert("short".to_string(), value.clone());
+        advance(600);
+        assert_eq!(cache.purge_expired(), 2);
+        assert_eq!(cache.purge_expired(), 0);
+        assert!(cache.get("short").is_none() && cache.get("long").is_none());
+        assert!(cache.get("forever").is_some());
+        assert_eq!((cache.current_bytes(), cache.expires_at.len()), (3, 0));
+    }
+
+    #[test]
+    fn test_unrepresentable_ttl_never_expires() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.snapshot");
+        let value = Arc::new(ColumnData::from(vec![1u8, 2, 3]));
+        let mut cache: HybridCache = HybridCache::new(10).unwrap();
+        cache.set_ttl(Some(Duration::MAX));
+        cache.insert("a".to_string(), value.clone());
+        cache.insert_with_ttl("b".to_string(), value.clone(), Some(Duration::MAX));
+        cache.set_negative_cache(2, Duration::MAX).unwrap();
+        cache.insert_negative("absent".to_string());
+        assert!(cache.expires_at.is_empty());
+        assert!(cache.get("a").is_some() && cache.get("b").is_some());
+        assert_eq!(cache.lookup("absent"), CacheLookup::KnownAbsent);
+
+        cache.snapshot_to(&path, true).unwrap();
+        let mut restored: HybridCache = HybridCache::new(10).unwrap();
+        restored.set_ttl(Some(Duration::MAX));
+        assert_eq!(restored.restore_from(&path, |_| None).unwrap(), 2);
+        assert!(restored.expires_at.is_empty());
+        assert_eq!(restored.purge_expired(), 0);
+    }
+
+    #[test]
+    fn test_removed_keys_start_cold() {
+        let mut cache = HybridCache::new(20).unwrap();
+        let value = |byte: u8| Arc::new(ColumnData::from(vec![byte; 4]));
+        for _ in 0..6 {
+            cache.insert("hot".to_string(), value(1));
+        }
+        assert!(cache.lfu.contains_key("hot"));
+        assert_eq!(cache.remove("hot").unwrap()[..], [1; 4]);
+        assert!(cache.remove("hot").is_none());
+        assert!(cache.get("hot").is_none());
+        // Счётчик начался заново: эта вставка первая, промах выше не считается
+        cache.insert("hot".to_string(), value(2));
+        assert!(!cache.lfu.contains_key("hot"));
+        assert_eq!(cache.access_stats["hot"].0, 1);
+
+        for key in ["sales/0", "sales/1", "sales/2", "salesman/0", "users/0"] {
+            cache.insert(key.to_string(), value(3));
+        }
+        for _ in 0..6 {
+            cache.insert("sales/3".to_string(), value(4));
+        }
+        assert_eq!(cache.invalidate_prefix("sales/"), 4);
+        assert!(cache.access_stats.keys().all(|key| !key.starts_with("sales/")));
+        assert!(cache.get("sales/3").is_none() && cache.get("salesman/0").is_some());
+        cache.insert("sales/3".to_string(), value(4));
+        assert!(!cache.lfu.contains_key("sales/3"));
+
+        cache.clear();
+        assert_eq!((cache.lfu.len(), cache.lru.len(), cache.current_bytes()), (0, 0, 0));
+        assert!(cache.access_stats.is_empty() && cache.get("users/0").is_none());
+    }
+
+    #[test]
+    fn test_stats_follow_access_pattern() {
+        let mut cache = HybridCache::new(4).unwrap();
+        let value = Arc::new(ColumnData::from(vec![0u8; 10]));
+        for _ in 0..6 {
+            cache.insert("hot".to_string(), value.clone());
+        }
+        cache.insert("a".to_string(), value.clone());
+        cache.insert("b".to_string(), value.clone());
+        // В LRU два места: c вытесняет a
+        cache.insert("c".to_string(), value.clone());
+        for key in ["hot", "hot", "b", "c", "a", "missing"] {
+            cache.get(key);
+        }
+        assert_eq!(
+            cache.stats(),
+            CacheStats { lfu_hits: 2, lru_hits: 2, misses: 2, insertions: 9, evictions: 1, bytes: 30, negative_hits: 0, cost: 30 }
+        );
+
+        cache.reset();
+        assert_eq!(cache.stats(), CacheStats { bytes: 30, cost: 30, ..CacheStats::default() });
+        cache.remove("b");
+        cache.get("b");
+        assert_eq!(cache.stats(), CacheStats { misses: 1, bytes: 20, cost: 20, ..CacheStats::default() });
+    }
+
+    #[test]
+    fn test_hot_key_promoted_by_reads_survives_flood() {
+        let mut cache = HybridCache::new(10).unwrap();
+        let value = Arc::new(ColumnData::from(vec![0u8; 4]));
+        cache.insert("read_often".to_string(), value.clone());
+        for _ in 0..6 {
+            assert!(cache.get("read_often").is_some());
+        }
+        assert!(cache.lfu.contains_key("read_often"));
+        assert_eq!((cache.lfu.len(), cache.lru.len()), (1, 0));
+        for i in 0..100 {
+            cache.insert(format!("cold_{}", i), value.clone());
+        }
+        assert!(cache.get("read_often").is_some());
+
+        // С порогом 1 достаточно второго обращения
+        cache.set_promote_threshold(1);
+        cache.insert("second".to_string(), value.clone());
+        cache.get("second");
+        assert!(cache.lfu.contains_key("second"));
+    }
+
+    #[test]
+    fn test_access_stats_stay_bounded() {
+        let mut cache = HybridCache::new(10).unwrap();
+        let value = Arc::new(ColumnData::from(vec![0u8; 4]));
+        for i in 0..100_000 {
+            assert!(cache.get(&format!("miss_{}", i)).is_none());
+        }
+        assert!(cache.access_stats.is_empty());
+        assert_eq!(cache.stats().misses, 100_000);
+
+        // Вытеснение, истёкший срок и слишком большое значение тоже не оставляют записей
+        let mut sized = HybridCache::with_byte_capacity(100).unwrap();
+        let now = Arc::new(std::sync::Mutex::new(Instant::now()));
+        let clock = now.clone();
+        sized.set_clock(move || *clock.lock().unwrap());
+        for i in 0..10_000 {
+            cache.insert(format!("scan_{}", i), value.clone());
+            sized.insert_with_ttl(format!("ttl_{}", i), value.clone(), Some(Duration::from_secs(1)));
+            sized.insert(format!("big_{}", i), Arc::new(ColumnData::from(vec![0u8; 51])));
+        }
+        assert_eq!(cache.access_stats.len(), cache.lfu.len() + cache.lru.len());
+        assert!(cache.access_stats.len() <= 10);
+        *now.lock().unwrap() += Duration::from_secs(1);
+        sized.purge_expired();
+        assert!(sized.access_stats.is_empty());
+    }
+
+    #[test]
+    fn test_resize_and_fraction_evict_by_tier_priority() {
+        let config = CacheConfig { capacity: 10, lfu_fraction: 0.8, promote_threshold: 1, decay_every: None };
+        let mut cache = HybridCache::with_config(config).unwrap();
+        assert_eq!((cache.lfu_capacity, cache.lru_capacity), (8, 2));
+        let value = Arc::new(ColumnData::from(vec![0u8; 4]));
+        // hot_i прочитан i раз сверх двух вставок
+        for i in 0..8 {
+            cache.insert(format!("hot_{}", i), value.clone());
+            cache.insert(format!("hot_{}", i), value.clone());
+            for _ in 0..i {
+                cache.get(&format!("hot_{}", i));
+            }
+        }
+        for i in 0..5 {
+            cache.insert(format!("cold_{}", i), value.clone());
+        }
+        let resident = |cache: &HybridCache| {
+            let mut lfu: Vec<String> = cache.lfu.keys().cloned().collect();
+            lfu.sort();
+            let lru: Vec<String> = cache.lru.iter().map(|(key, _)| key.clone()).collect();
+            (lfu, lru)
+        };
+        assert_eq!(resident(&cache).1, ["cold_4", "cold_3"]);
+
+        cache.resize(5).unwrap();
+        assert_eq!((cache.lfu_capacity, cache.lru_capacity), (4, 1));
+        assert_eq!(resident(&cache), (vec!["hot_4".into(), "hot_5".into(), "hot_6".into(), "hot_7".into()], vec!["cold_4".into()]));
+
+        cache.set_lfu_fraction(0.2).unwrap();
+        assert_eq!((cache.lfu_capacity, cache.lru_capacity), (1, 4));
+        assert_eq!(resident(&cache), (vec!["hot_7".into()], vec!["cold_4".into()]));
+        assert_eq!(cache.stats().evictions, 3 + 4 + 1 + 3);
+
+        for fraction in [-0.1, 1.5, f64::NAN] {
+            assert!(matches!(cache.set_lfu_fraction(fraction), Err(ColumnarError::CacheConfig(_))));
+        }
+        assert!(matches!(cache.resize(0), Err(ColumnarError::CacheConfig(_))));
+        assert_eq!((cache.lfu_capacity, cache.lru_capacity), (1, 4));
+        assert!(matches!(HybridCache::<String>::with_config(CacheConfig { lfu_fraction: 2.0, ..config }), Err(ColumnarError::CacheConfig(_))));
+
+        // При доле 1 всё попадает в LFU, при доле 0 горячие ключи остаются в LRU
+        let mut lfu_only = HybridCache::with_config(CacheConfig { capacity: 3, lfu_fraction: 1.0, promote_threshold: 5, decay_every: None }).unwrap();
+        lfu_only.insert("a".to_string(), value.clone());
+        assert!(lfu_only.lfu.contains_key("a") && lfu_only.get("a").is_some());
+        let mut lru_only = HybridCache::with_config(CacheConfig { capacity: 3, lfu_fraction: 0.0, promote_threshold: 0, decay_every: None }).unwrap();
+        lru_only.insert("a".to_string(), value.clone());
+        lru_only.get("a");
+        assert!(lru_only.lfu.is_empty() && lru_only.lru.len() == 1);
+    }
+
+    #[test]
+    fn test_concurrent_cache_under_contention() {
+        let cache = Arc::new(ConcurrentHybridCache::new(64, 8).unwrap());
+        let threads: Vec<_> = (0..16)
+            .map(|t| {
+                let cache = cache.clone();
+                std::thread::spawn(move || {
+                    for i in 0..5_000usize {
+                        let key = format!("key_{}", (i * 31 + t * 7) % 500);
+                        match i % 4 {
+                            0 => {
+                                cache.insert(key, Arc::new(ColumnData::from(vec![t as u8; 8])));
+                            }
+                            3 if i % 100 == 3 => {
+                                cache.remove(&key);
+                            }
+                            _ => {
+                                cache.get(&key);
+                            }
+                        }
+                        assert!(cache.len() <= 64);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert!(cache.len() <= 64 && cache.current_bytes() == cache.len() * 8);
+        let stats = cache.stats();
+        assert_eq!(stats.lfu_hits + stats.lru_hits + stats.misses, 16 * (5_000 - 1_250 - 50));
+        cache.reset();
+        assert_eq!(cache.stats(), CacheStats { bytes: stats.bytes, cost: stats.cost, ..CacheStats::default() });
+        let resident = cache.len();
+        assert_eq!(cache.invalidate_prefix("key_"), resident);
+        assert!(cache.is_empty());
+
+        assert!(matches!(ConcurrentHybridCache::<String>::new(15, 8), Err(ColumnarError::CacheConfig(_))));
+        assert!(matches!(ConcurrentHybridCache::<String>::new(16, 0), Err(ColumnarError::CacheConfig(_))));
+        let sizes: Vec<usize> = ConcurrentHybridCache::<String>::new(19, 4).unwrap().shards.iter().map(|s| lock(&s.cache).capacity).collect();
+        assert_eq!(sizes, [5, 5, 5, 4]);
+    }
+
+    #[test]
+    fn test_get_or_insert_with_runs_load_once() {
+        use std::sync::{atomic::{AtomicUsize, Ordering}, Barrier};
+        let cache = Arc::new(ConcurrentHybridCache::new(16, 2).unwrap());
+        let calls = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(2));
+        let threads: Vec<_> = (0..2)
+            .map(|_| {
+                let (cache, calls, barrier) = (cache.clone(), calls.clone(), barrier.clone());
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    cache
+                        .get_or_insert_with("col".to_string(), || {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            std::thread::sleep(Duration::from_millis(100));
+                            Ok::<_, ()>(Arc::new(ColumnData::from(vec![5u8; 8])))
+                        })
+                        .unwrap()
+                })
+            })
+            .collect();
+        let values: Vec<_> = threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(Arc::ptr_eq(&values[0], &values[1]));
+        assert_eq!(cache.stats().insertions, 1);
+
+        // Ошибка и паника load не оставляют следа: следующий вызов вычисляет заново
+        assert_eq!(cache.get_or_insert_with("bad".to_string(), || Err("нет данных")), Err("нет данных"));
+        let panicking = cache.clone();
+        assert!(std::thread::spawn(move || panicking.get_or_insert_with("bad".to_string(), || -> std::result::Result<_, ()> { panic!() }))
+            .join()
+            .is_err());
+        let value = cache.get_or_insert_with("bad".to_string(), || Ok::<_, ()>(Arc::new(ColumnData::from(vec![1u8; 2])))).unwrap();
+        assert_eq!(value.len(), 2);
+        assert_eq!(cache.get_or_insert_with("bad".to_string(), || Err(())).unwrap().len(), 2);
+
+        let mut single = HybridCache::new(4).unwrap();
+        assert!(single.get_or_insert_with("k".to_string(), || Err(())).is_err());
+        assert!(single.is_empty());
+        single.get_or_insert_with("k".to_string(), || Ok::<_, ()>(value.clone())).unwrap();
+        assert!(Arc::ptr_eq(&single.get_or_insert_with("k".to_string(), || Err(())).unwrap(), &value));
+    }
+
+    #[test]
+    fn test_pinned_entry_survives_until_unpinned() {
+        let mut cache = HybridCache::with_byte_capacity(200).unwrap();
+        let value = |len: usize| Arc::new(ColumnData::from(vec![0u8; len]));
+        for _ in 0..6 {
+            cache.insert("lfu_pinned".to_string(), value(60));
+        }
+        cache.insert("lru_pinned".to_string(), value(60));
+        assert!(cache.pin("lfu_pinned") && cache.pin("lru_pinned") && cache.pin("lru_pinned"));
+        assert!(!cache.pin("missing"));
+        for i in 0..50 {
+            for _ in 0..6 {
+                cache.insert(format!("hot_{}", i), value(40));
+            }
+            cache.insert(format!("cold_{}", i), value(40));
+            assert!(cache.lfu.contains_key("lfu_pinned") && cache.lru.contains("lru_pinned"));
+            // Закреплённые байты считаются: рядом помещается только одно значение
+            assert_eq!((cache.lfu.len(), cache.lru.len()), (2, 2), "{}", i);
+            assert!(cache.current_bytes() <= 200);
+        }
+
+        // Уменьшение ёмкости не трогает закреплённое, вытесняется только новое
+        let mut small = HybridCache::new(4).unwrap();
+        for key in ["a", "b"] {
+            small.insert(key.to_string(), value(1));
+            small.pin(key);
+        }
+        small.insert("c".to_string(), value(1));
+        assert_eq!(small.lru.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>(), ["b", "a"]);
+        small.resize(2).unwrap();
+        assert_eq!(small.lru.len(), 2);
+        // Снятие закрепления само ничего не вытесняет, переполнение уберёт следующая вставка
+        small.unpin("a");
+        assert_eq!(small.lru.len(), 2);
+        small.insert("d".to_string(), value(1));
+        assert_eq!(small.lru.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>(), ["b"]);
+        small.unpin("b");
+        small.insert("e".to_string(), value(1));
+        assert_eq!(small.lru.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>(), ["e"]);
+        assert!(small.pins.is_empty());
+    }
+
+    #[test]
+    fn test_decay_lets_stale_hot_key_leave_lfu() {
+        let value = Arc::new(ColumnData::from(vec![0u8; 4]));
+        let run = |decay_every| {
+            let config = CacheConfig { capacity: 4, lfu_fraction: 0.5, promote_threshold: 1, decay_every };
+            let mut cache = HybridCache::with_config(config).unwrap();
+            cache.insert("stale".to_string(), value.clone());
+            for _ in 0..100 {
+                cache.get("stale");
+            }
+            assert!(cache.lfu.contains_key("stale"));
+            // Свежие ключи обращаются по 3 раза и сменяют друг друга в LFU
+            for i in 0..50 {
+                let key = format!("fresh_{}", i);
+                cache.insert(key.clone(), value.clone());
+                cache.get(&key);
+                cache.get(&key);
+            }
+            cache.lfu.contains_key("stale")
+        };
+        assert!(run(None), "без старения 100 давних обращений держат ключ в LFU");
+        assert!(!run(Some(8)));
+
+        let config = CacheConfig { capacity: 4, lfu_fraction: 0.5, promote_threshold: 1, decay_every: Some(0) };
+        assert!(matches!(HybridCache::<String>::with_config(config), Err(ColumnarError::CacheConfig(_))));
+    }
+
+    #[test]
+    fn test_eviction_listener_reports_every_path() {
+        use std::sync::Mutex;
+        let log: Arc<Mutex<Vec<(String, EvictionReason)>>> = Arc::default();
+        let seen = log.clone();
+        let mut cache: HybridCache = HybridCache::new(4).unwrap();
+        cache.set_eviction_listener(move |key, _, reason| seen.lock().unwrap().push((key.to_string(), reason)));
+        let now = Arc::new(Mutex::new(Instant::now()));
+        let clock = now.clone();
+        cache.set_clock(move || *clock.lock().unwrap());
+        let value = Arc::new(ColumnData::from(vec![0u8; 4]));
+        let drain = || std::mem::take(&mut *log.lock().unwrap());
+        let entry = |key: &str, reason| (key.to_string(), reason);
+
+        for key in ["a", "b", "c"] {
+            cache.insert(key.to_string(), value.clone());
+        }
+        assert_eq!(drain(), [entry("a", EvictionReason::Capacity)]);
+        cache.insert("b".to_string(), value.clone());
+        assert_eq!(drain(), [entry("b", EvictionReason::Replaced)]);
+        assert!(cache.remove("b").is_some());
+        assert!(cache.remove("b").is_none());
+        assert_eq!(drain(), [entry("b", EvictionReason::Explicit)]);
+
+        cache.insert_with_ttl("t1".to_string(), value.clone(), Some(Duration::from_secs(1)));
+        cache.insert_with_ttl("t2".to_string(), value.clone(), Some(Duration::from_secs(1)));
+        assert_eq!(drain(), [entry("c", EvictionReason::Capacity)]);
+        *now.lock().unwrap() += Duration::from_secs(1);
+        assert!(cache.get("t1").is_none());
+        assert_eq!(cache.purge_expired(), 1);
+        assert_eq!(drain(), [entry("t1", EvictionReason::Expired), entry("t2", EvictionReason::Expired)]);
+
+        for key in ["x/1", "x/2"] {
+            cache.insert(key.to_string(), value.clone());
+        }
+        cache.set_promote_threshold(0);
+        cache.insert("hot".to_string(), value.clone());
+        assert_eq!(cache.invalidate_prefix("x/"), 2);
+        let mut removed = drain();
+        removed.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(removed, [entry("x/1", EvictionReason::Explicit), entry("x/2", EvictionReason::Explicit)]);
+        cache.clear();
+        assert_eq!(drain(), [entry("hot", EvictionReason::Explicit)]);
+        assert!(drain().is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_listener_may_reenter_cache() {
+        let mut cache: ConcurrentHybridCache = ConcurrentHybridCache::new(2, 1).unwrap();
+        let evicted = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = evicted.clone();
+        let cache = Arc::new_cyclic(|weak: &std::sync::Weak<ConcurrentHybridCache>| {
+            let weak = weak.clone();
+            cache.set_eviction_listener(move |key, _, reason| {
+                // Обращение к тому же сегменту из слушателя не должно зависнуть
+                if let Some(cache) = weak.upgrade() {
+                    cache.get(key);
+                }
+                seen.lock().unwrap().push((key.to_string(), reason));
+            });
+            cache
+        });
+        let value = Arc::new(ColumnData::from(vec![0u8; 4]));
+        cache.insert("a".to_string(), value.clone());
+        cache.insert("b".to_string(), value.clone());
+        cache.get_or_insert_with("c".to_string(), || Ok::<_, ()>(value.clone())).unwrap();
+        cache.remove("c");
+        assert_eq!(
+            *evicted.lock().unwrap(),
+            [("a".to_string(), EvictionReason::Capacity), ("b".to_string(), EvictionReason::Capacity), ("c".to_string(), EvictionReason::Explicit)]
+        );
+    }
+
+    // С функцией log события пишет установленный логгер, а он вправе выделять память
+    #[cfg(not(feature = "log"))]
+    #[test]
+    fn test_borrowed_get_does_not_allocate() {
+        let mut cache: HybridCache = HybridCache::new(10).unwrap();
+        cache.set_promote_threshold(1);
+        let value = Arc::new(ColumnData::from(vec![0u8; 8]));
+        cache.insert("cold".to_string(), value.clone());
+        cache.insert("hot".to_string(), value.clone());
+        cache.get("hot");
+        assert!(cache.lfu.contains_key("hot") && cache.lru.contains("cold"));
+
+        assert_eq!(allocations_in(|| assert!(cache.get("cold").is_some())), 0);
+        assert_eq!(allocations_in(|| assert!(cache.get("hot").is_some())), 0);
+        assert_eq!(allocations_in(|| assert!(cache.get("missing").is_none())), 0);
+    }
+
+    #[test]
+    fn test_cache_accepts_other_key_and_value_types() {
+        let mut cache: HybridCache<u64, Arc<Vec<u8>>> = HybridCache::with_byte_capacity(16).unwrap();
+        cache.insert(1, Arc::new(vec![0; 6]));
+        cache.insert(2, Arc::new(vec![0; 6]));
+        assert_eq!((cache.len(), cache.current_bytes()), (1, 6));
+        assert!(cache.get(&1).is_none() && cache.get(&2).is_some());
+        cache.insert(3, Arc::new(vec![0; 2]));
+        assert_eq!(cache.invalidate_where(|key| key % 2 == 1), 1);
+        assert_eq!(cache.remove(&2).map(|v| v.len()), Some(6));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_peek_does_not_change_eviction_choice() {
+        let value = Arc::new(ColumnData::from(vec![0u8; 4]));
+        let mut cache: HybridCache = HybridCache::new(4).unwrap();
+        cache.set_promote_threshold(2);
+        for key in ["old", "new"] {
+            cache.insert(key.to_string(), value.clone());
+        }
+        let stats = cache.stats();
+        for _ in 0..10 {
+            assert!(cache.peek("old").is_some() && cache.contains_key("old"));
+            assert!(cache.peek("missing").is_none() && !cache.contains_key("missing"));
+        }
+        assert_eq!(cache.stats(), stats);
+        assert_eq!(cache.access_stats["old"].0, 1);
+        assert!(cache.lru.contains("old") && !cache.lfu.contains_key("old"));
+
+        // Без peek "old" остался самым давним в LRU и вытесняется первым
+        cache.insert("third".to_string(), value.clone());
+        assert!(!cache.contains_key("old") && cache.contains_key("new"));
+
+        // Для сравнения: get делает ключ недавним, и вытесняется уже другой
+        cache.get("new");
+        cache.insert("fourth".to_string(), value.clone());
+        assert!(cache.contains_key("new") && !cache.contains_key("third"));
+
+        let mut clock = HybridCache::<String>::new(4).unwrap();
+        let now = Instant::now();
+        let offset = Arc::new(Mutex::new(Duration::ZERO));
+        let shift = offset.clone();
+        clock.set_clock(move || now + *shift.lock().unwrap());
+        clock.insert_with_ttl("short".to_string(), value.clone(), Some(Duration::from_secs(1)));
+        assert!(clock.contains_key("short"));
+        *offset.lock().unwrap() = Duration::from_secs(2);
+        assert!(clock.peek("short").is_none() && !clock.contains_key("short"));
+    }
+
+    #[test]
+    fn test_cheaper_entry_is_evicted_first() {
+        let value = Arc::new(ColumnData::from(vec![0u8; 10]));
+        let mut cache: HybridCache = HybridCache::new(4).unwrap();
+        cache.set_promote_threshold(1);
+        // Дорогой ключ старше дешёвого, но вытесняется дешёвый
+        cache.insert_weighted("expensive".to_string(), value.clone(), 1_000);
+        cache.insert_weighted("cheap".to_string(), value.clone(), 1);
+        assert_eq!(cache.stats().cost, 1_001);
+        cache.insert("next".to_string(), value.clone());
+        assert!(cache.contains_key("expensive") && !cache.contains_key("cheap"));
+        assert_eq!(cache.stats().cost, 1_010);
+
+        // В LFU при равном числе обращений — так же
+        for (key, cost) in [("lfu_expensive", 1_000), ("lfu_cheap", 1)] {
+            cache.insert_weighted(key.to_string(), value.clone(), cost);
+            cache.insert_weighted(key.to_string(), value.clone(), cost);
+        }
+        assert!(cache.lfu.contains_key("lfu_expensive") && cache.lfu.contains_key("lfu_cheap"));
+        cache.insert("third".to_string(), value.clone());
+        cache.insert("third".to_string(), value.clone());
+        assert!(cache.lfu.contains_key("lfu_expensive") && !cache.lfu.contains_key("lfu_cheap"));
+        // Повторная вставка заменяет стоимость, а не прибавляет её
+        cache.insert_weighted("third".to_string(), value.clone(), 7);
+        assert_eq!(cache.stats().cost, cache.costs.values().sum::<u64>());
+
+        // Стоимость уходит вместе со значением
+        let before = cache.stats().cost;
+        cache.remove("lfu_expensive");
+        assert_eq!(cache.stats().cost, before - 1_000);
+        cache.clear();
+        assert_eq!(cache.stats().cost, 0);
+    }
+
+    #[test]
+    fn test_snapshot_restores_hot_entry_as_immediate_hit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.snapshot");
+        let mut cache: HybridCache = HybridCache::new(10).unwrap();
+        for _ in 0..7 {
+            cache.insert("hot".to_string(), Arc::new(ColumnData::from(vec![7u8; 16])));
+        }
+        cache.insert_weighted("cold".to_string(), Arc::new(ColumnData::from(vec![1u8; 4])), 99);
+        assert!(cache.lfu.contains_key("hot"));
+        cache.snapshot_to(&path, true).unwrap();
+
+        let mut restored: HybridCache = HybridCache::new(10).unwrap();
+        assert_eq!(restored.restore_from(&path, |_| panic!("значения есть в снимке")).unwrap(), 2);
+        assert!(restored.lfu.contains_key("hot") && restored.lru.contains("cold"));
+        assert_eq!(restored.get("hot").as_deref().map(|v| &v[..]), Some(&[7u8; 16][..]));
+        assert_eq!(restored.stats().lfu_hits, 1);
+        assert_eq!((restored.access_stats["hot"].0, restored.stats().cost), (8, 16 + 99));
+
+        // Без байтов значений — через loader; ключ, который loader не знает, пропускается
+        cache.snapshot_to(&path, false).unwrap();
+        let sharded: ConcurrentHybridCache = ConcurrentHybridCache::new(12, 3).unwrap();
+        let loaded = sharded.restore_from(&path, |key| (key == "hot").then(|| Arc::new(ColumnData::from(vec![8u8; 16])))).unwrap();
+        assert_eq!((loaded, sharded.len()), (1, 1));
+        assert!(sharded.get("hot").is_some() && sharded.stats().lfu_hits == 1);
+
+        // Повреждённый снимок и снимок другой версии не меняют кэш
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[12] ^= 0xff;
+        fs::write(&path, &bytes).unwrap();
+        let mut cold: HybridCache = HybridCache::new(10).unwrap();
+        assert!(matches!(cold.restore_from(&path, |_| None), Err(ColumnarError::Corrupt { .. })));
+        bytes[12] ^= 0xff;
+        bytes[8] = 9;
+        let len = bytes.len();
+        let checksum = crc32fast::hash(&bytes[..len - 4]);
+        bytes[len - 4..].copy_from_slice(&checksum.to_le_bytes());
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(cold.restore_from(&path, |_| None), Err(ColumnarError::Corrupt { .. })));
+        fs::write(&path, b"garbage").unwrap();
+        assert!(cold.restore_from(&path, |_| None).is_err());
+        assert!(cold.restore_from(&dir.path().join("missing"), |_| None).is_err());
+        assert!(cold.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_lengths_must_fit_u32() {
+        assert_eq!(snapshot_len(u32::MAX as usize, "байт значения").unwrap(), u32::MAX);
+        assert!(matches!(snapshot_len(u32::MAX as usize + 1, "байт значения"), Err(ColumnarError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_negative_entry_expires_into_plain_miss() {
+        let now = Arc::new(Mutex::new(Instant::now()));
+        let advance = |secs: u64| *now.lock().unwrap() += Duration::from_secs(secs);
+        let mut cache: HybridCache = HybridCache::new(10).unwrap();
+        let clock = now.clone();
+        cache.set_clock(move || *clock.lock().unwrap());
+        // Без включения отметки не запоминаются
+        cache.insert_negative("optional".to_string());
+        assert_eq!(cache.lookup("optional"), CacheLookup::Miss);
+        assert!(matches!(cache.set_negative_cache(0, Duration::from_secs(1)), Err(ColumnarError::CacheConfig(_))));
+
+        cache.set_negative_cache(2, Duration::from_secs(30)).unwrap();
+        cache.reset();
+        cache.insert_negative("optional".to_string());
+        assert_eq!(cache.lookup("optional"), CacheLookup::KnownAbsent);
+        assert_eq!(cache.lookup("other"), CacheLookup::Miss);
+        assert_eq!((cache.len(), cache.current_bytes()), (0, 0));
+        assert_eq!((cache.stats().negative_hits, cache.stats().misses), (1, 1));
+
+        advance(31);
+        assert_eq!(cache.lookup("optional"), CacheLookup::Miss);
+        assert_eq!((cache.stats().negative_hits, cache.stats().misses), (1, 2));
+
+        // Вставка снимает отметку, а отметка удаляет прежнее значение
+        let value = Arc::new(ColumnData::from(vec![5u8; 4]));
+        cache.insert_negative("appeared".to_string());
+        cache.insert("appeared".to_string(), value.clone());
+        assert_eq!(cache.lookup("appeared"), CacheLookup::Hit(value.clone()));
+        cache.insert_negative("appeared".to_string());
+        assert_eq!(cache.lookup("appeared"), CacheLookup::KnownAbsent);
+        assert!(cache.is_empty());
+
+        // Своя ёмкость: самая давняя отметка вытесняется
+        for key in ["a", "b", "c"] {
+            cache.insert_negative(key.to_string());
+        }
+        assert_eq!(cache.lookup("a"), CacheLookup::Miss);
+        assert_eq!(cache.lookup("c"), CacheLookup::KnownAbsent);
+        cache.remove("c");
+        assert_eq!(cache.lookup("c"), CacheLookup::Miss);
+
+        let mut sharded: ConcurrentHybridCache = ConcurrentHybridCache::new(8, 2).unwrap();
+        sharded.set_negative_cache(4, Duration::from_secs(30)).unwrap();
+        sharded.insert_negative("t/missing".to_string());
+        assert_eq!(sharded.lookup("t/missing"), CacheLookup::KnownAbsent);
+        sharded.invalidate_prefix("t/");
+        assert_eq!(sharded.lookup("t/missing"), CacheLookup::Miss);
+    }
 }
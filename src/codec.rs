@@ -0,0 +1,175 @@
+#[cfg(feature = "zstd")]
+use zstd::{bulk::decompress as zstd_decompress, encode_all as zstd_compress};
+use std::fmt;
+use crate::error::{corrupt, invalid_input, ColumnarError, Result};
+
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+// Алгоритм сжатия фреймов колонки
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Codec {
+    #[default]
+    None,
+    Zstd { level: i32 },
+    Lz4,
+}
+
+impl Codec {
+    pub fn zstd() -> Self {
+        Codec::Zstd { level: DEFAULT_ZSTD_LEVEL }
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        *self != Codec::None
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        #[cfg(not(feature = "zstd"))]
+        if let Codec::Zstd { .. } = *self {
+            return Err(invalid_input(ZSTD_DISABLED));
+        }
+        #[cfg(feature = "zstd")]
+        if let Codec::Zstd { level } = *self {
+            let levels = zstd::compression_level_range();
+            if !levels.contains(&level) {
+                return Err(invalid_input(format!(
+                    "уровень сжатия {} вне допустимого диапазона {}..={}",
+                    level, levels.start(), levels.end()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn compress_frame(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match *self {
+            Codec::None => Ok(data.to_vec()),
+            #[cfg(feature = "zstd")]
+            Codec::Zstd { level } => Ok(zstd_compress(data, level)?),
+            #[cfg(not(feature = "zstd"))]
+            Codec::Zstd { .. } => Err(invalid_input(ZSTD_DISABLED)),
+            Codec::Lz4 => Ok(lz4_flex::compress_prepend_size(data)),
+        }
+    }
+
+    // len — длина исходных данных из метаданных: буфер выделяется сразу нужного
+    // размера, а фрейм, распаковавшийся в другую длину, считается повреждённым
+    pub fn decompress_frame(&self, frame: &[u8], len: usize) -> Result<Vec<u8>> {
+        let data = match *self {
+            Codec::None => frame.to_vec(),
+            #[cfg(feature = "zstd")]
+            Codec::Zstd { .. } => zstd_decompress(frame, len).map_err(|e| ColumnarError::Decompression {
+                detail: format!("повреждён zstd-фрейм: {}", e),
+            })?,
+            #[cfg(not(feature = "zstd"))]
+            Codec::Zstd { .. } => {
+                return Err(ColumnarError::Decompression { detail: ZSTD_DISABLED.to_string() });
+            }
+            Codec::Lz4 => {
+                let lz4_error = |e: lz4_flex::block::DecompressError| ColumnarError::Decompression {
+                    detail: format!("повреждён lz4-фрейм: {}", e),
+                };
+                // Длину из префикса фрейма сверяем до выделения памяти под неё
+                let (prefixed_len, block) = lz4_flex::block::uncompressed_size(frame).map_err(lz4_error)?;
+                if prefixed_len != len {
+                    return Err(length_mismatch(prefixed_len, len));
+                }
+                lz4_flex::decompress(block, len).map_err(lz4_error)?
+            }
+        };
+        if data.len() != len {
+            return Err(length_mismatch(data.len(), len));
+        }
+        Ok(data)
+    }
+
+    // Имя алгоритма для диагностики
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Codec::None => "none",
+            Codec::Zstd { .. } => "zstd",
+            Codec::Lz4 => "lz4",
+        }
+    }
+
+    // Идентификатор алгоритма в заголовке файла; уровень zstd хранится в метаданных
+    pub(crate) fn tag(&self) -> u8 {
+        match self {
+            Codec::None => 0,
+            Codec::Zstd { .. } => 1,
+            Codec::Lz4 => 2,
+        }
+    }
+
+    pub(crate) fn level(&self) -> i32 {
+        match *self {
+            Codec::Zstd { level } => level,
+            _ => 0,
+        }
+    }
+
+    pub(crate) fn from_parts(tag: u8, level: i32) -> Result<Self> {
+        match tag {
+            0 => Ok(Codec::None),
+            1 => Ok(Codec::Zstd { level }),
+            2 => Ok(Codec::Lz4),
+            _ => Err(corrupt(format!("неизвестный алгоритм сжатия: {}", tag))),
+        }
+    }
+}
+
+// Имя кодека; уровень zstd важен при сравнении размеров, поэтому он тоже выводится
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Codec::Zstd { level } => write!(f, "zstd({})", level),
+            other => f.write_str(other.name()),
+        }
+    }
+}
+
+#[cfg(not(feature = "zstd"))]
+const ZSTD_DISABLED: &str = "сжатие zstd недоступно: крейт собран без функции zstd";
+
+fn length_mismatch(actual: usize, expected: usize) -> ColumnarError {
+    ColumnarError::Decompression { detail: format!("фрейм распакован в {} байт вместо {}", actual, expected) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_frame_roundtrip_for_every_codec() {
+        let data: Vec<u8> = (0..10_000u32).flat_map(|x| (x % 251).to_le_bytes()).collect();
+
+        for codec in [Codec::None, Codec::zstd(), Codec::Zstd { level: 19 }, Codec::Lz4] {
+            let frame = codec.compress_frame(&data).unwrap();
+            assert_eq!(codec.decompress_frame(&frame, data.len()).unwrap(), data, "{:?}", codec);
+            // Длина из метаданных должна совпасть точно
+            for len in [data.len() - 1, data.len() + 1] {
+                assert!(
+                    matches!(codec.decompress_frame(&frame, len), Err(ColumnarError::Decompression { .. })),
+                    "{:?} {}",
+                    codec,
+                    len
+                );
+            }
+            assert_eq!(Codec::from_parts(codec.tag(), codec.level()).unwrap(), codec);
+        }
+        assert!(Codec::Zstd { level: 1000 }.validate().is_err());
+        assert!(Codec::from_parts(42, 0).is_err());
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn test_zstd_unavailable_without_feature() {
+        assert!(matches!(Codec::zstd().validate(), Err(ColumnarError::InvalidInput(_))));
+        assert!(matches!(Codec::zstd().compress_frame(b"abc"), Err(ColumnarError::InvalidInput(_))));
+        // Колонка, записанная со сжатием zstd, открывается, но не распаковывается
+        assert!(matches!(Codec::zstd().decompress_frame(b"abc", 3), Err(ColumnarError::Decompression { .. })));
+        let frame = Codec::Lz4.compress_frame(b"abc").unwrap();
+        assert_eq!(Codec::Lz4.decompress_frame(&frame, 3).unwrap(), b"abc");
+    }
+}
@@ -0,0 +1,129 @@
+use std::{collections::BTreeSet, path::Path};
+use crate::bools::bit_is_set;
+use crate::encoding::{Encoding, TotalOrd};
+use crate::error::Result;
+use crate::storage::Column;
+use crate::types::ColumnType;
+use crate::writer::ColumnWriter;
+
+impl<T: ColumnType> Column<T> {
+    // Переписывает колонку в path без удалённых строк: чанки читаются по одному, так
+    // что в памяти не больше распакованного чанка, а сегменты дозаписи сливаются в
+    // чанки обычного размера. Сводка, bloom-фильтр и словарь строятся заново по
+    // уцелевшим строкам, файла .del у результата нет. Запись атомарна, поэтому path
+    // может быть путём самой колонки: прежний файл остаётся целым, пока новый не
+    // сброшен на диск, а после этого прежний экземпляр стоит заменить результатом
+    pub fn compact(&self, path: &Path) -> Result<Column<T>> {
+        let mut writer = ColumnWriter::create_like(self, path)?;
+        if self.encoding == Encoding::Dictionary {
+            writer.set_dictionary(self.live_dictionary()?);
+        }
+        self.for_each_live_row(|value| writer.push_option(value))?;
+        writer.finish()
+    }
+
+    // Первый проход для словарной колонки: словарь уцелевших значений не больше прежнего.
+    // NULL без единого значения всё равно нужен заполнитель в словаре
+    fn live_dictionary(&self) -> Result<Vec<T>> {
+        let mut distinct = BTreeSet::new();
+        let mut has_nulls = false;
+        self.for_each_live_row(|value| {
+            match value {
+                Some(value) => {
+                    distinct.insert(TotalOrd(value));
+                }
+                None => has_nulls = true,
+            }
+            Ok(())
+        })?;
+        if distinct.is_empty() && has_nulls {
+            distinct.insert(TotalOrd(T::from_bits(0)));
+        }
+        Ok(distinct.into_iter().map(|value| value.0).collect())
+    }
+
+    fn for_each_live_row(&self, mut f: impl FnMut(Option<T>) -> Result<()>) -> Result<()> {
+        for idx in 0..self.chunks.len() {
+            let first_row = self.chunks[idx].first_row as usize;
+            let values = self.chunk_values(idx)?;
+            let validity = self.chunk_validity(idx)?;
+            for (row, raw) in values.chunks_exact(T::WIDTH).enumerate() {
+                if self.is_deleted(first_row + row) {
+                    continue;
+                }
+                let valid = validity.as_ref().is_none_or(|bits| bit_is_set(bits, row));
+                f(valid.then(|| T::read_le(raw)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ROWS_PER_CHUNK;
+    use crate::tombstone::deletions_path;
+    use crate::{Agg, AggValue, Codec, ColumnBuilder};
+
+    #[test]
+    fn test_compact_drops_deleted_rows_and_merges_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let mut values: Vec<Option<i32>> = (0..ROWS_PER_CHUNK as i32).map(|i| (i % 11 != 0).then_some(i % 5000)).collect();
+        let mut builder = ColumnBuilder::from_nullable("v".to_string(), &values);
+        builder.set_chunk_rows(10_000);
+        builder.compress_with(Codec::Lz4).unwrap();
+        let mut column = builder.build(&path).unwrap();
+        for segment in 0..3 {
+            let appended: Vec<i32> = (0..700).map(|i| 6000 + segment * 700 + i).collect();
+            column.append(&appended).unwrap();
+            values.extend(appended.into_iter().map(Some));
+        }
+        let doomed: Vec<usize> = (0..values.len()).filter(|row| row % 97 == 5 || *row >= values.len() - 10).collect();
+        column.delete_rows(&doomed).unwrap();
+        let survivors: Vec<Option<i32>> =
+            values.iter().enumerate().filter(|(row, _)| !doomed.contains(row)).map(|(_, v)| *v).collect();
+
+        let compacted = column.compact(&path).unwrap();
+        assert!(!deletions_path(&path).exists());
+        assert_eq!(compacted.len(), survivors.len());
+        assert_eq!(compacted.live_count(), compacted.len());
+        assert_eq!(compacted.nullable_values().unwrap(), survivors);
+        assert_eq!(compacted.chunks.len(), 2);
+        assert_eq!(compacted.chunks[0].row_count(), ROWS_PER_CHUNK as u64);
+        assert_eq!(compacted.null_count, survivors.iter().filter(|v| v.is_none()).count() as u64);
+        let present: Vec<i32> = survivors.iter().flatten().copied().collect();
+        assert_eq!(compacted.max, *present.iter().max().unwrap());
+        assert!(!compacted.contains(6000 + 3 * 700 - 1).unwrap());
+        assert_eq!(column.aggregate(Agg::Sum, None).unwrap(), compacted.aggregate(Agg::Sum, None).unwrap());
+        assert_eq!(compacted.aggregate(Agg::Count, None).unwrap(), AggValue::Count(present.len() as u64));
+
+        // Прежний экземпляр по-прежнему читает свой файл
+        assert_eq!(column.len(), values.len());
+        assert_eq!(column.get_value(1), Some(1));
+        drop(column);
+        let reopened = Column::<i32>::open(&path).unwrap();
+        assert_eq!(reopened.len(), survivors.len());
+    }
+
+    #[test]
+    fn test_compact_rebuilds_dictionary_from_survivors() {
+        let values: Vec<Option<i64>> = (0..300).map(|i| (i % 7 != 0).then_some(i % 4 * 100)).collect();
+        let mut builder = ColumnBuilder::from_nullable("d".to_string(), &values);
+        builder.set_encoding(Encoding::Dictionary);
+        let mut column = builder.build_in_memory().unwrap();
+        assert_eq!(column.dictionary, vec![0, 100, 200, 300]);
+        let doomed: Vec<usize> = (0..300).filter(|row| values[*row] == Some(300)).collect();
+        column.delete_rows(&doomed).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let compacted = column.compact(&dir.path().join("d.col")).unwrap();
+        assert_eq!(compacted.encoding, Encoding::Dictionary);
+        assert_eq!(compacted.dictionary, vec![0, 100, 200]);
+        assert_eq!(compacted.len(), 300 - doomed.len());
+        assert!(compacted.contains(200).unwrap() && !compacted.contains(300).unwrap());
+        let expected: Vec<Option<i64>> = values.into_iter().filter(|v| *v != Some(300)).collect();
+        assert_eq!(compacted.nullable_values().unwrap(), expected);
+    }
+}
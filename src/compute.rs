@@ -0,0 +1,324 @@
+use std::ops::Range;
+use crate::error::{invalid_input, Result};
+
+// Отобранные строки колонки или таблицы — общий вход и выход filter, aggregate,
+// group_by и take. Битовая карта по 64 строки в слове, младший бит — первая строка;
+// биты за концом последнего слова равны нулю, поэтому операции идут целыми словами
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    words: Vec<u64>,
+    len: usize,
+}
+
+// Прежнее имя Selection
+pub type Bitmap = Selection;
+
+impl Selection {
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    // Карта, в которой отобраны все строки
+    pub fn full(len: usize) -> Self {
+        let mut selection = Self {
+            words: vec![u64::MAX; len.div_ceil(64)],
+            len,
+        };
+        selection.clear_tail();
+        selection
+    }
+
+    // Номера строк в любом порядке; повторы допустимы
+    pub fn from_indices(len: usize, indices: &[u32]) -> Result<Self> {
+        let mut selection = Self::new(len);
+        for idx in indices {
+            if *idx as usize >= len {
+                return Err(invalid_input(format!("строка {} вне отбора из {} строк", idx, len)));
+            }
+            selection.set(*idx as usize);
+        }
+        Ok(selection)
+    }
+
+    // Номера отобранных строк по возрастанию — вход Column::take
+    pub fn to_indices(&self) -> Result<Vec<u32>> {
+        if self.len > u32::MAX as usize + 1 {
+            return Err(invalid_input(format!("в отборе {} строк, номера не помещаются в u32", self.len)));
+        }
+        Ok(self.iter_indices().map(|idx| idx as u32).collect())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    pub fn get(&self, idx: usize) -> bool {
+        idx < self.len && self.words[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    // Паникует на строке за концом карты, как индексация среза
+    pub fn set(&mut self, idx: usize) {
+        assert!(idx < self.len, "строка {} вне битовой карты из {} строк", idx, self.len);
+        self.words[idx / 64] |= 1 << (idx % 64);
+    }
+
+    // Поразрядные операции над картами одной длины; разная длина — ошибка вызывающего
+    pub fn intersect_with(&mut self, other: &Selection) {
+        assert_eq!(self.len, other.len, "битовые карты разной длины");
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word &= other;
+        }
+    }
+
+    pub fn union_with(&mut self, other: &Selection) {
+        assert_eq!(self.len, other.len, "битовые карты разной длины");
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word |= other;
+        }
+    }
+
+    // Снимает отбор со строк, отобранных в other
+    pub fn difference_with(&mut self, other: &Selection) {
+        assert_eq!(self.len, other.len, "битовые карты разной длины");
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word &= !other;
+        }
+    }
+
+    pub fn invert(&mut self) {
+        for word in &mut self.words {
+            *word = !*word;
+        }
+        self.clear_tail();
+    }
+
+    // То же, что intersect_with, union_with и invert, но с новой картой
+    pub fn and(&self, other: &Selection) -> Selection {
+        let mut result = self.clone();
+        result.intersect_with(other);
+        result
+    }
+
+    pub fn or(&self, other: &Selection) -> Selection {
+        let mut result = self.clone();
+        result.union_with(other);
+        result
+    }
+
+    pub fn not(&self) -> Selection {
+        let mut result = self.clone();
+        result.invert();
+        result
+    }
+
+    fn clear_tail(&mut self) {
+        if !self.len.is_multiple_of(64) {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1u64 << (self.len % 64)) - 1;
+            }
+        }
+    }
+
+    // Маска битов from..to внутри одного слова, 0 <= from < to <= 64
+    fn word_mask(from: usize, to: usize) -> u64 {
+        if to - from == 64 { u64::MAX } else { ((1u64 << (to - from)) - 1) << from }
+    }
+
+    // Отбирает строки range; целые слова заполняются без перебора битов
+    pub(crate) fn set_range(&mut self, range: Range<usize>) {
+        assert!(range.end <= self.len, "строки {:?} вне битовой карты из {} строк", range, self.len);
+        let mut idx = range.start;
+        while idx < range.end {
+            let word = idx / 64;
+            let to = (range.end - word * 64).min(64);
+            self.words[word] |= Self::word_mask(idx % 64, to);
+            idx = word * 64 + to;
+        }
+    }
+
+    // Число отобранных строк
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    // Число отобранных строк в range; целые слова считаются без перебора битов
+    pub fn count_range(&self, range: Range<usize>) -> usize {
+        let end = range.end.min(self.len);
+        let mut idx = range.start;
+        let mut count = 0;
+        while idx < end {
+            let word = idx / 64;
+            let to = (end - word * 64).min(64);
+            count += (self.words[word] & Self::word_mask(idx % 64, to)).count_ones() as usize;
+            idx = word * 64 + to;
+        }
+        count
+    }
+
+    // Номера отобранных строк по возрастанию
+    pub fn iter_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(idx, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(idx * 64 + bit)
+            })
+        })
+    }
+
+    // 64 бита начиная с позиции pos; биты вне карты, в том числе левее нуля, равны нулю
+    fn bits_from(&self, pos: i64) -> u64 {
+        let word = |idx: i64| if idx < 0 { 0 } else { self.words.get(idx as usize).copied().unwrap_or(0) };
+        let (idx, shift) = (pos.div_euclid(64), pos.rem_euclid(64));
+        if shift == 0 { word(idx) } else { (word(idx) >> shift) | (word(idx + 1) << (64 - shift)) }
+    }
+
+    // Строки range как отдельная карта с нумерацией от нуля
+    pub(crate) fn slice(&self, range: Range<usize>) -> Selection {
+        assert!(range.end <= self.len, "строки {:?} вне битовой карты из {} строк", range, self.len);
+        let mut result = Self::new(range.len());
+        for (idx, word) in result.words.iter_mut().enumerate() {
+            *word = self.bits_from((range.start + idx * 64) as i64);
+        }
+        result.clear_tail();
+        result
+    }
+
+    // Обратное к slice: карта из len строк, в которой эта карта начинается со строки offset
+    pub(crate) fn embed(&self, offset: usize, len: usize) -> Selection {
+        assert!(offset + self.len <= len, "отбор из {} строк не помещается в {} строк со сдвигом {}", self.len, len, offset);
+        let mut result = Self::new(len);
+        for (idx, word) in result.words.iter_mut().enumerate() {
+            *word = self.bits_from((idx * 64) as i64 - offset as i64);
+        }
+        result
+    }
+
+    // Непрерывные отрезки отобранных строк — удобно передавать в get_values другой колонки
+    pub fn ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for row in self.iter_indices() {
+            match ranges.last_mut() {
+                Some(last) if last.end == row => last.end += 1,
+                _ => ranges.push(row..row + 1),
+            }
+        }
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use crate::error::ColumnarError;
+    use crate::{ColumnBuilder, Predicate};
+
+    // Детерминированный генератор для случайных отборов
+    struct Xorshift(u64);
+
+    impl Xorshift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn indices(&mut self, len: usize, density: u64) -> Vec<u32> {
+            (0..len as u32).filter(|_| self.next() % 100 < density).collect()
+        }
+    }
+
+    fn as_set(selection: &Selection) -> HashSet<u32> {
+        selection.to_indices().unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn test_set_operations_match_hash_sets() {
+        let mut rng = Xorshift(0x2545_F491_4F6C_DD1D);
+        for len in [0, 1, 63, 64, 65, 127, 128, 1000, 4099] {
+            for (da, db) in [(0, 50), (3, 97), (50, 50), (100, 10)] {
+                let (a, b) = (rng.indices(len, da), rng.indices(len, db));
+                let (sa, sb) = (Selection::from_indices(len, &a).unwrap(), Selection::from_indices(len, &b).unwrap());
+                let (ha, hb): (HashSet<u32>, HashSet<u32>) = (a.iter().copied().collect(), b.iter().copied().collect());
+                let everything: HashSet<u32> = (0..len as u32).collect();
+
+                assert_eq!(as_set(&sa.and(&sb)), &ha & &hb, "len {}", len);
+                assert_eq!(as_set(&sa.or(&sb)), &ha | &hb, "len {}", len);
+                assert_eq!(as_set(&sa.not()), &everything - &ha, "len {}", len);
+                assert_eq!(sa.not().count(), len - ha.len());
+                assert_eq!(sa.not().not(), sa);
+                // Де Морган на картах
+                assert_eq!(sa.and(&sb).not(), sa.not().or(&sb.not()));
+
+                let from = len / 3;
+                let in_range = ha.iter().filter(|idx| (from..len).contains(&(**idx as usize))).count();
+                assert_eq!(sa.count_range(from..len), in_range);
+                assert_eq!(sa.to_indices().unwrap(), a, "номера по возрастанию без повторов");
+
+                // slice и embed сдвигают номера строк целыми словами
+                let sliced: HashSet<u32> = ha.iter().filter(|idx| **idx as usize >= from).map(|idx| idx - from as u32).collect();
+                assert_eq!(as_set(&sa.slice(from..len)), sliced);
+                let embedded: HashSet<u32> = ha.iter().map(|idx| idx + 37).collect();
+                assert_eq!(as_set(&sa.embed(37, len + 100)), embedded);
+                assert_eq!(sa.embed(37, len + 100).slice(37..len + 37), sa);
+            }
+        }
+    }
+
+    #[test]
+    fn test_full_ranges_and_tail() {
+        let mut odds = Selection::full(70).not();
+        assert_eq!(odds.count(), 0);
+        odds = Selection::from_indices(70, &(1..70).step_by(2).collect::<Vec<_>>()).unwrap();
+        assert!(odds.get(69) && !odds.get(70));
+        assert_eq!(odds.not().count(), 35);
+        assert_eq!(Selection::full(70).count(), 70);
+        assert_eq!(Selection::full(64).words(), &[u64::MAX]);
+        assert!(Selection::full(0).words().is_empty());
+
+        let mut ranges = Selection::new(300);
+        ranges.set_range(5..200);
+        ranges.set_range(250..300);
+        assert_eq!(ranges.ranges(), vec![5..200, 250..300]);
+        assert_eq!(ranges.count(), 195 + 50);
+        // Повторы в номерах схлопываются
+        assert_eq!(Selection::from_indices(10, &[3, 3, 9]).unwrap().to_indices().unwrap(), vec![3, 9]);
+        assert!(matches!(Selection::from_indices(10, &[10]), Err(ColumnarError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_filter_aggregate_and_take_share_one_selection() {
+        let values: Vec<i64> = (0..10_000).map(|i| (i * 37) % 1000).collect();
+        let mut builder = ColumnBuilder::from_i64("v".to_string(), &values);
+        builder.set_chunk_rows(1000);
+        let column = builder.build_in_memory().unwrap();
+
+        let low = column.filter(Predicate::Lt(100)).unwrap();
+        let even = Selection::from_indices(values.len(), &(0..10_000).step_by(2).collect::<Vec<_>>()).unwrap();
+        let both = low.and(&even);
+        let expected: Vec<i64> = values.iter().enumerate().filter(|(i, v)| **v < 100 && i % 2 == 0).map(|(_, v)| *v).collect();
+
+        assert_eq!(column.count(Some(&both)).unwrap(), expected.len() as u64);
+        assert_eq!(column.sum(Some(&both)).unwrap(), expected.iter().map(|v| *v as i128).sum::<i128>());
+        let taken = column.take_selected(&both).unwrap().build_in_memory().unwrap();
+        assert_eq!(taken.values().unwrap(), expected);
+        assert!(matches!(column.take_selected(&Selection::new(3)), Err(ColumnarError::InvalidInput(_))));
+    }
+}
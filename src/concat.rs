@@ -0,0 +1,164 @@
+use std::{path::Path, sync::Arc};
+use crate::compute::Selection;
+use crate::error::{invalid_input, Result};
+use crate::storage::Column;
+use crate::tombstone::write_deletions;
+use crate::types::ColumnType;
+use crate::writer::ColumnWriter;
+
+impl<T: ColumnType> Column<T> {
+    // Колонка из частей подряд в файле path. Чанки переносятся как есть, без распаковки,
+    // поэтому части должны совпадать по кодеку и кодированию (словарные — и по словарю).
+    // Короткие последние чанки частей остаются отдельными чанками: поиск строки не
+    // требует одинакового размера чанков. Имя и настройки метаданных берутся у первой части
+    pub fn concat(parts: &[&Column<T>], path: &Path) -> Result<Column<T>> {
+        let Some(first) = parts.first() else {
+            return Err(invalid_input("нечего объединять: список колонок пуст"));
+        };
+        for (idx, part) in parts.iter().enumerate().skip(1) {
+            if part.codec != first.codec {
+                return Err(invalid_input(format!(
+                    "колонка {} сжата {:?}, а первая — {:?}",
+                    idx, part.codec, first.codec
+                )));
+            }
+            if part.encoding != first.encoding {
+                return Err(invalid_input(format!(
+                    "колонка {} закодирована {:?}, а первая — {:?}",
+                    idx, part.encoding, first.encoding
+                )));
+            }
+            let same_dictionary = part.dictionary.len() == first.dictionary.len()
+                && part.dictionary.iter().zip(&first.dictionary).all(|(a, b)| a.total_cmp(b).is_eq());
+            if !same_dictionary {
+                return Err(invalid_input(format!("словарь колонки {} отличается от словаря первой", idx)));
+            }
+            if part.distinct.precision() != first.distinct.precision() {
+                return Err(invalid_input(format!(
+                    "точность HyperLogLog колонки {} — {}, а первой — {}",
+                    idx,
+                    part.distinct.precision(),
+                    first.distinct.precision()
+                )));
+            }
+        }
+
+        let mut writer = ColumnWriter::create_like(first, path)?;
+        for part in parts {
+            writer.copy_chunks(part)?;
+        }
+        let mut column = writer.finish()?;
+
+        // Удалённые строки частей остаются удалёнными и в объединённой колонке
+        if parts.iter().any(|part| part.deleted_rows().is_some()) {
+            let mut deleted = Selection::new(column.len());
+            let mut offset = 0;
+            for part in parts {
+                if let Some(part_deleted) = part.deleted_rows() {
+                    deleted.union_with(&part_deleted.embed(offset, column.len()));
+                }
+                offset += part.len();
+            }
+            write_deletions(path, Some(&deleted))?;
+            column.deleted = Some(Arc::new(deleted));
+        }
+        Ok(column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ColumnarError;
+    use std::borrow::Cow;
+    use crate::{Agg, AggValue, Codec, ColumnBuilder, Encoding};
+
+    fn part(values: &[Option<i64>], chunk_rows: usize, codec: Codec) -> Column<i64> {
+        let mut builder = ColumnBuilder::from_nullable("v".to_string(), values);
+        builder.set_chunk_rows(chunk_rows);
+        builder.compress_with(codec).unwrap();
+        builder.build_in_memory().unwrap()
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_concat_three_columns_round_trips_every_row() {
+        let a: Vec<Option<i64>> = (0..250).map(|i| (i % 7 != 0).then_some(i * 3)).collect();
+        let b: Vec<Option<i64>> = (0..40).map(|i| Some(-i)).collect();
+        let c: Vec<Option<i64>> = (0..333).map(|i| (i % 5 != 2).then_some(10_000 + i)).collect();
+        let parts = [part(&a, 100, Codec::Zstd { level: 3 }), part(&b, 100, Codec::Zstd { level: 3 }), part(&c, 128, Codec::Zstd { level: 3 })];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("concat.col");
+
+        let column = Column::concat(&parts.iter().collect::<Vec<_>>(), &path).unwrap();
+        let expected: Vec<Option<i64>> = a.iter().chain(&b).chain(&c).copied().collect();
+        assert_eq!(column.len(), expected.len());
+        for (row, value) in expected.iter().enumerate() {
+            assert_eq!(column.get_nullable(row), Some(*value), "строка {}", row);
+        }
+        assert_eq!(column.null_count, expected.iter().filter(|v| v.is_none()).count() as u64);
+        assert_eq!((column.min, column.max), (-39, 10_331));
+        assert!(!column.is_sorted);
+
+        // Чанки скопированы байт в байт, включая короткие хвосты частей
+        let copied: Vec<Cow<[u8]>> = (0..column.chunks.len()).map(|idx| column.checked_chunk(idx).unwrap()).collect();
+        let original: Vec<Cow<[u8]>> = parts
+            .iter()
+            .flat_map(|part| (0..part.chunks.len()).map(move |idx| part.checked_chunk(idx).unwrap()))
+            .collect();
+        assert_eq!(copied, original);
+        assert!(column.contains(-17).unwrap());
+        assert!(column.contains(10_100).unwrap());
+        assert!(!column.contains(1).unwrap());
+
+        let reopened = Column::<i64>::open(&path).unwrap();
+        assert_eq!(reopened.values().unwrap(), column.values().unwrap());
+    }
+
+    #[test]
+    fn test_concat_carries_deleted_rows() {
+        let values: Vec<Option<i64>> = (0..50).map(Some).collect();
+        let (mut a, mut b) = (part(&values, 16, Codec::Lz4), part(&values, 16, Codec::Lz4));
+        a.delete_rows(&[0, 49]).unwrap();
+        b.delete_rows(&[7]).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("concat.col");
+        Column::concat(&[&a, &b], &path).unwrap();
+        let column = Column::<i64>::open(&path).unwrap();
+        assert_eq!(column.live_count(), 97);
+        assert!(column.is_deleted(0) && column.is_deleted(49) && column.is_deleted(57));
+        assert_eq!(column.aggregate(Agg::Sum, None).unwrap(), AggValue::Sum(2 * 1225 - 49 - 7));
+    }
+
+    #[test]
+    fn test_concat_keeps_sortedness_only_across_ordered_seams() {
+        let ascending = |range: std::ops::Range<i64>| part(&range.map(Some).collect::<Vec<_>>(), 64, Codec::Lz4);
+        let dir = tempfile::tempdir().unwrap();
+        let (low, high) = (ascending(0..100), ascending(100..300));
+        let sorted = Column::concat(&[&low, &high], &dir.path().join("sorted.col")).unwrap();
+        assert!(sorted.is_sorted);
+        assert_eq!(sorted.values().unwrap(), (0..300).collect::<Vec<_>>());
+        let unsorted = Column::concat(&[&high, &low], &dir.path().join("unsorted.col")).unwrap();
+        assert!(!unsorted.is_sorted);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_concat_rejects_mismatched_parts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("concat.col");
+        let values = [Some(1), Some(2), None];
+        let lz4 = part(&values, 2, Codec::Lz4);
+        let zstd = part(&values, 2, Codec::Zstd { level: 3 });
+        let invalid = |result: Result<Column<i64>>| matches!(result, Err(ColumnarError::InvalidInput(_)));
+        assert!(invalid(Column::concat(&[], &path)));
+        assert!(invalid(Column::concat(&[&lz4, &zstd], &path)));
+
+        let mut builder = ColumnBuilder::from_nullable("v".to_string(), &values);
+        builder.compress_with(Codec::Lz4).unwrap();
+        builder.set_encoding(Encoding::Delta);
+        let delta = builder.build_in_memory().unwrap();
+        assert!(invalid(Column::concat(&[&lz4, &delta], &path)));
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,412 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+use crate::error::{invalid_input, ColumnarError, Result};
+use crate::ingest::{parse_cell, parse_error, TableSink};
+use crate::scan::{ColumnValues, RowBatch};
+use crate::schema::Schema;
+use crate::table::Table;
+
+// Что делать с записью, которая не разбирается
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnBadRow {
+    // Первая же ошибка прерывает загрузку
+    #[default]
+    Fail,
+    // Запись пропускается, а ошибка возвращается вместе с таблицей
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    // Первая запись — имена колонок; без заголовка колонки идут в порядке схемы
+    pub has_header: bool,
+    pub on_bad_row: OnBadRow,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self { delimiter: b',', has_header: true, on_bad_row: OnBadRow::Fail }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvWriteOptions {
+    pub delimiter: u8,
+    // Имена колонок первой строкой
+    pub header: bool,
+    // Чем записывается NULL. Если это пустая строка, пустые строковые значения
+    // берутся в кавычки, чтобы их можно было отличить от NULL
+    pub null: String,
+}
+
+impl Default for CsvWriteOptions {
+    fn default() -> Self {
+        Self { delimiter: b',', header: true, null: String::new() }
+    }
+}
+
+fn check_delimiter(delimiter: u8) -> Result<()> {
+    if !delimiter.is_ascii() || matches!(delimiter, b'"' | b'\r' | b'\n') {
+        return Err(invalid_input(format!("разделитель {:?} недопустим в CSV", delimiter as char)));
+    }
+    Ok(())
+}
+
+// Потоковый разбор записей по RFC 4180: поле в кавычках может содержать разделитель,
+// перевод строки и удвоенную кавычку. Концы строк \n и \r\n
+pub(crate) struct CsvReader<R: BufRead> {
+    inner: R,
+    delimiter: char,
+    line: String,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    pub fn new(inner: R, delimiter: u8) -> Result<Self> {
+        check_delimiter(delimiter)?;
+        Ok(Self { inner, delimiter: delimiter as char, line: String::new() })
+    }
+
+    // Поля следующей записи; None — входные данные кончились. row нужен только для ошибки
+    pub fn next_record(&mut self, row: u64) -> Result<Option<Vec<String>>> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        // Поле начиналось с кавычки; после закрывающей допустим только разделитель
+        let mut was_quoted = false;
+        let mut started = false;
+        loop {
+            self.line.clear();
+            if self.inner.read_line(&mut self.line)? == 0 {
+                if quoted {
+                    return Err(parse_error(row, "кавычка не закрыта до конца файла"));
+                }
+                if !started {
+                    return Ok(None);
+                }
+                fields.push(field);
+                return Ok(Some(fields));
+            }
+            started = true;
+            let line = self.line.strip_suffix('\n').map_or(self.line.as_str(), |l| l.strip_suffix('\r').unwrap_or(l));
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                if quoted {
+                    if c != '"' {
+                        field.push(c);
+                    } else if chars.peek() == Some(&'"') {
+                        field.push('"');
+                        chars.next();
+                    } else {
+                        quoted = false;
+                    }
+                } else if c == self.delimiter {
+                    fields.push(std::mem::take(&mut field));
+                    was_quoted = false;
+                } else if c == '"' && field.is_empty() && !was_quoted {
+                    quoted = true;
+                    was_quoted = true;
+                } else if was_quoted {
+                    return Err(parse_error(row, format!("символ {:?} после закрывающей кавычки", c)));
+                } else {
+                    field.push(c);
+                }
+            }
+            if !quoted {
+                // Пустая строка вне кавычек — не запись
+                if fields.is_empty() && field.is_empty() && !was_quoted {
+                    started = false;
+                    continue;
+                }
+                fields.push(field);
+                return Ok(Some(fields));
+            }
+            // Перевод строки внутри кавычек — часть значения
+            field.push('\n');
+        }
+    }
+}
+
+impl Table {
+    // Таблица из CSV-файла с именем по имени файла без расширения. Во втором элементе —
+    // ошибки пропущенных записей, если выбран OnBadRow::Skip
+    pub fn from_csv(path: &Path, schema: &Schema, options: CsvOptions) -> Result<(Table, Vec<ColumnarError>)> {
+        let name = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        Self::from_csv_reader(name, BufReader::new(File::open(path)?), schema, options)
+    }
+
+    // Записи читаются и разбираются по одной, числовые колонки пишутся потоково.
+    // Номер записи в ошибках считается с единицы без заголовка. Лишние колонки CSV
+    // с заголовком пропускаются, а каждое поле схемы должно в нём найтись
+    pub fn from_csv_reader(
+        name: String,
+        reader: impl BufRead,
+        schema: &Schema,
+        options: CsvOptions,
+    ) -> Result<(Table, Vec<ColumnarError>)> {
+        let mut reader = CsvReader::new(reader, options.delimiter)?;
+        // Для каждого поля схемы — номер колонки CSV
+        let positions: Vec<usize> = if options.has_header {
+            let header = reader.next_record(0)?.unwrap_or_default();
+            schema
+                .fields
+                .iter()
+                .map(|field| {
+                    header
+                        .iter()
+                        .position(|name| *name == field.name)
+                        .ok_or_else(|| invalid_input(format!("колонки {} нет в заголовке CSV", field.name)))
+                })
+                .collect::<Result<_>>()?
+        } else {
+            (0..schema.len()).collect()
+        };
+        let width = positions.iter().max().map_or(0, |max| max + 1);
+
+        let mut sink = TableSink::new(schema)?;
+        let mut skipped = Vec::new();
+        let mut row = 0;
+        loop {
+            row += 1;
+            // Испорченная запись дочитывается до конца строки, так что следующая
+            // разбирается с начала; незакрытая кавычка съедает остаток файла
+            let record = match reader.next_record(row) {
+                Ok(Some(record)) => Ok(record),
+                Ok(None) => break,
+                Err(e @ ColumnarError::Parse { .. }) => Err(e),
+                Err(e) => return Err(e),
+            };
+            let cells = record.and_then(|record| {
+                if record.len() < width {
+                    return Err(parse_error(row, format!("в записи {} полей, а нужно хотя бы {}", record.len(), width)));
+                }
+                schema
+                    .fields
+                    .iter()
+                    .zip(&positions)
+                    .map(|(field, pos)| parse_cell(field, &record[*pos]).map_err(|detail| parse_error(row, detail)))
+                    .collect::<Result<Vec<_>>>()
+            });
+            match (cells, options.on_bad_row) {
+                (Ok(cells), _) => sink.push_row(cells)?,
+                (Err(e), OnBadRow::Skip) => skipped.push(e),
+                (Err(e), OnBadRow::Fail) => return Err(e),
+            }
+        }
+        Ok((sink.finish(name)?, skipped))
+    }
+}
+
+// Поле в кавычках, если без них его не прочитать обратно
+fn push_field(line: &mut String, value: &str, options: &CsvWriteOptions) {
+    let delimiter = options.delimiter as char;
+    if value.contains([delimiter, '"', '\r', '\n']) || value == options.null {
+        line.push('"');
+        line.push_str(&value.replace('"', "\"\""));
+        line.push('"');
+    } else {
+        line.push_str(value);
+    }
+}
+
+fn push_value<T: ToString>(line: &mut String, value: Option<T>, options: &CsvWriteOptions) {
+    match value {
+        Some(value) => push_field(line, &value.to_string(), options),
+        None => line.push_str(&options.null),
+    }
+}
+
+// Пакеты скана в CSV по одному, так что результат любого размера не держится в памяти
+// целиком. Заголовок берётся из имён колонок первого пакета; пустой результат даёт
+// пустой вывод. f64 пишется в кратчайшем виде, который читается обратно без потерь.
+// Возвращает число записанных строк
+pub fn write_csv(
+    batches: impl IntoIterator<Item = Result<RowBatch>>,
+    mut writer: impl Write,
+    options: &CsvWriteOptions,
+) -> Result<u64> {
+    check_delimiter(options.delimiter)?;
+    let delimiter = options.delimiter as char;
+    let mut rows = 0;
+    let mut line = String::new();
+    let mut header = options.header;
+    for batch in batches {
+        let batch = batch?;
+        if header {
+            header = false;
+            line.clear();
+            for (idx, (name, _)) in batch.columns.iter().enumerate() {
+                if idx > 0 {
+                    line.push(delimiter);
+                }
+                push_field(&mut line, name, options);
+            }
+            line.push('\n');
+            writer.write_all(line.as_bytes())?;
+        }
+        for row in 0..batch.len() {
+            line.clear();
+            for (idx, (_, values)) in batch.columns.iter().enumerate() {
+                if idx > 0 {
+                    line.push(delimiter);
+                }
+                match values {
+                    ColumnValues::Int32(values) => push_value(&mut line, values[row], options),
+                    ColumnValues::Int64(values) => push_value(&mut line, values[row], options),
+                    ColumnValues::Float64(values) => push_value(&mut line, values[row], options),
+                    ColumnValues::Utf8(values) => push_field(&mut line, &values[row], options),
+                    ColumnValues::Bool(values) => push_value(&mut line, Some(values[row]), options),
+                }
+            }
+            line.push('\n');
+            writer.write_all(line.as_bytes())?;
+        }
+        rows += batch.len() as u64;
+    }
+    writer.flush()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use crate::filter::Predicate;
+    use crate::scan::ColumnPredicate;
+    use crate::schema::Field;
+    use crate::table::TableColumn;
+    use crate::types::DataType;
+    use tempfile::NamedTempFile;
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("amount", DataType::Float64, true),
+            Field::new("note", DataType::Utf8, false),
+            Field::new("ok", DataType::Bool, false),
+        ])
+    }
+
+    fn load(text: &str, options: CsvOptions) -> Result<(Table, Vec<ColumnarError>)> {
+        Table::from_csv_reader("t".to_string(), text.as_bytes(), &schema(), options)
+    }
+
+    #[test]
+    fn test_header_quotes_and_types() {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        // Лишняя колонка extra пропускается, порядок колонок не совпадает со схемой
+        write!(
+            file,
+            "note,id,extra,amount,ok\r\n\"a, b\",1,x,2.5,true\n\"say \"\"hi\"\"\",2,y,,false\n\"two\nlines\",3,z,-1e3,1\n"
+        )
+        .unwrap();
+        let (table, skipped) = Table::from_csv(file.path(), &schema(), CsvOptions::default()).unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(table.row_count(), 3);
+        assert_eq!(table.schema(), schema());
+        assert_eq!(table.column::<i32>("id").unwrap().values().unwrap(), vec![1, 2, 3]);
+        assert_eq!(table.column::<f64>("amount").unwrap().nullable_values().unwrap(), vec![Some(2.5), None, Some(-1000.0)]);
+        let TableColumn::Utf8(note) = table.get("note").unwrap() else { panic!() };
+        assert_eq!([note.get_str(0), note.get_str(1), note.get_str(2)], [Some("a, b"), Some("say \"hi\""), Some("two\nlines")]);
+        let TableColumn::Bool(ok) = table.get("ok").unwrap() else { panic!() };
+        assert_eq!(ok.count_true(), 2);
+    }
+
+    #[test]
+    fn test_bad_value_reports_its_row() {
+        let text = "id;amount;note;ok\n1;1.0;a;true\n2;2.0;b;false\nthree;3.0;c;true\n4;4.0;d;false\n";
+        let options = CsvOptions { delimiter: b';', ..CsvOptions::default() };
+        match load(text, options) {
+            Err(ColumnarError::Parse { row, detail }) => {
+                assert_eq!(row, 3);
+                assert!(detail.contains("three"), "{}", detail);
+            }
+            other => panic!("{:?}", other.map(|(t, _)| t.row_count())),
+        }
+
+        let (table, skipped) = load(text, CsvOptions { on_bad_row: OnBadRow::Skip, ..options }).unwrap();
+        assert_eq!(table.column::<i32>("id").unwrap().values().unwrap(), vec![1, 2, 4]);
+        assert!(matches!(skipped.as_slice(), [ColumnarError::Parse { row: 3, .. }]));
+    }
+
+    #[test]
+    fn test_rows_without_header_and_malformed_records() {
+        let options = CsvOptions { has_header: false, on_bad_row: OnBadRow::Skip, ..CsvOptions::default() };
+        // Короткая запись, NULL в колонке без NULL и мусор после кавычки пропускаются
+        let text = "1,,x,0\n\n2,1.5\n,1.0,y,1\n\"3\"z,1.0,w,0\n5,2.0,\"\",true\n";
+        let (table, skipped) = load(text, options).unwrap();
+        assert_eq!(table.column::<i32>("id").unwrap().values().unwrap(), vec![1, 5]);
+        let rows: Vec<u64> = skipped
+            .iter()
+            .map(|e| match e {
+                ColumnarError::Parse { row, .. } => *row,
+                other => panic!("{:?}", other),
+            })
+            .collect();
+        assert_eq!(rows, vec![2, 3, 4]);
+
+        assert!(matches!(load("id,amount,note,ok\n1,2,\"open", CsvOptions::default()), Err(ColumnarError::Parse { row: 1, .. })));
+        assert!(matches!(load("id,note\n", CsvOptions::default()), Err(ColumnarError::InvalidInput(_))));
+        assert!(matches!(load("", CsvOptions { delimiter: b'"', ..CsvOptions::default() }), Err(ColumnarError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_filtered_scan_round_trips_through_csv() {
+        let mut text = String::from("id,amount,note,ok\n");
+        for i in 0..3000 {
+            let amount = if i % 7 == 0 { String::new() } else { format!("{}", i as f64 / 3.0) };
+            let note = match i % 4 {
+                0 => "\"a, \"\"quoted\"\"\nnote\"".to_string(),
+                1 => "\"\"".to_string(),
+                _ => format!("n{}", i),
+            };
+            text.push_str(&format!("{},{},{},{}\n", i, amount, note, i % 3 == 0));
+        }
+        let (table, _) = load(&text, CsvOptions::default()).unwrap();
+        let filter = ColumnPredicate::Int32("id".to_string(), Predicate::Ge(1000));
+        let mut out = Vec::new();
+        let batches = table.scan(filter, &["id", "amount", "note", "ok"]).unwrap();
+        let written = write_csv(batches, &mut out, &CsvWriteOptions::default()).unwrap();
+        assert_eq!(written, 2000);
+
+        let (back, skipped) = load(std::str::from_utf8(&out).unwrap(), CsvOptions::default()).unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(back.column::<i32>("id").unwrap().values().unwrap(), (1000..3000).collect::<Vec<_>>());
+        let amounts = table.column::<f64>("amount").unwrap().nullable_values().unwrap();
+        assert_eq!(back.column::<f64>("amount").unwrap().nullable_values().unwrap(), amounts[1000..]);
+        let (TableColumn::Utf8(before), TableColumn::Utf8(after)) = (table.get("note").unwrap(), back.get("note").unwrap()) else {
+            panic!()
+        };
+        assert!((0..2000).all(|row| before.get_str(row + 1000) == after.get_str(row)));
+        assert_eq!(after.get_str(1), Some(""));
+    }
+
+    #[test]
+    fn test_write_options_delimiter_null_and_header() {
+        let batch = RowBatch {
+            rows: vec![0, 1],
+            columns: vec![
+                ("x;y".to_string(), ColumnValues::Int64(vec![Some(-1), None])),
+                ("f".to_string(), ColumnValues::Float64(vec![Some(0.1), Some(f64::NAN)])),
+                ("s".to_string(), ColumnValues::Utf8(vec!["NULL".to_string(), String::new()])),
+                ("b".to_string(), ColumnValues::Bool(vec![true, false])),
+            ],
+        };
+        let options = CsvWriteOptions { delimiter: b';', null: "NULL".to_string(), ..CsvWriteOptions::default() };
+        let mut out = Vec::new();
+        write_csv([Ok(batch.clone()), Ok(batch.clone())], &mut out, &options).unwrap();
+        let row = "-1;0.1;\"NULL\";true\nNULL;NaN;;false\n";
+        assert_eq!(String::from_utf8(out).unwrap(), format!("\"x;y\";f;s;b\n{}{}", row, row));
+
+        let mut out = Vec::new();
+        let options = CsvWriteOptions { header: false, ..CsvWriteOptions::default() };
+        assert_eq!(write_csv(std::iter::empty(), &mut out, &options).unwrap(), 0);
+        write_csv([Ok(batch)], &mut out, &options).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "-1,0.1,NULL,true\n,NaN,\"\",false\n");
+        let bad = CsvWriteOptions { delimiter: b'\n', ..options };
+        assert!(matches!(write_csv(std::iter::empty(), Vec::new(), &bad), Err(ColumnarError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeSet;
+use crate::bools::bit_is_set;
+use crate::encoding::TotalOrd;
+use crate::error::Result;
+use crate::storage::Column;
+use crate::types::ColumnType;
+
+impl<T: ColumnType> Column<T> {
+    // Различные значения живых строк колонки без NULL по возрастанию в полном порядке типа.
+    // С limit просмотр останавливается, как только набрано limit значений, и
+    // следующие чанки не распаковываются; какие значения попадут в результат, зависит
+    // от порядка строк. Чанк с одним значением (min == max) берётся из zone map, а у
+    // отсортированной колонки чанк внутри уже просмотренного диапазона пропускается
+    pub fn distinct(&self, limit: Option<usize>) -> Result<Vec<T>> {
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut seen: BTreeSet<TotalOrd<T>> = BTreeSet::new();
+        // Граница просмотренного у отсортированной колонки: все значения до неё уже в seen.
+        // Это наибольшее из seen, а не max чанка: строка с max могла быть удалена
+        let mut seen_up_to: Option<T> = None;
+        for idx in 0..self.chunks().len() {
+            if seen.len() >= limit {
+                break;
+            }
+            let chunk = &self.chunks()[idx];
+            if chunk.null_count == chunk.row_count() {
+                continue;
+            }
+            if self.is_sorted && seen_up_to.is_some_and(|bound| chunk.max.total_cmp(&bound).is_le()) {
+                continue;
+            }
+            let range = chunk.first_row as usize..chunk.end_row() as usize;
+            let has_deleted = self.deleted_rows().is_some_and(|deleted| deleted.count_range(range) > 0);
+            // NaN в zone map не попадает, поэтому без него min == max значит одно значение
+            if !self.has_nan && !has_deleted && chunk.min.total_cmp(&chunk.max).is_eq() {
+                seen.insert(TotalOrd(chunk.min));
+            } else {
+                let values = self.chunk_values(idx)?;
+                let validity = self.chunk_validity(idx)?;
+                for (row, raw) in values.chunks_exact(T::WIDTH).enumerate() {
+                    if validity.as_ref().is_none_or(|bits| bit_is_set(bits, row)) && !self.is_deleted(chunk.first_row as usize + row) {
+                        seen.insert(TotalOrd(T::read_le(raw)));
+                        if seen.len() >= limit {
+                            break;
+                        }
+                    }
+                }
+            }
+            seen_up_to = seen.last().map(|value| value.0);
+        }
+        Ok(seen.into_iter().map(|value| value.0).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Codec, ColumnBuilder};
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_distinct_with_and_without_limit() {
+        let values: Vec<i32> = (0..50_000).map(|i| (i * 7919) % 300 - 150).collect();
+        let mut builder = ColumnBuilder::from_i32("d".to_string(), &values);
+        builder.set_chunk_rows(5000);
+        builder.compress_with(Codec::zstd()).unwrap();
+        let column = builder.build_in_memory().unwrap();
+
+        assert_eq!(column.distinct(None).unwrap(), (-150..150).collect::<Vec<_>>());
+        assert_eq!(column.frames_decoded(), 10);
+
+        // Пять значений находятся в первом же чанке, остальные не распаковываются
+        let first = column.distinct(Some(5)).unwrap();
+        assert_eq!(column.frames_decoded(), 11);
+        let mut expected: Vec<i32> = Vec::new();
+        for value in &values {
+            if !expected.contains(value) {
+                expected.push(*value);
+            }
+            if expected.len() == 5 {
+                break;
+            }
+        }
+        expected.sort();
+        assert_eq!(first, expected);
+        assert!(column.distinct(Some(0)).unwrap().is_empty());
+        assert_eq!(column.distinct(Some(1000)).unwrap().len(), 300);
+    }
+
+    #[test]
+    fn test_distinct_on_identical_sorted_and_nullable_data() {
+        let mut same = ColumnBuilder::from_i64("same".to_string(), &vec![42; 20_000]);
+        same.set_chunk_rows(1000);
+        same.compress_with(Codec::Lz4).unwrap();
+        let same = same.build_in_memory().unwrap();
+        assert_eq!(same.distinct(None).unwrap(), vec![42]);
+        assert_eq!(same.frames_decoded(), 0);
+
+        // Отсортированная колонка с длинными сериями: чанки внутри уже виденного диапазона пропускаются
+        let runs: Vec<i32> = (0..20_000).map(|i| i / 2500).collect();
+        let mut sorted = ColumnBuilder::from_i32("runs".to_string(), &runs);
+        sorted.set_chunk_rows(1000);
+        sorted.compress_with(Codec::Lz4).unwrap();
+        let sorted = sorted.build_in_memory().unwrap();
+        assert!(sorted.stats().is_sorted);
+        assert_eq!(sorted.distinct(None).unwrap(), (0..8).collect::<Vec<_>>());
+        // Распаковываются только чанки, внутри которых меняется значение: 2, 7, 12 и 17
+        assert_eq!(sorted.frames_decoded(), 4);
+
+        let nullable = ColumnBuilder::from_nullable("n".to_string(), &[None, Some(1.5), Some(f64::NAN), None, Some(1.5)])
+            .build_in_memory()
+            .unwrap();
+        let found = nullable.distinct(None).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0], 1.5);
+        assert!(found[1].is_nan());
+        let empty = ColumnBuilder::<i32>::from_nullable("e".to_string(), &[None, None]).build_in_memory().unwrap();
+        assert!(empty.distinct(None).unwrap().is_empty());
+    }
+}
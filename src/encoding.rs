@@ -0,0 +1,275 @@
+use crate::error::{corrupt, Result};
+use crate::types::ColumnType;
+
+// Логическое кодирование значений перед сжатием. Каждый фрейм кодируется независимо,
+// поэтому его можно декодировать без соседних фреймов.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Plain,
+    // Первое значение и разности соседних значений (в 64 битах) в zigzag-varint
+    Delta,
+    // Пары (значение, длина серии u32)
+    Rle,
+    // Индексы u8/u16 в общий для колонки словарь, хранящийся в метаданных
+    Dictionary,
+}
+
+// Максимальное число различных значений, при котором индекс помещается в u16
+pub const MAX_DICTIONARY_SIZE: usize = u16::MAX as usize + 1;
+
+// Упорядоченный словарь различных значений колонки
+pub(crate) fn build_dictionary<T: ColumnType>(values: &[u8]) -> Option<Vec<T>> {
+    let mut distinct = std::collections::BTreeSet::new();
+    for value in values.chunks_exact(T::WIDTH).map(T::read_le) {
+        distinct.insert(TotalOrd(value));
+        if distinct.len() > MAX_DICTIONARY_SIZE {
+            return None;
+        }
+    }
+    Some(distinct.into_iter().map(|v| v.0).collect())
+}
+
+// Позиция значения в упорядоченном словаре
+pub(crate) fn dictionary_index<T: ColumnType>(dictionary: &[T], value: &T) -> Option<usize> {
+    dictionary.binary_search_by(|probe| probe.total_cmp(value)).ok()
+}
+
+// Обёртка для упорядоченных коллекций по ColumnType::total_cmp
+pub(crate) struct TotalOrd<T>(pub(crate) T);
+
+impl<T: ColumnType> PartialEq for TotalOrd<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.total_cmp(&other.0).is_eq()
+    }
+}
+
+impl<T: ColumnType> Eq for TotalOrd<T> {}
+
+impl<T: ColumnType> PartialOrd for TotalOrd<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: ColumnType> Ord for TotalOrd<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+fn index_width<T>(dictionary: &[T]) -> usize {
+    if dictionary.len() <= u8::MAX as usize + 1 { 1 } else { 2 }
+}
+
+impl Encoding {
+    // values — значения в little-endian, длина кратна T::WIDTH;
+    // dictionary используется только для Encoding::Dictionary
+    pub(crate) fn encode<T: ColumnType>(&self, values: &[u8], dictionary: &[T]) -> Vec<u8> {
+        match self {
+            Encoding::Plain => values.to_vec(),
+            Encoding::Delta => {
+                let mut out = Vec::with_capacity(values.len() / 2);
+                let mut prev = 0i64;
+                for value in values.chunks_exact(T::WIDTH).map(|v| T::read_le(v).to_bits()) {
+                    write_varint(&mut out, zigzag(value.wrapping_sub(prev)));
+                    prev = value;
+                }
+                out
+            }
+            Encoding::Rle => {
+                let mut out = Vec::new();
+                let mut values = values.chunks_exact(T::WIDTH);
+                if let Some(first) = values.next() {
+                    let (mut current, mut run) = (first, 1u32);
+                    for value in values {
+                        if value == current && run < u32::MAX {
+                            run += 1;
+                        } else {
+                            write_run(&mut out, current, run);
+                            (current, run) = (value, 1);
+                        }
+                    }
+                    write_run(&mut out, current, run);
+                }
+                out
+            }
+            Encoding::Dictionary => {
+                let width = index_width(dictionary);
+                let mut out = Vec::with_capacity(values.len() / 4 * width);
+                for value in values.chunks_exact(T::WIDTH).map(T::read_le) {
+                    // Инвариант: словарь строится по всем значениям колонки, а ColumnWriter::push
+                    // отклоняет значения вне словаря, поэтому поиск всегда успешен
+                    let idx = dictionary_index(dictionary, &value).expect("значение отсутствует в словаре");
+                    out.extend_from_slice(&(idx as u16).to_le_bytes()[..width]);
+                }
+                out
+            }
+        }
+    }
+
+    pub(crate) fn decode<T: ColumnType>(&self, encoded: &[u8], dictionary: &[T]) -> Result<Vec<u8>> {
+        match self {
+            Encoding::Plain => Ok(encoded.to_vec()),
+            Encoding::Delta => {
+                let mut out = Vec::with_capacity(encoded.len() * T::WIDTH);
+                let mut pos = 0;
+                let mut prev = 0i64;
+                while pos < encoded.len() {
+                    let value = prev.wrapping_add(unzigzag(read_varint(encoded, &mut pos)?));
+                    T::from_bits(value).write_le(&mut out);
+                    prev = value;
+                }
+                Ok(out)
+            }
+            Encoding::Rle => {
+                let pair_len = T::WIDTH + 4;
+                if !encoded.len().is_multiple_of(pair_len) {
+                    return Err(corrupt("длина RLE-фрейма не кратна размеру пары"));
+                }
+                let total: usize = encoded.chunks_exact(pair_len).map(|p| read_u32(&p[T::WIDTH..]) as usize).sum();
+                let mut out = Vec::with_capacity(total * T::WIDTH);
+                for pair in encoded.chunks_exact(pair_len) {
+                    let value = &pair[..T::WIDTH];
+                    for _ in 0..read_u32(&pair[T::WIDTH..]) {
+                        out.extend_from_slice(value);
+                    }
+                }
+                Ok(out)
+            }
+            Encoding::Dictionary => {
+                let width = index_width(dictionary);
+                let mut out = Vec::with_capacity(encoded.len() / width * T::WIDTH);
+                for raw in encoded.chunks(width) {
+                    if raw.len() != width {
+                        return Err(corrupt("длина словарного фрейма не кратна ширине индекса"));
+                    }
+                    let idx = if width == 1 { raw[0] as usize } else { u16::from_le_bytes([raw[0], raw[1]]) as usize };
+                    let value = dictionary
+                        .get(idx)
+                        .ok_or_else(|| corrupt(format!("индекс {} вне словаря размера {}", idx, dictionary.len())))?;
+                    value.write_le(&mut out);
+                }
+                Ok(out)
+            }
+        }
+    }
+
+    pub(crate) fn tag(&self) -> u8 {
+        match self {
+            Encoding::Plain => 0,
+            Encoding::Delta => 1,
+            Encoding::Rle => 2,
+            Encoding::Dictionary => 3,
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u8) -> Result<Self> {
+        match tag {
+            0 => Ok(Encoding::Plain),
+            1 => Ok(Encoding::Delta),
+            2 => Ok(Encoding::Rle),
+            3 => Ok(Encoding::Dictionary),
+            _ => Err(corrupt(format!("неизвестное кодирование значений: {}", tag))),
+        }
+    }
+}
+
+fn read_u32(chunk: &[u8]) -> u32 {
+    u32::from_le_bytes(chunk.try_into().unwrap())
+}
+
+fn write_run(out: &mut Vec<u8>, value: &[u8], run: u32) {
+    out.extend_from_slice(value);
+    out.extend_from_slice(&run.to_le_bytes());
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+fn unzigzag(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut result = 0u64;
+    for shift in (0..70).step_by(7) {
+        let byte = *buf.get(*pos).ok_or_else(|| corrupt("обрезанный varint в delta-фрейме"))?;
+        *pos += 1;
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+    Err(corrupt("слишком длинный varint в delta-фрейме"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_roundtrip_with_extremes() {
+        let values = [0i32, 1, -1, i32::MAX, i32::MIN, 5, 5, 4, i32::MIN, i32::MAX];
+        let bytes: Vec<u8> = values.iter().flat_map(|x| x.to_le_bytes()).collect();
+
+        let encoded = Encoding::Delta.encode::<i32>(&bytes, &[]);
+        assert_eq!(Encoding::Delta.decode::<i32>(&encoded, &[]).unwrap(), bytes);
+        assert!(Encoding::Delta.decode::<i32>(&[0x80], &[]).is_err());
+
+        // Разности i64 не помещаются в 32 бита
+        let values = [i64::MIN, i64::MAX, 0, -1, i64::MIN + 1, 1 << 40];
+        let bytes: Vec<u8> = values.iter().flat_map(|x| x.to_le_bytes()).collect();
+        let encoded = Encoding::Delta.encode::<i64>(&bytes, &[]);
+        assert_eq!(Encoding::Delta.decode::<i64>(&encoded, &[]).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_rle_roundtrip() {
+        let values = [7i32, 7, 7, -1, 7, 7, 0, 0, 0, 0];
+        let bytes: Vec<u8> = values.iter().flat_map(|x| x.to_le_bytes()).collect();
+
+        let encoded = Encoding::Rle.encode::<i32>(&bytes, &[]);
+        assert_eq!(encoded.len(), 4 * 8);
+        assert_eq!(Encoding::Rle.decode::<i32>(&encoded, &[]).unwrap(), bytes);
+        assert!(Encoding::Rle.encode::<i32>(&[], &[]).is_empty());
+        assert!(Encoding::Rle.decode::<i32>(&[1, 2, 3], &[]).is_err());
+
+        // Для i64 пара занимает 12 байт
+        let wide: Vec<u8> = [1i64 << 33, 1 << 33, -7].iter().flat_map(|x| x.to_le_bytes()).collect();
+        let encoded = Encoding::Rle.encode::<i64>(&wide, &[]);
+        assert_eq!(encoded.len(), 2 * 12);
+        assert_eq!(Encoding::Rle.decode::<i64>(&encoded, &[]).unwrap(), wide);
+    }
+
+    #[test]
+    fn test_dictionary_index_width() {
+        let narrow: Vec<u8> = (0..1000i32).flat_map(|x| (x % 200 * 1000).to_le_bytes()).collect();
+        let dict = build_dictionary::<i32>(&narrow).unwrap();
+        assert_eq!(dict.len(), 200);
+        let encoded = Encoding::Dictionary.encode(&narrow, &dict);
+        assert_eq!(encoded.len(), 1000);
+        assert_eq!(Encoding::Dictionary.decode(&encoded, &dict).unwrap(), narrow);
+
+        let wide: Vec<u8> = (0..3000i32).flat_map(|x| (x % 1500 - 700).to_le_bytes()).collect();
+        let dict = build_dictionary::<i32>(&wide).unwrap();
+        let encoded = Encoding::Dictionary.encode(&wide, &dict);
+        assert_eq!(encoded.len(), 3000 * 2);
+        assert_eq!(Encoding::Dictionary.decode(&encoded, &dict).unwrap(), wide);
+
+        // Индекс за пределами словаря — ошибка, а не паника
+        assert!(Encoding::Dictionary.decode::<i32>(&[5], &[1, 2]).is_err());
+
+        let too_many: Vec<u8> = (0..MAX_DICTIONARY_SIZE as i32 + 1).flat_map(|x| x.to_le_bytes()).collect();
+        assert!(build_dictionary::<i32>(&too_many).is_none());
+    }
+}
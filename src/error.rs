@@ -0,0 +1,96 @@
+use std::{error::Error, fmt, io};
+
+// Ошибка любой операции крейта
+#[derive(Debug)]
+pub enum ColumnarError {
+    // Ошибка файловой системы при чтении или записи
+    Io(io::Error),
+    // Файл не соответствует формату колонки: обрезан, испорчен или записан другим типом
+    Corrupt { detail: String },
+    // Фрейм не распаковывается кодеком
+    Decompression { detail: String },
+    // Неверные аргументы вызова
+    InvalidInput(String),
+    // Неверные параметры кэша
+    CacheConfig(String),
+    // Запись входных данных (CSV, JSON Lines) не разбирается; row — её номер с единицы
+    Parse { row: u64, detail: String },
+    // Колонку пишет другой писатель, а вызов не ждёт блокировки
+    Locked(String),
+}
+
+// Второй параметр оставлен, чтобы алиас не мешал Result с другим типом ошибки
+pub type Result<T, E = ColumnarError> = std::result::Result<T, E>;
+
+impl fmt::Display for ColumnarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnarError::Io(e) => write!(f, "ошибка ввода-вывода: {}", e),
+            ColumnarError::Corrupt { detail } => write!(f, "повреждённый файл колонки: {}", detail),
+            ColumnarError::Decompression { detail } => write!(f, "ошибка распаковки: {}", detail),
+            ColumnarError::InvalidInput(detail) => write!(f, "неверные аргументы: {}", detail),
+            ColumnarError::CacheConfig(detail) => write!(f, "неверные параметры кэша: {}", detail),
+            ColumnarError::Parse { row, detail } => write!(f, "ошибка разбора записи {}: {}", row, detail),
+            ColumnarError::Locked(detail) => write!(f, "колонка заблокирована: {}", detail),
+        }
+    }
+}
+
+impl Error for ColumnarError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ColumnarError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ColumnarError {
+    fn from(e: io::Error) -> Self {
+        ColumnarError::Io(e)
+    }
+}
+
+// Для вызывающего кода, которому достаточно io::Error
+impl From<ColumnarError> for io::Error {
+    fn from(e: ColumnarError) -> Self {
+        match e {
+            ColumnarError::Io(e) => e,
+            ColumnarError::Corrupt { .. } | ColumnarError::Decompression { .. } | ColumnarError::Parse { .. } => {
+                io::Error::new(io::ErrorKind::InvalidData, e)
+            }
+            ColumnarError::InvalidInput(_) | ColumnarError::CacheConfig(_) => {
+                io::Error::new(io::ErrorKind::InvalidInput, e)
+            }
+            ColumnarError::Locked(_) => io::Error::new(io::ErrorKind::WouldBlock, e),
+        }
+    }
+}
+
+pub(crate) fn corrupt(detail: impl Into<String>) -> ColumnarError {
+    ColumnarError::Corrupt { detail: detail.into() }
+}
+
+pub(crate) fn invalid_input(detail: impl Into<String>) -> ColumnarError {
+    ColumnarError::InvalidInput(detail.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion_to_io_error_keeps_kind_and_message() {
+        let err: io::Error = corrupt("обрезан footer").into();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("обрезан footer"));
+
+        let err: io::Error = ColumnarError::CacheConfig("размер 0".to_string()).into();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let original = io::Error::new(io::ErrorKind::NotFound, "нет файла");
+        let wrapped = ColumnarError::from(original);
+        assert!(wrapped.source().is_some());
+        assert_eq!(io::Error::from(wrapped).kind(), io::ErrorKind::NotFound);
+    }
+}
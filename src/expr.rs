@@ -0,0 +1,281 @@
+use std::ops::Range;
+use crate::error::{invalid_input, Result};
+use crate::compute::Selection;
+use crate::scan::ColumnPredicate;
+use crate::table::{Table, TableColumn};
+
+// Логическое условие над колонками таблицы. And без операндов отбирает все строки,
+// Or без операндов — ни одной. NULL не удовлетворяет ни одному листу, поэтому Not
+// отбирает строки с NULL
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Leaf(ColumnPredicate),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+}
+
+impl From<ColumnPredicate> for Expr {
+    fn from(predicate: ColumnPredicate) -> Self {
+        Expr::Leaf(predicate)
+    }
+}
+
+impl ColumnPredicate {
+    fn resolve<'a>(&self, table: &'a Table) -> Result<&'a TableColumn> {
+        let column = table.require(self.column())?;
+        let matches = matches!(
+            (self, column),
+            (ColumnPredicate::Int32(..), TableColumn::Int32(_))
+                | (ColumnPredicate::Int64(..), TableColumn::Int64(_))
+                | (ColumnPredicate::Float64(..), TableColumn::Float64(_))
+        );
+        if !matches {
+            return Err(invalid_input(format!(
+                "условие на колонку {} не совпадает с её типом {:?}",
+                self.column(), column.data_type()
+            )));
+        }
+        Ok(column)
+    }
+
+    fn filter(&self, column: &TableColumn, skip: impl Fn(Range<usize>) -> bool + Sync) -> Result<Selection> {
+        match (self, column) {
+            (ColumnPredicate::Int32(_, p), TableColumn::Int32(c)) => c.filter_chunks(p, skip),
+            (ColumnPredicate::Int64(_, p), TableColumn::Int64(c)) => c.filter_chunks(p, skip),
+            (ColumnPredicate::Float64(_, p), TableColumn::Float64(c)) => c.filter_chunks(p, skip),
+            _ => unreachable!("тип проверен в resolve"),
+        }
+    }
+
+    fn estimate_selectivity(&self, column: &TableColumn) -> f64 {
+        match (self, column) {
+            (ColumnPredicate::Int32(_, p), TableColumn::Int32(c)) => c.estimate_selectivity(p),
+            (ColumnPredicate::Int64(_, p), TableColumn::Int64(c)) => c.estimate_selectivity(p),
+            (ColumnPredicate::Float64(_, p), TableColumn::Float64(c)) => c.estimate_selectivity(p),
+            _ => unreachable!("тип проверен в resolve"),
+        }
+    }
+}
+
+impl Expr {
+    // Все листья ссылаются на существующие колонки подходящего типа. Проверка идёт
+    // до вычисления, чтобы отсечённый операнд не скрыл ошибку в условии
+    fn check(&self, table: &Table) -> Result<()> {
+        match self {
+            Expr::Leaf(predicate) => predicate.resolve(table).map(|_| ()),
+            Expr::And(operands) | Expr::Or(operands) => operands.iter().try_for_each(|e| e.check(table)),
+            Expr::Not(operand) => operand.check(table),
+        }
+    }
+
+    // Верхняя оценка доли отобранных строк по zone maps; Not оценивается как 1
+    fn estimate_selectivity(&self, table: &Table) -> f64 {
+        match self {
+            Expr::Leaf(predicate) => predicate.estimate_selectivity(table.get(predicate.column()).unwrap()),
+            Expr::And(operands) => operands.iter().map(|e| e.estimate_selectivity(table)).fold(1.0, f64::min),
+            Expr::Or(operands) => operands.iter().map(|e| e.estimate_selectivity(table)).sum::<f64>().min(1.0),
+            Expr::Not(_) => 1.0,
+        }
+    }
+
+    // Результат точен для строк care (для всех строк, если care не задан), остальные
+    // биты не определены. Чанки без строк из care не читаются
+    fn evaluate(&self, table: &Table, care: Option<&Selection>) -> Result<Selection> {
+        let rows = table.row_count();
+        match self {
+            Expr::Leaf(predicate) => {
+                let column = table.get(predicate.column()).unwrap();
+                predicate.filter(column, |range| care.is_some_and(|care| care.count_range(range) == 0))
+            }
+            // Первым идёт самый селективный операнд: следующим остаётся меньше строк
+            Expr::And(operands) => {
+                let mut acc = Selection::full(rows);
+                for operand in ordered(operands, table, false) {
+                    let mut narrowed = acc.clone();
+                    if let Some(care) = care {
+                        narrowed.intersect_with(care);
+                    }
+                    if narrowed.count() == 0 {
+                        return Ok(Selection::new(rows));
+                    }
+                    acc.intersect_with(&operand.evaluate(table, Some(&narrowed))?);
+                }
+                Ok(acc)
+            }
+            // Первым идёт самый широкий операнд: уже отобранные строки не проверяются снова
+            Expr::Or(operands) => {
+                let mut acc = Selection::new(rows);
+                for operand in ordered(operands, table, true) {
+                    let mut rest = acc.clone();
+                    rest.invert();
+                    if let Some(care) = care {
+                        rest.intersect_with(care);
+                    }
+                    if rest.count() == 0 {
+                        return Ok(Selection::full(rows));
+                    }
+                    acc.union_with(&operand.evaluate(table, Some(&rest))?);
+                }
+                Ok(acc)
+            }
+            Expr::Not(operand) => {
+                let mut bitmap = operand.evaluate(table, care)?;
+                bitmap.invert();
+                Ok(bitmap)
+            }
+        }
+    }
+}
+
+fn ordered<'a>(operands: &'a [Expr], table: &Table, widest_first: bool) -> Vec<&'a Expr> {
+    let mut keyed: Vec<(f64, &Expr)> = operands.iter().map(|e| (e.estimate_selectivity(table), e)).collect();
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    if widest_first {
+        keyed.reverse();
+    }
+    keyed.into_iter().map(|(_, e)| e).collect()
+}
+
+impl Table {
+    // Строки, удовлетворяющие условию; длина карты равна числу строк таблицы.
+    // Строка, удалённая хотя бы в одной колонке, не отбирается — даже под Not
+    pub fn evaluate(&self, expr: &Expr) -> Result<Selection> {
+        expr.check(self)?;
+        let mut selection = expr.evaluate(self, None)?;
+        if let Some(deleted) = self.deleted_rows() {
+            selection.difference_with(&deleted);
+        }
+        Ok(selection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ColumnarError;
+    use crate::{Codec, ColumnBuilder, Predicate};
+
+    const ROWS: usize = 20_000;
+
+    fn table() -> Table {
+        let a: Vec<i32> = (0..ROWS as i32).map(|i| (i * 31) % 1000).collect();
+        let b: Vec<Option<i64>> = (0..ROWS as i64).map(|i| (i % 17 != 0).then_some(i)).collect();
+        let c: Vec<f64> = (0..ROWS).map(|i| (i % 50) as f64).collect();
+        let mut a = ColumnBuilder::from_i32("a".to_string(), &a);
+        a.set_chunk_rows(1000);
+        a.compress_with(Codec::Lz4).unwrap();
+        let mut b = ColumnBuilder::from_nullable("b".to_string(), &b);
+        b.set_chunk_rows(1000);
+        b.compress_with(Codec::zstd()).unwrap();
+        let mut c = ColumnBuilder::from_f64("c".to_string(), &c);
+        c.set_chunk_rows(1000);
+
+        let mut table = Table::new("t".to_string());
+        table.add_column(a.build_in_memory().unwrap()).unwrap();
+        table.add_column(b.build_in_memory().unwrap()).unwrap();
+        table.add_column(c.build_in_memory().unwrap()).unwrap();
+        table
+    }
+
+    fn a(p: Predicate<i32>) -> Expr {
+        ColumnPredicate::Int32("a".to_string(), p).into()
+    }
+
+    fn b(p: Predicate<i64>) -> Expr {
+        ColumnPredicate::Int64("b".to_string(), p).into()
+    }
+
+    fn c(p: Predicate<f64>) -> Expr {
+        ColumnPredicate::Float64("c".to_string(), p).into()
+    }
+
+    fn not(e: Expr) -> Expr {
+        Expr::Not(Box::new(e))
+    }
+
+    // Построчная проверка без zone maps и отсечения чанков
+    fn naive(table: &Table, expr: &Expr, row: usize) -> bool {
+        match expr {
+            Expr::Leaf(ColumnPredicate::Int32(name, p)) => {
+                table.column::<i32>(name).unwrap().get_value(row).is_some_and(|v| p.matches(&v))
+            }
+            Expr::Leaf(ColumnPredicate::Int64(name, p)) => {
+                table.column::<i64>(name).unwrap().get_value(row).is_some_and(|v| p.matches(&v))
+            }
+            Expr::Leaf(ColumnPredicate::Float64(name, p)) => {
+                table.column::<f64>(name).unwrap().get_value(row).is_some_and(|v| p.matches(&v))
+            }
+            Expr::And(operands) => operands.iter().all(|e| naive(table, e, row)),
+            Expr::Or(operands) => operands.iter().any(|e| naive(table, e, row)),
+            Expr::Not(operand) => !naive(table, operand, row),
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_expressions_match_row_by_row_evaluation() {
+        let table = table();
+        for expr in [
+            // a > 5 AND b < 100 OR c == 7
+            Expr::Or(vec![Expr::And(vec![a(Predicate::Gt(5)), b(Predicate::Lt(100))]), c(Predicate::Eq(7.0))]),
+            Expr::And(vec![b(Predicate::Between(3000, 9000)), not(a(Predicate::In(vec![0, 31, 62])))]),
+            not(Expr::Or(vec![b(Predicate::Ge(0)), c(Predicate::Lt(0.0))])),
+            Expr::And(vec![]),
+            Expr::Or(vec![]),
+        ] {
+            let bitmap = table.evaluate(&expr).unwrap();
+            assert_eq!(bitmap.len(), ROWS);
+            let expected: Vec<usize> = (0..ROWS).filter(|row| naive(&table, &expr, *row)).collect();
+            assert_eq!(bitmap.iter_indices().collect::<Vec<_>>(), expected, "{:?}", expr);
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_de_morgan_equivalents_give_identical_bitmaps() {
+        let table = table();
+        let x = || a(Predicate::Le(400));
+        let y = || b(Predicate::Between(5000, 15_000));
+        let z = || c(Predicate::Ne(3.0));
+        let pairs = [
+            (not(Expr::And(vec![x(), y()])), Expr::Or(vec![not(x()), not(y())])),
+            (not(Expr::Or(vec![x(), y(), z()])), Expr::And(vec![not(x()), not(y()), not(z())])),
+            (
+                Expr::And(vec![z(), not(Expr::Or(vec![x(), y()]))]),
+                Expr::And(vec![z(), not(x()), not(y())]),
+            ),
+            (not(not(y())), y()),
+        ];
+        for (left, right) in pairs {
+            assert_eq!(table.evaluate(&left).unwrap(), table.evaluate(&right).unwrap(), "{:?}", left);
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_selective_operand_runs_first_and_skips_chunks() {
+        let table = table();
+        // b в [2500, 3400] лежит в чанках 2 и 3; по a читаются только они
+        let expr = Expr::And(vec![a(Predicate::Gt(5)), b(Predicate::Between(2500, 3400))]);
+        let bitmap = table.evaluate(&expr).unwrap();
+        assert!(bitmap.count() > 0);
+        assert_eq!(table.column::<i32>("a").unwrap().frames_decoded(), 2);
+
+        // Пустой первый операнд отменяет вычисление остальных
+        let before = table.column::<i32>("a").unwrap().frames_decoded();
+        let empty = Expr::And(vec![a(Predicate::Ne(1)), b(Predicate::Lt(-1))]);
+        assert_eq!(table.evaluate(&empty).unwrap().count(), 0);
+        assert_eq!(table.column::<i32>("a").unwrap().frames_decoded(), before);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_invalid_leaf_is_reported_even_if_skipped() {
+        let table = table();
+        let skipped = Expr::And(vec![b(Predicate::Lt(-1)), ColumnPredicate::Int64("a".to_string(), Predicate::Eq(1)).into()]);
+        assert!(matches!(table.evaluate(&skipped), Err(ColumnarError::InvalidInput(_))));
+        let missing = Expr::Or(vec![ColumnPredicate::Int32("d".to_string(), Predicate::Eq(1)).into()]);
+        assert!(matches!(table.evaluate(&missing), Err(ColumnarError::InvalidInput(_))));
+    }
+}
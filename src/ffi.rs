@@ -0,0 +1,302 @@
+// C API для встраивания в сервисы не на Rust; заголовок — include/columnar.h,
+// его можно перегенерировать cbindgen по cbindgen.toml. Каждая функция возвращает
+// код COLUMNAR_*, а текст последней ошибки потока отдаёт columnar_last_error_message.
+// Паники перехватываются и становятся кодом COLUMNAR_ERR_PANIC.
+//
+// Указатели: path — строка UTF-8 с завершающим нулём; column — значение, полученное
+// из columnar_column_open и ещё не освобождённое; out-параметры указывают на
+// записываемую память. Нарушение этих условий — неопределённое поведение, как и в C.
+// Один ColumnarColumn можно читать из нескольких потоков одновременно
+#![allow(clippy::missing_safety_doc)]
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    ptr,
+};
+use crate::error::{ColumnarError, Result};
+use crate::storage::Column;
+
+pub const COLUMNAR_OK: i32 = 0;
+pub const COLUMNAR_ERR_NULL_POINTER: i32 = 1;
+pub const COLUMNAR_ERR_IO: i32 = 2;
+pub const COLUMNAR_ERR_CORRUPT: i32 = 3;
+pub const COLUMNAR_ERR_DECOMPRESSION: i32 = 4;
+pub const COLUMNAR_ERR_INVALID_INPUT: i32 = 5;
+pub const COLUMNAR_ERR_LOCKED: i32 = 6;
+pub const COLUMNAR_ERR_OUT_OF_RANGE: i32 = 7;
+// Строка NULL или удалена; значение не записано
+pub const COLUMNAR_ERR_NULL_VALUE: i32 = 8;
+// Буфер меньше данных; нужный размер записан в out_len
+pub const COLUMNAR_ERR_BUFFER_TOO_SMALL: i32 = 9;
+pub const COLUMNAR_ERR_PANIC: i32 = 10;
+pub const COLUMNAR_ERR_OTHER: i32 = 11;
+
+// Колонка значений i32, открытая из C
+pub struct ColumnarColumn {
+    column: Column<i32>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+// Ошибка с кодом и сообщением для columnar_last_error_message
+struct Failure(i32, String);
+
+impl From<ColumnarError> for Failure {
+    fn from(e: ColumnarError) -> Self {
+        let code = match e {
+            ColumnarError::Io(_) => COLUMNAR_ERR_IO,
+            ColumnarError::Corrupt { .. } => COLUMNAR_ERR_CORRUPT,
+            ColumnarError::Decompression { .. } => COLUMNAR_ERR_DECOMPRESSION,
+            ColumnarError::InvalidInput(_) => COLUMNAR_ERR_INVALID_INPUT,
+            ColumnarError::Locked(_) => COLUMNAR_ERR_LOCKED,
+            ColumnarError::CacheConfig(_) | ColumnarError::Parse { .. } => COLUMNAR_ERR_OTHER,
+        };
+        Failure(code, e.to_string())
+    }
+}
+
+fn set_last_error(message: String) {
+    // Нулевой байт внутри сообщения обрезал бы его в C, поэтому он заменяется
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+// Выполняет тело функции API: успех очищает последнюю ошибку, ошибка и паника
+// запоминают её текст
+fn guard(body: impl FnOnce() -> std::result::Result<(), Failure>) -> i32 {
+    let outcome = panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        let detail = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "неизвестная причина".to_string());
+        Err(Failure(COLUMNAR_ERR_PANIC, format!("паника в columnar: {}", detail)))
+    });
+    match outcome {
+        Ok(()) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = None);
+            COLUMNAR_OK
+        }
+        Err(Failure(code, message)) => {
+            set_last_error(message);
+            code
+        }
+    }
+}
+
+unsafe fn non_null<'a, P>(pointer: *const P, name: &str) -> std::result::Result<&'a P, Failure> {
+    unsafe { pointer.as_ref() }.ok_or_else(|| Failure(COLUMNAR_ERR_NULL_POINTER, format!("указатель {} равен NULL", name)))
+}
+
+unsafe fn non_null_mut<'a, P>(pointer: *mut P, name: &str) -> std::result::Result<&'a mut P, Failure> {
+    unsafe { pointer.as_mut() }.ok_or_else(|| Failure(COLUMNAR_ERR_NULL_POINTER, format!("указатель {} равен NULL", name)))
+}
+
+fn open(path: &CStr) -> Result<Column<i32>> {
+    let path = path
+        .to_str()
+        .map_err(|_| ColumnarError::InvalidInput("путь к колонке не в UTF-8".to_string()))?;
+    Column::open(Path::new(path))
+}
+
+// Открывает колонку i32 и записывает её в *out; освобождать — columnar_column_free
+#[no_mangle]
+pub unsafe extern "C" fn columnar_column_open(path: *const c_char, out: *mut *mut ColumnarColumn) -> i32 {
+    guard(|| {
+        let out = unsafe { non_null_mut(out, "out") }?;
+        *out = ptr::null_mut();
+        unsafe { non_null(path, "path") }?;
+        let column = open(unsafe { CStr::from_ptr(path) })?;
+        *out = Box::into_raw(Box::new(ColumnarColumn { column }));
+        Ok(())
+    })
+}
+
+// Число строк вместе с NULL и удалёнными
+#[no_mangle]
+pub unsafe extern "C" fn columnar_column_len(column: *const ColumnarColumn, out: *mut u64) -> i32 {
+    guard(|| {
+        let column = unsafe { non_null(column, "column") }?;
+        *unsafe { non_null_mut(out, "out") }? = column.column.len() as u64;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn columnar_column_get_i32(column: *const ColumnarColumn, idx: u64, out: *mut i32) -> i32 {
+    guard(|| {
+        let column = &unsafe { non_null(column, "column") }?.column;
+        let out = unsafe { non_null_mut(out, "out") }?;
+        if idx >= column.len() as u64 {
+            return Err(Failure(
+                COLUMNAR_ERR_OUT_OF_RANGE,
+                format!("строка {} вне колонки из {} строк", idx, column.len()),
+            ));
+        }
+        match column.try_get_value(idx as usize)? {
+            Some(value) => {
+                *out = value;
+                Ok(())
+            }
+            None => Err(Failure(COLUMNAR_ERR_NULL_VALUE, format!("строка {} — NULL или удалена", idx))),
+        }
+    })
+}
+
+// Копирует распакованные значения (little-endian) в buffer и пишет их размер в
+// *out_len. Если capacity не хватает, ничего не копируется, а в *out_len
+// оказывается нужный размер: так его можно узнать вызовом с buffer = NULL и
+// capacity = 0
+#[no_mangle]
+pub unsafe extern "C" fn columnar_column_decompress(
+    column: *const ColumnarColumn,
+    buffer: *mut u8,
+    capacity: usize,
+    out_len: *mut usize,
+) -> i32 {
+    guard(|| {
+        let column = &unsafe { non_null(column, "column") }?.column;
+        let out_len = unsafe { non_null_mut(out_len, "out_len") }?;
+        let needed = column.uncompressed_len() as usize;
+        *out_len = needed;
+        if capacity < needed {
+            return Err(Failure(
+                COLUMNAR_ERR_BUFFER_TOO_SMALL,
+                format!("буфер {} байт, а колонке нужно {}", capacity, needed),
+            ));
+        }
+        if needed == 0 {
+            return Ok(());
+        }
+        unsafe { non_null_mut(buffer, "buffer") }?;
+        let data = column.decompress_parallel()?;
+        let target = unsafe { std::slice::from_raw_parts_mut(buffer, capacity) };
+        target[..data.len()].copy_from_slice(&data);
+        *out_len = data.len();
+        Ok(())
+    })
+}
+
+// NULL допустим и ничего не делает
+#[no_mangle]
+pub unsafe extern "C" fn columnar_column_free(column: *mut ColumnarColumn) {
+    if !column.is_null() {
+        // Drop колонки не паникует: он только снимает отображение файла
+        drop(unsafe { Box::from_raw(column) });
+    }
+}
+
+// Сообщение последней ошибки в этом потоке или NULL, если последний вызов был
+// успешным. Строка принадлежит библиотеке и живёт до следующего вызова в потоке
+#[no_mangle]
+pub extern "C" fn columnar_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ColumnBuilder;
+
+    fn last_error() -> Option<String> {
+        let message = columnar_last_error_message();
+        (!message.is_null()).then(|| unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn test_open_read_and_free_through_c_api() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let values: Vec<Option<i32>> = (0..10_000).map(|i| (i % 100 != 5).then_some(i * 3)).collect();
+        let mut builder = ColumnBuilder::from_nullable("v".to_string(), &values);
+        builder.set_chunk_rows(1024);
+        builder.compress_with(crate::Codec::Lz4).unwrap();
+        builder.build(&path).unwrap();
+        let c_path = CString::new(path.to_str().unwrap()).unwrap();
+
+        unsafe {
+            let mut column = ptr::null_mut();
+            assert_eq!(columnar_column_open(c_path.as_ptr(), &mut column), COLUMNAR_OK);
+            assert!(!column.is_null() && last_error().is_none());
+
+            let mut len = 0u64;
+            assert_eq!(columnar_column_len(column, &mut len), COLUMNAR_OK);
+            assert_eq!(len, 10_000);
+
+            let mut value = 0i32;
+            assert_eq!(columnar_column_get_i32(column, 7, &mut value), COLUMNAR_OK);
+            assert_eq!(value, 21);
+            assert_eq!(columnar_column_get_i32(column, 105, &mut value), COLUMNAR_ERR_NULL_VALUE);
+            assert_eq!(columnar_column_get_i32(column, 10_000, &mut value), COLUMNAR_ERR_OUT_OF_RANGE);
+            assert!(last_error().unwrap().contains("вне колонки"));
+
+            let mut needed = 0usize;
+            assert_eq!(
+                columnar_column_decompress(column, ptr::null_mut(), 0, &mut needed),
+                COLUMNAR_ERR_BUFFER_TOO_SMALL
+            );
+            assert_eq!(needed, 40_000);
+            let mut buffer = vec![0u8; needed];
+            let mut written = 0usize;
+            assert_eq!(columnar_column_decompress(column, buffer.as_mut_ptr(), buffer.len(), &mut written), COLUMNAR_OK);
+            assert_eq!(written, needed);
+            let decoded: Vec<i32> = buffer.chunks_exact(4).map(|b| i32::from_le_bytes(b.try_into().unwrap())).collect();
+            assert_eq!(decoded[7], 21);
+            assert_eq!(decoded[9_999], 29_997);
+
+            columnar_column_free(column);
+            columnar_column_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_errors_become_codes_and_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        unsafe {
+            let mut column = ptr::null_mut();
+            let missing = CString::new(dir.path().join("missing.col").to_str().unwrap()).unwrap();
+            assert_eq!(columnar_column_open(missing.as_ptr(), &mut column), COLUMNAR_ERR_IO);
+            assert!(column.is_null());
+            assert!(last_error().unwrap().starts_with("ошибка ввода-вывода"));
+
+            let path = dir.path().join("wide.col");
+            ColumnBuilder::from_values("wide".to_string(), &[1i64, 2]).build(&path).unwrap();
+            let wide = CString::new(path.to_str().unwrap()).unwrap();
+            assert_eq!(columnar_column_open(wide.as_ptr(), &mut column), COLUMNAR_ERR_CORRUPT);
+
+            assert_eq!(columnar_column_open(ptr::null(), &mut column), COLUMNAR_ERR_NULL_POINTER);
+            assert_eq!(columnar_column_len(ptr::null(), &mut 0), COLUMNAR_ERR_NULL_POINTER);
+            assert!(last_error().unwrap().contains("column"));
+
+            // Сообщение у каждого потока своё
+            std::thread::spawn(|| assert!(last_error().is_none())).join().unwrap();
+        }
+
+        let code = guard(|| panic!("сбой"));
+        assert_eq!(code, COLUMNAR_ERR_PANIC);
+        assert_eq!(last_error().unwrap(), "паника в columnar: сбой");
+    }
+
+    // Каждая функция и константа API объявлена в заголовке
+    #[test]
+    fn test_header_declares_api() {
+        let header = include_str!("../include/columnar.h");
+        let source = include_str!("ffi.rs");
+        let exported = source
+            .lines()
+            .filter_map(|line| line.trim_start().strip_prefix("pub unsafe extern \"C\" fn ").or_else(|| line.strip_prefix("pub extern \"C\" fn ")))
+            .chain(source.lines().filter_map(|line| line.strip_prefix("pub const ")))
+            .map(|rest| rest.split(['(', ':']).next().unwrap());
+        let mut count = 0;
+        for name in exported {
+            assert!(header.contains(name), "{} нет в include/columnar.h", name);
+            count += 1;
+        }
+        assert_eq!(count, 18);
+    }
+}
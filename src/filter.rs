@@ -0,0 +1,260 @@
+use std::ops::Range;
+use crate::bools::bit_is_set;
+use crate::compute::Selection;
+use crate::error::Result;
+use crate::par::*;
+use crate::storage::Column;
+use crate::types::ColumnType;
+
+// Условие на значение колонки. NULL не удовлетворяет ни одному условию,
+// сравнения с NaN следуют PartialOrd, как в scan_range
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate<T: ColumnType = i32> {
+    Eq(T),
+    Ne(T),
+    Lt(T),
+    Le(T),
+    Gt(T),
+    Ge(T),
+    // Включительно с обеих сторон
+    Between(T, T),
+    In(Vec<T>),
+}
+
+// Что zone map чанка говорит об условии
+enum ZoneMatch {
+    None,
+    All,
+    Scan,
+}
+
+impl<T: ColumnType> Predicate<T> {
+    pub fn matches(&self, value: &T) -> bool {
+        match self {
+            Predicate::Eq(v) => value == v,
+            Predicate::Ne(v) => value != v,
+            Predicate::Lt(v) => value < v,
+            Predicate::Le(v) => value <= v,
+            Predicate::Gt(v) => value > v,
+            Predicate::Ge(v) => value >= v,
+            Predicate::Between(lo, hi) => lo <= value && value <= hi,
+            Predicate::In(set) => set.contains(value),
+        }
+    }
+
+    // min/max — границы значений чанка без NULL и NaN
+    fn zone_match(&self, min: &T, max: &T) -> ZoneMatch {
+        let (none, all) = match self {
+            Predicate::Eq(v) => (v < min || v > max, min == v && max == v),
+            Predicate::Ne(v) => (min == v && max == v, v < min || v > max),
+            Predicate::Lt(v) => (min >= v, max < v),
+            Predicate::Le(v) => (min > v, max <= v),
+            Predicate::Gt(v) => (max <= v, min > v),
+            Predicate::Ge(v) => (max < v, min >= v),
+            Predicate::Between(lo, hi) => (max < lo || min > hi || lo > hi, lo <= min && max <= hi),
+            Predicate::In(set) => (set.iter().all(|v| v < min || v > max), false),
+        };
+        if none {
+            ZoneMatch::None
+        } else if all {
+            ZoneMatch::All
+        } else {
+            ZoneMatch::Scan
+        }
+    }
+
+    // false означает, что точного совпадения в чанке нет
+    fn bloom_allows(&self, might_contain: impl Fn(&T) -> bool) -> bool {
+        match self {
+            Predicate::Eq(v) => might_contain(v),
+            Predicate::In(set) => set.iter().any(might_contain),
+            _ => true,
+        }
+    }
+}
+
+impl<T: ColumnType> Column<T> {
+    // Отбор строк по условию: чанки сначала отсекаются по zone map и bloom-фильтру,
+    // оставшиеся просматриваются параллельно. Длина карты равна числу строк, удалённые
+    // строки не отбираются
+    pub fn filter(&self, predicate: Predicate<T>) -> Result<Selection> {
+        self.filter_chunks(&predicate, |_| false)
+    }
+
+    // filter, который не трогает чанки, отвергнутые skip по диапазону их строк:
+    // их строки в результате не отобраны
+    pub(crate) fn filter_chunks(
+        &self,
+        predicate: &Predicate<T>,
+        skip: impl Fn(Range<usize>) -> bool + Sync,
+    ) -> Result<Selection> {
+        let matched: Vec<Option<Vec<u32>>> = (0..self.chunks().len())
+            .into_par_iter()
+            .map(|idx| {
+                let chunk = &self.chunks()[idx];
+                let first_row = chunk.first_row as usize;
+                if skip(first_row..first_row + chunk.row_count() as usize) {
+                    return Ok(Some(Vec::new()));
+                }
+                self.filter_chunk(idx, predicate)
+            })
+            .collect::<Result<_>>()?;
+
+        let mut bitmap = Selection::new(self.len());
+        for (chunk, rows) in self.chunks().iter().zip(matched) {
+            let first_row = chunk.first_row as usize;
+            match rows {
+                // Чанк подходит целиком
+                None => bitmap.set_range(first_row..first_row + chunk.row_count() as usize),
+                Some(rows) => {
+                    for row in rows {
+                        bitmap.set(first_row + row as usize);
+                    }
+                }
+            }
+        }
+        if let Some(deleted) = self.deleted_rows() {
+            bitmap.difference_with(deleted);
+        }
+        Ok(bitmap)
+    }
+
+    // Доля строк в чанках, которые zone map не исключает для условия: верхняя оценка
+    // селективности без чтения данных
+    pub(crate) fn estimate_selectivity(&self, predicate: &Predicate<T>) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let candidates: u64 = self
+            .chunks()
+            .iter()
+            .filter(|chunk| chunk.null_count < chunk.row_count())
+            .filter(|chunk| self.has_nan || !matches!(predicate.zone_match(&chunk.min, &chunk.max), ZoneMatch::None))
+            .map(|chunk| chunk.row_count())
+            .sum();
+        candidates as f64 / self.len() as f64
+    }
+
+    // None — подходят все строки чанка, иначе номера подходящих строк внутри чанка
+    fn filter_chunk(&self, idx: usize, predicate: &Predicate<T>) -> Result<Option<Vec<u32>>> {
+        let chunk = &self.chunks()[idx];
+        if chunk.null_count == chunk.row_count() {
+            return Ok(Some(Vec::new()));
+        }
+        match predicate.zone_match(&chunk.min, &chunk.max) {
+            // NaN не попадает в zone map, а NULL не удовлетворяет условию, поэтому
+            // вывод о всём чанке допустим только без них
+            ZoneMatch::None if !self.has_nan => return Ok(Some(Vec::new())),
+            ZoneMatch::All if !self.has_nan && chunk.null_count == 0 => return Ok(None),
+            _ => {}
+        }
+        if !predicate.bloom_allows(|value| self.chunk_might_contain(idx, value)) {
+            return Ok(Some(Vec::new()));
+        }
+
+        let values = self.chunk_values(idx)?;
+        let validity = self.chunk_validity(idx)?;
+        let rows = values
+            .chunks_exact(T::WIDTH)
+            .enumerate()
+            .filter(|(row, value)| {
+                validity.as_ref().is_none_or(|bits| bit_is_set(bits, *row)) && predicate.matches(&T::read_le(value))
+            })
+            .map(|(row, _)| row as u32)
+            .collect();
+        Ok(Some(rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Codec, ColumnBuilder};
+    use tempfile::NamedTempFile;
+
+    fn naive<T: ColumnType>(values: &[T], predicate: &Predicate<T>) -> Vec<usize> {
+        (0..values.len()).filter(|idx| predicate.matches(&values[*idx])).collect()
+    }
+
+    #[test]
+    fn test_filter_nothing_everything_and_narrow_band() {
+        let values: Vec<i32> = (0..20_000).map(|i| i - 10_000).collect();
+        let mut builder = ColumnBuilder::from_i32("f".to_string(), &values);
+        builder.compress_with(Codec::Lz4).unwrap();
+        builder.set_chunk_rows(1000);
+        let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+
+        let nothing = column.filter(Predicate::Gt(10_000)).unwrap();
+        assert_eq!(nothing.len(), values.len());
+        assert_eq!(nothing.count(), 0);
+        // Ни один чанк не распакован
+        assert_eq!(column.frames_decoded(), 0);
+
+        let everything = column.filter(Predicate::Between(i32::MIN, i32::MAX)).unwrap();
+        assert_eq!(everything.count(), values.len());
+        assert_eq!(everything.ranges(), vec![0..values.len()]);
+        assert_eq!(column.frames_decoded(), 0);
+
+        let band = column.filter(Predicate::Between(-15, -5)).unwrap();
+        assert_eq!(band.iter_indices().collect::<Vec<_>>(), (9985..=9995).collect::<Vec<_>>());
+        assert_eq!(column.frames_decoded(), 1);
+
+        for predicate in [
+            Predicate::Eq(77),
+            Predicate::Ne(77),
+            Predicate::Lt(-9000),
+            Predicate::Le(0),
+            Predicate::Gt(9998),
+            Predicate::Ge(-10_000),
+            Predicate::Between(3, 2),
+            Predicate::In(vec![-10_000, 4, 4, 9_999, 123_456]),
+        ] {
+            let bitmap = column.filter(predicate.clone()).unwrap();
+            assert_eq!(bitmap.iter_indices().collect::<Vec<_>>(), naive(&values, &predicate), "{:?}", predicate);
+        }
+    }
+
+    #[test]
+    fn test_bitmap_drives_other_column() {
+        let ids: Vec<i64> = (0..5000).collect();
+        let prices: Vec<i32> = (0..5000).map(|i| (i * 13) % 100).collect();
+        let ids = ColumnBuilder::from_i64("id".to_string(), &ids)
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        let prices = ColumnBuilder::from_i32("price".to_string(), &prices)
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+
+        let cheap = prices.filter(Predicate::Lt(3)).unwrap();
+        let mut selected = Vec::new();
+        for range in cheap.ranges() {
+            selected.extend(ids.get_values(range).unwrap());
+        }
+        let expected: Vec<i64> = (0..5000).filter(|i| (i * 13) % 100 < 3).collect();
+        assert_eq!(selected, expected);
+        assert!(cheap.get(0) && !cheap.get(1) && !cheap.get(5000));
+        assert_eq!(cheap.words().len(), 5000usize.div_ceil(64));
+    }
+
+    #[test]
+    fn test_filter_skips_nulls_and_nan() {
+        let values = [Some(1), None, Some(1), None, Some(2)];
+        let column = ColumnBuilder::from_nullable("n".to_string(), &values)
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        let ones = column.filter(Predicate::Le(1)).unwrap();
+        assert_eq!(ones.iter_indices().collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(column.filter(Predicate::Ne(7)).unwrap().count(), 3);
+
+        let floats = ColumnBuilder::from_f64("f".to_string(), &[1.0, f64::NAN, 3.0])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert_eq!(floats.filter(Predicate::Ge(0.0)).unwrap().iter_indices().collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(floats.filter(Predicate::Ne(5.0)).unwrap().count(), 3);
+
+        let empty = ColumnBuilder::from_i32("e".to_string(), &[])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert!(empty.filter(Predicate::Eq(0)).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,655 @@
+use std::{io::Write, ops::Range};
+use memmap2::Mmap;
+use crate::bloom::Bloom;
+use crate::codec::Codec;
+use crate::error::{corrupt, invalid_input, Result};
+use crate::histogram::Histogram;
+use crate::hll::HyperLogLog;
+use crate::types::ColumnType;
+
+// Файл колонки: [заголовок][данные][метаданные][длина метаданных: u32][FOOTER_MAGIC]
+pub(crate) const MAGIC: &[u8; 8] = b"COLSTOR\0";
+pub(crate) const FORMAT_VERSION: u16 = 3;
+pub(crate) const HEADER_SIZE: usize = 24;
+// Наибольший размер распакованного или закодированного чанка; запись индекса с
+// большим размером считается повреждённой, чтобы не выделять под неё память.
+// Писатель не даёт задать чанк больше половины: кодирование может раздуть значения
+pub(crate) const MAX_CHUNK_BYTES: u64 = 1 << 32;
+
+const FOOTER_MAGIC: &[u8; 8] = b"CSTRMETA";
+pub(crate) const TRAILER_SIZE: usize = 4 + FOOTER_MAGIC.len();
+
+// Флаги заголовка. FLAG_CHUNK_FRAMES: перед каждым чанком записан его кадр, по
+// которому open_recover находит чанки файла, потерявшего footer
+pub(crate) const FLAG_CHUNK_FRAMES: u16 = 1;
+const KNOWN_FLAGS: u16 = FLAG_CHUNK_FRAMES;
+
+// Флаги метаданных: записаны ли bloom-фильтры и встречались ли NaN
+const FOOTER_HAS_BLOOM: u8 = 1;
+const FOOTER_HAS_NAN: u8 = 2;
+const FOOTER_IS_SORTED: u8 = 4;
+const FOOTER_HAS_HISTOGRAM: u8 = 8;
+
+// Заголовок фиксированного размера:
+// magic [8] | version u16 | flags u16 | codec u8 | encoding u8 | value type u8 | reserved [1] | row_count u64
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Header {
+    pub version: u16,
+    pub flags: u16,
+    pub codec_tag: u8,
+    pub encoding_tag: u8,
+    pub type_tag: u8,
+    pub row_count: u64,
+}
+
+impl Header {
+    pub fn new(codec_tag: u8, encoding_tag: u8, type_tag: u8, row_count: u64) -> Self {
+        Self { version: FORMAT_VERSION, flags: 0, codec_tag, encoding_tag, type_tag, row_count }
+    }
+
+    pub fn encode(&self) -> [u8; HEADER_SIZE] {
+        let mut out = [0u8; HEADER_SIZE];
+        out[..8].copy_from_slice(MAGIC);
+        out[8..10].copy_from_slice(&self.version.to_le_bytes());
+        out[10..12].copy_from_slice(&self.flags.to_le_bytes());
+        out[12] = self.codec_tag;
+        out[13] = self.encoding_tag;
+        out[14] = self.type_tag;
+        out[16..24].copy_from_slice(&self.row_count.to_le_bytes());
+        out
+    }
+
+    pub fn decode(file: &[u8]) -> Result<Header> {
+        if file.len() < HEADER_SIZE || &file[..8] != MAGIC {
+            return Err(corrupt("неверная сигнатура: файл не является колонкой"));
+        }
+        let version = u16::from_le_bytes(file[8..10].try_into().unwrap());
+        if version != FORMAT_VERSION {
+            return Err(corrupt(format!(
+                "неподдерживаемая версия формата {} (поддерживается {})",
+                version, FORMAT_VERSION
+            )));
+        }
+        let flags = u16::from_le_bytes(file[10..12].try_into().unwrap());
+        if flags & !KNOWN_FLAGS != 0 {
+            return Err(corrupt(format!("неизвестные флаги заголовка: {:#06x}", flags)));
+        }
+        Ok(Header {
+            version,
+            flags,
+            codec_tag: file[12],
+            encoding_tag: file[13],
+            type_tag: file[14],
+            row_count: u64::from_le_bytes(file[16..24].try_into().unwrap()),
+        })
+    }
+}
+
+// Запись индекса чанков в footer
+#[derive(Debug, Clone)]
+pub struct ChunkMeta<T: ColumnType = i32> {
+    // Смещение хранимых байтов чанка от начала файла
+    pub offset: u64,
+    pub compressed_len: u64,
+    pub uncompressed_len: u64,
+    // Длина значений после кодирования и до сжатия: по ней буфер распаковки
+    // выделяется сразу нужного размера
+    pub encoded_len: u64,
+    pub first_row: u64,
+    // min/max и bloom-фильтр считаются только по значениям, отличным от NULL
+    pub min: T,
+    pub max: T,
+    pub null_count: u64,
+    // Длина хранимой битовой карты валидности сразу после значений; 0, если NULL нет
+    pub validity_len: u64,
+    // CRC32 хранимых байтов значений и битовой карты
+    pub checksum: u32,
+    pub bloom: Bloom<T>,
+}
+
+impl<T: ColumnType> ChunkMeta<T> {
+    pub fn row_count(&self) -> u64 {
+        self.uncompressed_len / T::WIDTH as u64
+    }
+
+    pub fn end_row(&self) -> u64 {
+        self.first_row + self.row_count()
+    }
+
+    // Смещение конца чанка в файле вместе с битовой картой валидности
+    pub fn stored_end(&self) -> u64 {
+        self.offset + self.compressed_len + self.validity_len
+    }
+
+    // stored_end для непроверенной записи: None при переполнении или размерах
+    // больше MAX_CHUNK_BYTES
+    fn checked_stored_end(&self) -> Option<u64> {
+        if self.uncompressed_len > MAX_CHUNK_BYTES || self.encoded_len > MAX_CHUNK_BYTES {
+            return None;
+        }
+        self.offset.checked_add(self.compressed_len)?.checked_add(self.validity_len)
+    }
+
+    fn encode(&self, out: &mut Vec<u8>, with_bloom: bool) {
+        out.extend_from_slice(&self.offset.to_le_bytes());
+        out.extend_from_slice(&self.compressed_len.to_le_bytes());
+        out.extend_from_slice(&self.uncompressed_len.to_le_bytes());
+        out.extend_from_slice(&self.encoded_len.to_le_bytes());
+        out.extend_from_slice(&self.first_row.to_le_bytes());
+        self.min.write_le(out);
+        self.max.write_le(out);
+        out.extend_from_slice(&self.null_count.to_le_bytes());
+        out.extend_from_slice(&self.validity_len.to_le_bytes());
+        out.extend_from_slice(&self.checksum.to_le_bytes());
+        if with_bloom {
+            encode_bloom(&self.bloom, out);
+        }
+    }
+
+    fn decode(r: &mut ByteReader, with_bloom: bool) -> Result<ChunkMeta<T>> {
+        Ok(ChunkMeta {
+            offset: r.u64()?,
+            compressed_len: r.u64()?,
+            uncompressed_len: r.u64()?,
+            encoded_len: r.u64()?,
+            first_row: r.u64()?,
+            min: r.value()?,
+            max: r.value()?,
+            null_count: r.u64()?,
+            validity_len: r.u64()?,
+            checksum: r.u32()?,
+            bloom: decode_optional_bloom(r, with_bloom)?,
+        })
+    }
+}
+
+// Кадр чанка в файле с FLAG_CHUNK_FRAMES, сразу перед хранимыми байтами:
+// CHUNK_MAGIC [4] | compressed_len u64 | validity_len u64 | encoded_len u64 |
+// uncompressed_len u64 | crc32 чанка u32 | crc32 предыдущих байт кадра u32
+pub(crate) const CHUNK_FRAME_SIZE: usize = 44;
+const CHUNK_MAGIC: &[u8; 4] = b"CHNK";
+
+impl<T: ColumnType> ChunkMeta<T> {
+    pub(crate) fn encode_frame(&self) -> [u8; CHUNK_FRAME_SIZE] {
+        let mut out = [0u8; CHUNK_FRAME_SIZE];
+        out[..4].copy_from_slice(CHUNK_MAGIC);
+        out[4..12].copy_from_slice(&self.compressed_len.to_le_bytes());
+        out[12..20].copy_from_slice(&self.validity_len.to_le_bytes());
+        out[20..28].copy_from_slice(&self.encoded_len.to_le_bytes());
+        out[28..36].copy_from_slice(&self.uncompressed_len.to_le_bytes());
+        out[36..40].copy_from_slice(&self.checksum.to_le_bytes());
+        let checksum = crc32fast::hash(&out[..40]);
+        out[40..].copy_from_slice(&checksum.to_le_bytes());
+        out
+    }
+
+    // Запись индекса по кадру, лежащему в начале bytes; None — там не кадр чанка.
+    // min, max, число NULL и bloom-фильтр в кадре не хранятся: вызывающий
+    // восстанавливает их по данным
+    pub(crate) fn decode_frame(bytes: &[u8], offset: u64, first_row: u64) -> Option<ChunkMeta<T>> {
+        let frame = bytes.get(..CHUNK_FRAME_SIZE)?;
+        if &frame[..4] != CHUNK_MAGIC || crc32fast::hash(&frame[..40]).to_le_bytes() != frame[40..] {
+            return None;
+        }
+        let u64_at = |at: usize| u64::from_le_bytes(frame[at..at + 8].try_into().unwrap());
+        let meta = ChunkMeta {
+            offset: offset + CHUNK_FRAME_SIZE as u64,
+            compressed_len: u64_at(4),
+            uncompressed_len: u64_at(28),
+            encoded_len: u64_at(20),
+            first_row,
+            min: T::MAX,
+            max: T::MIN,
+            null_count: 0,
+            validity_len: u64_at(12),
+            checksum: u32::from_le_bytes(frame[36..40].try_into().unwrap()),
+            bloom: placeholder_bloom(),
+        };
+        let valid = meta.uncompressed_len.is_multiple_of(T::WIDTH as u64)
+            && meta.uncompressed_len > 0
+            && meta.checked_stored_end().is_some();
+        valid.then_some(meta)
+    }
+}
+
+pub(crate) struct Footer<T: ColumnType> {
+    pub name: String,
+    pub min: T,
+    pub max: T,
+    pub compression_level: i32,
+    pub has_nan: bool,
+    pub is_sorted: bool,
+    pub null_count: u64,
+    // Регистры оценки числа различных значений
+    pub distinct: HyperLogLog,
+    // None — гистограмма при записи не строилась
+    pub histogram: Option<Histogram<T>>,
+    // Целевая доля ложных срабатываний и число значений, на которое рассчитан общий
+    // фильтр; None — bloom-фильтры не строились
+    pub bloom_fp_rate: Option<f64>,
+    pub bloom_capacity: u64,
+    pub bloom_filter: Bloom<T>,
+    // Словарь значений для словарного кодирования (пусто для остальных)
+    pub dictionary: Vec<T>,
+    pub chunks: Vec<ChunkMeta<T>>,
+}
+
+impl<T: ColumnType> Footer<T> {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(
+            64 + self.name.len() + self.dictionary.len() * T::WIDTH + self.chunks.len() * 128 + TRAILER_SIZE,
+        );
+        out.extend_from_slice(&(self.name.len() as u32).to_le_bytes());
+        out.extend_from_slice(self.name.as_bytes());
+        self.min.write_le(&mut out);
+        self.max.write_le(&mut out);
+        out.extend_from_slice(&self.compression_level.to_le_bytes());
+        let mut flags = 0;
+        if self.bloom_fp_rate.is_some() {
+            flags |= FOOTER_HAS_BLOOM;
+        }
+        if self.has_nan {
+            flags |= FOOTER_HAS_NAN;
+        }
+        if self.is_sorted {
+            flags |= FOOTER_IS_SORTED;
+        }
+        if self.histogram.is_some() {
+            flags |= FOOTER_HAS_HISTOGRAM;
+        }
+        out.push(flags);
+        out.extend_from_slice(&self.null_count.to_le_bytes());
+        self.distinct.encode(&mut out);
+        if let Some(histogram) = &self.histogram {
+            histogram.encode(&mut out);
+        }
+        if let Some(fp_rate) = self.bloom_fp_rate {
+            out.extend_from_slice(&fp_rate.to_bits().to_le_bytes());
+            out.extend_from_slice(&self.bloom_capacity.to_le_bytes());
+            encode_bloom(&self.bloom_filter, &mut out);
+        }
+        out.extend_from_slice(&(self.dictionary.len() as u32).to_le_bytes());
+        for value in &self.dictionary {
+            value.write_le(&mut out);
+        }
+        out.extend_from_slice(&(self.chunks.len() as u32).to_le_bytes());
+        for chunk in &self.chunks {
+            chunk.encode(&mut out, self.bloom_fp_rate.is_some());
+        }
+        write_trailer(&mut out);
+        out
+    }
+
+    // Разбирает хвост файла; возвращает смещение конца секции данных и метаданные
+    pub fn decode(file: &[u8]) -> Result<(usize, Footer<T>)> {
+        let (data_end, meta) = split_trailer(file)?;
+        let header = Header::decode(file)?;
+        Ok((data_end, Self::decode_meta(meta, data_end, &header)?))
+    }
+
+    // Метаданные без хвоста файла: meta начинается со смещения data_end
+    pub fn decode_meta(meta: &[u8], data_end: usize, header: &Header) -> Result<Footer<T>> {
+        let frames = header.flags & FLAG_CHUNK_FRAMES != 0;
+        let mut r = ByteReader::new(meta);
+
+        let name_len = r.u32()? as usize;
+        let name = String::from_utf8(r.bytes(name_len)?.to_vec())
+            .map_err(|_| corrupt("имя колонки в метаданных не является UTF-8"))?;
+        let min = r.value()?;
+        let max = r.value()?;
+        let compression_level = r.i32()?;
+        let flags = r.bytes(1)?[0];
+        let with_bloom = flags & FOOTER_HAS_BLOOM != 0;
+        let null_count = r.u64()?;
+        let distinct = HyperLogLog::decode(&mut r)?;
+        let histogram = if flags & FOOTER_HAS_HISTOGRAM != 0 { Some(Histogram::decode(&mut r)?) } else { None };
+        let (bloom_fp_rate, bloom_capacity) = if with_bloom {
+            let fp_rate = f64::from_bits(r.u64()?);
+            if fp_rate.is_nan() || fp_rate <= 0.0 || fp_rate >= 1.0 {
+                return Err(corrupt("неверная доля ложных срабатываний bloom-фильтра в метаданных"));
+            }
+            (Some(fp_rate), r.u64()?)
+        } else {
+            (None, 0)
+        };
+        let bloom_filter = decode_optional_bloom(&mut r, with_bloom)?;
+        let dictionary_len = r.u32()? as usize;
+        let mut dictionary = Vec::with_capacity(dictionary_len.min(r.remaining() / T::WIDTH));
+        for _ in 0..dictionary_len {
+            dictionary.push(r.value()?);
+        }
+        let chunk_count = r.u32()? as usize;
+        let mut chunks = Vec::with_capacity(chunk_count.min(r.remaining() / 64));
+        for _ in 0..chunk_count {
+            chunks.push(ChunkMeta::decode(&mut r, with_bloom)?);
+        }
+        validate_chunks(&chunks, data_end, if frames { CHUNK_FRAME_SIZE as u64 } else { 0 })?;
+
+        Ok(Footer {
+            name,
+            min,
+            max,
+            compression_level,
+            has_nan: flags & FOOTER_HAS_NAN != 0,
+            is_sorted: flags & FOOTER_IS_SORTED != 0,
+            null_count,
+            distinct,
+            histogram,
+            bloom_fp_rate,
+            bloom_capacity,
+            bloom_filter,
+            dictionary,
+            chunks,
+        })
+    }
+}
+
+// Колонки без чанков (строки, булевы) хранят секцию данных как несколько
+// целиком сжимаемых буферов; Section описывает расположение одного из них
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Section {
+    pub offset: u64,
+    pub stored_len: u64,
+    pub raw_len: u64,
+    // CRC32 хранимых байтов
+    pub checksum: u32,
+}
+
+// Байты буфера: срез mmap для несжатой колонки или распакованная копия
+#[derive(Debug)]
+pub(crate) enum Buffer {
+    Mapped(Range<usize>),
+    Owned(Vec<u8>),
+}
+
+impl Buffer {
+    pub fn bytes<'a>(&'a self, mmap: &'a Mmap) -> &'a [u8] {
+        match self {
+            Buffer::Mapped(range) => &mmap[range.clone()],
+            Buffer::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl Section {
+    // Сжимает и записывает буфер начиная со смещения offset
+    pub fn write(out: &mut impl Write, offset: u64, raw: &[u8], codec: Codec) -> Result<Section> {
+        if raw.len() as u64 > MAX_CHUNK_BYTES {
+            return Err(invalid_input(format!("буфер колонки из {} байт больше допустимого", raw.len())));
+        }
+        let stored = if codec.is_compressed() {
+            std::borrow::Cow::Owned(codec.compress_frame(raw)?)
+        } else {
+            std::borrow::Cow::Borrowed(raw)
+        };
+        out.write_all(&stored)?;
+        Ok(Section {
+            offset,
+            stored_len: stored.len() as u64,
+            raw_len: raw.len() as u64,
+            checksum: crc32fast::hash(&stored),
+        })
+    }
+
+    // Сверяет контрольную сумму буфера и при необходимости распаковывает его
+    pub fn load(&self, mmap: &Mmap, codec: Codec) -> Result<Buffer> {
+        let end = self.end().ok_or_else(|| corrupt("буфер колонки выходит за пределы файла"))?;
+        let range = self.offset as usize..end as usize;
+        let stored = mmap.get(range.clone()).ok_or_else(|| corrupt("буфер колонки выходит за пределы файла"))?;
+        if crc32fast::hash(stored) != self.checksum {
+            return Err(corrupt("контрольная сумма буфера колонки не совпадает"));
+        }
+        let buffer = if codec.is_compressed() {
+            Buffer::Owned(codec.decompress_frame(stored, self.raw_len as usize)?)
+        } else {
+            Buffer::Mapped(range)
+        };
+        if buffer.bytes(mmap).len() as u64 != self.raw_len {
+            return Err(corrupt("буфер колонки распакован в неверное число байт"));
+        }
+        Ok(buffer)
+    }
+
+    // Конец буфера в файле; None при переполнении или размерах больше MAX_CHUNK_BYTES
+    fn end(&self) -> Option<u64> {
+        if self.stored_len > MAX_CHUNK_BYTES || self.raw_len > MAX_CHUNK_BYTES {
+            return None;
+        }
+        self.offset.checked_add(self.stored_len)
+    }
+
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.offset.to_le_bytes());
+        out.extend_from_slice(&self.stored_len.to_le_bytes());
+        out.extend_from_slice(&self.raw_len.to_le_bytes());
+        out.extend_from_slice(&self.checksum.to_le_bytes());
+    }
+
+    pub fn decode(r: &mut ByteReader) -> Result<Section> {
+        Ok(Section {
+            offset: r.u64()?,
+            stored_len: r.u64()?,
+            raw_len: r.u64()?,
+            checksum: r.u32()?,
+        })
+    }
+}
+
+// Буферы должны вплотную идти от заголовка до конца секции данных
+pub(crate) fn validate_sections(sections: &[Section], data_end: usize) -> Result<()> {
+    let mut offset = HEADER_SIZE as u64;
+    for section in sections {
+        // Как и в validate_chunks, длины из footer не проверены контрольной суммой
+        match section.end() {
+            Some(end) if section.offset == offset && end <= data_end as u64 => offset = end,
+            _ => return Err(corrupt("буферы колонки не покрывают секцию данных")),
+        }
+    }
+    if offset != data_end as u64 {
+        return Err(corrupt("буферы колонки не покрывают секцию данных"));
+    }
+    Ok(())
+}
+
+pub(crate) fn write_str(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+pub(crate) fn read_str(r: &mut ByteReader) -> Result<String> {
+    let len = r.u32()? as usize;
+    String::from_utf8(r.bytes(len)?.to_vec()).map_err(|_| corrupt("строка в метаданных не является UTF-8"))
+}
+
+// Дописывает к метаданным их длину и FOOTER_MAGIC
+pub(crate) fn write_trailer(out: &mut Vec<u8>) {
+    let meta_len = out.len() as u32;
+    out.extend_from_slice(&meta_len.to_le_bytes());
+    out.extend_from_slice(FOOTER_MAGIC);
+}
+
+// Находит метаданные в хвосте файла; возвращает конец секции данных и байты метаданных
+pub(crate) fn split_trailer(file: &[u8]) -> Result<(usize, &[u8])> {
+    if file.len() < HEADER_SIZE + TRAILER_SIZE {
+        return Err(corrupt("файл не содержит метаданных колонки"));
+    }
+    let data_end = decode_trailer(&file[file.len() - TRAILER_SIZE..], file.len())?;
+    Ok((data_end, &file[data_end..file.len() - TRAILER_SIZE]))
+}
+
+// Конец секции данных по последним TRAILER_SIZE байтам файла длины file_len
+pub(crate) fn decode_trailer(trailer: &[u8], file_len: usize) -> Result<usize> {
+    if file_len < HEADER_SIZE + TRAILER_SIZE || trailer.len() != TRAILER_SIZE || &trailer[4..] != FOOTER_MAGIC {
+        return Err(corrupt("файл не содержит метаданных колонки"));
+    }
+    let meta_len = u32::from_le_bytes(trailer[..4].try_into().unwrap()) as usize;
+    let trailer_start = file_len - TRAILER_SIZE;
+    if meta_len > trailer_start - HEADER_SIZE {
+        return Err(corrupt("длина метаданных превышает размер файла"));
+    }
+    Ok(trailer_start - meta_len)
+}
+
+// Чанки вместе с кадрами размера frame_size должны вплотную покрывать секцию данных
+// и нумерацию строк
+fn validate_chunks<T: ColumnType>(chunks: &[ChunkMeta<T>], data_end: usize, frame_size: u64) -> Result<()> {
+    let mut offset = HEADER_SIZE as u64;
+    let mut row = 0u64;
+    for (idx, chunk) in chunks.iter().enumerate() {
+        // У footer нет контрольной суммы, поэтому вся арифметика — с проверкой
+        let bad = || corrupt(format!("повреждена запись чанка {} в индексе", idx));
+        let end = chunk.checked_stored_end().filter(|end| *end <= data_end as u64).ok_or_else(bad)?;
+        if offset.checked_add(frame_size) != Some(chunk.offset)
+            || chunk.first_row != row
+            || !chunk.uncompressed_len.is_multiple_of(T::WIDTH as u64)
+            // Писатель не создаёт пустых чанков, а поиск по чанку рассчитывает на строку в нём
+            || chunk.uncompressed_len == 0
+            || chunk.null_count > chunk.row_count()
+            || (chunk.null_count == 0) != (chunk.validity_len == 0)
+        {
+            return Err(bad());
+        }
+        offset = end;
+        row = row.checked_add(chunk.row_count()).ok_or_else(bad)?;
+    }
+    if offset != data_end as u64 {
+        return Err(corrupt("индекс чанков не покрывает секцию данных"));
+    }
+    Ok(())
+}
+
+// bloom-фильтр: bits u64 | k u32 | sip-ключи 4×u64 | длина битовой карты u32 | битовая карта
+pub(crate) fn encode_bloom<T: ?Sized>(bloom: &Bloom<T>, out: &mut Vec<u8>) {
+    let bitmap = bloom.bitmap();
+    out.extend_from_slice(&bloom.number_of_bits().to_le_bytes());
+    out.extend_from_slice(&bloom.number_of_hash_functions().to_le_bytes());
+    for (k0, k1) in bloom.sip_keys() {
+        out.extend_from_slice(&k0.to_le_bytes());
+        out.extend_from_slice(&k1.to_le_bytes());
+    }
+    out.extend_from_slice(&(bitmap.len() as u32).to_le_bytes());
+    out.extend_from_slice(&bitmap);
+}
+
+pub(crate) fn decode_bloom<T: ?Sized>(r: &mut ByteReader) -> Result<Bloom<T>> {
+    let bits = r.u64()?;
+    let k_num = r.u32()?;
+    let sip_keys = [(r.u64()?, r.u64()?), (r.u64()?, r.u64()?)];
+    let bitmap_len = r.u32()? as usize;
+    let bitmap = r.bytes(bitmap_len)?;
+    if bits == 0 || k_num == 0 || bits > bitmap_len as u64 * 8 {
+        return Err(corrupt("повреждён bloom-фильтр в метаданных"));
+    }
+    Ok(Bloom::from_existing(bitmap, bits, k_num, sip_keys))
+}
+
+// Для типов без bloom-фильтров в памяти держится фильтр-заглушка минимального размера
+pub(crate) fn placeholder_bloom<T>() -> Bloom<T> {
+    Bloom::new(1, 1)
+}
+
+fn decode_optional_bloom<T>(r: &mut ByteReader, present: bool) -> Result<Bloom<T>> {
+    if present {
+        decode_bloom(r)
+    } else {
+        Ok(placeholder_bloom())
+    }
+}
+
+pub(crate) struct ByteReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    pub fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.buf.len() - self.pos < n {
+            return Err(corrupt("метаданные колонки обрезаны"));
+        }
+        let out = &self.buf[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(out)
+    }
+
+    pub fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    pub fn value<T: ColumnType>(&mut self) -> Result<T> {
+        Ok(T::read_le(self.bytes(T::WIDTH)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ColumnarError;
+    use crate::storage::ColumnBuilder;
+
+    #[test]
+    fn test_validate_chunks_rejects_overflowing_lengths() {
+        let mut builder = ColumnBuilder::from_i32("col".to_string(), &(0..100).collect::<Vec<_>>());
+        builder.set_chunk_rows(40);
+        let column = builder.build_in_memory().unwrap();
+        let chunks = column.chunks().to_vec();
+        let data_end = chunks.last().unwrap().stored_end() as usize;
+        validate_chunks(&chunks, data_end, 0).unwrap();
+
+        // Поля, на которых раньше переполнялись сложения или выделялась огромная память
+        let corruptions: [fn(&mut ChunkMeta<i32>); 5] = [
+            |c| c.compressed_len = u64::MAX,
+            |c| c.validity_len = u64::MAX - c.offset,
+            |c| c.offset = u64::MAX,
+            |c| c.uncompressed_len = MAX_CHUNK_BYTES + 4,
+            |c| c.encoded_len = u64::MAX,
+        ];
+        for corrupt_chunk in corruptions {
+            let mut broken = chunks.clone();
+            corrupt_chunk(&mut broken[1]);
+            assert!(matches!(validate_chunks(&broken, data_end, 0), Err(ColumnarError::Corrupt { .. })));
+        }
+
+        // Последний чанк не может выходить за секцию данных
+        let mut broken = chunks.clone();
+        broken.last_mut().unwrap().compressed_len += 8;
+        assert!(matches!(validate_chunks(&broken, data_end, 0), Err(ColumnarError::Corrupt { .. })));
+
+        // Пустой последний чанк не ломает нумерацию строк, но отвергается сам по себе
+        let mut broken = chunks.clone();
+        broken.last_mut().unwrap().uncompressed_len = 0;
+        assert!(matches!(validate_chunks(&broken, data_end, 0), Err(ColumnarError::Corrupt { .. })));
+    }
+
+    #[test]
+    fn test_validate_sections_rejects_overflowing_lengths() {
+        let section = |offset: u64, stored_len: u64| Section { offset, stored_len, raw_len: stored_len, checksum: 0 };
+        let data_end = HEADER_SIZE + 100;
+        validate_sections(&[section(HEADER_SIZE as u64, 60), section(HEADER_SIZE as u64 + 60, 40)], data_end).unwrap();
+
+        let broken = [
+            vec![section(HEADER_SIZE as u64, u64::MAX)],
+            vec![section(HEADER_SIZE as u64, 60), section(HEADER_SIZE as u64 + 60, u64::MAX - 80)],
+            vec![section(HEADER_SIZE as u64, MAX_CHUNK_BYTES + 1)],
+            vec![section(HEADER_SIZE as u64, 200)],
+        ];
+        for sections in broken {
+            assert!(matches!(validate_sections(&sections, data_end), Err(ColumnarError::Corrupt { .. })));
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use crate::aggregate::{Agg, AggValue};
+use crate::error::{invalid_input, Result};
+use crate::compute::Selection;
+use crate::scan::ColumnValues;
+use crate::table::{Table, TableColumn};
+use crate::types::ColumnType;
+
+// Блок строк для ключей без чанков (строковых и булевых колонок)
+const GROUP_BLOCK_ROWS: usize = 65_536;
+
+// Значение ключа группировки; строки с NULL в ключе образуют отдельную группу
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GroupKey {
+    Null,
+    Int32(i32),
+    Int64(i64),
+    Utf8(String),
+    Bool(bool),
+}
+
+// Результат одной агрегации группы в типе агрегируемой колонки
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupValue {
+    Int32(AggValue<i32>),
+    Int64(AggValue<i64>),
+    Float64(AggValue<f64>),
+}
+
+// Группа и её агрегаты в порядке, в котором они запрошены
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub key: GroupKey,
+    pub values: Vec<GroupValue>,
+}
+
+// Накопитель одной колонки в одной группе; NULL пропускается, NaN не входит в min/max
+#[derive(Debug, Clone, Copy)]
+struct Acc<T: ColumnType> {
+    sum: T::Sum,
+    count: u64,
+    min: Option<T>,
+    max: Option<T>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Accumulator {
+    Int32(Acc<i32>),
+    Int64(Acc<i64>),
+    Float64(Acc<f64>),
+}
+
+impl<T: ColumnType> Acc<T> {
+    fn new() -> Self {
+        Self { sum: T::Sum::default(), count: 0, min: None, max: None }
+    }
+
+    fn push(&mut self, value: T) {
+        self.sum = self.sum + value.widen();
+        self.count += 1;
+        if value.is_nan() {
+            return;
+        }
+        if self.min.is_none_or(|min| value.total_cmp(&min).is_lt()) {
+            self.min = Some(value);
+        }
+        if self.max.is_none_or(|max| value.total_cmp(&max).is_gt()) {
+            self.max = Some(value);
+        }
+    }
+
+    fn finish(&self, agg: Agg) -> AggValue<T> {
+        match agg {
+            Agg::Sum => AggValue::Sum(self.sum),
+            Agg::Min => AggValue::Min(self.min),
+            Agg::Max => AggValue::Max(self.max),
+            Agg::Avg => AggValue::Avg((self.count > 0).then(|| T::sum_to_f64(self.sum) / self.count as f64)),
+            Agg::Count => AggValue::Count(self.count),
+        }
+    }
+}
+
+impl Accumulator {
+    fn new(column: &TableColumn) -> Self {
+        match column {
+            TableColumn::Int32(_) => Accumulator::Int32(Acc::new()),
+            TableColumn::Int64(_) => Accumulator::Int64(Acc::new()),
+            TableColumn::Float64(_) => Accumulator::Float64(Acc::new()),
+            TableColumn::Utf8(_) | TableColumn::Bool(_) => unreachable!("тип проверен до группировки"),
+        }
+    }
+
+    fn push(&mut self, values: &ColumnValues, idx: usize) {
+        match (self, values) {
+            (Accumulator::Int32(acc), ColumnValues::Int32(values)) => values[idx].into_iter().for_each(|v| acc.push(v)),
+            (Accumulator::Int64(acc), ColumnValues::Int64(values)) => values[idx].into_iter().for_each(|v| acc.push(v)),
+            (Accumulator::Float64(acc), ColumnValues::Float64(values)) => values[idx].into_iter().for_each(|v| acc.push(v)),
+            _ => unreachable!("накопитель создан по типу колонки"),
+        }
+    }
+
+    fn finish(&self, agg: Agg) -> GroupValue {
+        match self {
+            Accumulator::Int32(acc) => GroupValue::Int32(acc.finish(agg)),
+            Accumulator::Int64(acc) => GroupValue::Int64(acc.finish(agg)),
+            Accumulator::Float64(acc) => GroupValue::Float64(acc.finish(agg)),
+        }
+    }
+}
+
+impl ColumnValues {
+    fn group_key(&self, idx: usize) -> GroupKey {
+        match self {
+            ColumnValues::Int32(values) => values[idx].map_or(GroupKey::Null, GroupKey::Int32),
+            ColumnValues::Int64(values) => values[idx].map_or(GroupKey::Null, GroupKey::Int64),
+            ColumnValues::Utf8(values) => GroupKey::Utf8(values[idx].clone()),
+            ColumnValues::Bool(values) => GroupKey::Bool(values[idx]),
+            ColumnValues::Float64(_) => unreachable!("тип ключа проверен до группировки"),
+        }
+    }
+}
+
+impl TableColumn {
+    // Диапазоны строк, которыми читается ключ: чанки числовой колонки или блоки фиксированного размера
+    fn row_blocks(&self) -> Vec<Range<usize>> {
+        match self {
+            TableColumn::Int32(c) => c.chunks().iter().map(|c| c.first_row as usize..c.end_row() as usize).collect(),
+            TableColumn::Int64(c) => c.chunks().iter().map(|c| c.first_row as usize..c.end_row() as usize).collect(),
+            _ => (0..self.len())
+                .step_by(GROUP_BLOCK_ROWS)
+                .map(|start| start..(start + GROUP_BLOCK_ROWS).min(self.len()))
+                .collect(),
+        }
+    }
+}
+
+impl Table {
+    // Группировка строк по значению колонки key и агрегаты числовых колонок в каждой
+    // группе. Ключ читается по чанкам, для каждого чанка распаковываются только
+    // отобранные строки агрегируемых колонок; в памяти держатся лишь накопители групп.
+    // selection, если задан, должен иметь длину таблицы. Строка, удалённая хотя бы в
+    // одной колонке, не учитывается, как в Table::evaluate. Группы идут по возрастанию
+    // ключа, NULL первым; группа без отобранных строк в результат не попадает
+    pub fn group_by(&self, key: &str, aggregates: &[(&str, Agg)], selection: Option<&Selection>) -> Result<Vec<Group>> {
+        let key_column = self.require(key)?;
+        if matches!(key_column, TableColumn::Float64(_)) {
+            return Err(invalid_input(format!("группировка по колонке {} с типом Float64 не поддерживается", key)));
+        }
+        let mut value_columns: Vec<&TableColumn> = Vec::new();
+        let mut column_of = Vec::with_capacity(aggregates.len());
+        for (name, _) in aggregates {
+            let column = self.require(name)?;
+            if matches!(column, TableColumn::Utf8(_) | TableColumn::Bool(_)) {
+                return Err(invalid_input(format!("агрегировать можно только числовые колонки, {} — {:?}", name, column.data_type())));
+            }
+            // Колонка с несколькими агрегатами читается и накапливается один раз
+            let idx = value_columns.iter().position(|c| c.name() == *name).unwrap_or_else(|| {
+                value_columns.push(column);
+                value_columns.len() - 1
+            });
+            column_of.push(idx);
+        }
+        if let Some(bitmap) = selection {
+            if bitmap.len() != self.row_count() {
+                return Err(invalid_input(format!(
+                    "битовая карта из {} строк для таблицы из {} строк",
+                    bitmap.len(),
+                    self.row_count()
+                )));
+            }
+        }
+
+        let live = match (selection, self.deleted_rows()) {
+            (selection, None) => selection.cloned(),
+            (None, Some(deleted)) => Some(deleted.not()),
+            (Some(selection), Some(deleted)) => {
+                let mut live = selection.clone();
+                live.difference_with(&deleted);
+                Some(live)
+            }
+        };
+        let mut index: HashMap<GroupKey, usize> = HashMap::new();
+        let mut groups: Vec<(GroupKey, Vec<Accumulator>)> = Vec::new();
+        for block in key_column.row_blocks() {
+            let rows: Vec<usize> = match &live {
+                None => block.collect(),
+                Some(bitmap) if bitmap.count_range(block.clone()) == 0 => continue,
+                Some(bitmap) => block.filter(|row| bitmap.get(*row)).collect(),
+            };
+            let keys = key_column.take_sorted(&rows)?;
+            let values: Vec<ColumnValues> = value_columns.iter().map(|c| c.take_sorted(&rows)).collect::<Result<_>>()?;
+            for idx in 0..rows.len() {
+                let key = keys.group_key(idx);
+                let group = match index.get(&key) {
+                    Some(group) => *group,
+                    None => {
+                        index.insert(key.clone(), groups.len());
+                        groups.push((key, value_columns.iter().map(|c| Accumulator::new(c)).collect()));
+                        groups.len() - 1
+                    }
+                };
+                for (acc, values) in groups[group].1.iter_mut().zip(&values) {
+                    acc.push(values, idx);
+                }
+            }
+        }
+
+        let mut result: Vec<Group> = groups
+            .into_iter()
+            .map(|(key, accs)| Group {
+                key,
+                values: aggregates.iter().zip(&column_of).map(|((_, agg), idx)| accs[*idx].finish(*agg)).collect(),
+            })
+            .collect();
+        result.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ColumnarError;
+    use crate::{Codec, ColumnBuilder, ColumnPredicate, Expr, Predicate, StringColumnBuilder};
+    use tempfile::NamedTempFile;
+
+    const ROWS: usize = 12_000;
+
+    fn sales(labels: &NamedTempFile) -> Table {
+        // Регион i % 4, у каждой седьмой строки регион неизвестен
+        let regions: Vec<Option<i32>> = (0..ROWS as i32).map(|i| (i % 7 != 0).then_some(i % 4)).collect();
+        let amounts: Vec<Option<i64>> = (0..ROWS as i64).map(|i| (i % 5 != 0).then_some(i * 1000)).collect();
+        let prices: Vec<f64> = (0..ROWS).map(|i| (i % 100) as f64 / 4.0).collect();
+        let names: Vec<String> = (0..ROWS).map(|i| ["north", "south", "east"][i % 3].to_string()).collect();
+
+        let mut region = ColumnBuilder::from_nullable("region".to_string(), &regions);
+        region.set_chunk_rows(1000);
+        region.compress_with(Codec::Lz4).unwrap();
+        let mut amount = ColumnBuilder::from_nullable("amount".to_string(), &amounts);
+        amount.set_chunk_rows(700);
+        amount.compress_with(Codec::zstd()).unwrap();
+        let mut price = ColumnBuilder::from_f64("price".to_string(), &prices);
+        price.set_chunk_rows(1300);
+
+        let mut table = Table::new("sales".to_string());
+        table.add_column(region.build_in_memory().unwrap()).unwrap();
+        table.add_column(amount.build_in_memory().unwrap()).unwrap();
+        table.add_column(price.build_in_memory().unwrap()).unwrap();
+        table.add_column(StringColumnBuilder::from_strs("name".to_string(), &names).build(labels.path()).unwrap()).unwrap();
+        table
+    }
+
+    fn region_of(row: usize) -> GroupKey {
+        if row.is_multiple_of(7) { GroupKey::Null } else { GroupKey::Int32((row % 4) as i32) }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_group_by_known_groups() {
+        let labels = NamedTempFile::new().unwrap();
+        let table = sales(&labels);
+        let aggregates = [("amount", Agg::Sum), ("amount", Agg::Count), ("price", Agg::Max), ("amount", Agg::Avg)];
+        let groups = table.group_by("region", &aggregates, None).unwrap();
+        let keys: Vec<GroupKey> = groups.iter().map(|g| g.key.clone()).collect();
+        assert_eq!(keys, vec![GroupKey::Null, GroupKey::Int32(0), GroupKey::Int32(1), GroupKey::Int32(2), GroupKey::Int32(3)]);
+
+        for group in &groups {
+            let rows: Vec<usize> = (0..ROWS).filter(|row| region_of(*row) == group.key).collect();
+            let amounts: Vec<i64> = rows.iter().filter(|row| *row % 5 != 0).map(|row| *row as i64 * 1000).collect();
+            let sum: i128 = amounts.iter().map(|v| *v as i128).sum();
+            let max_price = rows.iter().map(|row| (row % 100) as f64 / 4.0).fold(f64::MIN, f64::max);
+            assert_eq!(
+                group.values,
+                vec![
+                    GroupValue::Int64(AggValue::Sum(sum)),
+                    GroupValue::Int64(AggValue::Count(amounts.len() as u64)),
+                    GroupValue::Float64(AggValue::Max(Some(max_price))),
+                    GroupValue::Int64(AggValue::Avg(Some(sum as f64 / amounts.len() as f64))),
+                ],
+                "{:?}",
+                group.key
+            );
+        }
+
+        let by_name = table.group_by("name", &[("region", Agg::Min), ("region", Agg::Sum)], None).unwrap();
+        assert_eq!(by_name.iter().map(|g| g.key.clone()).collect::<Vec<_>>(), vec![
+            GroupKey::Utf8("east".to_string()),
+            GroupKey::Utf8("north".to_string()),
+            GroupKey::Utf8("south".to_string()),
+        ]);
+        // Сумма i32 накапливается в i64
+        let north: i64 = (0..ROWS).filter(|row| row % 3 == 0 && row % 7 != 0).map(|row| (row % 4) as i64).sum();
+        assert_eq!(by_name[1].values, vec![GroupValue::Int32(AggValue::Min(Some(0))), GroupValue::Int32(AggValue::Sum(north))]);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_group_by_with_selection_and_empty_input() {
+        let labels = NamedTempFile::new().unwrap();
+        let table = sales(&labels);
+        // Нулевая цена только у строк с i % 100 == 0: регион там 0 или NULL, сумма всегда NULL
+        let selection = table.evaluate(&Expr::from(ColumnPredicate::Float64("price".to_string(), Predicate::Eq(0.0)))).unwrap();
+        let groups = table.group_by("region", &[("amount", Agg::Count)], Some(&selection)).unwrap();
+        assert_eq!(groups.iter().map(|g| g.key.clone()).collect::<Vec<_>>(), vec![GroupKey::Null, GroupKey::Int32(0)]);
+        assert_eq!(groups[1].values, vec![GroupValue::Int64(AggValue::Count(0))]);
+
+        let nothing = Selection::new(ROWS);
+        assert!(table.group_by("region", &[("amount", Agg::Sum)], Some(&nothing)).unwrap().is_empty());
+        let empty = Table::new("empty".to_string());
+        assert!(matches!(empty.group_by("region", &[], None), Err(ColumnarError::InvalidInput(_))));
+        let mut empty = Table::new("empty".to_string());
+        empty.add_column(ColumnBuilder::from_i64("k".to_string(), &[]).build_in_memory().unwrap()).unwrap();
+        assert!(empty.group_by("k", &[("k", Agg::Avg)], None).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_group_by_rejects_bad_arguments() {
+        let labels = NamedTempFile::new().unwrap();
+        let table = sales(&labels);
+        let invalid = |result: Result<Vec<Group>>| matches!(result, Err(ColumnarError::InvalidInput(_)));
+        assert!(invalid(table.group_by("price", &[("amount", Agg::Sum)], None)));
+        assert!(invalid(table.group_by("region", &[("name", Agg::Count)], None)));
+        assert!(invalid(table.group_by("region", &[("missing", Agg::Count)], None)));
+        assert!(invalid(table.group_by("region", &[], Some(&Selection::new(3)))));
+    }
+
+    #[test]
+    fn test_group_by_high_cardinality_key() {
+        let keys: Vec<i64> = (0..50_000).map(|i| (i * 7919) % 20_000).collect();
+        let mut table = Table::new("wide".to_string());
+        table.add_column(ColumnBuilder::from_i64("k".to_string(), &keys).build_in_memory().unwrap()).unwrap();
+        let groups = table.group_by("k", &[("k", Agg::Count)], None).unwrap();
+        assert_eq!(groups.len(), 20_000);
+        let counts: u64 = groups.iter().map(|g| match g.values[0] {
+            GroupValue::Int64(AggValue::Count(count)) => count,
+            other => panic!("{:?}", other),
+        }).sum();
+        assert_eq!(counts, 50_000);
+        assert_eq!(groups[0].key, GroupKey::Int64(0));
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    ops::Deref,
+    sync::{Arc, Mutex, RwLock},
+};
+use crate::error::Result;
+use crate::storage::Column;
+use crate::table::Table;
+
+// Текущее поколение неизменяемой колонки или таблицы. Читатель берёт снимок и
+// видит одно поколение целиком, сколько бы его ни держал; replace публикует новое
+// поколение атомарно, и следующие read видят уже его. Прежнее поколение живёт,
+// пока на него есть снимки
+#[derive(Debug)]
+pub struct Handle<V> {
+    current: RwLock<Snapshot<V>>,
+    // Последовательные update не должны терять результаты друг друга
+    updating: Mutex<()>,
+}
+
+pub type ColumnHandle<T = i32> = Handle<Column<T>>;
+pub type TableHandle = Handle<Table>;
+
+// Закреплённое поколение: разыменовывается в колонку или таблицу
+#[derive(Debug)]
+pub struct Snapshot<V> {
+    generation: u64,
+    value: Arc<V>,
+}
+
+impl<V> Clone for Snapshot<V> {
+    fn clone(&self) -> Self {
+        Self { generation: self.generation, value: Arc::clone(&self.value) }
+    }
+}
+
+impl<V> Snapshot<V> {
+    // Номер поколения: 0 у исходного значения, каждое replace прибавляет единицу
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn value(&self) -> &Arc<V> {
+        &self.value
+    }
+}
+
+impl<V> Deref for Snapshot<V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.value
+    }
+}
+
+impl<V> Handle<V> {
+    pub fn new(value: V) -> Self {
+        Self::from_arc(Arc::new(value))
+    }
+
+    pub fn from_arc(value: Arc<V>) -> Self {
+        Self {
+            current: RwLock::new(Snapshot { generation: 0, value }),
+            updating: Mutex::new(()),
+        }
+    }
+
+    // Под блокировкой только клонируется Arc, поэтому читатели не ждут друг друга
+    // и почти не ждут replace
+    pub fn read(&self) -> Snapshot<V> {
+        self.current.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.read().generation
+    }
+
+    // Публикует новое поколение и возвращает его номер
+    pub fn replace(&self, value: V) -> u64 {
+        self.replace_arc(Arc::new(value))
+    }
+
+    pub fn replace_arc(&self, value: Arc<V>) -> u64 {
+        let mut current = self.current.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let generation = current.generation + 1;
+        *current = Snapshot { generation, value };
+        generation
+    }
+
+    // Новое поколение из текущего, например Column::compact. Вызовы update идут по
+    // очереди, так что каждый видит результат предыдущего; replace в обход update
+    // между чтением и публикацией будет перезаписан. Ошибка f ничего не публикует
+    pub fn update(&self, f: impl FnOnce(&V) -> Result<V>) -> Result<u64> {
+        let _guard = self.updating.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let next = f(&self.read())?;
+        Ok(self.replace(next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use crate::error::invalid_input;
+    use crate::{Agg, AggValue, ColumnBuilder};
+
+    // Все строки поколения равны его номеру, поэтому смешанное чтение сразу заметно
+    fn generation_column(generation: u64) -> Column<i64> {
+        let values = vec![generation as i64; 5000 + generation as usize];
+        let mut builder = ColumnBuilder::from_values("v".to_string(), &values);
+        builder.set_chunk_rows(512);
+        builder.build_in_memory().unwrap()
+    }
+
+    #[test]
+    fn test_readers_see_whole_generations_during_replacements() {
+        let handle = ColumnHandle::new(generation_column(0));
+        let done = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            let readers: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut last = 0;
+                        let mut reads = 0;
+                        while !done.load(Ordering::Acquire) || reads == 0 {
+                            let snapshot = handle.read();
+                            let generation = snapshot.generation();
+                            assert!(generation >= last, "поколение {} после {}", generation, last);
+                            last = generation;
+                            assert_eq!(snapshot.len(), 5000 + generation as usize);
+                            assert!(snapshot.iter().all(|v| v.unwrap() == generation as i64));
+                            let sum = snapshot.aggregate(Agg::Sum, None).unwrap();
+                            assert_eq!(sum, AggValue::Sum((generation * (5000 + generation)) as i128));
+                            reads += 1;
+                        }
+                        reads
+                    })
+                })
+                .collect();
+            for generation in 1..=40 {
+                assert_eq!(handle.replace(generation_column(generation)), generation);
+            }
+            done.store(true, Ordering::Release);
+            for reader in readers {
+                assert!(reader.join().unwrap() > 0);
+            }
+        });
+        assert_eq!(handle.generation(), 40);
+    }
+
+    #[test]
+    fn test_snapshot_outlives_replace_and_failed_update_publishes_nothing() {
+        let handle = ColumnHandle::new(generation_column(0));
+        let pinned = handle.read();
+        assert_eq!(handle.update(|column| Ok(generation_column(column.len() as u64 - 4999))).unwrap(), 1);
+        assert_eq!(pinned.generation(), 0);
+        assert_eq!(pinned.get_value(0), Some(0));
+        assert_eq!(handle.read().get_value(0), Some(1));
+
+        assert!(handle.update(|_| Err(invalid_input("отказ"))).is_err());
+        assert_eq!(handle.generation(), 1);
+        let table = TableHandle::new(Table::new("t".to_string()));
+        assert_eq!(table.read().name, "t");
+    }
+}
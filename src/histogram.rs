@@ -0,0 +1,279 @@
+use crate::error::{corrupt, invalid_input, Result};
+use crate::format::ByteReader;
+use crate::types::ColumnType;
+
+// Число корзин, если оно не задано явно
+pub const DEFAULT_HISTOGRAM_BUCKETS: usize = 64;
+const MAX_HISTOGRAM_BUCKETS: usize = 4096;
+// Размер выборки на корзину: границы по 256 значениям на корзину отклоняются от точных
+// квантилей на доли глубины корзины
+const SAMPLE_PER_BUCKET: u64 = 256;
+
+// Корзина гистограммы: значения от lower до upper включительно
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket<T: ColumnType = i32> {
+    pub lower: T,
+    pub upper: T,
+    pub count: u64,
+}
+
+// Равноглубинная гистограмма значений колонки без NULL и NaN: в корзинах примерно
+// поровну значений, а ширина корзин следует распределению. Границы строятся по
+// случайной выборке, поэтому для больших колонок они приближённые
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram<T: ColumnType = i32> {
+    // Запрошенное при записи число корзин; при малом числе значений корзин меньше
+    target_buckets: usize,
+    buckets: Vec<Bucket<T>>,
+}
+
+pub(crate) fn check_histogram_buckets(buckets: usize) -> Result<()> {
+    if !(1..=MAX_HISTOGRAM_BUCKETS).contains(&buckets) {
+        return Err(invalid_input(format!(
+            "число корзин гистограммы {} вне диапазона 1..={}",
+            buckets, MAX_HISTOGRAM_BUCKETS
+        )));
+    }
+    Ok(())
+}
+
+fn to_f64<T: ColumnType>(value: T) -> f64 {
+    T::sum_to_f64(value.widen())
+}
+
+impl<T: ColumnType> Histogram<T> {
+    pub fn buckets(&self) -> &[Bucket<T>] {
+        &self.buckets
+    }
+
+    pub fn target_buckets(&self) -> usize {
+        self.target_buckets
+    }
+
+    // Число значений, по которым построена гистограмма
+    pub fn total(&self) -> u64 {
+        self.buckets.iter().map(|b| b.count).sum()
+    }
+
+    // Оценка доли значений в [lo, hi]: корзины внутри диапазона берутся целиком,
+    // частично перекрытые — пропорционально ширине перекрытия
+    pub fn selectivity(&self, lo: T, hi: T) -> f64 {
+        let total = self.total();
+        if total == 0 || lo.total_cmp(&hi).is_gt() {
+            return 0.0;
+        }
+        let covered: f64 = self
+            .buckets
+            .iter()
+            .map(|b| {
+                if b.upper.total_cmp(&lo).is_lt() || b.lower.total_cmp(&hi).is_gt() {
+                    return 0.0;
+                }
+                if lo.total_cmp(&b.lower).is_le() && b.upper.total_cmp(&hi).is_le() {
+                    return b.count as f64;
+                }
+                let (lower, upper) = (to_f64(b.lower), to_f64(b.upper));
+                let from = lower.max(to_f64(lo));
+                let to = upper.min(to_f64(hi));
+                b.count as f64 * ((to - from) / (upper - lower)).clamp(0.0, 1.0)
+            })
+            .sum();
+        covered / total as f64
+    }
+
+    // sample — значения в любом порядке, values — сколько значений они представляют
+    pub(crate) fn from_sample(mut sample: Vec<T>, values: u64, target_buckets: usize) -> Self {
+        sample.sort_by(|a, b| a.total_cmp(b));
+        let m = sample.len();
+        let count = target_buckets.min(m);
+        // Доли выборки переводятся в число значений так, чтобы сумма была точно values
+        let scaled = |idx: usize| (values as u128 * idx as u128 / m as u128) as u64;
+        let buckets = (0..count)
+            .map(|i| {
+                let (start, end) = (i * m / count, (i + 1) * m / count);
+                Bucket { lower: sample[start], upper: sample[end - 1], count: scaled(end) - scaled(start) }
+            })
+            .collect();
+        Self { target_buckets, buckets }
+    }
+
+    // Гистограмма объединения двух наборов значений без их повторного чтения, например
+    // прежних строк и дописанных. Корзины обеих сортируются по нижней границе и
+    // группируются подряд по target_buckets, пока не набрана доля значений. Исходная
+    // корзина не делится, поэтому границы грубее построенных заново; перестроит их compact
+    pub(crate) fn merge(&self, other: &Self) -> Self {
+        let mut parts: Vec<Bucket<T>> = self.buckets.iter().chain(&other.buckets).filter(|b| b.count > 0).copied().collect();
+        parts.sort_by(|a, b| a.lower.total_cmp(&b.lower).then(a.upper.total_cmp(&b.upper)));
+        let total = parts.iter().map(|b| b.count as u128).sum::<u128>();
+        let target = self.target_buckets as u128;
+        let mut buckets: Vec<Bucket<T>> = Vec::new();
+        let mut open: Option<Bucket<T>> = None;
+        let mut seen = 0u128;
+        for part in parts {
+            let bucket = open.get_or_insert(Bucket { count: 0, ..part });
+            if part.upper.total_cmp(&bucket.upper).is_gt() {
+                bucket.upper = part.upper;
+            }
+            bucket.count += part.count;
+            seen += part.count as u128;
+            if seen * target >= (buckets.len() as u128 + 1) * total {
+                buckets.extend(open.take());
+            }
+        }
+        buckets.extend(open);
+        Self { target_buckets: self.target_buckets, buckets }
+    }
+
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&(self.target_buckets as u32).to_le_bytes());
+        out.extend_from_slice(&(self.buckets.len() as u32).to_le_bytes());
+        for bucket in &self.buckets {
+            bucket.lower.write_le(out);
+            bucket.upper.write_le(out);
+            out.extend_from_slice(&bucket.count.to_le_bytes());
+        }
+    }
+
+    pub(crate) fn decode(r: &mut ByteReader) -> Result<Self> {
+        let target_buckets = r.u32()? as usize;
+        check_histogram_buckets(target_buckets).map_err(|_| corrupt("неверное число корзин гистограммы"))?;
+        let len = r.u32()? as usize;
+        if len > target_buckets {
+            return Err(corrupt("в гистограмме больше корзин, чем запрошено при записи"));
+        }
+        let mut buckets = Vec::with_capacity(len);
+        for _ in 0..len {
+            buckets.push(Bucket { lower: r.value()?, upper: r.value()?, count: r.u64()? });
+        }
+        Ok(Self { target_buckets, buckets })
+    }
+}
+
+// Равномерная выборка фиксированного размера из потока значений (reservoir sampling).
+// Выборка через каждое k-е значение совпадала бы по фазе с периодическими данными,
+// поэтому позиции выбираются псевдослучайно, но с постоянным зерном: одинаковые данные
+// дают одинаковую гистограмму
+#[derive(Debug)]
+pub(crate) struct Sampler<T: ColumnType> {
+    limit: u64,
+    seen: u64,
+    state: u64,
+    sample: Vec<T>,
+}
+
+impl<T: ColumnType> Sampler<T> {
+    pub fn new(target_buckets: usize) -> Self {
+        Self { limit: target_buckets as u64 * SAMPLE_PER_BUCKET, seen: 0, state: 0x9E37_79B9_7F4A_7C15, sample: Vec::new() }
+    }
+
+    pub fn push(&mut self, value: T) {
+        if value.is_nan() {
+            return;
+        }
+        self.seen += 1;
+        if (self.sample.len() as u64) < self.limit {
+            self.sample.push(value);
+            return;
+        }
+        // Значение замещает случайный элемент выборки с вероятностью limit / seen
+        let slot = self.next_random() % self.seen;
+        if slot < self.limit {
+            self.sample[slot as usize] = value;
+        }
+    }
+
+    pub fn finish(self, target_buckets: usize) -> Histogram<T> {
+        Histogram::from_sample(self.sample, self.seen, target_buckets)
+    }
+
+    // splitmix64
+    fn next_random(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnBuilder, ColumnarError};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_uniform_data_gets_evenly_spaced_bounds() {
+        let values: Vec<i32> = (0..1_000_000i64).map(|i| ((i * 7919) % 1_000_000) as i32).collect();
+        let mut builder = ColumnBuilder::from_i32("uniform".to_string(), &values);
+        builder.enable_histogram(DEFAULT_HISTOGRAM_BUCKETS).unwrap();
+        let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+        let stats = column.stats();
+        let histogram = stats.histogram().unwrap();
+        assert_eq!(histogram.buckets().len(), 64);
+        assert_eq!(histogram.total(), 1_000_000);
+
+        // Каждая корзина покрывает около 1/64 диапазона; границы по выборке сдвинуты
+        // не больше чем на четверть ширины корзины
+        let width = 1_000_000.0 / 64.0;
+        for (idx, bucket) in histogram.buckets().iter().enumerate() {
+            assert!((bucket.lower as f64 - idx as f64 * width).abs() < width * 0.25, "{:?}", bucket);
+            assert!((bucket.count as f64 - 15_625.0).abs() < 200.0, "{:?}", bucket);
+        }
+        assert!((histogram.selectivity(250_000, 499_999) - 0.25).abs() < 0.01);
+        assert_eq!(histogram.selectivity(2_000_000, 3_000_000), 0.0);
+        assert_eq!(histogram.selectivity(i32::MIN, i32::MAX), 1.0);
+    }
+
+    #[test]
+    fn test_skewed_data_narrows_buckets_where_values_concentrate() {
+        // 90% значений в [0, 100), остальные размазаны по [100, 1_000_000)
+        let values: Vec<i64> = (0..200_000)
+            .map(|i| if i % 10 != 0 { i % 100 } else { 100 + (i * 7919) % 999_900 })
+            .collect();
+        let mut builder = ColumnBuilder::from_i64("skewed".to_string(), &values);
+        builder.enable_histogram(20).unwrap();
+        builder.set_chunk_rows(30_000);
+        let path = NamedTempFile::new().unwrap();
+        builder.build(path.path()).unwrap();
+        // Гистограмма переживает повторное открытие файла
+        let column = crate::Column::<i64>::open(path.path()).unwrap();
+        let histogram = column.stats().histogram().unwrap().clone();
+        let buckets = histogram.buckets();
+        assert_eq!(buckets.len(), 20);
+        // Плотный участок делится на узкие корзины, а хвост укладывается в пару широких
+        assert!(buckets[..17].iter().all(|b| b.upper < 100), "{:?}", buckets);
+        assert!(buckets[19].lower >= 100 && buckets[19].upper > 900_000, "{:?}", buckets);
+        assert!((histogram.selectivity(0, 99) - 0.9).abs() < 0.02);
+        assert!((histogram.selectivity(100, 1_000_000) - 0.1).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_histogram_is_optional_and_follows_appends() {
+        let plain = ColumnBuilder::from_f64("plain".to_string(), &[1.0, 2.0]).build_in_memory().unwrap();
+        assert!(plain.stats().histogram().is_none());
+
+        let mut builder = ColumnBuilder::from_nullable("n".to_string(), &[Some(1.0), None, Some(f64::NAN), Some(3.0)]);
+        builder.enable_histogram(8).unwrap();
+        let mut column = builder.build_in_memory().unwrap();
+        let histogram = column.stats().histogram().unwrap().clone();
+        // NULL и NaN в гистограмму не входят, а корзин не больше, чем значений
+        assert_eq!(histogram.buckets(), &[
+            Bucket { lower: 1.0, upper: 1.0, count: 1 },
+            Bucket { lower: 3.0, upper: 3.0, count: 1 },
+        ]);
+        column.append(&(0..1000).map(|i| i as f64).collect::<Vec<_>>()).unwrap();
+        let histogram = column.stats().histogram().unwrap().clone();
+        assert_eq!((histogram.buckets().len(), histogram.target_buckets(), histogram.total()), (8, 8, 1002));
+        // Дозапись сливает выборку новых строк с прежними корзинами
+        column.append(&(1000..3000).map(|i| i as f64).collect::<Vec<_>>()).unwrap();
+        let histogram = column.stats().histogram().unwrap().clone();
+        assert_eq!((histogram.buckets().len(), histogram.total()), (8, 3002));
+        assert!(histogram.buckets().windows(2).all(|w| w[0].upper <= w[1].lower), "{:?}", histogram.buckets());
+        assert!((histogram.selectivity(0.0, 999.0) - 1.0 / 3.0).abs() < 0.05);
+
+        let mut builder = ColumnBuilder::from_i32("bad".to_string(), &[1]);
+        assert!(matches!(builder.enable_histogram(0), Err(ColumnarError::InvalidInput(_))));
+        assert!(matches!(builder.enable_histogram(1 << 20), Err(ColumnarError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::error::{corrupt, invalid_input, Result};
+use crate::format::ByteReader;
+use crate::types::ColumnType;
+
+// Точность по умолчанию: 4096 регистров, стандартная ошибка около 1,6%
+pub(crate) const DEFAULT_HLL_PRECISION: u8 = 12;
+const MIN_PRECISION: u8 = 4;
+const MAX_PRECISION: u8 = 16;
+
+// HyperLogLog для приближённого числа различных значений. Регистры хранятся в footer,
+// поэтому дозапись продолжает ту же оценку, а не пересчитывает её по всей колонке
+#[derive(Debug, Clone)]
+pub(crate) struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub fn new(precision: u8) -> Result<Self> {
+        if !(MIN_PRECISION..=MAX_PRECISION).contains(&precision) {
+            return Err(invalid_input(format!(
+                "точность HyperLogLog {} вне диапазона {}..={}",
+                precision, MIN_PRECISION, MAX_PRECISION
+            )));
+        }
+        Ok(Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        })
+    }
+
+    pub fn insert<T: ColumnType>(&mut self, value: &T) {
+        let hash = mix64(value.to_bits() as u64);
+        let idx = (hash >> (64 - self.precision)) as usize;
+        // Сторожевой бит ограничивает ранг, когда оставшиеся биты хеша нулевые
+        let rest = (hash << self.precision) | (1 << (self.precision - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        if rank > self.registers[idx] {
+            self.registers[idx] = rank;
+        }
+    }
+
+    // Пустой счётчик той же точности, который можно слить с этим
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    pub fn empty_like(&self) -> Self {
+        Self {
+            precision: self.precision,
+            registers: vec![0; self.registers.len()],
+        }
+    }
+
+    // Оценка по объединению значений обоих счётчиков; точности должны совпадать
+    pub fn merge(&mut self, other: &Self) {
+        debug_assert_eq!(self.precision, other.precision);
+        for (register, other) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    pub fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self.registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+        let raw = alpha * m * m / sum;
+        // На малых кардинальностях точнее линейный подсчёт по пустым регистрам
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        let estimate = if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        };
+        estimate.round() as u64
+    }
+
+    // precision u8 | регистры
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.push(self.precision);
+        out.extend_from_slice(&self.registers);
+    }
+
+    pub fn decode(r: &mut ByteReader) -> Result<Self> {
+        let precision = r.bytes(1)?[0];
+        let mut hll = Self::new(precision).map_err(|_| corrupt("повреждена точность HyperLogLog в метаданных"))?;
+        let registers = r.bytes(hll.registers.len())?;
+        hll.registers.copy_from_slice(registers);
+        if hll.registers.iter().any(|r| *r > 64 - precision + 1) {
+            return Err(corrupt("повреждён регистр HyperLogLog в метаданных"));
+        }
+        Ok(hll)
+    }
+}
+
+// Финализатор splitmix64: хеш не зависит от версии стандартной библиотеки,
+// поэтому регистры из старого файла остаются совместимыми при дозаписи
+fn mix64(mut x: u64) -> u64 {
+    x ^= x >> 30;
+    x = x.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relative_error(estimate: u64, actual: u64) -> f64 {
+        (estimate as f64 - actual as f64).abs() / actual as f64
+    }
+
+    #[test]
+    fn test_estimate_within_error_bounds() {
+        for (precision, tolerance) in [(10, 0.08), (12, 0.04), (14, 0.02)] {
+            for actual in [50u64, 1_000, 30_000, 1_000_000] {
+                let mut hll = HyperLogLog::new(precision).unwrap();
+                // Повторы не меняют оценку
+                for _ in 0..2 {
+                    for value in 0..actual as i64 {
+                        hll.insert(&(value * 7919 - 500_000));
+                    }
+                }
+                let error = relative_error(hll.estimate(), actual);
+                assert!(error < tolerance, "p={} n={} оценка {}", precision, actual, hll.estimate());
+            }
+        }
+        assert_eq!(HyperLogLog::new(12).unwrap().estimate(), 0);
+    }
+
+    #[test]
+    fn test_encode_roundtrip_and_precision_bounds() {
+        let mut hll = HyperLogLog::new(8).unwrap();
+        for value in 0..10_000i32 {
+            hll.insert(&value);
+        }
+        let mut out = Vec::new();
+        hll.encode(&mut out);
+        assert_eq!(out.len(), 1 + 256);
+        let decoded = HyperLogLog::decode(&mut ByteReader::new(&out)).unwrap();
+        assert_eq!(decoded.estimate(), hll.estimate());
+        assert_eq!(decoded.precision, 8);
+
+        assert!(HyperLogLog::new(3).is_err());
+        assert!(HyperLogLog::new(17).is_err());
+        out[0] = 40;
+        assert!(HyperLogLog::decode(&mut ByteReader::new(&out)).is_err());
+    }
+}
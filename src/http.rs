@@ -0,0 +1,480 @@
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+use lru::LruCache;
+use crate::error::{corrupt, invalid_input, ColumnarError, Result};
+use crate::read_at::ReadAt;
+
+// Параметры HttpReadAt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpOptions {
+    // Повторы запроса после сетевой ошибки или ответа 5xx и 429
+    pub retries: u32,
+    // Пауза перед первым повтором, дальше она удваивается
+    pub retry_delay: Duration,
+    // Таймаут соединения, чтения и записи одного запроса
+    pub timeout: Duration,
+    // Чтения округляются до блоков этого размера и кэшируются по ним
+    pub block_size: usize,
+    // Ёмкость кэша блоков; 0 отключает кэш
+    pub cache_blocks: usize,
+    // Сколько байт с конца файла берёт первый запрос: в них хвост и обычно весь footer
+    pub tail_size: usize,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            retry_delay: Duration::from_millis(100),
+            timeout: Duration::from_secs(30),
+            block_size: 64 * 1024,
+            cache_blocks: 256,
+            tail_size: 64 * 1024,
+        }
+    }
+}
+
+// Файл колонки на HTTP-сервере, читаемый запросами Range. open узнаёт длину файла
+// запросом суффикса и сохраняет его, так что ColumnMeta::read не ходит за хвостом
+// и footer повторно; остальные чтения идут блоками через кэш LRU. Сервер, который
+// не поддерживает Range и отвечает 200 всем телом, определяется по ответу: файл
+// тогда хранится целиком и дальше читается из памяти. Только http://, без TLS
+pub struct HttpReadAt {
+    url: String,
+    addr: String,
+    authority: String,
+    target: String,
+    options: HttpOptions,
+    len: u64,
+    tail_start: u64,
+    tail: Vec<u8>,
+    whole: OnceLock<Vec<u8>>,
+    blocks: Mutex<LruCache<u64, Arc<Vec<u8>>>>,
+    requests: AtomicU64,
+}
+
+struct Response {
+    status: u16,
+    // Начало, конец включительно и длина всего файла из Content-Range
+    content_range: Option<(u64, u64, u64)>,
+    body: Vec<u8>,
+}
+
+impl HttpReadAt {
+    pub fn open(url: &str, options: HttpOptions) -> Result<Self> {
+        if options.block_size == 0 || options.tail_size == 0 {
+            return Err(invalid_input("размер блока и хвоста HTTP-источника должен быть больше нуля"));
+        }
+        let (authority, target) = split_url(url)?;
+        let addr = if has_port(authority) { authority.to_string() } else { format!("{}:80", authority) };
+        let mut source = Self {
+            url: url.to_string(),
+            addr,
+            authority: authority.to_string(),
+            target: target.to_string(),
+            options,
+            len: 0,
+            tail_start: 0,
+            tail: Vec::new(),
+            whole: OnceLock::new(),
+            blocks: Mutex::new(LruCache::new(NonZeroUsize::new(options.cache_blocks.max(1)).unwrap())),
+            requests: AtomicU64::new(0),
+        };
+        let response = source.get(&format!("bytes=-{}", options.tail_size))?;
+        match response.content_range {
+            Some((start, end, len)) if response.status == 206 => {
+                if end + 1 != len || response.body.len() as u64 != len - start {
+                    return Err(corrupt(format!("сервер вернул не хвост файла {}", url)));
+                }
+                source.len = len;
+                source.tail_start = start;
+                source.tail = response.body;
+            }
+            _ => {
+                source.len = response.body.len() as u64;
+                source.tail_start = source.len;
+                source.whole.set(response.body).unwrap();
+            }
+        }
+        Ok(source)
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    // Отвечает ли сервер на запросы Range
+    pub fn supports_ranges(&self) -> bool {
+        self.whole.get().is_none()
+    }
+
+    // Число HTTP-запросов с открытия, включая повторы
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    // Байты start..end одним запросом Range
+    fn fetch(&self, start: u64, end: u64) -> Result<Vec<u8>> {
+        let response = self.get(&format!("bytes={}-{}", start, end - 1))?;
+        if response.status == 200 {
+            if response.body.len() as u64 != self.len {
+                return Err(corrupt(format!("файл {} изменился: {} байт вместо {}", self.url, response.body.len(), self.len)));
+            }
+            let whole = self.whole.get_or_init(|| response.body);
+            return Ok(whole[start as usize..end as usize].to_vec());
+        }
+        match response.content_range {
+            Some((first, last, len)) if first == start && last + 1 == end && len == self.len => {}
+            _ => return Err(corrupt(format!("сервер вернул не тот диапазон {}-{} файла {}", start, end - 1, self.url))),
+        }
+        if response.body.len() as u64 != end - start {
+            return Err(corrupt(format!("сервер вернул {} байт вместо {}", response.body.len(), end - start)));
+        }
+        Ok(response.body)
+    }
+
+    fn read_blocks(&self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        let block_size = self.options.block_size as u64;
+        let end = offset + len as u64;
+        let first = offset / block_size;
+        let last = (end - 1) / block_size;
+        let mut blocks: Vec<Option<Arc<Vec<u8>>>> = {
+            let mut cache = self.blocks.lock().unwrap();
+            (first..=last).map(|block| cache.get(&block).cloned()).collect()
+        };
+        // Недостающие блоки берутся одним запросом от первого до последнего из них
+        let missing = blocks.iter().position(Option::is_none).zip(blocks.iter().rposition(Option::is_none));
+        if let Some((from, to)) = missing {
+            let start = (first + from as u64) * block_size;
+            let stop = ((first + to as u64 + 1) * block_size).min(self.len);
+            let bytes = self.fetch(start, stop)?;
+            let mut cache = self.blocks.lock().unwrap();
+            for (i, block) in bytes.chunks(self.options.block_size).enumerate() {
+                let block = Arc::new(block.to_vec());
+                cache.put(first + (from + i) as u64, block.clone());
+                blocks[from + i] = Some(block);
+            }
+        }
+        let mut out = Vec::with_capacity(len);
+        for (i, block) in blocks.iter().enumerate() {
+            let block_start = (first + i as u64) * block_size;
+            let from = offset.saturating_sub(block_start) as usize;
+            let to = ((end - block_start) as usize).min(block.as_ref().unwrap().len());
+            out.extend_from_slice(&block.as_ref().unwrap()[from..to]);
+        }
+        Ok(out)
+    }
+
+    // Запрос с повторами после сетевых ошибок и ответов 5xx и 429
+    fn get(&self, range: &str) -> Result<Response> {
+        let mut delay = self.options.retry_delay;
+        let mut attempt = 0;
+        let result = loop {
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            let result = self.request(range);
+            let retryable = match &result {
+                Ok(response) => response.status >= 500 || response.status == 429,
+                Err(_) => true,
+            };
+            if !retryable || attempt >= self.options.retries {
+                break result;
+            }
+            thread::sleep(delay);
+            delay *= 2;
+            attempt += 1;
+        };
+        let response = result?;
+        if response.status != 200 && response.status != 206 {
+            return Err(ColumnarError::Io(io::Error::other(format!("HTTP {} на GET {}", response.status, self.url))));
+        }
+        Ok(response)
+    }
+
+    fn request(&self, range: &str) -> io::Result<Response> {
+        let addr = self.addr.to_socket_addrs()?.next().ok_or_else(|| io::Error::other(format!("адрес {} не найден", self.addr)))?;
+        let mut stream = TcpStream::connect_timeout(&addr, self.options.timeout)?;
+        stream.set_read_timeout(Some(self.options.timeout))?;
+        stream.set_write_timeout(Some(self.options.timeout))?;
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: {}\r\nRange: {}\r\nAccept-Encoding: identity\r\nConnection: close\r\n\r\n",
+            self.target, self.authority, range
+        )?;
+        stream.flush()?;
+        read_response(BufReader::new(stream))
+    }
+}
+
+impl ReadAt for HttpReadAt {
+    fn read_at(&self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        if offset.checked_add(len as u64).is_none_or(|end| end > self.len) {
+            return Err(corrupt(format!("диапазон {}+{} вне файла из {} байт", offset, len, self.len)));
+        }
+        if let Some(whole) = self.whole.get() {
+            return Ok(whole[offset as usize..offset as usize + len].to_vec());
+        }
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        if offset >= self.tail_start {
+            let start = (offset - self.tail_start) as usize;
+            return Ok(self.tail[start..start + len].to_vec());
+        }
+        // Чтение больше кэша, например файл целиком, идёт мимо него
+        if len.div_ceil(self.options.block_size) > self.options.cache_blocks {
+            return self.fetch(offset, offset + len as u64);
+        }
+        self.read_blocks(offset, len)
+    }
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+// Байты кэша и хвоста в отладочном выводе не нужны
+impl fmt::Debug for HttpReadAt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpReadAt")
+            .field("url", &self.url)
+            .field("len", &self.len)
+            .field("supports_ranges", &self.supports_ranges())
+            .field("requests", &self.requests())
+            .finish()
+    }
+}
+
+// http://host[:port]/path -> (host[:port], /path)
+fn split_url(url: &str) -> Result<(&str, &str)> {
+    let Some(rest) = url.strip_prefix("http://") else {
+        return Err(invalid_input(format!("поддерживаются только адреса http:// без TLS: {}", url)));
+    };
+    let (authority, target) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() {
+        return Err(invalid_input(format!("в адресе нет хоста: {}", url)));
+    }
+    Ok((authority, target))
+}
+
+// Порт после последнего двоеточия; у адреса IPv6 двоеточия стоят и внутри скобок
+fn has_port(authority: &str) -> bool {
+    authority
+        .rsplit_once(':')
+        .is_some_and(|(host, port)| port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']')))
+}
+
+fn read_response(mut reader: impl BufRead) -> io::Result<Response> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let status = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("неверная строка статуса HTTP: {:?}", line.trim_end())))?;
+    let mut content_length = None;
+    let mut content_range = None;
+    let mut chunked = false;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => content_length = value.parse::<usize>().ok(),
+            "content-range" => content_range = parse_content_range(value),
+            "transfer-encoding" => chunked = value.to_ascii_lowercase().contains("chunked"),
+            _ => {}
+        }
+    }
+    let body = if chunked {
+        read_chunked(&mut reader)?
+    } else if let Some(len) = content_length {
+        let mut body = vec![0u8; len];
+        reader.read_exact(&mut body)?;
+        body
+    } else {
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body)?;
+        body
+    };
+    Ok(Response { status, content_range, body })
+}
+
+// bytes START-END/LEN
+fn parse_content_range(value: &str) -> Option<(u64, u64, u64)> {
+    let (range, len) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    Some((start.trim().parse().ok()?, end.trim().parse().ok()?, len.trim().parse().ok()?))
+}
+
+fn read_chunked(reader: &mut impl BufRead) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let size = line.trim_end().split(';').next().unwrap_or("");
+        let size = usize::from_str_radix(size.trim(), 16)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("неверный размер куска HTTP: {:?}", line.trim_end())))?;
+        if size == 0 {
+            // Завершающие заголовки не нужны
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+                    return Ok(body);
+                }
+            }
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        line.clear();
+        reader.read_line(&mut line)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, sync::atomic::AtomicUsize};
+    use crate::read_at::ColumnMeta;
+    use crate::{Codec, Column, ColumnBuilder, Predicate};
+
+    // Сервер на случайном порту. ranges = false — сервер без Range, отвечающий 200
+    // всем файлом; первые failures запросов получают 503
+    fn serve(bytes: Vec<u8>, ranges: bool, failures: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/data/v.col", listener.local_addr().unwrap());
+        let served = AtomicUsize::new(0);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut range = None;
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && !line.trim_end().is_empty() {
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        range = Some(value.trim().to_string());
+                    }
+                    line.clear();
+                }
+                if served.fetch_add(1, Ordering::Relaxed) < failures {
+                    stream.write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n").unwrap();
+                    continue;
+                }
+                let len = bytes.len();
+                let (start, end) = match range.as_deref().map(|r| r.split_once('-').unwrap()) {
+                    Some(("", suffix)) if ranges => (len.saturating_sub(suffix.parse().unwrap()), len - 1),
+                    Some((start, end)) if ranges => (start.parse().unwrap(), end.parse::<usize>().unwrap().min(len - 1)),
+                    _ => {
+                        write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", len).unwrap();
+                        stream.write_all(&bytes).unwrap();
+                        continue;
+                    }
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\n\r\n",
+                    start,
+                    end,
+                    len,
+                    end + 1 - start
+                )
+                .unwrap();
+                stream.write_all(&bytes[start..=end]).unwrap();
+            }
+        });
+        url
+    }
+
+    fn build(path: &std::path::Path) -> Column<i64> {
+        let values: Vec<i64> = (0..200_000).map(|i| i * 7 % 100_003).collect();
+        let mut builder = ColumnBuilder::from_values("v".to_string(), &values);
+        builder.set_chunk_rows(10_000);
+        builder.compress_with(Codec::Lz4).unwrap();
+        builder.build(path).unwrap()
+    }
+
+    fn open(source: &Arc<HttpReadAt>) -> Column<i64> {
+        let meta = ColumnMeta::read(&**source).unwrap();
+        Column::open_with(source.clone(), &meta).unwrap()
+    }
+
+    #[test]
+    fn test_point_lookups_and_filter_over_ranges() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let local = build(&path);
+        let url = serve(std::fs::read(&path).unwrap(), true, 0);
+
+        let source = Arc::new(HttpReadAt::open(&url, HttpOptions::default()).unwrap());
+        assert!(source.supports_ranges());
+        assert_eq!(source.len(), local.backing.len() as u64);
+        let remote = open(&source);
+        let after_open = source.requests();
+        assert_eq!(remote.get_value(123_456), local.get_value(123_456));
+        assert_eq!(remote.get_value(123_457), local.get_value(123_457));
+        assert_eq!(source.requests(), after_open + 1);
+        // Вторая колонка над тем же источником берёт метаданные и чанк из кэша блоков
+        let other = open(&source);
+        assert_eq!(other.get_value(120_000), local.get_value(120_000));
+        assert_eq!(source.requests(), after_open + 1);
+
+        assert_eq!(remote.filter(Predicate::Between(10, 20)).unwrap(), local.filter(Predicate::Between(10, 20)).unwrap());
+        assert_eq!(remote.values().unwrap(), local.values().unwrap());
+        assert!(remote.verify().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_server_without_ranges_and_retries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let local = build(&path);
+        let bytes = std::fs::read(&path).unwrap();
+
+        // Сервер вернул 200 всем файлом: дальше запросов нет
+        let source = Arc::new(HttpReadAt::open(&serve(bytes.clone(), false, 0), HttpOptions::default()).unwrap());
+        assert!(!source.supports_ranges());
+        let remote = open(&source);
+        assert_eq!(remote.filter(Predicate::Between(10, 20)).unwrap(), local.filter(Predicate::Between(10, 20)).unwrap());
+        assert_eq!(source.requests(), 1);
+
+        let options = HttpOptions { retry_delay: Duration::from_millis(1), ..HttpOptions::default() };
+        let url = serve(bytes.clone(), true, 2);
+        let source = HttpReadAt::open(&url, options).unwrap();
+        assert_eq!(source.requests(), 3);
+        assert_eq!(source.read_at(0, 4).unwrap(), bytes[..4]);
+
+        let url = serve(bytes, true, 1);
+        let err = HttpReadAt::open(&url, HttpOptions { retries: 0, ..options }).unwrap_err();
+        assert!(matches!(err, ColumnarError::Io(_)) && err.to_string().contains("503"));
+        assert!(matches!(HttpReadAt::open("https://example.com/v.col", options), Err(ColumnarError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_response_parsing() {
+        let raw = b"HTTP/1.1 206 Partial Content\r\nTransfer-Encoding: chunked\r\ncontent-range: bytes 10-14/100\r\n\r\n3\r\nabc\r\n2;ext=1\r\nde\r\n0\r\n\r\n";
+        let response = read_response(&raw[..]).unwrap();
+        assert_eq!((response.status, response.content_range), (206, Some((10, 14, 100))));
+        assert_eq!(response.body, b"abcde");
+        assert!(has_port("127.0.0.1:8080") && has_port("[::1]:80") && !has_port("[::1]") && !has_port("host"));
+        assert_eq!(split_url("http://host").unwrap(), ("host", "/"));
+    }
+}
@@ -0,0 +1,136 @@
+use tempfile::NamedTempFile;
+use crate::bools::BoolColumnBuilder;
+use crate::error::{invalid_input, ColumnarError, Result};
+use crate::schema::{Field, Schema};
+use crate::storage::ColumnBuilder;
+use crate::strings::StringColumnBuilder;
+use crate::table::Table;
+use crate::types::DataType;
+use crate::writer::ColumnWriter;
+
+// Значение одной ячейки, уже приведённое к типу поля схемы
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Cell {
+    Null,
+    Int32(i32),
+    Int64(i64),
+    Float64(f64),
+    Utf8(String),
+    Bool(bool),
+}
+
+// Разбор текстового значения по типу поля; пустая строка — NULL для числовых полей.
+// Строковые и логические колонки не хранят NULL: пустое поле Utf8 остаётся пустой
+// строкой, а пустое поле Bool — ошибка
+pub(crate) fn parse_cell(field: &Field, text: &str) -> std::result::Result<Cell, String> {
+    let trimmed = text.trim();
+    if trimmed.is_empty() && matches!(field.data_type, DataType::Int32 | DataType::Int64 | DataType::Float64) {
+        return null_cell(field);
+    }
+    let invalid = || format!("значение {:?} колонки {} не является {:?}", text, field.name, field.data_type);
+    match field.data_type {
+        DataType::Int32 => trimmed.parse().map(Cell::Int32).map_err(|_| invalid()),
+        DataType::Int64 => trimmed.parse().map(Cell::Int64).map_err(|_| invalid()),
+        DataType::Float64 => trimmed.parse().map(Cell::Float64).map_err(|_| invalid()),
+        DataType::Utf8 => Ok(Cell::Utf8(text.to_string())),
+        DataType::Bool => match trimmed.to_ascii_lowercase().as_str() {
+            "true" | "1" => Ok(Cell::Bool(true)),
+            "false" | "0" => Ok(Cell::Bool(false)),
+            _ => Err(invalid()),
+        },
+    }
+}
+
+// NULL допустим только в числовых полях, объявленных nullable
+pub(crate) fn null_cell(field: &Field) -> std::result::Result<Cell, String> {
+    if !field.nullable {
+        return Err(format!("колонка {} объявлена без NULL, а значение пустое", field.name));
+    }
+    match field.data_type {
+        DataType::Int32 | DataType::Int64 | DataType::Float64 => Ok(Cell::Null),
+        data_type => Err(format!("колонка {} типа {:?} не хранит NULL", field.name, data_type)),
+    }
+}
+
+// Ошибка разбора записи row (с единицы)
+pub(crate) fn parse_error(row: u64, detail: impl Into<String>) -> ColumnarError {
+    ColumnarError::Parse { row, detail: detail.into() }
+}
+
+enum ColumnSink {
+    Int32(ColumnWriter<i32>),
+    Int64(ColumnWriter<i64>),
+    Float64(ColumnWriter<f64>),
+    // Строковые и логические колонки строятся только целиком, поэтому их значения
+    // копятся в памяти до finish
+    Utf8(Vec<String>),
+    Bool(Vec<bool>),
+}
+
+// Построчная сборка таблицы по схеме: числовые колонки пишутся потоково и держат
+// в памяти не больше чанка исходных значений
+pub(crate) struct TableSink {
+    schema: Schema,
+    sinks: Vec<ColumnSink>,
+}
+
+impl TableSink {
+    pub fn new(schema: &Schema) -> Result<Self> {
+        let sinks = schema
+            .fields
+            .iter()
+            .map(|field| {
+                let name = field.name.clone();
+                Ok(match field.data_type {
+                    DataType::Int32 => ColumnSink::Int32(ColumnBuilder::create_in_memory(name)?),
+                    DataType::Int64 => ColumnSink::Int64(ColumnBuilder::create_in_memory(name)?),
+                    DataType::Float64 => ColumnSink::Float64(ColumnBuilder::create_in_memory(name)?),
+                    DataType::Utf8 => ColumnSink::Utf8(Vec::new()),
+                    DataType::Bool => ColumnSink::Bool(Vec::new()),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { schema: schema.clone(), sinks })
+    }
+
+    // cells — по значению на каждое поле схемы в её порядке, типы уже проверены разбором
+    pub fn push_row(&mut self, cells: Vec<Cell>) -> Result<()> {
+        debug_assert_eq!(cells.len(), self.sinks.len());
+        for (sink, cell) in self.sinks.iter_mut().zip(cells) {
+            match (sink, cell) {
+                (ColumnSink::Int32(writer), Cell::Int32(value)) => writer.push(value)?,
+                (ColumnSink::Int64(writer), Cell::Int64(value)) => writer.push(value)?,
+                (ColumnSink::Float64(writer), Cell::Float64(value)) => writer.push(value)?,
+                (ColumnSink::Int32(writer), Cell::Null) => writer.push_null()?,
+                (ColumnSink::Int64(writer), Cell::Null) => writer.push_null()?,
+                (ColumnSink::Float64(writer), Cell::Null) => writer.push_null()?,
+                (ColumnSink::Utf8(values), Cell::Utf8(value)) => values.push(value),
+                (ColumnSink::Bool(values), Cell::Bool(value)) => values.push(value),
+                (_, cell) => return Err(invalid_input(format!("значение {:?} не подходит колонке", cell))),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self, name: String) -> Result<Table> {
+        let mut table = Table::with_schema(name, self.schema.clone());
+        for (sink, field) in self.sinks.into_iter().zip(&self.schema.fields) {
+            match sink {
+                ColumnSink::Int32(writer) => table.add_column(writer.finish()?)?,
+                ColumnSink::Int64(writer) => table.add_column(writer.finish()?)?,
+                ColumnSink::Float64(writer) => table.add_column(writer.finish()?)?,
+                // Файл удаляется сразу после построения: отображение в память остаётся
+                // действительным, пока жива колонка
+                ColumnSink::Utf8(values) => {
+                    let file = NamedTempFile::new()?;
+                    table.add_column(StringColumnBuilder::from_strs(field.name.clone(), &values).build(file.path())?)?;
+                }
+                ColumnSink::Bool(values) => {
+                    let file = NamedTempFile::new()?;
+                    table.add_column(BoolColumnBuilder::from_bools(field.name.clone(), &values).build(file.path())?)?;
+                }
+            }
+        }
+        Ok(table)
+    }
+}
@@ -0,0 +1,174 @@
+use std::{borrow::Cow, iter::FusedIterator, ops::Range};
+use crate::error::Result;
+use crate::storage::Column;
+use crate::types::ColumnType;
+
+// Ленивый обход значений колонки: в памяти держится не больше одного распакованного
+// чанка, для несжатой колонки значения читаются прямо из mmap. На месте NULL стоит
+// значение-заполнитель, как в Column::values. Удалённые строки пропускаются
+pub struct ColumnIter<'a, T: ColumnType> {
+    column: &'a Column<T>,
+    row: usize,
+    // Неудалённых строк, которые ещё осталось вернуть
+    remaining: usize,
+    // Первая строка текущего чанка и его значения
+    current: Option<(usize, Cow<'a, [u8]>)>,
+}
+
+impl<T: ColumnType> Column<T> {
+    pub fn iter(&self) -> ColumnIter<'_, T> {
+        self.iter_from(0)
+    }
+
+    // Обход со строки start для возобновляемых сканов; start за концом колонки даёт пустой обход
+    pub fn iter_from(&self, start: usize) -> ColumnIter<'_, T> {
+        self.iter_range(start..self.len())
+    }
+
+    // range не выходит за колонку; проверяет вызывающий
+    pub(crate) fn iter_range(&self, range: Range<usize>) -> ColumnIter<'_, T> {
+        let deleted = self.deleted_rows().map_or(0, |deleted| deleted.count_range(range.clone()));
+        ColumnIter {
+            column: self,
+            row: range.start,
+            remaining: range.len() - deleted,
+            current: None,
+        }
+    }
+}
+
+impl<'a, T: ColumnType> ColumnIter<'a, T> {
+    // Номер строки, которую вернёт следующий вызов next
+    pub fn position(&self) -> usize {
+        self.row
+    }
+}
+
+// Ошибка распаковки чанка возвращается один раз, после чего обход заканчивается
+impl<'a, T: ColumnType> Iterator for ColumnIter<'a, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // Неудалённая строка впереди есть, пока remaining больше нуля
+        while self.column.is_deleted(self.row) {
+            self.row += 1;
+        }
+        let loaded = self
+            .current
+            .as_ref()
+            .is_some_and(|(first_row, values)| self.row < first_row + values.len() / T::WIDTH);
+        if !loaded {
+            // Строка меньше len, поэтому чанк для неё существует
+            let idx = self.column.chunk_for_row(self.row as u64).unwrap();
+            match self.column.chunk_values(idx) {
+                Ok(values) => self.current = Some((self.column.chunks()[idx].first_row as usize, values)),
+                Err(e) => {
+                    self.remaining = 0;
+                    self.current = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+        let (first_row, values) = self.current.as_ref().unwrap();
+        let offset = (self.row - first_row) * T::WIDTH;
+        let value = T::read_le(&values[offset..offset + T::WIDTH]);
+        self.row += 1;
+        self.remaining -= 1;
+        Some(Ok(value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T: ColumnType> ExactSizeIterator for ColumnIter<'a, T> {}
+
+impl<'a, T: ColumnType> FusedIterator for ColumnIter<'a, T> {}
+
+impl<'a, T: ColumnType> IntoIterator for &'a Column<T> {
+    type Item = Result<T>;
+    type IntoIter = ColumnIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Codec, ColumnBuilder};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_iter_matches_decompress_parallel() {
+        let values: Vec<i32> = (0..250_000).map(|i| (i * 37) % 10_007 - 5000).collect();
+        let mut builder = ColumnBuilder::from_i32("iter".to_string(), &values);
+        builder.compress_with(Codec::Lz4).unwrap();
+        let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+        assert!(column.chunks().len() > 1);
+
+        let expected: Vec<i32> = column.decompress_parallel().unwrap()
+            .chunks_exact(4)
+            .map(|b| i32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        let before = column.frames_decoded();
+        let mut iter = column.iter();
+        assert_eq!(iter.len(), values.len());
+        iter.next().unwrap().unwrap();
+        assert_eq!(iter.len(), values.len() - 1);
+        let mut collected = vec![values[0]];
+        for value in iter {
+            collected.push(value.unwrap());
+        }
+        assert_eq!(collected, expected);
+        // Каждый чанк распакован ровно один раз
+        assert_eq!(column.frames_decoded() - before, column.chunks().len());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_iter_from_resumes_scan() {
+        let values: Vec<i64> = (0..3000).map(|i| i * i).collect();
+        for codec in [Codec::None, Codec::zstd()] {
+            let mut builder = ColumnBuilder::from_i64("resume".to_string(), &values);
+            builder.compress_with(codec).unwrap();
+            builder.set_chunk_rows(700);
+            let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+
+            let mut iter = column.iter_from(1399);
+            assert_eq!(iter.len(), 1601);
+            let head: Vec<i64> = iter.by_ref().take(5).map(Result::unwrap).collect();
+            assert_eq!(head, &values[1399..1404]);
+            assert_eq!(iter.position(), 1404);
+            let tail: Vec<i64> = column.iter_from(iter.position()).map(Result::unwrap).collect();
+            assert_eq!(tail, &values[1404..]);
+
+            assert_eq!(column.iter_from(3000).len(), 0);
+            assert!(column.iter_from(10_000).next().is_none());
+            let total: i64 = (&column).into_iter().map(Result::unwrap).sum();
+            assert_eq!(total, values.iter().sum::<i64>());
+        }
+    }
+
+    #[test]
+    fn test_iter_reports_corrupted_chunk_once() {
+        let tmp_file = NamedTempFile::new().unwrap();
+        let mut builder = ColumnBuilder::from_i32("broken".to_string(), &(0..100).collect::<Vec<_>>());
+        builder.set_chunk_rows(50);
+        builder.build(tmp_file.path()).unwrap();
+        let mut raw = std::fs::read(tmp_file.path()).unwrap();
+        raw[crate::format::HEADER_SIZE + 50 * 4] ^= 0xff;
+        std::fs::write(tmp_file.path(), &raw).unwrap();
+
+        let column = Column::<i32>::open(tmp_file.path()).unwrap();
+        let results: Vec<Result<i32>> = column.iter().collect();
+        assert_eq!(results.len(), 51);
+        assert!(results[..50].iter().all(Result::is_ok));
+        assert!(results[50].is_err());
+    }
+}
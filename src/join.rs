@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use crate::compute::Selection;
+use crate::error::{invalid_input, Result};
+use crate::scan::{ColumnValues, SCAN_BATCH_ROWS};
+use crate::storage::Column;
+use crate::table::{Table, TableColumn};
+
+// Пакет результата соединения: пары совпавших строк левой и правой таблиц
+// и значения колонок проекции для каждой пары
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoinBatch {
+    pub left_rows: Vec<usize>,
+    pub right_rows: Vec<usize>,
+    pub columns: Vec<(String, ColumnValues)>,
+}
+
+// Из какой таблицы берётся колонка проекции
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+impl JoinBatch {
+    pub fn len(&self) -> usize {
+        self.left_rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left_rows.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ColumnValues> {
+        self.columns.iter().find(|(column, _)| column == name).map(|(_, values)| values)
+    }
+}
+
+impl ColumnValues {
+    fn select(&self, positions: &[usize]) -> ColumnValues {
+        match self {
+            ColumnValues::Int32(values) => ColumnValues::Int32(positions.iter().map(|p| values[*p]).collect()),
+            ColumnValues::Int64(values) => ColumnValues::Int64(positions.iter().map(|p| values[*p]).collect()),
+            ColumnValues::Float64(values) => ColumnValues::Float64(positions.iter().map(|p| values[*p]).collect()),
+            ColumnValues::Utf8(values) => ColumnValues::Utf8(positions.iter().map(|p| values[*p].clone()).collect()),
+            ColumnValues::Bool(values) => ColumnValues::Bool(positions.iter().map(|p| values[*p]).collect()),
+        }
+    }
+}
+
+impl TableColumn {
+    // Значения строк rows в их порядке; повторяющиеся строки читаются один раз
+    fn gather(&self, rows: &[usize]) -> Result<ColumnValues> {
+        let mut unique = rows.to_vec();
+        unique.sort_unstable();
+        unique.dedup();
+        let values = self.take_sorted(&unique)?;
+        let positions: Vec<usize> = rows.iter().map(|row| unique.binary_search(row).unwrap()).collect();
+        Ok(values.select(&positions))
+    }
+}
+
+impl Column<i32> {
+    // Ключи по чанкам; чанк, диапазон которого не пересекается с [lo, hi], не читается.
+    // NULL ни с чем не совпадает и пропускается, как и строки из deleted
+    fn for_each_key(&self, lo: i32, hi: i32, deleted: Option<&Selection>, mut f: impl FnMut(usize, i32)) -> Result<()> {
+        for chunk in self.chunks() {
+            if chunk.null_count == chunk.row_count() || chunk.max < lo || chunk.min > hi {
+                continue;
+            }
+            let rows: Vec<usize> = (chunk.first_row as usize..chunk.end_row() as usize)
+                .filter(|row| !deleted.is_some_and(|deleted| deleted.get(*row)))
+                .collect();
+            for (row, key) in rows.iter().zip(self.take_sorted(&rows)?) {
+                if let Some(key) = key {
+                    f(*row, key);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Table {
+    fn key_column(&self, name: &str) -> Result<&Column<i32>> {
+        match self.require(name)? {
+            TableColumn::Int32(column) => Ok(column),
+            other => Err(invalid_input(format!(
+                "ключ соединения {} должен быть Int32, а не {:?}",
+                name,
+                other.data_type()
+            ))),
+        }
+    }
+
+    // Колонка проекции ищется в обеих таблицах; имя, которое есть в обеих,
+    // указывается с именем таблицы: orders.id
+    fn resolve_projection<'a>(&'a self, other: &'a Table, name: &str) -> Result<(Side, &'a TableColumn)> {
+        if let Some((table, column)) = name.split_once('.') {
+            if table == self.name {
+                if let Some(column) = self.get(column) {
+                    return Ok((Side::Left, column));
+                }
+            }
+            if table == other.name {
+                if let Some(column) = other.get(column) {
+                    return Ok((Side::Right, column));
+                }
+            }
+        }
+        match (self.get(name), other.get(name)) {
+            (Some(column), None) => Ok((Side::Left, column)),
+            (None, Some(column)) => Ok((Side::Right, column)),
+            (Some(_), Some(_)) => Err(invalid_input(format!(
+                "колонка {} есть в обеих таблицах; укажите её как {}.{} или {}.{}",
+                name, self.name, name, other.name, name
+            ))),
+            (None, None) => Err(invalid_input(format!(
+                "колонки {} нет ни в {}, ни в {}",
+                name, self.name, other.name
+            ))),
+        }
+    }
+
+    // Внутреннее соединение по равенству ключей Int32. Хэш-таблица строится по стороне
+    // с меньшим числом различных ключей, другая сторона просматривается по чанкам;
+    // чанки обеих сторон вне диапазона ключей другой стороны не читаются. Повторы
+    // ключа дают все сочетания совпавших строк. Пары идут по возрастанию строки левой
+    // таблицы, затем правой; колонки проекции читаются по пакетам из SCAN_BATCH_ROWS пар.
+    // Строка, удалённая хотя бы в одной колонке своей таблицы, не соединяется
+    pub fn join<'a>(
+        &'a self,
+        other: &'a Table,
+        left_key: &str,
+        right_key: &str,
+        projection: &[&str],
+    ) -> Result<impl Iterator<Item = Result<JoinBatch>> + 'a> {
+        let left = self.key_column(left_key)?;
+        let right = other.key_column(right_key)?;
+        let projected: Vec<(String, Side, &TableColumn)> = projection
+            .iter()
+            .map(|name| self.resolve_projection(other, name).map(|(side, column)| (name.to_string(), side, column)))
+            .collect::<Result<_>>()?;
+
+        let build_left = (left.stats().distinct_count, left.len()) <= (right.stats().distinct_count, right.len());
+        let (left_deleted, right_deleted) = (self.deleted_rows(), other.deleted_rows());
+        let (build, probe) = if build_left { (left, right) } else { (right, left) };
+        let (build_deleted, probe_deleted) =
+            if build_left { (left_deleted.as_ref(), right_deleted.as_ref()) } else { (right_deleted.as_ref(), left_deleted.as_ref()) };
+        let mut table: HashMap<i32, Vec<usize>> = HashMap::new();
+        let (mut lo, mut hi) = (i32::MAX, i32::MIN);
+        build.for_each_key(probe.min, probe.max, build_deleted, |row, key| {
+            table.entry(key).or_default().push(row);
+            (lo, hi) = (lo.min(key), hi.max(key));
+        })?;
+
+        // Отсечение по ключам, попавшим в хэш-таблицу, а не по всей колонке
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        if !table.is_empty() {
+            probe.for_each_key(lo, hi, probe_deleted, |row, key| {
+                for matched in table.get(&key).into_iter().flatten() {
+                    pairs.push(if build_left { (*matched, row) } else { (row, *matched) });
+                }
+            })?;
+        }
+        pairs.sort_unstable();
+
+        Ok((0..pairs.len()).step_by(SCAN_BATCH_ROWS).map(move |start| {
+            let batch = &pairs[start..(start + SCAN_BATCH_ROWS).min(pairs.len())];
+            let left_rows: Vec<usize> = batch.iter().map(|(left, _)| *left).collect();
+            let right_rows: Vec<usize> = batch.iter().map(|(_, right)| *right).collect();
+            let columns = projected
+                .iter()
+                .map(|(name, side, column)| {
+                    let rows = if *side == Side::Left { &left_rows } else { &right_rows };
+                    Ok((name.clone(), column.gather(rows)?))
+                })
+                .collect::<Result<_>>()?;
+            Ok(JoinBatch { left_rows, right_rows, columns })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ColumnarError;
+    use crate::{Codec, ColumnBuilder, StringColumnBuilder};
+    use tempfile::NamedTempFile;
+
+    fn int_table(name: &str, columns: &[(&str, &[Option<i32>])]) -> Table {
+        let mut table = Table::new(name.to_string());
+        for (column, values) in columns {
+            let mut builder = ColumnBuilder::from_nullable(column.to_string(), values);
+            builder.set_chunk_rows(100);
+            builder.compress_with(Codec::Lz4).unwrap();
+            table.add_column(builder.build_in_memory().unwrap()).unwrap();
+        }
+        table
+    }
+
+    fn collect(batches: impl Iterator<Item = Result<JoinBatch>>) -> Vec<JoinBatch> {
+        let batches: Vec<JoinBatch> = batches.collect::<Result<_>>().unwrap();
+        assert!(batches.iter().all(|b| !b.is_empty() && b.len() <= SCAN_BATCH_ROWS));
+        batches
+    }
+
+    #[test]
+    fn test_join_many_to_many_matches_nested_loop() {
+        let left_keys: Vec<Option<i32>> = (0..900).map(|i| (i % 13 != 0).then_some(i % 40)).collect();
+        let right_keys: Vec<Option<i32>> = (0..700).map(|i| Some(i % 25 + 20)).collect();
+        let left_payload: Vec<Option<i32>> = (0..900).map(Some).collect();
+        let right_payload: Vec<Option<i32>> = (0..700).map(|i| Some(-i)).collect();
+        let orders = int_table("orders", &[("id", &left_keys), ("amount", &left_payload)]);
+        let tmp_file = NamedTempFile::new().unwrap();
+        let mut users = int_table("users", &[("id", &right_keys), ("score", &right_payload)]);
+        let names: Vec<String> = (0..700).map(|i| format!("user{}", i)).collect();
+        users.add_column(StringColumnBuilder::from_strs("name".to_string(), &names).build(tmp_file.path()).unwrap()).unwrap();
+
+        let mut expected = Vec::new();
+        for (l, lk) in left_keys.iter().enumerate() {
+            for (r, rk) in right_keys.iter().enumerate() {
+                if lk.is_some() && lk == rk {
+                    expected.push((l, r));
+                }
+            }
+        }
+        let pairs = |batches: &[JoinBatch]| -> Vec<(usize, usize)> {
+            batches.iter().flat_map(|b| b.left_rows.iter().copied().zip(b.right_rows.iter().copied())).collect()
+        };
+        let batches = collect(orders.join(&users, "id", "id", &["amount", "users.id", "name", "score"]).unwrap());
+        assert_eq!(pairs(&batches), expected);
+        // Сторона построения выбирается по числу ключей, а порядок пар от неё не зависит
+        let mut flipped: Vec<(usize, usize)> = expected.iter().map(|(l, r)| (*r, *l)).collect();
+        flipped.sort_unstable();
+        assert_eq!(pairs(&collect(users.join(&orders, "id", "id", &[]).unwrap())), flipped);
+
+        for batch in &batches {
+            let ColumnValues::Int32(amounts) = batch.get("amount").unwrap() else { panic!() };
+            let ColumnValues::Int32(ids) = batch.get("users.id").unwrap() else { panic!() };
+            let ColumnValues::Utf8(names) = batch.get("name").unwrap() else { panic!() };
+            let ColumnValues::Int32(scores) = batch.get("score").unwrap() else { panic!() };
+            for (idx, (l, r)) in batch.left_rows.iter().zip(&batch.right_rows).enumerate() {
+                assert_eq!(amounts[idx], Some(*l as i32));
+                assert_eq!(ids[idx], right_keys[*r]);
+                assert_eq!(names[idx], format!("user{}", r));
+                assert_eq!(scores[idx], Some(-(*r as i32)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_join_without_matches_and_zone_map_pruning() {
+        // Ключи левой таблицы 0..1000, правой — 500..600 и 5000..5100
+        let left_keys: Vec<Option<i32>> = (0..1000).map(Some).collect();
+        let right_keys: Vec<Option<i32>> = (0..200).map(|i| Some(if i < 100 { 500 + i } else { 4900 + i })).collect();
+        let left = int_table("l", &[("k", &left_keys)]);
+        let right = int_table("r", &[("k", &right_keys)]);
+        let batches = collect(left.join(&right, "k", "k", &[]).unwrap());
+        assert_eq!(batches.iter().map(JoinBatch::len).sum::<usize>(), 100);
+        // Из десяти чанков левой стороны читается только чанк с ключами 500..600,
+        // из двух чанков правой — только первый
+        assert_eq!(left.column::<i32>("k").unwrap().frames_decoded(), 1);
+        assert_eq!(right.column::<i32>("k").unwrap().frames_decoded(), 1);
+
+        let far_keys: Vec<Option<i32>> = (0..300).map(|i| Some(10_000 + i)).collect();
+        let far = int_table("far", &[("k", &far_keys)]);
+        assert_eq!(left.join(&far, "k", "k", &["far.k"]).unwrap().count(), 0);
+        assert_eq!(far.column::<i32>("k").unwrap().frames_decoded(), 0);
+
+        let nulls = int_table("nulls", &[("k", &[None, None])]);
+        assert_eq!(left.join(&nulls, "k", "k", &[]).unwrap().count(), 0);
+        let empty = int_table("empty", &[("k", &[])]);
+        assert_eq!(empty.join(&left, "k", "k", &[]).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_join_rejects_bad_keys_and_projection() {
+        let left = int_table("l", &[("k", &[Some(1)]), ("v", &[Some(2)])]);
+        let right = int_table("r", &[("k", &[Some(1)]), ("v", &[Some(3)])]);
+        let mut wide = Table::new("w".to_string());
+        wide.add_column(ColumnBuilder::from_i64("k".to_string(), &[1]).build_in_memory().unwrap()).unwrap();
+        let invalid = |result: Result<Vec<JoinBatch>>| matches!(result, Err(ColumnarError::InvalidInput(_)));
+        let run = |a: &Table, b: &Table, projection: &[&str]| {
+            a.join(b, "k", "k", projection).and_then(|batches| batches.collect::<Result<Vec<_>>>())
+        };
+        assert!(invalid(run(&left, &wide, &[])));
+        assert!(invalid(run(&left, &right, &["v"])));
+        assert!(invalid(run(&left, &right, &["missing"])));
+        let batches = run(&left, &right, &["l.v", "r.v"]).unwrap();
+        assert_eq!(batches[0].get("l.v"), Some(&ColumnValues::Int32(vec![Some(2)])));
+        assert_eq!(batches[0].get("r.v"), Some(&ColumnValues::Int32(vec![Some(3)])));
+    }
+}
@@ -0,0 +1,317 @@
+use std::{collections::HashMap, io::BufRead};
+use crate::error::Result;
+use crate::ingest::{null_cell, parse_error, Cell, TableSink};
+use crate::schema::{Field, Schema};
+use crate::table::Table;
+use crate::types::DataType;
+
+// Скалярное значение JSON; число хранится текстом, чтобы разобрать его по типу поля
+#[derive(Debug, Clone, PartialEq)]
+enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+}
+
+// Разбор одного объекта верхнего уровня. Вложенные объекты и массивы не поддерживаются
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\r' | b'\n')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> std::result::Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            return Err(self.unexpected(&format!("ожидался {:?}", byte as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn unexpected(&self, what: &str) -> String {
+        match self.text[self.pos..].chars().next() {
+            Some(c) => format!("{}, а в позиции {} стоит {:?}", what, self.pos + 1, c),
+            None => format!("{}, а строка кончилась", what),
+        }
+    }
+
+    fn object(mut self) -> std::result::Result<HashMap<String, JsonValue>, String> {
+        let mut object = HashMap::new();
+        self.expect(b'{')?;
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+        } else {
+            loop {
+                self.skip_whitespace();
+                let key = self.string()?;
+                self.expect(b':')?;
+                let value = self.value(&key)?;
+                // Повторный ключ заменяет прежнее значение
+                object.insert(key, value);
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(b',') => self.pos += 1,
+                    Some(b'}') => {
+                        self.pos += 1;
+                        break;
+                    }
+                    _ => return Err(self.unexpected("ожидалась , или }")),
+                }
+            }
+        }
+        self.skip_whitespace();
+        if self.pos != self.text.len() {
+            return Err(self.unexpected("после объекта ничего не должно быть"));
+        }
+        Ok(object)
+    }
+
+    fn value(&mut self, key: &str) -> std::result::Result<JsonValue, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'"') => self.string().map(JsonValue::String),
+            Some(b'{' | b'[') => Err(format!("поле {}: вложенные объекты и массивы не поддерживаются", key)),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => {
+                let literals = [("null", JsonValue::Null), ("true", JsonValue::Bool(true)), ("false", JsonValue::Bool(false))];
+                for (literal, value) in literals {
+                    if self.text[self.pos..].starts_with(literal) {
+                        self.pos += literal.len();
+                        return Ok(value);
+                    }
+                }
+                Err(self.unexpected(&format!("поле {}: ожидалось значение", key)))
+            }
+        }
+    }
+
+    // -?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?
+    fn number(&mut self) -> std::result::Result<JsonValue, String> {
+        let start = self.pos;
+        let digits = |p: &mut Self| {
+            let from = p.pos;
+            while matches!(p.peek(), Some(b'0'..=b'9')) {
+                p.pos += 1;
+            }
+            p.pos > from
+        };
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        if self.peek() == Some(b'0') {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(format!("число {:?} с ведущим нулём", &self.text[start..=self.pos]));
+            }
+        } else if !digits(self) {
+            return Err(self.unexpected("ожидались цифры числа"));
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if !digits(self) {
+                return Err(self.unexpected("после точки ожидались цифры"));
+            }
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                return Err(self.unexpected("в экспоненте ожидались цифры"));
+            }
+        }
+        Ok(JsonValue::Number(self.text[start..self.pos].to_string()))
+    }
+
+    fn string(&mut self) -> std::result::Result<String, String> {
+        if self.peek() != Some(b'"') {
+            return Err(self.unexpected("ожидалась строка"));
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += offset + 1;
+                    return Ok(out);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let high = hex4(&mut chars)?;
+                            // Символы вне BMP записываются суррогатной парой
+                            let code = if (0xD800..0xDC00).contains(&high) {
+                                let next: String = chars.by_ref().take(2).map(|(_, c)| c).collect();
+                                let low = if next == "\\u" { hex4(&mut chars)? } else { 0 };
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err("непарный суррогат в \\u".to_string());
+                                }
+                                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                            } else {
+                                high
+                            };
+                            char::from_u32(code).ok_or_else(|| "непарный суррогат в \\u".to_string())?
+                        }
+                        other => {
+                            let other = other.map_or(String::new(), String::from);
+                            return Err(format!("неверная escape-последовательность \\{}", other));
+                        }
+                    };
+                    out.push(escaped);
+                }
+                c if (c as u32) < 0x20 => return Err("управляющий символ внутри строки".to_string()),
+                c => out.push(c),
+            }
+        }
+        Err("строка не закрыта кавычкой".to_string())
+    }
+}
+
+// Четыре шестнадцатеричные цифры после \u
+fn hex4(chars: &mut impl Iterator<Item = (usize, char)>) -> std::result::Result<u32, String> {
+    let hex: String = chars.take(4).map(|(_, c)| c).collect();
+    u32::from_str_radix(&hex, 16)
+        .ok()
+        .filter(|_| hex.len() == 4)
+        .ok_or_else(|| format!("неверная последовательность \\u{}", hex))
+}
+
+// Значение поля по типу схемы; отсутствующее поле равносильно null
+fn to_cell(field: &Field, value: Option<JsonValue>) -> std::result::Result<Cell, String> {
+    let mismatch = |value: &JsonValue| format!("поле {} типа {:?} не принимает {:?}", field.name, field.data_type, value);
+    match (field.data_type, value) {
+        (_, None | Some(JsonValue::Null)) => null_cell(field),
+        (DataType::Int32, Some(JsonValue::Number(text))) => {
+            text.parse().map(Cell::Int32).map_err(|_| mismatch(&JsonValue::Number(text)))
+        }
+        (DataType::Int64, Some(JsonValue::Number(text))) => {
+            text.parse().map(Cell::Int64).map_err(|_| mismatch(&JsonValue::Number(text)))
+        }
+        (DataType::Float64, Some(JsonValue::Number(text))) => {
+            text.parse().map(Cell::Float64).map_err(|_| mismatch(&JsonValue::Number(text)))
+        }
+        (DataType::Utf8, Some(JsonValue::String(text))) => Ok(Cell::Utf8(text)),
+        (DataType::Bool, Some(JsonValue::Bool(value))) => Ok(Cell::Bool(value)),
+        (_, Some(value)) => Err(mismatch(&value)),
+    }
+}
+
+impl Table {
+    // Таблица из JSON Lines: по объекту на строку, пустые строки пропускаются.
+    // Из объекта берутся поля схемы, остальные ключи игнорируются; отсутствующее поле
+    // и null дают NULL. Первая ошибка прерывает загрузку с номером строки, считая с единицы
+    pub fn from_jsonl(name: String, mut reader: impl BufRead, schema: &Schema) -> Result<Table> {
+        let mut sink = TableSink::new(schema)?;
+        let mut line = String::new();
+        let mut number = 0;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut object = Parser::new(line.trim_end()).object().map_err(|detail| parse_error(number, detail))?;
+            let cells = schema
+                .fields
+                .iter()
+                .map(|field| to_cell(field, object.remove(&field.name)).map_err(|detail| parse_error(number, detail)))
+                .collect::<Result<Vec<_>>>()?;
+            sink.push_row(cells)?;
+        }
+        sink.finish(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ColumnarError;
+    use crate::table::TableColumn;
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("ts", DataType::Int64, false),
+            Field::new("user", DataType::Int32, true),
+            Field::new("latency", DataType::Float64, true),
+            Field::new("event", DataType::Utf8, false),
+        ])
+    }
+
+    fn load(text: &str) -> Result<Table> {
+        Table::from_jsonl("events".to_string(), text.as_bytes(), &schema())
+    }
+
+    fn failed_line(text: &str) -> (u64, String) {
+        match load(text) {
+            Err(ColumnarError::Parse { row, detail }) => (row, detail),
+            other => panic!("{:?}", other.map(|t| t.row_count())),
+        }
+    }
+
+    #[test]
+    fn test_missing_fields_and_nulls_become_null() {
+        let text = concat!(
+            "{\"ts\": 1, \"user\": 7, \"latency\": 0.5, \"event\": \"click\"}\n",
+            "\n",
+            "{\"event\": \"view \\\"home\\\" \\u00e9\\ud83d\\ude00\", \"ts\": 2, \"user\": null, \"extra\": true}\n",
+            "  {\"ts\":-3,\"latency\":1e-3,\"event\":\"a\\nb\"}  \r\n",
+        );
+        let table = load(text).unwrap();
+        assert_eq!(table.row_count(), 3);
+        assert_eq!(table.column::<i64>("ts").unwrap().values().unwrap(), vec![1, 2, -3]);
+        assert_eq!(table.column::<i32>("user").unwrap().nullable_values().unwrap(), vec![Some(7), None, None]);
+        assert_eq!(table.column::<f64>("latency").unwrap().nullable_values().unwrap(), vec![Some(0.5), None, Some(0.001)]);
+        let TableColumn::Utf8(event) = table.get("event").unwrap() else { panic!() };
+        assert_eq!(event.get_str(1), Some("view \"home\" é😀"));
+        assert_eq!(event.get_str(2), Some("a\nb"));
+    }
+
+    #[test]
+    fn test_type_mismatch_and_malformed_lines_report_line_number() {
+        let good = "{\"ts\": 1, \"event\": \"e\"}\n";
+        assert_eq!(failed_line(&format!("{}{}{{\"ts\": \"2\", \"event\": \"e\"}}\n", good, good)).0, 3);
+        // Дробное число в целом поле и число вне диапазона i32
+        assert_eq!(failed_line(&format!("{}{{\"ts\": 1.5, \"event\": \"e\"}}\n", good)).0, 2);
+        assert_eq!(failed_line("{\"ts\": 1, \"user\": 4294967296, \"event\": \"e\"}").0, 1);
+        // Отсутствующее поле без NULL
+        assert_eq!(failed_line(&format!("{}{{\"event\": \"e\"}}\n", good)).0, 2);
+        // Обрезанная строка, мусор после объекта, ведущий ноль
+        assert_eq!(failed_line(&format!("{}{{\"ts\": 1, \"event\": \"e\"\n", good)).0, 2);
+        assert_eq!(failed_line("{\"ts\": 1, \"event\": \"e\"} x").0, 1);
+        assert_eq!(failed_line("{\"ts\": 01, \"event\": \"e\"}").0, 1);
+
+        let (line, detail) = failed_line(&format!("{}\n{{\"ts\": 1, \"event\": \"e\", \"meta\": {{\"a\": 1}}}}\n", good));
+        assert_eq!(line, 3);
+        assert!(detail.contains("вложенные"), "{}", detail);
+    }
+}
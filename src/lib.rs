@@ -1,8 +1,90 @@
+// Тесты, которым нужны zstd или bloom-фильтры, без этих функций не собираются, и
+// их импорты и вспомогательные функции остаются неиспользованными
+#![cfg_attr(all(test, not(all(feature = "zstd", feature = "bloom"))), allow(unused_imports, dead_code))]
+
 pub mod storage;
 pub mod cache;
 pub mod prefetch;
+pub mod codec;
+pub mod compute;
+pub mod csv;
+pub mod encoding;
+pub mod writer;
+pub mod types;
+pub mod strings;
+pub mod bools;
+pub mod iter;
+pub mod filter;
+pub mod aggregate;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod advice;
+pub mod backing;
+pub mod table;
+pub mod view;
+pub mod schema;
+pub mod scan;
+pub mod expr;
+pub mod group;
+pub mod handle;
+pub mod histogram;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod join;
+pub mod lock;
+pub mod read_at;
+pub mod recover;
+pub mod summary;
+pub mod verify;
+#[cfg(feature = "async")]
+mod async_io;
+mod jsonl;
+mod topk;
+mod sort;
+mod compact;
+mod concat;
+mod distinct;
+mod format;
+mod hll;
+mod ingest;
+mod bloom;
+mod par;
+mod tombstone;
+mod trace;
+mod wal;
 
 // Реэкспорт основных типов для удобства использования
-pub use cache::HybridCache;
+pub use advice::AccessPattern;
+pub use backing::{Backing, ColumnData};
+pub use aggregate::{Agg, AggValue};
+pub use cache::{CacheConfig, CacheLookup, CacheStats, CacheValue, ChunkCache, ChunkKey, ConcurrentHybridCache, EvictionReason, HybridCache};
+pub use codec::Codec;
+pub use encoding::Encoding;
+pub use error::{ColumnarError, Result};
+pub use expr::Expr;
+pub use compute::{Bitmap, Selection};
+pub use csv::{write_csv, CsvOptions, CsvWriteOptions, OnBadRow};
+pub use filter::Predicate;
+pub use group::{Group, GroupKey, GroupValue};
+pub use handle::{ColumnHandle, Handle, Snapshot, TableHandle};
+pub use histogram::{Bucket, Histogram, DEFAULT_HISTOGRAM_BUCKETS};
+#[cfg(feature = "http")]
+pub use http::{HttpOptions, HttpReadAt};
+pub use join::JoinBatch;
+pub use lock::WriteLock;
 pub use prefetch::Prefetcher;
-pub use storage::{Column, ColumnBuilder};
\ No newline at end of file
+pub use read_at::{ColumnMeta, FileReadAt, ReadAt};
+pub use recover::RecoveryReport;
+pub use storage::{BloomParams, Column, ColumnBuilder, ColumnStats, Float64Column, Int32Column, Int64Column};
+pub use bools::{BoolColumn, BoolColumnBuilder};
+pub use iter::ColumnIter;
+pub use strings::{StringColumn, StringColumnBuilder};
+pub use summary::ColumnSummary;
+pub use scan::{ColumnPredicate, ColumnValues, RowBatch};
+pub use schema::{Field, Schema};
+pub use table::{Table, TableColumn};
+pub use types::{ColumnType, DataType};
+pub use verify::{VerifyOptions, VerifyProblem, VerifyReport};
+pub use view::ColumnView;
+pub use writer::{BuildOptions, ColumnWriter};
\ No newline at end of file
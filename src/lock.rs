@@ -0,0 +1,112 @@
+use std::{
+    ffi::OsString,
+    fs::{File, OpenOptions, TryLockError},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use crate::error::{ColumnarError, Result};
+
+// Рекомендательная блокировка писателя колонки на файле <файл>.lock: сам файл колонки
+// при атомарной записи заменяется переименованием, и блокировка на нём не действовала
+// бы на новый. Её берут build, потоковая запись, append, delete_rows и повтор журнала;
+// читателям она не нужна. Снимается, когда отпущены все копии
+#[derive(Debug, Clone)]
+pub struct WriteLock {
+    _file: Arc<File>,
+}
+
+pub(crate) fn lock_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+impl WriteLock {
+    // Ждёт, пока другой писатель колонки path не закончит
+    pub fn acquire(path: &Path) -> Result<Self> {
+        let file = open_lock_file(path)?;
+        file.lock()?;
+        Ok(Self { _file: Arc::new(file) })
+    }
+
+    // Занятая блокировка — сразу ошибка Locked
+    pub fn try_acquire(path: &Path) -> Result<Self> {
+        let file = open_lock_file(path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Self { _file: Arc::new(file) }),
+            Err(TryLockError::WouldBlock) => Err(ColumnarError::Locked(format!(
+                "колонку {} сейчас записывает другой писатель",
+                path.display()
+            ))),
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+}
+
+// Файл блокировки не удаляется: иначе два писателя могли бы заблокировать разные файлы
+fn open_lock_file(path: &Path) -> Result<File> {
+    Ok(OpenOptions::new().read(true).write(true).create(true).truncate(false).open(lock_path(path))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::time::Duration;
+    use crate::{Column, ColumnBuilder};
+
+    #[test]
+    fn test_only_one_of_racing_builds_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let barrier = Barrier::new(2);
+        let (first, second) = std::thread::scope(|scope| {
+            let first = scope.spawn(|| {
+                let mut writer = ColumnBuilder::<i64>::try_create("a".to_string(), &path).unwrap();
+                writer.push_slice(&[1, 2, 3]).unwrap();
+                barrier.wait();
+                // Второй писатель пробует build, пока первый держит блокировку
+                barrier.wait();
+                writer.finish().map(|column| column.len())
+            });
+            let second = scope.spawn(|| {
+                barrier.wait();
+                let result = ColumnBuilder::from_values("b".to_string(), &[9i64; 10]).try_build(&path);
+                barrier.wait();
+                result.map(|column| column.len())
+            });
+            (first.join().unwrap(), second.join().unwrap())
+        });
+        assert_eq!(first.unwrap(), 3);
+        assert!(matches!(second, Err(ColumnarError::Locked(_))));
+        let column = Column::<i64>::open(&path).unwrap();
+        assert_eq!((column.name.as_str(), column.values().unwrap()), ("a", vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_blocking_writers_wait_and_readers_do_not() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let mut column = ColumnBuilder::from_values("v".to_string(), &[1i64, 2]).build(&path).unwrap();
+
+        let lock = WriteLock::try_acquire(&path).unwrap();
+        assert!(matches!(WriteLock::try_acquire(&path), Err(ColumnarError::Locked(_))));
+        assert!(matches!(column.try_append(&[3]), Err(ColumnarError::Locked(_))));
+        assert_eq!(Column::<i64>::open(&path).unwrap().values().unwrap(), vec![1, 2]);
+
+        std::thread::scope(|scope| {
+            let waiting = scope.spawn(|| column.append(&[3]));
+            std::thread::sleep(Duration::from_millis(100));
+            assert!(!waiting.is_finished());
+            drop(lock);
+            waiting.join().unwrap().unwrap();
+        });
+        assert_eq!(column.values().unwrap(), vec![1, 2, 3]);
+
+        // Дозапись экземпляром, который не видел чужую дозапись, затёрла бы её
+        let mut stale = Column::<i64>::open(&path).unwrap();
+        column.append(&[4]).unwrap();
+        assert!(matches!(stale.append(&[5]), Err(ColumnarError::InvalidInput(_))));
+        assert_eq!(Column::<i64>::open(&path).unwrap().values().unwrap(), vec![1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,76 @@
+// Параллельные итераторы: с функцией parallel это rayon, без неё — обычные
+// итераторы std с теми же именами методов, и те же вызовы выполняются в одном потоке
+#[cfg(feature = "parallel")]
+pub(crate) use rayon::prelude::*;
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) use sequential::*;
+
+#[cfg(feature = "parallel")]
+pub(crate) fn current_num_threads() -> usize {
+    rayon::current_num_threads()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn current_num_threads() -> usize {
+    1
+}
+
+#[cfg(not(feature = "parallel"))]
+mod sequential {
+    use std::{cmp::Ordering, slice};
+
+    pub(crate) trait IntoParallelIterator: IntoIterator + Sized {
+        fn into_par_iter(self) -> Self::IntoIter {
+            self.into_iter()
+        }
+    }
+
+    impl<I: IntoIterator> IntoParallelIterator for I {}
+
+    pub(crate) trait ParallelSlice<T> {
+        fn par_iter(&self) -> slice::Iter<'_, T>;
+        fn par_chunks(&self, size: usize) -> slice::Chunks<'_, T>;
+    }
+
+    impl<T> ParallelSlice<T> for [T] {
+        fn par_iter(&self) -> slice::Iter<'_, T> {
+            self.iter()
+        }
+
+        fn par_chunks(&self, size: usize) -> slice::Chunks<'_, T> {
+            self.chunks(size)
+        }
+    }
+
+    pub(crate) trait ParallelSliceMut<T> {
+        fn par_sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F);
+    }
+
+    impl<T> ParallelSliceMut<T> for [T] {
+        fn par_sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F) {
+            self.sort_by(compare)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Те же вызовы дают тот же результат в обеих сборках
+    #[test]
+    fn test_iterators_match_sequential_results() {
+        let squares: Vec<u64> = (0..1000u64).into_par_iter().map(|x| x * x).collect();
+        assert_eq!(squares, (0..1000u64).map(|x| x * x).collect::<Vec<_>>());
+        let sums: Vec<u64> = squares.par_chunks(100).map(|chunk| chunk.iter().sum()).collect();
+        assert_eq!(sums.par_iter().sum::<u64>(), squares.iter().sum());
+        let mut sorted = squares.clone();
+        sorted.reverse();
+        sorted.par_sort_by(|a, b| a.cmp(b));
+        assert_eq!(sorted, squares);
+        assert!(current_num_threads() >= 1);
+        #[cfg(not(feature = "parallel"))]
+        assert_eq!(current_num_threads(), 1);
+    }
+}
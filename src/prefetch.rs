@@ -1,27 +1,115 @@
-use super::{storage::Column, cache::HybridCache};
-use crate::ColumnBuilder;
+use super::{backing::ColumnData, storage::Column, cache::ConcurrentHybridCache, handle::ColumnHandle, table::Table, types::ColumnType};
+use crate::error::Result;
+use crate::trace::event;
 use crossbeam::channel::{bounded, Sender};
-use std::{
-    sync::{Arc, Mutex},
-    thread,
-    time::Duration
-};
+use std::{sync::Arc, thread, time::Instant};
+
+// Запросы обрабатываются по порядку, поэтому сброс, отправленный после записи в
+// колонку, не обгонит ранее запланированную предзагрузку её старых данных
+enum Request {
+    Prefetch(String),
+    Invalidate(String),
+}
 
 pub struct Prefetcher {
-    sender: Sender<String>,
+    sender: Sender<Request>,
 }
 
 impl Prefetcher {
-    pub fn new(column: Arc<Column>, cache: Arc<Mutex<HybridCache>>) -> Self {
-        let (sender, receiver) = bounded::<String>(10);
+    // Поток предзагрузки получает свою копию колонки; clone колонки дешёвый. Данные
+    // кладутся под именем колонки, другие имена пропускаются. Копия не видит
+    // последующих записей: после append или compact Prefetcher создаётся заново
+    // либо строится через from_handle
+    pub fn new<T: ColumnType>(column: Column<T>, cache: Arc<ConcurrentHybridCache>) -> Self {
+        let name = column.name.clone();
+        Self::spawn(
+            cache,
+            move |requested| (requested == name).then(|| name.clone()),
+            move |_| column.decompress_parallel().map(Some),
+        )
+    }
+
+    // Каждая предзагрузка читает текущее поколение handle, поэтому после
+    // ColumnHandle::update достаточно invalidate
+    pub fn from_handle<T: ColumnType>(handle: Arc<ColumnHandle<T>>, cache: Arc<ConcurrentHybridCache>) -> Self {
+        let keys = handle.clone();
+        Self::spawn(
+            cache,
+            move |requested| (requested == keys.read().name).then(|| requested.to_string()),
+            move |_| handle.read().decompress_parallel().map(Some),
+        )
+    }
+
+    // Имя колонки ищется в таблице, а данные кладутся в кэш под ключом Table::cache_key.
+    // Неизвестные имена и колонки без ColumnData пропускаются
+    pub fn for_table(table: Arc<Table>, cache: Arc<ConcurrentHybridCache>) -> Self {
+        let keys = table.clone();
+        Self::spawn(
+            cache,
+            move |name| keys.get(name).map(|_| keys.cache_key(name)),
+            move |name| table.get(name).map_or(Ok(None), |column| column.decompress_parallel()),
+        )
+    }
+
+    fn spawn(
+        cache: Arc<ConcurrentHybridCache>,
+        key: impl Fn(&str) -> Option<String> + Send + 'static,
+        load: impl Fn(&str) -> Result<Option<ColumnData>> + Send + 'static,
+    ) -> Self {
+        let (sender, receiver) = bounded::<Request>(10);
 
         thread::spawn(move || {
-            while let Ok(col_name) = receiver.recv() {
-                if cache.lock().unwrap().get(&col_name).is_none() {
-                    if let Ok(data) = column.decompress_parallel() {
-                        cache.lock().unwrap().insert(col_name, Arc::new(data));
+            while let Ok(request) = receiver.recv() {
+                let col_name = match request {
+                    Request::Prefetch(col_name) => col_name,
+                    Request::Invalidate(col_name) => {
+                        if let Some(key) = key(&col_name) {
+                            cache.remove(&key);
+                        }
+                        continue;
                     }
-                }
+                };
+                let start = Instant::now();
+                let outcome = match key(&col_name) {
+                    None => "skipped",
+                    // Проверка не считается обращением, иначе всё предзагруженное
+                    // выглядело бы горячим
+                    Some(key) if cache.contains_key(&key) => "hit",
+                    Some(key) => {
+                        // Два запроса одной колонки из разных Prefetcher-ов с общим кэшем
+                        // распаковывают её один раз
+                        let mut loaded = false;
+                        let result = cache.get_or_insert_with(key.clone(), || {
+                            loaded = true;
+                            match load(&col_name) {
+                                Ok(Some(data)) => Ok(Arc::new(data)),
+                                Ok(None) => Err(None),
+                                Err(err) => Err(Some(err)),
+                            }
+                        });
+                        match result {
+                            Ok(_) if loaded => "loaded",
+                            Ok(_) => "hit",
+                            // Колонку больше не прочитать — прежние данные в кэше тоже неверны
+                            Err(None) => {
+                                cache.remove(&key);
+                                "skipped"
+                            }
+                            Err(Some(_)) => {
+                                cache.remove(&key);
+                                "error"
+                            }
+                        }
+                    }
+                };
+                event!(
+                    "columnar::prefetch",
+                    "item",
+                    column = col_name.as_str(),
+                    queue_depth = receiver.len(),
+                    latency_us = start.elapsed().as_micros() as u64,
+                    outcome = outcome
+                );
             }
         });
 
@@ -29,29 +117,35 @@ impl Prefetcher {
     }
 
     pub fn schedule_prefetch(&self, column_name: String) {
-        let _ = self.sender.send(column_name);
+        let _ = self.sender.send(Request::Prefetch(column_name));
+    }
+
+    // Убирает колонку из кэша после предзагрузок, запланированных раньше. Вызывается
+    // после записи в колонку (append, compact): иначе предзагрузка попадёт в старое
+    // значение и не перечитает данные. Новые данные придут только из from_handle —
+    // копия из new или for_table вернёт в кэш прежние
+    pub fn invalidate(&self, column_name: String) {
+        let _ = self.sender.send(Request::Invalidate(column_name));
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ColumnBuilder;
+    use std::time::Duration;
     use tempfile::NamedTempFile;
 
     #[test]
     fn test_prefetch_mechanism() {
         // Создаем тестовую колонку
-        let data = vec![1i32, 2, 3];
-        let bytes: Vec<u8> = data.iter()
-            .flat_map(|x| x.to_le_bytes())
-            .collect();
-        
-        let column = ColumnBuilder::new("test_col".to_string(), bytes)
+        let data = [1i32, 2, 3];
+
+        let column = ColumnBuilder::from_i32("test_col".to_string(), &data)
             .build(NamedTempFile::new().unwrap().path())
             .unwrap();
         
-        let column = Arc::new(column);
-        let cache = Arc::new(Mutex::new(HybridCache::new(100)));
+        let cache = Arc::new(ConcurrentHybridCache::new(100, 4).unwrap());
         
         let prefetcher = Prefetcher::new(column.clone(), cache.clone());
         
@@ -62,6 +156,75 @@ mod tests {
         thread::sleep(Duration::from_millis(50));
         
         // Проверяем, что данные появились в кэше
-        assert!(cache.lock().unwrap().get("test_col").is_some());
+        assert!(cache.peek("test_col").is_some());
+
+        // Повторная предзагрузка только проверяет кэш и не делает ключ горячим
+        let stats = cache.stats();
+        prefetcher.schedule_prefetch("test_col".to_string());
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(cache.stats(), stats);
+        assert!(cache.get("test_col").is_some());
+    }
+
+    #[test]
+    fn test_prefetch_through_table() {
+        let mut table = Table::new("metrics".to_string());
+        table.add_column(ColumnBuilder::from_i32("id".to_string(), &[1, 2, 3]).build_in_memory().unwrap()).unwrap();
+        table.add_column(ColumnBuilder::from_f64("value".to_string(), &[0.5, 1.5, 2.5]).build_in_memory().unwrap()).unwrap();
+        let cache = Arc::new(ConcurrentHybridCache::new(100, 4).unwrap());
+
+        let prefetcher = Prefetcher::for_table(Arc::new(table), cache.clone());
+        prefetcher.schedule_prefetch("value".to_string());
+        prefetcher.schedule_prefetch("missing".to_string());
+        thread::sleep(Duration::from_millis(50));
+
+        let cached = cache.get("metrics/value").expect("колонка должна попасть в кэш под ключом таблицы");
+        assert_eq!(cached.len(), 3 * 8);
+        assert!(cache.get("value").is_none());
+        assert!(cache.get("metrics/id").is_none());
+        assert!(cache.get("metrics/missing").is_none());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_invalidate_drops_entry_in_order() {
+        let column = ColumnBuilder::from_i32("col".to_string(), &[1, 2, 3]).build_in_memory().unwrap();
+        let cache = Arc::new(ConcurrentHybridCache::new(100, 4).unwrap());
+        let prefetcher = Prefetcher::new(column, cache.clone());
+        prefetcher.schedule_prefetch("col".to_string());
+        prefetcher.invalidate("col".to_string());
+        thread::sleep(Duration::from_millis(50));
+        assert!(cache.get("col").is_none());
+
+        prefetcher.schedule_prefetch("col".to_string());
+        thread::sleep(Duration::from_millis(50));
+        assert!(cache.get("col").is_some());
+
+        // Копия колонки отвечает только за своё имя
+        prefetcher.schedule_prefetch("other".to_string());
+        thread::sleep(Duration::from_millis(50));
+        assert!(cache.get("other").is_none());
+    }
+
+    #[test]
+    fn test_prefetch_from_handle_sees_appended_rows() {
+        let column = ColumnBuilder::from_i32("col".to_string(), &[1, 2, 3]).build_in_memory().unwrap();
+        let handle = Arc::new(ColumnHandle::new(column));
+        let cache = Arc::new(ConcurrentHybridCache::new(100, 4).unwrap());
+        let prefetcher = Prefetcher::from_handle(handle.clone(), cache.clone());
+        prefetcher.schedule_prefetch("col".to_string());
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(cache.get("col").unwrap().len(), 3 * 4);
+
+        handle
+            .update(|column| {
+                let mut next = column.clone();
+                next.append(&[4, 5])?;
+                Ok(next)
+            })
+            .unwrap();
+        prefetcher.invalidate("col".to_string());
+        prefetcher.schedule_prefetch("col".to_string());
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(cache.get("col").unwrap().len(), 5 * 4);
+    }
+}
@@ -0,0 +1,241 @@
+use std::{fmt::Debug, fs::File, io, path::Path, sync::Arc};
+use crate::backing::Backing;
+use crate::error::{corrupt, invalid_input, Result};
+use crate::format::{decode_trailer, Footer, Header, HEADER_SIZE, TRAILER_SIZE};
+use crate::storage::Column;
+use crate::types::{type_name, ColumnType, DataType};
+
+// Источник байтов файла колонки с чтением по диапазонам, например объект в S3 или GCS.
+// Колонка из источника читает только заголовок, метаданные и нужные чанки
+pub trait ReadAt: Send + Sync + Debug {
+    // Ровно len байт со смещения offset; диапазон за концом файла — ошибка
+    fn read_at(&self, offset: u64, len: usize) -> Result<Vec<u8>>;
+    fn len(&self) -> u64;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Отображённый в память файл или буфер: диапазон копируется из среза
+impl ReadAt for Backing {
+    fn read_at(&self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        let start = offset as usize;
+        Ok(self.read(start..start + len)?.into_owned())
+    }
+
+    fn len(&self) -> u64 {
+        Backing::len(self) as u64
+    }
+}
+
+// Локальный файл, который читается pread без отображения в память
+#[derive(Debug)]
+pub struct FileReadAt {
+    file: File,
+    len: u64,
+}
+
+impl FileReadAt {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self { file, len })
+    }
+}
+
+impl ReadAt for FileReadAt {
+    fn read_at(&self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        if offset.checked_add(len as u64).is_none_or(|end| end > self.len) {
+            return Err(corrupt(format!("диапазон {}+{} вне файла из {} байт", offset, len, self.len)));
+        }
+        let mut buf = vec![0u8; len];
+        read_exact_at(&self.file, &mut buf, offset)?;
+        Ok(buf)
+    }
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+// Заголовок и метаданные колонки, прочитанные у источника тремя запросами: заголовок,
+// хвост с длиной метаданных и сами метаданные. Их можно прочитать один раз и
+// переиспользовать, тогда open_with к источнику не обращается
+#[derive(Debug, Clone)]
+pub struct ColumnMeta {
+    header: Header,
+    footer: Arc<Vec<u8>>,
+    data_end: usize,
+    len: u64,
+}
+
+impl ColumnMeta {
+    pub fn read(source: &dyn ReadAt) -> Result<ColumnMeta> {
+        let len = source.len();
+        if len < (HEADER_SIZE + TRAILER_SIZE) as u64 {
+            return Err(corrupt("файл не содержит метаданных колонки"));
+        }
+        let header = Header::decode(&read_exact(source, 0, HEADER_SIZE)?)?;
+        let trailer = read_exact(source, len - TRAILER_SIZE as u64, TRAILER_SIZE)?;
+        let data_end = decode_trailer(&trailer, len as usize)?;
+        let footer = read_exact(source, data_end as u64, len as usize - TRAILER_SIZE - data_end)?;
+        Ok(ColumnMeta { header, footer: Arc::new(footer), data_end, len })
+    }
+
+    // Длина файла, по которому прочитаны метаданные
+    pub fn file_len(&self) -> u64 {
+        self.len
+    }
+
+    // Тип значений из заголовка: по нему выбирают, как открыть файл
+    pub fn data_type(&self) -> Result<DataType> {
+        DataType::from_tag(self.header.type_tag)
+            .ok_or_else(|| corrupt(format!("неизвестный тип значений {} в заголовке", self.header.type_tag)))
+    }
+}
+
+fn read_exact(source: &dyn ReadAt, offset: u64, len: usize) -> Result<Vec<u8>> {
+    let bytes = source.read_at(offset, len)?;
+    if bytes.len() != len {
+        return Err(corrupt(format!("источник вернул {} байт вместо {}", bytes.len(), len)));
+    }
+    Ok(bytes)
+}
+
+impl<T: ColumnType> Column<T> {
+    // Колонка из внешнего источника по метаданным ColumnMeta::read. Чтения чанков,
+    // точечные и по диапазону, запрашивают у источника только байты нужных чанков;
+    // то, что требует файла целиком (data, сохранение в таблицу), скачивает его один
+    // раз. Колонка только для чтения: дозапись строит копию в памяти
+    pub fn open_with(source: Arc<dyn ReadAt>, meta: &ColumnMeta) -> Result<Column<T>> {
+        if source.len() != meta.len {
+            return Err(invalid_input(format!(
+                "метаданные прочитаны из файла в {} байт, а у источника {}",
+                meta.len,
+                source.len()
+            )));
+        }
+        if meta.header.type_tag != T::TAG {
+            return Err(corrupt(format!(
+                "колонка хранит значения {}, а открывается как {}",
+                type_name(meta.header.type_tag), type_name(T::TAG)
+            )));
+        }
+        let footer = Footer::<T>::decode_meta(&meta.footer, meta.data_end, &meta.header)?;
+        Self::from_parts(None, Backing::remote(source, meta.len), meta.header, meta.data_end, footer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use crate::{Agg, Codec, ColumnBuilder, ColumnarError, Predicate};
+
+    // Источник в памяти, запоминающий запрошенные диапазоны
+    #[derive(Debug)]
+    struct Recording {
+        bytes: Vec<u8>,
+        reads: Mutex<Vec<(u64, usize)>>,
+    }
+
+    impl Recording {
+        fn take_reads(&self) -> Vec<(u64, usize)> {
+            std::mem::take(&mut *self.reads.lock().unwrap())
+        }
+    }
+
+    impl ReadAt for Recording {
+        fn read_at(&self, offset: u64, len: usize) -> Result<Vec<u8>> {
+            self.reads.lock().unwrap().push((offset, len));
+            let start = offset as usize;
+            self.bytes.get(start..start + len).map(<[u8]>::to_vec).ok_or_else(|| corrupt("вне файла"))
+        }
+
+        fn len(&self) -> u64 {
+            self.bytes.len() as u64
+        }
+    }
+
+    fn build() -> Column<i64> {
+        let values: Vec<i64> = (0..200_000).map(|i| i * 3 % 100_003).collect();
+        let mut builder = ColumnBuilder::from_values("v".to_string(), &values);
+        builder.set_chunk_rows(10_000);
+        builder.compress_with(Codec::Lz4).unwrap();
+        builder.build_in_memory().unwrap()
+    }
+
+    #[test]
+    fn test_point_lookup_reads_one_chunk_range() {
+        let local = build();
+        let source = Arc::new(Recording { bytes: local.backing.to_vec(), reads: Mutex::new(Vec::new()) });
+        let meta = ColumnMeta::read(&*source).unwrap();
+        assert_eq!(source.take_reads().len(), 3);
+
+        let remote = Column::<i64>::open_with(source.clone(), &meta).unwrap();
+        assert!(source.take_reads().is_empty());
+        assert_eq!(remote.get_value(123_456), local.get_value(123_456));
+        let chunk = &local.chunks[12];
+        assert_eq!(source.take_reads(), vec![(chunk.offset, (chunk.stored_end() - chunk.offset) as usize)]);
+        assert!(((chunk.stored_end() - chunk.offset) as usize) < source.bytes.len() / 10);
+        // Соседняя строка того же чанка уже распакована
+        assert_eq!(remote.get_value(123_457), local.get_value(123_457));
+        assert!(source.take_reads().is_empty());
+
+        assert_eq!(remote.aggregate(Agg::Sum, None).unwrap(), local.aggregate(Agg::Sum, None).unwrap());
+        assert_eq!(remote.filter(Predicate::Between(10, 20)).unwrap(), local.filter(Predicate::Between(10, 20)).unwrap());
+        assert_eq!(source.take_reads().len(), 20 + local.chunks_matching_range(10, 20).len());
+        assert_eq!(remote.values().unwrap(), local.values().unwrap());
+    }
+
+    #[test]
+    fn test_file_and_backing_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let values: Vec<Option<i32>> = (0..50_000).map(|i| (i % 9 != 0).then_some(i)).collect();
+        let mut builder = ColumnBuilder::from_nullable("v".to_string(), &values);
+        builder.set_chunk_rows(4096);
+        let local = builder.build(&path).unwrap();
+
+        let file: Arc<dyn ReadAt> = Arc::new(FileReadAt::open(&path).unwrap());
+        let remote = Column::<i32>::open_with(file.clone(), &ColumnMeta::read(&*file).unwrap()).unwrap();
+        assert!(remote.backing.is_remote());
+        assert_eq!(remote.nullable_values().unwrap(), values);
+        assert_eq!(remote.get_nullable(9), Some(None));
+        assert!(!remote.decompress_parallel().unwrap().is_zero_copy());
+        assert!(remote.verify().unwrap().is_ok());
+
+        let mapped: Arc<dyn ReadAt> = Arc::new(local.backing.clone());
+        let meta = ColumnMeta::read(&*mapped).unwrap();
+        assert_eq!(meta.file_len(), local.backing.len() as u64);
+        assert_eq!(Column::<i32>::open_with(mapped.clone(), &meta).unwrap().stats(), local.stats());
+        assert!(matches!(Column::<i64>::open_with(mapped, &meta), Err(ColumnarError::Corrupt { .. })));
+        assert!(matches!(file.read_at(file.len() - 2, 4), Err(ColumnarError::Corrupt { .. })));
+        // Метаданные другого файла
+        let other: Arc<dyn ReadAt> = Arc::new(build().backing.clone());
+        assert!(matches!(Column::<i32>::open_with(other, &meta), Err(ColumnarError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,209 @@
+use std::{fs::File, path::Path, sync::Arc};
+use crate::backing::Backing;
+use crate::bools::bit_is_set;
+use crate::codec::{Codec, DEFAULT_ZSTD_LEVEL};
+use crate::encoding::Encoding;
+use crate::error::{corrupt, ColumnarError, Result};
+use crate::format::{ChunkMeta, Header, FLAG_CHUNK_FRAMES, HEADER_SIZE};
+use crate::lock::WriteLock;
+use crate::storage::Column;
+use crate::tombstone::{load_deletions, write_deletions};
+use crate::types::ColumnType;
+use crate::verify::decode_stored;
+use crate::wal::{clear_wal, wal_is_empty};
+use crate::writer::{BuildOptions, ColumnWriter};
+
+// Что open_recover сохранил из повреждённого файла
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecoveryReport {
+    pub chunks_recovered: usize,
+    pub rows_recovered: u64,
+    // Строки из заголовка файла, не попавшие в целые чанки
+    pub rows_dropped: u64,
+    // Байты после последнего целого чанка: недописанный чанк и остатки метаданных
+    pub bytes_dropped: u64,
+}
+
+impl<T: ColumnType> Column<T> {
+    // Открывает колонку, даже если её хвост потерян, например диск переполнился
+    // посреди дозаписи. Целый файл открывается как обычно. Иначе чанки ищутся по
+    // кадрам от начала данных, пока кадр и контрольная сумма чанка сходятся и чанк
+    // распаковывается, и найденные переписываются в path атомарно с новым footer.
+    // Годится только для колонок с кадрами чанков (enable_chunk_frames) и целым
+    // заголовком. Имя колонки хранилось в потерянном footer, поэтому им становится
+    // имя файла; bloom-фильтры, HLL и сводка строятся заново, гистограмма теряется,
+    // а удаления сохраняются для уцелевших строк. Словарная колонка без footer не
+    // восстанавливается: словарь был в нём
+    pub fn open_recover(path: &Path) -> Result<(Column<T>, RecoveryReport)> {
+        match Self::open(path) {
+            Ok(column) => {
+                let report = RecoveryReport {
+                    chunks_recovered: column.chunks.len(),
+                    rows_recovered: column.len() as u64,
+                    ..RecoveryReport::default()
+                };
+                return Ok((column, report));
+            }
+            Err(ColumnarError::Corrupt { .. }) => {}
+            Err(e) => return Err(e),
+        }
+
+        let lock = WriteLock::acquire(path)?;
+        let backing = Backing::map(&File::open(path)?)?;
+        let header = Header::decode(&backing)?;
+        if header.type_tag != T::TAG {
+            return Err(corrupt(format!("в заголовке тег типа {}, а колонка открывается как {}", header.type_tag, T::TAG)));
+        }
+        if header.flags & FLAG_CHUNK_FRAMES == 0 {
+            return Err(corrupt("колонка записана без кадров чанков: без footer её чанки не найти"));
+        }
+        // Уровень zstd хранился в footer; распаковке он не нужен
+        let codec = Codec::from_parts(header.codec_tag, DEFAULT_ZSTD_LEVEL)?;
+        let encoding = Encoding::from_tag(header.encoding_tag)?;
+        if encoding == Encoding::Dictionary {
+            return Err(corrupt("словарь колонки хранился в потерянном footer"));
+        }
+
+        let name = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        let mut writer = ColumnWriter::create_atomic_locked(name, path, lock, |file| Box::new(file))?;
+        writer.compress_with(codec)?;
+        writer.set_encoding(encoding)?;
+        writer.enable_chunk_frames()?;
+        let mut offset = HEADER_SIZE as u64;
+        let mut rows = 0u64;
+        let mut chunks_recovered = 0;
+        while let Some(chunk) = backing.get(offset as usize..).and_then(|rest| ChunkMeta::<T>::decode_frame(rest, offset, rows)) {
+            let Some(stored) = backing.get(chunk.offset as usize..chunk.stored_end() as usize) else {
+                break;
+            };
+            if crc32fast::hash(stored) != chunk.checksum {
+                break;
+            }
+            let Ok((values, validity)) = decode_stored(codec, encoding, &[], &chunk, stored) else {
+                break;
+            };
+            if chunks_recovered == 0 {
+                writer.set_chunk_rows(chunk.row_count() as usize)?;
+            }
+            for (row, raw) in values.chunks_exact(T::WIDTH).enumerate() {
+                let valid = validity.as_ref().is_none_or(|bits| bit_is_set(bits, row));
+                writer.push_option(valid.then(|| T::read_le(raw)))?;
+            }
+            offset = chunk.stored_end();
+            rows = chunk.end_row();
+            chunks_recovered += 1;
+        }
+
+        // Удаления читаются до finish: новая колонка пишется не дозаписью, и finish
+        // удаляет прежний файл .del
+        let deleted = load_deletions(path, header.row_count.max(rows))?;
+        let mut column = writer.finish_with(BuildOptions { sync: true })?;
+        if let Some(deleted) = deleted.map(|deleted| deleted.slice(0..rows as usize)).filter(|kept| kept.count() > 0) {
+            write_deletions(path, Some(&deleted))?;
+            column.deleted = Some(Arc::new(deleted));
+        }
+        // Журнал дозаписи, который open не смог применить, к новому файлу не относится
+        if !wal_is_empty(path)? {
+            clear_wal(path)?;
+        }
+        let report = RecoveryReport {
+            chunks_recovered,
+            rows_recovered: rows,
+            rows_dropped: header.row_count.saturating_sub(rows),
+            bytes_dropped: backing.len() as u64 - offset,
+        };
+        Ok((column, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, OpenOptions};
+    use crate::tombstone::deletions_path;
+    use crate::ColumnBuilder;
+
+    fn values() -> Vec<Option<i64>> {
+        (0..20_500).map(|i| (i % 17 != 3 || i >= 20_000).then_some(i * 2)).collect()
+    }
+
+    // Семь чанков по 3000 строк и сегмент дозаписи из одного чанка
+    fn build(path: &Path) -> Column<i64> {
+        let values = values();
+        let mut builder = ColumnBuilder::from_nullable("v".to_string(), &values[..20_000]);
+        builder.set_chunk_rows(3000);
+        builder.compress_with(Codec::Lz4).unwrap();
+        builder.enable_chunk_frames();
+        let mut column = builder.build(path).unwrap();
+        let tail: Vec<i64> = values[20_000..].iter().map(|v| v.unwrap()).collect();
+        column.append(&tail).unwrap();
+        column.delete_rows(&[5, 9000, 20_400]).unwrap();
+        column
+    }
+
+    #[test]
+    fn test_open_recover_keeps_every_complete_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("original.col");
+        let column = build(&original);
+        assert_eq!(column.chunks.len(), 8);
+        assert_eq!(column.values().unwrap().len(), 20_500);
+        let file_len = fs::metadata(&original).unwrap().len();
+        let data_end = column.chunks[7].stored_end();
+
+        // Посреди кадра, посреди данных чанка, ровно по границе чанков и в footer
+        let cuts = [
+            (column.chunks[4].offset - 30, 4),
+            (column.chunks[4].offset + 17, 4),
+            (column.chunks[2].stored_end(), 3),
+            (data_end + 5, 8),
+            (HEADER_SIZE as u64 + 2, 0),
+        ];
+        for (cut, complete) in cuts {
+            let path = dir.path().join("v.col");
+            fs::copy(&original, &path).unwrap();
+            fs::copy(deletions_path(&original), deletions_path(&path)).unwrap();
+            OpenOptions::new().write(true).open(&path).unwrap().set_len(cut).unwrap();
+            assert!(matches!(Column::<i64>::open(&path), Err(ColumnarError::Corrupt { .. })));
+
+            let (recovered, report) = Column::<i64>::open_recover(&path).unwrap();
+            let rows = if complete == 0 { 0 } else { column.chunks[complete - 1].end_row() };
+            assert_eq!(report, RecoveryReport {
+                chunks_recovered: complete,
+                rows_recovered: rows,
+                rows_dropped: 20_500 - rows,
+                bytes_dropped: cut - column.chunks.get(complete.wrapping_sub(1)).map_or(HEADER_SIZE as u64, ChunkMeta::stored_end),
+            }, "обрезка на {}", cut);
+            assert_eq!(recovered.nullable_values().unwrap(), values()[..rows as usize]);
+            assert_eq!(recovered.name, "v");
+            assert!(recovered.is_deleted(5) == (rows > 5) && !recovered.is_deleted(6));
+            assert_eq!(recovered.live_count(), rows as usize - [5, 9000, 20_400].iter().filter(|r| **r < rows).count());
+            assert!(recovered.verify().unwrap().is_ok());
+
+            // Новый файл целый и по-прежнему с кадрами
+            let reopened = Column::<i64>::open(&path).unwrap();
+            assert_eq!((reopened.len(), reopened.deleted_rows().map(|d| d.count())), (recovered.len(), recovered.deleted_rows().map(|d| d.count())));
+            assert!(reopened.chunk_frames);
+        }
+        assert!(data_end + 5 < file_len);
+    }
+
+    #[test]
+    fn test_open_recover_on_intact_and_unframed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        build(&path);
+        let (column, report) = Column::<i64>::open_recover(&path).unwrap();
+        assert_eq!(report, RecoveryReport { chunks_recovered: 8, rows_recovered: 20_500, ..RecoveryReport::default() });
+        assert_eq!(column.name, "v");
+
+        let plain = dir.path().join("plain.col");
+        let values: Vec<i64> = (0..10_000).collect();
+        let mut builder = ColumnBuilder::from_values("p".to_string(), &values);
+        builder.set_chunk_rows(1000);
+        let column = builder.build(&plain).unwrap();
+        assert!(!column.chunk_frames);
+        OpenOptions::new().write(true).open(&plain).unwrap().set_len(column.chunks[5].offset).unwrap();
+        assert!(matches!(Column::<i64>::open_recover(&plain), Err(ColumnarError::Corrupt { .. })));
+    }
+}
@@ -0,0 +1,284 @@
+use crate::bools::bit_is_set;
+use crate::error::{invalid_input, Result};
+use crate::expr::Expr;
+use crate::filter::Predicate;
+use crate::storage::Column;
+use crate::table::{Table, TableColumn};
+use crate::types::ColumnType;
+
+// Сколько отобранных строк попадает в один RowBatch
+pub const SCAN_BATCH_ROWS: usize = 1024;
+
+// Условие скана: имя колонки и предикат того же типа, что и её значения
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnPredicate {
+    Int32(String, Predicate<i32>),
+    Int64(String, Predicate<i64>),
+    Float64(String, Predicate<f64>),
+}
+
+// Значения одной колонки для строк пакета. NULL бывают только у числовых колонок
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnValues {
+    Int32(Vec<Option<i32>>),
+    Int64(Vec<Option<i64>>),
+    Float64(Vec<Option<f64>>),
+    Utf8(Vec<String>),
+    Bool(Vec<bool>),
+}
+
+// Пакет строк скана: номера строк таблицы по возрастанию и значения колонок
+// проекции в её порядке
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowBatch {
+    pub rows: Vec<usize>,
+    pub columns: Vec<(String, ColumnValues)>,
+}
+
+impl ColumnPredicate {
+    pub fn column(&self) -> &str {
+        match self {
+            ColumnPredicate::Int32(name, _) | ColumnPredicate::Int64(name, _) | ColumnPredicate::Float64(name, _) => name,
+        }
+    }
+}
+
+impl ColumnValues {
+    pub fn len(&self) -> usize {
+        match self {
+            ColumnValues::Int32(values) => values.len(),
+            ColumnValues::Int64(values) => values.len(),
+            ColumnValues::Float64(values) => values.len(),
+            ColumnValues::Utf8(values) => values.len(),
+            ColumnValues::Bool(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl RowBatch {
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ColumnValues> {
+        self.columns.iter().find(|(column, _)| column == name).map(|(_, values)| values)
+    }
+}
+
+impl<T: ColumnType> Column<T> {
+    // Значения строк rows (по возрастанию); каждый затронутый чанк распаковывается один раз,
+    // чанки без отобранных строк не читаются вовсе
+    pub(crate) fn take_sorted(&self, rows: &[usize]) -> Result<Vec<Option<T>>> {
+        let mut result = Vec::with_capacity(rows.len());
+        let mut start = 0;
+        while start < rows.len() {
+            let idx = self
+                .chunk_for_row(rows[start] as u64)
+                .ok_or_else(|| invalid_input(format!("строка {} вне колонки из {} строк", rows[start], self.len())))?;
+            let chunk = &self.chunks()[idx];
+            let end = start + rows[start..].partition_point(|row| (*row as u64) < chunk.end_row());
+            let values = self.chunk_values(idx)?;
+            let validity = self.chunk_validity(idx)?;
+            for row in &rows[start..end] {
+                let row = row - chunk.first_row as usize;
+                if validity.as_ref().is_some_and(|bits| !bit_is_set(bits, row)) {
+                    result.push(None);
+                } else {
+                    result.push(Some(T::read_le(&values[row * T::WIDTH..(row + 1) * T::WIDTH])));
+                }
+            }
+            start = end;
+        }
+        Ok(result)
+    }
+}
+
+impl TableColumn {
+    pub(crate) fn take_sorted(&self, rows: &[usize]) -> Result<ColumnValues> {
+        Ok(match self {
+            TableColumn::Int32(column) => ColumnValues::Int32(column.take_sorted(rows)?),
+            TableColumn::Int64(column) => ColumnValues::Int64(column.take_sorted(rows)?),
+            TableColumn::Float64(column) => ColumnValues::Float64(column.take_sorted(rows)?),
+            TableColumn::Utf8(column) => {
+                ColumnValues::Utf8(rows.iter().map(|row| column.get_str(*row).unwrap_or_default().to_string()).collect())
+            }
+            TableColumn::Bool(column) => {
+                ColumnValues::Bool(rows.iter().map(|row| column.get_bool(*row).unwrap_or_default()).collect())
+            }
+        })
+    }
+}
+
+impl Table {
+    // Отбор строк по условию и чтение колонок projection только для отобранных строк.
+    // Условие вычисляется сразу, колонки проекции распаковываются лениво, по пакетам
+    // из SCAN_BATCH_ROWS строк
+    pub fn scan<'a>(
+        &'a self,
+        filter: impl Into<Expr>,
+        projection: &[&str],
+    ) -> Result<impl Iterator<Item = Result<RowBatch>> + 'a> {
+        let projected: Vec<&TableColumn> = projection.iter().map(|name| self.require(name)).collect::<Result<_>>()?;
+        let rows: Vec<usize> = self.evaluate(&filter.into())?.iter_indices().collect();
+
+        Ok((0..rows.len()).step_by(SCAN_BATCH_ROWS).map(move |start| {
+            let rows = rows[start..(start + SCAN_BATCH_ROWS).min(rows.len())].to_vec();
+            let columns = projected
+                .iter()
+                .map(|column| Ok((column.name().to_string(), column.take_sorted(&rows)?)))
+                .collect::<Result<_>>()?;
+            Ok(RowBatch { rows, columns })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ColumnarError;
+    use crate::{BoolColumnBuilder, Codec, ColumnBuilder, StringColumnBuilder};
+    use tempfile::NamedTempFile;
+
+    const ROWS: usize = 10_000;
+
+    fn events(files: &[NamedTempFile; 2]) -> Table {
+        let ts: Vec<i64> = (0..ROWS as i64).map(|i| 1_000_000 + i * 10).collect();
+        let ids: Vec<i32> = (0..ROWS as i32).map(|i| (i * 7919) % 10_007).collect();
+        let values: Vec<Option<f64>> = (0..ROWS).map(|i| (i % 11 != 0).then_some(i as f64 * 0.25)).collect();
+
+        let mut ts_builder = ColumnBuilder::from_i64("ts".to_string(), &ts);
+        ts_builder.set_chunk_rows(500);
+        ts_builder.compress_with(Codec::Lz4).unwrap();
+        let mut id_builder = ColumnBuilder::from_i32("id".to_string(), &ids);
+        id_builder.set_chunk_rows(500);
+        id_builder.compress_with(Codec::zstd()).unwrap();
+        let mut value_builder = ColumnBuilder::from_nullable("value".to_string(), &values);
+        value_builder.set_chunk_rows(500);
+        let labels: Vec<String> = (0..ROWS).map(|i| format!("e{}", i % 13)).collect();
+        let flags: Vec<bool> = (0..ROWS).map(|i| i % 3 == 0).collect();
+
+        let mut table = Table::new("events".to_string());
+        table.add_column(ts_builder.build_in_memory().unwrap()).unwrap();
+        table.add_column(id_builder.build_in_memory().unwrap()).unwrap();
+        table.add_column(value_builder.build_in_memory().unwrap()).unwrap();
+        table.add_column(StringColumnBuilder::from_strs("label".to_string(), &labels).build(files[0].path()).unwrap()).unwrap();
+        table.add_column(BoolColumnBuilder::from_bools("flag".to_string(), &flags).build(files[1].path()).unwrap()).unwrap();
+        table
+    }
+
+    // Эталон: распаковать всё, затем отфильтровать
+    fn naive(table: &Table, filter: &ColumnPredicate, projection: &[&str]) -> (Vec<usize>, Vec<ColumnValues>) {
+        let rows: Vec<usize> = match filter {
+            ColumnPredicate::Int32(name, p) => {
+                let values = table.column::<i32>(name).unwrap().nullable_values().unwrap();
+                (0..values.len()).filter(|i| values[*i].is_some_and(|v| p.matches(&v))).collect()
+            }
+            ColumnPredicate::Int64(name, p) => {
+                let values = table.column::<i64>(name).unwrap().nullable_values().unwrap();
+                (0..values.len()).filter(|i| values[*i].is_some_and(|v| p.matches(&v))).collect()
+            }
+            ColumnPredicate::Float64(name, p) => {
+                let values = table.column::<f64>(name).unwrap().nullable_values().unwrap();
+                (0..values.len()).filter(|i| values[*i].is_some_and(|v| p.matches(&v))).collect()
+            }
+        };
+        let columns = projection
+            .iter()
+            .map(|name| match table.get(name).unwrap() {
+                TableColumn::Int32(c) => {
+                    let all = c.nullable_values().unwrap();
+                    ColumnValues::Int32(rows.iter().map(|r| all[*r]).collect())
+                }
+                TableColumn::Int64(c) => {
+                    let all = c.nullable_values().unwrap();
+                    ColumnValues::Int64(rows.iter().map(|r| all[*r]).collect())
+                }
+                TableColumn::Float64(c) => {
+                    let all = c.nullable_values().unwrap();
+                    ColumnValues::Float64(rows.iter().map(|r| all[*r]).collect())
+                }
+                TableColumn::Utf8(c) => ColumnValues::Utf8(rows.iter().map(|r| c.get_str(*r).unwrap().to_string()).collect()),
+                TableColumn::Bool(c) => ColumnValues::Bool(rows.iter().map(|r| c.get_bool(*r).unwrap()).collect()),
+            })
+            .collect();
+        (rows, columns)
+    }
+
+    fn collect(table: &Table, filter: ColumnPredicate, projection: &[&str]) -> (Vec<usize>, Vec<ColumnValues>) {
+        let batches: Vec<RowBatch> = table.scan(filter, projection).unwrap().collect::<Result<_>>().unwrap();
+        assert!(batches.iter().all(|b| !b.is_empty() && b.len() <= SCAN_BATCH_ROWS));
+        let rows = batches.iter().flat_map(|b| b.rows.clone()).collect();
+        let columns = projection
+            .iter()
+            .map(|name| {
+                let mut merged = table.get(name).unwrap().take_sorted(&[]).unwrap();
+                for part in batches.iter().map(|b| b.get(name).unwrap().clone()) {
+                    match (&mut merged, part) {
+                        (ColumnValues::Int32(a), ColumnValues::Int32(b)) => a.extend(b),
+                        (ColumnValues::Int64(a), ColumnValues::Int64(b)) => a.extend(b),
+                        (ColumnValues::Float64(a), ColumnValues::Float64(b)) => a.extend(b),
+                        (ColumnValues::Utf8(a), ColumnValues::Utf8(b)) => a.extend(b),
+                        (ColumnValues::Bool(a), ColumnValues::Bool(b)) => a.extend(b),
+                        _ => unreachable!(),
+                    }
+                }
+                merged
+            })
+            .collect();
+        (rows, columns)
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_scan_matches_full_decode_then_filter() {
+        let files = [NamedTempFile::new().unwrap(), NamedTempFile::new().unwrap()];
+        let table = events(&files);
+        let projection = ["id", "value", "label", "flag", "ts"];
+        for filter in [
+            ColumnPredicate::Int64("ts".to_string(), Predicate::Between(1_020_000, 1_045_000)),
+            ColumnPredicate::Int64("ts".to_string(), Predicate::Ge(0)),
+            ColumnPredicate::Int64("ts".to_string(), Predicate::Lt(0)),
+            ColumnPredicate::Int32("id".to_string(), Predicate::Lt(300)),
+            ColumnPredicate::Float64("value".to_string(), Predicate::Gt(2000.0)),
+        ] {
+            assert_eq!(collect(&table, filter.clone(), &projection), naive(&table, &filter, &projection), "{:?}", filter);
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_scan_decodes_only_selected_chunks() {
+        let files = [NamedTempFile::new().unwrap(), NamedTempFile::new().unwrap()];
+        let table = events(&files);
+        // ts 1_020_000..=1_045_000 — строки 2000..=4500, то есть чанки 4..=9 из 20
+        let filter = ColumnPredicate::Int64("ts".to_string(), Predicate::Between(1_020_000, 1_045_000));
+        let batches: Vec<RowBatch> = table.scan(filter, &["id"]).unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(batches.iter().map(RowBatch::len).sum::<usize>(), 2501);
+        assert_eq!(batches.len(), 2501usize.div_ceil(SCAN_BATCH_ROWS));
+        // Шесть затронутых чанков из 20; чанк на границе пакетов распаковывается обоими
+        assert_eq!(table.column::<i32>("id").unwrap().frames_decoded(), 6 + batches.len() - 1);
+        assert_eq!(batches[0].columns.len(), 1);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_scan_rejects_unknown_columns_and_mismatched_types() {
+        let files = [NamedTempFile::new().unwrap(), NamedTempFile::new().unwrap()];
+        let table = events(&files);
+        let ts = || ColumnPredicate::Int64("ts".to_string(), Predicate::Eq(1));
+        assert!(matches!(table.scan(ts(), &["missing"]).err(), Some(ColumnarError::InvalidInput(_))));
+        let missing = ColumnPredicate::Int32("missing".to_string(), Predicate::Eq(1));
+        assert!(matches!(table.scan(missing, &["id"]).err(), Some(ColumnarError::InvalidInput(_))));
+        let wrong = ColumnPredicate::Int32("ts".to_string(), Predicate::Eq(1));
+        assert!(matches!(table.scan(wrong, &["id"]).err(), Some(ColumnarError::InvalidInput(_))));
+        assert_eq!(table.scan(ts(), &[]).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,141 @@
+use std::cmp::Ordering;
+use crate::compute::Selection;
+use crate::error::{invalid_input, Result};
+use crate::par::*;
+use crate::storage::{Column, ColumnBuilder};
+use crate::types::ColumnType;
+
+// Колонки короче сортируются в одном потоке: запуск rayon дороже самой сортировки
+const PARALLEL_SORT_ROWS: usize = 1 << 16;
+
+impl<T: ColumnType> Column<T> {
+    // Перестановка строк, упорядочивающая колонку: i-й элемент — номер строки, которая
+    // встанет на место i. Сортировка устойчива, равные значения сохраняют исходный
+    // порядок; значения сравниваются в полном порядке типа, NULL всегда идут последними
+    pub fn sort_indices(&self, ascending: bool) -> Result<Vec<u32>> {
+        if self.len() > u32::MAX as usize {
+            return Err(invalid_input(format!("в колонке {} строк, перестановка не помещается в u32", self.len())));
+        }
+        let mut pairs: Vec<(Option<T>, u32)> =
+            self.nullable_values()?.into_iter().enumerate().map(|(idx, value)| (value, idx as u32)).collect();
+        let compare = |a: &(Option<T>, u32), b: &(Option<T>, u32)| match (a.0, b.0) {
+            (Some(x), Some(y)) if ascending => x.total_cmp(&y),
+            (Some(x), Some(y)) => y.total_cmp(&x),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        if pairs.len() >= PARALLEL_SORT_ROWS {
+            pairs.par_sort_by(compare);
+        } else {
+            pairs.sort_by(compare);
+        }
+        Ok(pairs.into_iter().map(|(_, idx)| idx).collect())
+    }
+
+    // Строки indices в их порядке, в том числе повторяющиеся. Построитель наследует
+    // имя, кодек, кодирование, размер чанка, bloom-фильтр и гистограмму колонки, так что
+    // перестановка из sort_indices переписывает колонку в отсортированном виде
+    pub fn take(&self, indices: &[u32]) -> Result<ColumnBuilder<T>> {
+        if let Some(idx) = indices.iter().find(|idx| **idx as usize >= self.len()) {
+            return Err(invalid_input(format!("строка {} вне колонки из {} строк", idx, self.len())));
+        }
+        let values = self.nullable_values()?;
+        let mut builder = if self.null_count == 0 {
+            let taken: Vec<T> = indices.iter().map(|idx| values[*idx as usize].unwrap()).collect();
+            ColumnBuilder::from_values(self.name.clone(), &taken)
+        } else {
+            let taken: Vec<Option<T>> = indices.iter().map(|idx| values[*idx as usize]).collect();
+            ColumnBuilder::from_nullable(self.name.clone(), &taken)
+        };
+        builder.compress_with(self.codec)?;
+        builder.set_encoding(self.encoding);
+        if self.chunks().len() > 1 {
+            builder.set_chunk_rows(self.chunks()[0].row_count() as usize);
+        }
+        match self.bloom_fp_rate {
+            Some(fp_rate) => builder.set_bloom_fp_rate(fp_rate)?,
+            None => builder.disable_bloom_filter(),
+        }
+        if let Some(histogram) = &self.histogram {
+            builder.enable_histogram(histogram.target_buckets())?;
+        }
+        Ok(builder)
+    }
+
+    // take по отбору, например по результату filter: строки идут по возрастанию
+    pub fn take_selected(&self, selection: &Selection) -> Result<ColumnBuilder<T>> {
+        if selection.len() != self.len() {
+            return Err(invalid_input(format!(
+                "отбор из {} строк не совпадает с длиной колонки {}",
+                selection.len(), self.len()
+            )));
+        }
+        self.take(&selection.to_indices()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ColumnarError;
+    use crate::{Codec, Encoding, Table};
+
+    #[test]
+    fn test_sort_table_by_one_column_keeps_rows_aligned() {
+        let rows = 100_000;
+        let keys: Vec<i64> = (0..rows as i64).map(|i| (i * 7919) % 1000).collect();
+        let ids: Vec<i32> = (0..rows as i32).collect();
+        let weights: Vec<Option<f64>> = (0..rows).map(|i| (i % 9 != 0).then_some(i as f64 / 8.0)).collect();
+        let mut key = ColumnBuilder::from_i64("key".to_string(), &keys);
+        key.set_chunk_rows(10_000);
+        key.compress_with(Codec::Lz4).unwrap();
+
+        let mut table = Table::new("t".to_string());
+        table.add_column(key.build_in_memory().unwrap()).unwrap();
+        table.add_column(ColumnBuilder::from_i32("id".to_string(), &ids).build_in_memory().unwrap()).unwrap();
+        table.add_column(ColumnBuilder::from_nullable("weight".to_string(), &weights).build_in_memory().unwrap()).unwrap();
+
+        let order = table.column::<i64>("key").unwrap().sort_indices(true).unwrap();
+        let mut sorted = Table::new("sorted".to_string());
+        sorted.add_column(table.column::<i64>("key").unwrap().take(&order).unwrap().build_in_memory().unwrap()).unwrap();
+        sorted.add_column(table.column::<i32>("id").unwrap().take(&order).unwrap().build_in_memory().unwrap()).unwrap();
+        sorted.add_column(table.column::<f64>("weight").unwrap().take(&order).unwrap().build_in_memory().unwrap()).unwrap();
+
+        let key = sorted.column::<i64>("key").unwrap();
+        assert!(key.stats().is_sorted);
+        assert_eq!((key.codec, key.chunks().len()), (Codec::Lz4, 10));
+        let sorted_keys = key.values().unwrap();
+        let sorted_ids = sorted.column::<i32>("id").unwrap().values().unwrap();
+        let sorted_weights = sorted.column::<f64>("weight").unwrap().nullable_values().unwrap();
+        for row in 0..rows {
+            let original = sorted_ids[row] as usize;
+            assert_eq!(sorted_keys[row], keys[original]);
+            assert_eq!(sorted_weights[row], weights[original]);
+            // Устойчивость: при равных ключах исходный порядок строк сохраняется
+            if row > 0 && sorted_keys[row - 1] == sorted_keys[row] {
+                assert!(sorted_ids[row - 1] < sorted_ids[row]);
+            }
+        }
+        assert_eq!(sorted.column::<f64>("weight").unwrap().null_count, table.column::<f64>("weight").unwrap().null_count);
+    }
+
+    #[test]
+    fn test_descending_order_nulls_and_nan() {
+        let values = [Some(2.0), None, Some(f64::NAN), Some(-1.0), Some(2.0), None, Some(7.5)];
+        let mut builder = ColumnBuilder::from_nullable("f".to_string(), &values);
+        builder.set_encoding(Encoding::Plain);
+        let column = builder.build_in_memory().unwrap();
+        // NaN в полном порядке f64 больше любого числа
+        assert_eq!(column.sort_indices(true).unwrap(), vec![3, 0, 4, 6, 2, 1, 5]);
+        assert_eq!(column.sort_indices(false).unwrap(), vec![2, 6, 0, 4, 3, 1, 5]);
+
+        let taken = column.take(&[6, 6, 1]).unwrap().build_in_memory().unwrap();
+        assert_eq!(taken.nullable_values().unwrap(), vec![Some(7.5), Some(7.5), None]);
+        assert!(matches!(column.take(&[7]), Err(ColumnarError::InvalidInput(_))));
+
+        let empty = ColumnBuilder::from_i32("e".to_string(), &[]).build_in_memory().unwrap();
+        assert!(empty.sort_indices(false).unwrap().is_empty());
+        assert!(empty.take(&[]).unwrap().build_in_memory().unwrap().is_empty());
+    }
+}
@@ -1,135 +1,1074 @@
-use std::{fs::File, path::Path, sync::Arc};
-use memmap2::Mmap;
-use bloomfilter::Bloom;
-use zstd::{encode_all as zstd_compress, decode_all as zstd_decompress};
-use rayon::prelude::*;
-
-#[derive(Debug)]
-pub struct Column {
+use std::{
+    borrow::Cow,
+    fs::File,
+    ops::{Deref, Range},
+    path::{Path, PathBuf},
+    sync::{atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering}, Arc, Mutex},
+};
+use crate::advice::AccessPattern;
+use crate::backing::{Backing, ColumnData};
+use crate::bloom::Bloom;
+use crate::bools::bit_is_set;
+use crate::cache::{ChunkCache, ChunkKey};
+use crate::codec::Codec;
+use crate::compute::Selection;
+use crate::encoding::{build_dictionary, Encoding};
+use crate::error::{corrupt, invalid_input, Result};
+use crate::format::{Footer, Header, FLAG_CHUNK_FRAMES, HEADER_SIZE};
+use crate::histogram::{check_histogram_buckets, Histogram};
+use crate::hll::{HyperLogLog, DEFAULT_HLL_PRECISION};
+use crate::lock::WriteLock;
+use crate::par::*;
+use crate::tombstone::load_deletions;
+use crate::trace::span;
+use crate::wal::replay_wal;
+use crate::types::{type_name, ColumnType};
+use crate::writer::{check_fp_rate, is_framed, BuildOptions, ColumnWriter};
+
+pub use crate::format::ChunkMeta;
+
+// Колонка — дешёвый дескриптор: отображение файла и метаданные лежат в ColumnInner
+// за Arc и после open не меняются, поэтому clone только увеличивает счётчик ссылок,
+// а копии можно раздать потокам. Поля ColumnInner читаются через Deref. Чтения берут
+// &self; состояние, которое они обновляют (сверенные чанки, счётчик фреймов, режим
+// доступа), хранится в атомиках, а кэш последнего чанка у каждой копии свой.
+// append и delete_rows меняют только свою копию, остальные видят колонку прежней,
+// как читатели со старым mmap. Debug и Display — в summary.rs
+pub struct Column<T: ColumnType = i32> {
+    inner: Arc<ColumnInner<T>>,
+    verify_checksums: bool,
+    // Дозапись через журнал .wal, см. set_write_ahead_log
+    pub(crate) write_ahead_log: bool,
+    // Удалённые строки из файла .del; None — удалений нет
+    pub(crate) deleted: Option<Arc<Selection>>,
+    // Последний распакованный чанк для точечных чтений
+    cached_chunk: Mutex<Option<(usize, Arc<Vec<u8>>)>>,
+    // Общий кэш распакованных чанков и имя колонки в нём, см. set_chunk_cache
+    chunk_cache: Option<(Arc<ChunkCache>, Arc<str>)>,
+}
+
+// Общая неизменяемая часть колонки
+pub struct ColumnInner<T: ColumnType = i32> {
     pub name: String,
-    pub mmap: Arc<Mmap>,
-    pub min: i32,
-    pub max: i32,
-    pub is_compressed: bool,
-    pub bloom_filter: Bloom<i32>,
+    pub backing: Backing,
+    pub min: T,
+    pub max: T,
+    // Встречались ли NaN; они не учитываются в min/max
+    pub has_nan: bool,
+    // Значения не убывают в полном порядке типа; определяется при записи
+    pub is_sorted: bool,
+    // Число строк NULL; они не учитываются ни в min/max, ни в bloom-фильтрах
+    pub null_count: u64,
+    pub codec: Codec,
+    pub encoding: Encoding,
+    // None у колонки, собранной в памяти
+    pub(crate) path: Option<PathBuf>,
+    data_end: usize,
+    // Логическое число строк из заголовка; не зависит от сжатия
+    row_count: u64,
+    pub(crate) chunks: Vec<ChunkMeta<T>>,
+    pub(crate) dictionary: Vec<T>,
+    // Фильтр по всем значениям колонки; закрыт, чтобы он всегда соответствовал данным.
+    // Проверки — через might_contain и contains
+    pub(crate) bloom_filter: Bloom<T>,
+    // None — колонка записана без bloom-фильтров
+    pub(crate) bloom_fp_rate: Option<f64>,
+    pub(crate) bloom_capacity: u64,
+    // Регистры HyperLogLog нужны для дозаписи; оценка считается один раз при open
+    pub(crate) distinct: HyperLogLog,
+    distinct_count: u64,
+    // None — гистограмма при записи не строилась
+    pub(crate) histogram: Option<Histogram<T>>,
+    // Чанки, контрольная сумма которых уже сверена
+    verified_chunks: Vec<AtomicBool>,
+    // Перед чанками в файле записаны их кадры, см. ColumnWriter::enable_chunk_frames
+    pub(crate) chunk_frames: bool,
+    // Сколько фреймов было распаковано за время жизни колонки
+    frames_decoded: AtomicUsize,
+    // Режим доступа, заданный через advise
+    pub(crate) access_pattern: AtomicU8,
+}
+
+impl<T: ColumnType> Clone for Column<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            verify_checksums: self.verify_checksums,
+            write_ahead_log: self.write_ahead_log,
+            deleted: self.deleted.clone(),
+            cached_chunk: Mutex::new(None),
+            chunk_cache: self.chunk_cache.clone(),
+        }
+    }
+}
+
+impl<T: ColumnType> Deref for Column<T> {
+    type Target = ColumnInner<T>;
+
+    fn deref(&self) -> &ColumnInner<T> {
+        &self.inner
+    }
+}
+
+impl<T: ColumnType> Column<T> {
+    // Для тестов, портящих метаданные только что собранной колонки
+    #[cfg(test)]
+    pub(crate) fn inner_mut(&mut self) -> &mut ColumnInner<T> {
+        Arc::get_mut(&mut self.inner).expect("у колонки есть другие копии")
+    }
+}
+
+// Как static_assertions: сборка не пройдёт, если колонка перестанет быть Clone,
+// Send или Sync
+const _: fn() = || {
+    fn shareable<C: Clone + Send + Sync + 'static>() {}
+    shareable::<Column<i32>>();
+    shareable::<Column<i64>>();
+    shareable::<Column<f64>>();
+};
+
+// Статистика колонки для планирования запросов
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats<T: ColumnType = i32> {
+    pub row_count: u64,
+    pub null_count: u64,
+    // Оценка HyperLogLog по значениям, отличным от NULL
+    pub distinct_count: u64,
+    pub min: T,
+    pub max: T,
+    pub has_nan: bool,
+    pub is_sorted: bool,
+    histogram: Option<Histogram<T>>,
+}
+
+impl<T: ColumnType> ColumnStats<T> {
+    // Гистограмма значений, если она включена в ColumnBuilder::enable_histogram
+    pub fn histogram(&self) -> Option<&Histogram<T>> {
+        self.histogram.as_ref()
+    }
+}
+
+// Параметры общего bloom-фильтра колонки, выбранные при записи
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomParams {
+    // Целевая доля ложных срабатываний; с ней же строятся фильтры чанков
+    pub fp_rate: f64,
+    // Число различных значений, на которое рассчитан фильтр
+    pub capacity: u64,
+    pub bits: u64,
+    pub hashes: u32,
 }
 
-pub struct ColumnBuilder {
+// Колонки конкретных типов. Int32Column — прежний негенерический Column; алиас
+// нужен там, где тип нельзя вывести из использования, например Int32Column::open
+pub type Int32Column = Column<i32>;
+pub type Int64Column = Column<i64>;
+pub type Float64Column = Column<f64>;
+
+pub struct ColumnBuilder<T: ColumnType = i32> {
     name: String,
     data: Vec<u8>,
-    min: i32,
-    max: i32,
-    is_compressed: bool,
+    // Признак заполненности каждой строки; None — колонка без NULL
+    validity: Option<Vec<bool>>,
+    codec: Codec,
+    encoding: Encoding,
+    chunk_rows: usize,
+    hll_precision: u8,
+    bloom_fp_rate: Option<f64>,
+    histogram_buckets: Option<usize>,
+    chunk_frames: bool,
+    value_type: std::marker::PhantomData<T>,
+}
+
+// Целевая доля ложных срабатываний bloom-фильтров, если она не задана явно
+pub const DEFAULT_BLOOM_FP_RATE: f64 = 0.01;
+
+// Объём исходных значений i32 в одном фрейме; фреймы кодируются и сжимаются независимо.
+// Число строк в чанке одинаково для всех типов
+pub const COMPRESSION_CHUNK_SIZE: usize = 256 * 1024;
+pub const ROWS_PER_CHUNK: usize = COMPRESSION_CHUNK_SIZE / 4;
+
+impl ColumnBuilder<i32> {
+    pub fn from_i32(name: String, values: &[i32]) -> Self {
+        Self::from_values(name, values)
+    }
+}
+
+impl ColumnBuilder<i64> {
+    pub fn from_i64(name: String, values: &[i64]) -> Self {
+        Self::from_values(name, values)
+    }
 }
 
-impl ColumnBuilder {
-    pub fn new(name: String, data: Vec<u8>) -> Self {
-        let (min, max) = Self::compute_stats(&data);
-        Self { name, data, min, max, is_compressed: false }
+impl ColumnBuilder<f64> {
+    pub fn from_f64(name: String, values: &[f64]) -> Self {
+        Self::from_values(name, values)
+    }
+}
+
+impl<T: ColumnType> ColumnBuilder<T> {
+    // data — значения в little-endian, поэтому длина должна быть кратна ширине типа
+    pub fn new(name: String, data: Vec<u8>) -> Result<Self> {
+        if !data.len().is_multiple_of(T::WIDTH) {
+            return Err(invalid_input(format!(
+                "длина данных {} байт не кратна размеру значения {}",
+                data.len(), type_name(T::TAG)
+            )));
+        }
+        Ok(Self::with_data(name, data))
+    }
+
+    pub fn from_values(name: String, values: &[T]) -> Self {
+        let mut data = Vec::with_capacity(values.len() * T::WIDTH);
+        for value in values {
+            value.write_le(&mut data);
+        }
+        Self::with_data(name, data)
+    }
+
+    // None становится NULL: ячейка значения заполняется нулём, а строка отмечается
+    // в битовой карте валидности
+    pub fn from_nullable(name: String, values: &[Option<T>]) -> Self {
+        let mut data = Vec::with_capacity(values.len() * T::WIDTH);
+        for value in values {
+            value.unwrap_or(T::from_bits(0)).write_le(&mut data);
+        }
+        let mut builder = Self::with_data(name, data);
+        builder.validity = Some(values.iter().map(Option::is_some).collect());
+        builder
     }
 
-    pub fn compress(&mut self) -> std::io::Result<()> {
-        if !self.is_compressed {
-            self.data = zstd_compress(&*self.data, 3)?;
-            self.is_compressed = true;
+    fn with_data(name: String, data: Vec<u8>) -> Self {
+        Self {
+            name,
+            data,
+            validity: None,
+            codec: Codec::None,
+            encoding: Encoding::Plain,
+            chunk_rows: ROWS_PER_CHUNK,
+            hll_precision: DEFAULT_HLL_PRECISION,
+            bloom_fp_rate: Some(DEFAULT_BLOOM_FP_RATE),
+            histogram_buckets: None,
+            chunk_frames: false,
+            value_type: std::marker::PhantomData,
         }
+    }
+
+    // Потоковая запись без материализации всей колонки в памяти. Блокировка писателя
+    // держится до finish: другой писатель того же path ждёт
+    pub fn create(name: String, path: &Path) -> Result<ColumnWriter<T>> {
+        ColumnWriter::create(name, path)
+    }
+
+    // create без ожидания: если path пишет другой писатель — ошибка Locked
+    pub fn try_create(name: String, path: &Path) -> Result<ColumnWriter<T>> {
+        ColumnWriter::create_locked(name, path, WriteLock::try_acquire(path)?)
+    }
+
+    // Потоковая запись, результат которой появляется по path только после finish
+    pub fn create_atomic(name: String, path: &Path) -> Result<ColumnWriter<T>> {
+        ColumnWriter::create_atomic(name, path)
+    }
+
+    pub fn create_in_memory(name: String) -> Result<ColumnWriter<T>> {
+        ColumnWriter::create_in_memory(name)
+    }
+
+    pub fn compress(&mut self) -> Result<()> {
+        self.compress_with(Codec::zstd())
+    }
+
+    // 1 — быстрое сжатие для горячих колонок, 19 и выше — для архивных
+    pub fn compress_with_level(&mut self, level: i32) -> Result<()> {
+        self.compress_with(Codec::Zstd { level })
+    }
+
+    // Кодек применяется при build; повторный вызов заменяет ранее выбранный
+    pub fn compress_with(&mut self, codec: Codec) -> Result<()> {
+        codec.validate()?;
+        self.codec = codec;
         Ok(())
     }
 
-    pub fn build(self, path: &Path) -> std::io::Result<Column> {
-        std::fs::write(path, &self.data)?;
-        let file = File::open(path)?;
-        let mmap = unsafe { Mmap::map(&file)? };
-        
-        let mut bloom = Bloom::new_for_fp_rate(1000, 0.01);
-        for chunk in self.data.chunks_exact(4) {
-            let value = i32::from_le_bytes(chunk.try_into().unwrap());
-            bloom.set(&value);
+    // Кодирование значений выполняется до сжатия; статистика остаётся по исходным значениям
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    pub fn set_chunk_rows(&mut self, rows: usize) {
+        self.chunk_rows = rows;
+    }
+
+    // Кадры перед чанками для Column::open_recover, см. ColumnWriter::enable_chunk_frames
+    pub fn enable_chunk_frames(&mut self) {
+        self.chunk_frames = true;
+    }
+
+    // Точность оценки distinct_count от 4 до 16: каждая единица вдвое увеличивает
+    // число регистров в footer и уменьшает ошибку примерно в √2 раз
+    pub fn set_hll_precision(&mut self, precision: u8) -> Result<()> {
+        HyperLogLog::new(precision)?;
+        self.hll_precision = precision;
+        Ok(())
+    }
+
+    // Общий фильтр рассчитывается на число различных значений колонки, поэтому
+    // заданная доля ложных срабатываний сохраняется при любом её размере
+    pub fn set_bloom_fp_rate(&mut self, fp_rate: f64) -> Result<()> {
+        check_fp_rate(fp_rate)?;
+        self.bloom_fp_rate = Some(fp_rate);
+        Ok(())
+    }
+
+    // Без фильтров might_contain всегда true, а чанки отсекаются только по zone maps
+    pub fn disable_bloom_filter(&mut self) {
+        self.bloom_fp_rate = None;
+    }
+
+    // Равноглубинная гистограмма из buckets корзин (от 1 до 4096) в footer для оценки
+    // селективности диапазонов; NULL и NaN в неё не входят. Без вызова не строится
+    pub fn enable_histogram(&mut self, buckets: usize) -> Result<()> {
+        check_histogram_buckets(buckets)?;
+        self.histogram_buckets = Some(buckets);
+        Ok(())
+    }
+
+    pub fn build(self, path: &Path) -> Result<Column<T>> {
+        self.build_with(path, BuildOptions::default())
+    }
+
+    pub fn build_with(self, path: &Path, options: BuildOptions) -> Result<Column<T>> {
+        let writer = ColumnWriter::create(self.name.clone(), path)?;
+        self.write_into(writer, options)
+    }
+
+    // build без ожидания другого писателя path: занятая блокировка — ошибка Locked
+    pub fn try_build(self, path: &Path) -> Result<Column<T>> {
+        let writer = ColumnWriter::create_locked(self.name.clone(), path, WriteLock::try_acquire(path)?)?;
+        self.write_into(writer, BuildOptions::default())
+    }
+
+    // Как build, но файл пишется рядом во временный, сбрасывается на диск и
+    // переименовывается в path: прежнее содержимое path заменяется целиком или не
+    // меняется вовсе, а при ошибке временный файл удаляется
+    pub fn build_atomic(self, path: &Path) -> Result<Column<T>> {
+        self.build_atomic_with(path, BuildOptions::default())
+    }
+
+    pub fn build_atomic_with(self, path: &Path, options: BuildOptions) -> Result<Column<T>> {
+        let writer = ColumnWriter::create_atomic(self.name.clone(), path)?;
+        self.write_into(writer, options)
+    }
+
+    // Колонка в том же формате, что и файл, но в буфере памяти: для тестов и
+    // временных данных. Поддерживает все чтения и дозапись
+    pub fn build_in_memory(self) -> Result<Column<T>> {
+        let writer = ColumnWriter::create_in_memory(self.name.clone())?;
+        self.write_into(writer, BuildOptions::default())
+    }
+
+    fn write_into(self, mut writer: ColumnWriter<T>, options: BuildOptions) -> Result<Column<T>> {
+        let _span = span!(
+            "columnar::build",
+            "build",
+            column = self.name.as_str(),
+            rows = self.data.len() / T::WIDTH,
+            codec = self.codec.name()
+        );
+        writer.compress_with(self.codec)?;
+        writer.set_chunk_rows(self.chunk_rows)?;
+        writer.set_hll_precision(self.hll_precision)?;
+        if self.chunk_frames {
+            writer.enable_chunk_frames()?;
         }
-        
+        match self.bloom_fp_rate {
+            Some(fp_rate) => writer.set_bloom_fp_rate(fp_rate)?,
+            None => writer.disable_bloom_filter()?,
+        }
+        if let Some(buckets) = self.histogram_buckets {
+            writer.enable_histogram(buckets)?;
+        }
+        match self.encoding {
+            // Словарь строится только если число различных значений помещается в индекс u16,
+            // иначе колонка записывается без словарного кодирования
+            Encoding::Dictionary => {
+                if let Some(dictionary) = build_dictionary(&self.data) {
+                    writer.set_dictionary(dictionary);
+                }
+            }
+            encoding => writer.set_encoding(encoding)?,
+        }
+        writer.write_all_parallel(&self.data, self.validity.as_deref())?;
+        writer.finish_with(options)
+    }
+}
+
+impl<T: ColumnType> Column<T> {
+    // Открывает ранее записанный файл колонки, восстанавливая метаданные из footer.
+    // Тип значений в файле должен совпадать с T. Непустой журнал дозаписи сначала
+    // применяется к файлу
+    pub fn open(path: &Path) -> Result<Column<T>> {
+        replay_wal::<T>(path)?;
+        Self::open_mapped(path)
+    }
+
+    // open без журнала дозаписи
+    pub(crate) fn open_mapped(path: &Path) -> Result<Column<T>> {
+        Self::from_backing(Some(path.to_path_buf()), Backing::map(&File::open(path)?)?)
+    }
+
+    // path запоминается для дозаписи; None — колонка живёт только в памяти
+    pub(crate) fn from_backing(path: Option<PathBuf>, backing: Backing) -> Result<Column<T>> {
+        let header = Header::decode(&backing)?;
+        if header.type_tag != T::TAG {
+            return Err(corrupt(format!(
+                "колонка хранит значения {}, а открывается как {}",
+                type_name(header.type_tag), type_name(T::TAG)
+            )));
+        }
+        let (data_end, footer) = Footer::<T>::decode(&backing)?;
+        Self::from_parts(path, backing, header, data_end, footer)
+    }
+
+    // Колонка по уже разобранным заголовку и метаданным; тег типа сверил вызывающий
+    pub(crate) fn from_parts(
+        path: Option<PathBuf>,
+        backing: Backing,
+        header: Header,
+        data_end: usize,
+        footer: Footer<T>,
+    ) -> Result<Column<T>> {
+        let codec = Codec::from_parts(header.codec_tag, footer.compression_level)?;
+        let encoding = Encoding::from_tag(header.encoding_tag)?;
+        let rows_in_chunks = footer.chunks.last().map_or(0, ChunkMeta::end_row);
+        if rows_in_chunks != header.row_count {
+            return Err(corrupt("число строк в индексе чанков не совпадает с заголовком"));
+        }
+        if !is_framed(codec, encoding) && footer.chunks.iter().any(|c| c.compressed_len != c.uncompressed_len) {
+            return Err(corrupt("размер чанка несжатой колонки не совпадает с числом строк"));
+        }
+        if (encoding == Encoding::Dictionary) == footer.dictionary.is_empty() && header.row_count > 0 {
+            return Err(corrupt("словарь в метаданных не соответствует кодированию колонки"));
+        }
+        if footer.chunks.iter().map(|c| c.null_count).sum::<u64>() != footer.null_count {
+            return Err(corrupt("число NULL в индексе чанков не совпадает с метаданными"));
+        }
+
+        let deleted = match &path {
+            Some(path) => load_deletions(path, header.row_count)?,
+            None => None,
+        };
+        let inner = ColumnInner {
+            name: footer.name,
+            backing,
+            min: footer.min,
+            max: footer.max,
+            has_nan: footer.has_nan,
+            is_sorted: footer.is_sorted,
+            null_count: footer.null_count,
+            codec,
+            encoding,
+            bloom_filter: footer.bloom_filter,
+            bloom_fp_rate: footer.bloom_fp_rate,
+            bloom_capacity: footer.bloom_capacity,
+            path,
+            data_end,
+            row_count: header.row_count,
+            verified_chunks: footer.chunks.iter().map(|_| AtomicBool::new(false)).collect(),
+            chunks: footer.chunks,
+            dictionary: footer.dictionary,
+            distinct_count: footer.distinct.estimate(),
+            distinct: footer.distinct,
+            histogram: footer.histogram,
+            chunk_frames: header.flags & FLAG_CHUNK_FRAMES != 0,
+            frames_decoded: AtomicUsize::new(0),
+            access_pattern: AtomicU8::new(AccessPattern::Normal.tag()),
+        };
         Ok(Column {
-            name: self.name,
-            mmap: Arc::new(mmap),
+            inner: Arc::new(inner),
+            verify_checksums: true,
+            write_ahead_log: false,
+            deleted: deleted.map(Arc::new),
+            cached_chunk: Mutex::new(None),
+            chunk_cache: None,
+        })
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.codec.is_compressed()
+    }
+
+    pub fn len(&self) -> usize {
+        self.row_count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.row_count == 0
+    }
+
+    // Размер распакованных значений в байтах, записанный при сжатии: столько памяти
+    // займёт результат decompress_parallel или decompress_into
+    pub fn uncompressed_len(&self) -> u64 {
+        self.chunks.iter().map(|chunk| chunk.uncompressed_len).sum()
+    }
+
+    // Байты секции данных без заголовка и метаданных
+    pub fn data(&self) -> &[u8] {
+        &self.backing[HEADER_SIZE..self.data_end]
+    }
+
+    pub fn chunks(&self) -> &[ChunkMeta<T>] {
+        &self.chunks
+    }
+
+    pub fn stats(&self) -> ColumnStats<T> {
+        ColumnStats {
+            row_count: self.row_count,
+            null_count: self.null_count,
+            distinct_count: self.distinct_count,
             min: self.min,
             max: self.max,
-            is_compressed: self.is_compressed,
-            bloom_filter: bloom,
-        })
+            has_nan: self.has_nan,
+            is_sorted: self.is_sorted,
+            histogram: self.histogram.clone(),
+        }
+    }
+
+    // Отключает сверку контрольных сумм для критичных к производительности путей
+    pub fn set_verify_checksums(&mut self, enabled: bool) {
+        self.verify_checksums = enabled;
     }
 
-    fn compute_stats(data: &[u8]) -> (i32, i32) {
-        let mut min = i32::MAX;
-        let mut max = i32::MIN;
-        for chunk in data.chunks_exact(4) {
-            let value = i32::from_le_bytes(chunk.try_into().unwrap());
-            min = min.min(value);
-            max = max.max(value);
+    // Распакованные чанки сжатой или закодированной колонки хранятся в общем кэше под
+    // ключами (name, номер чанка), так что точечные чтения и узкие диапазоны держат в
+    // памяти только нужные чанки, а не всю колонку. get_value и get_values кладут
+    // чанк в кэш; чтение всей колонки берёт уже закэшированные чанки, но новых не
+    // добавляет, чтобы полный проход не вытеснил рабочий набор. Имя должно быть
+    // уникально среди колонок кэша; после compact под тем же именем старые чанки
+    // нужно убрать, см. invalidate_chunk_cache. Несжатой колонке кэш не нужен
+    pub fn set_chunk_cache(&mut self, cache: Arc<ChunkCache>, name: &str) {
+        self.chunk_cache = Some((cache, Arc::from(name)));
+    }
+
+    // Убирает чанки этой колонки из кэша чанков; возвращает их число
+    pub fn invalidate_chunk_cache(&self) -> usize {
+        match &self.chunk_cache {
+            Some((cache, name)) => cache.invalidate_where(|key| key.column == *name),
+            None => 0,
         }
-        (min, max)
     }
-}
 
-impl Column {
-    pub fn decompress_parallel(&self) -> std::io::Result<Vec<u8>> {
-        if !self.is_compressed {
-            return Ok(self.mmap[..].to_vec());
+    // Дописывает значения новыми чанками и переоткрывает файл. Байты уже записанных
+    // чанков не меняются, а файл только растёт, поэтому читатели со старым mmap
+    // продолжают видеть прежнее содержимое колонки
+    // Другой писатель колонки в файле — дождаться его
+    pub fn append(&mut self, values: &[T]) -> Result<()> {
+        let lock = self.path.as_deref().map(WriteLock::acquire).transpose()?;
+        self.append_locked(values, lock)
+    }
+
+    // append без ожидания: если колонку пишет другой писатель — ошибка Locked
+    pub fn try_append(&mut self, values: &[T]) -> Result<()> {
+        let lock = self.path.as_deref().map(WriteLock::try_acquire).transpose()?;
+        self.append_locked(values, lock)
+    }
+
+    fn append_locked(&mut self, values: &[T], lock: Option<WriteLock>) -> Result<()> {
+        match lock {
+            Some(lock) if self.write_ahead_log => self.append_logged(values, lock),
+            lock => self.append_with(values, BuildOptions::default(), lock),
         }
+    }
 
-        const CHUNK_SIZE: usize = 1024 * 1024;
-        let compressed_data = &self.mmap[..];
-        
-        if compressed_data.len() <= CHUNK_SIZE {
-            return zstd_decompress(compressed_data);
+    pub(crate) fn append_with(&mut self, values: &[T], options: BuildOptions, lock: Option<WriteLock>) -> Result<()> {
+        // Признак сортировки продолжается от последнего хранимого значения: удалённая
+        // строка остаётся в файле, и новые значения всё равно идут после неё
+        let last_value = if self.is_sorted && !self.is_empty() {
+            Some(self.stored_row(self.len() - 1)?)
+        } else {
+            None
+        };
+        let mut writer = ColumnWriter::append_to(self, last_value, lock)?;
+        writer.push_slice(values)?;
+        let mut column = writer.finish_with(options)?;
+        column.verify_checksums = self.verify_checksums;
+        column.write_ahead_log = self.write_ahead_log;
+        // Записанные чанки не изменились, поэтому их копии в кэше остаются верными
+        column.chunk_cache = self.chunk_cache.clone();
+        // Колонка в файле перечитывает удаления из .del, в памяти — переносит их
+        if column.path.is_none() {
+            column.deleted = self.deleted.as_ref().map(|deleted| Arc::new(deleted.embed(0, column.len())));
         }
+        *self = column;
+        Ok(())
+    }
 
-        let chunks: Vec<_> = compressed_data.chunks(CHUNK_SIZE).collect();
-        let decompressed_chunks: Vec<Vec<u8>> = chunks
-            .into_par_iter()
-            .map(|chunk| zstd_decompress(chunk).unwrap())
-            .collect();
-        
-        let mut result = Vec::with_capacity(compressed_data.len() * 2);
-        for chunk in decompressed_chunks {
-            result.extend(chunk);
+    // Все значения колонки в порядке строк; на месте NULL стоит значение-заполнитель
+    pub fn values(&self) -> Result<Vec<T>> {
+        Ok(self.decompress_parallel()?.chunks_exact(T::WIDTH).map(T::read_le).collect())
+    }
+
+    // Все значения колонки в порядке строк с NULL в виде None
+    pub fn nullable_values(&self) -> Result<Vec<Option<T>>> {
+        let mut result = Vec::with_capacity(self.len());
+        for idx in 0..self.chunks.len() {
+            let values = self.chunk_values(idx)?;
+            let validity = self.chunk_validity(idx)?;
+            for (row, value) in values.chunks_exact(T::WIDTH).enumerate() {
+                let valid = validity.as_ref().is_none_or(|bits| bit_is_set(bits, row));
+                result.push(valid.then(|| T::read_le(value)));
+            }
+        }
+        Ok(result)
+    }
+
+    // Распаковывает и декодирует всю колонку в исходные значения little-endian.
+    // Несжатая колонка без NULL не копируется: результат ссылается на её байты.
+    // На время прохода mmap переводится в режим Sequential
+    pub fn decompress_parallel(&self) -> Result<ColumnData> {
+        let _span = span!(
+            "columnar::decompress",
+            "decompress_parallel",
+            column = self.name.as_str(),
+            chunks = self.chunks.len(),
+            bytes = self.uncompressed_len()
+        );
+        self.with_access_pattern(AccessPattern::Sequential, || self.decompress_all())
+    }
+
+    fn decompress_all(&self) -> Result<ColumnData> {
+        // Без битовых карт и кадров значения несжатой колонки лежат в файле подряд
+        if !self.is_framed() && self.null_count == 0 && !self.chunk_frames && !self.backing.is_remote() {
+            (0..self.chunks.len()).into_par_iter().try_for_each(|idx| self.checked_chunk(idx).map(|_| ()))?;
+            return Ok(ColumnData::Slice { backing: self.backing.clone(), range: HEADER_SIZE..self.data_end });
+        }
+
+        let mut result = Vec::new();
+        self.fill(&mut result)?;
+        Ok(ColumnData::Owned(result))
+    }
+
+    // То же, что decompress_parallel, но в буфер вызывающего: buf очищается и заполняется
+    // значениями колонки. Ёмкость растёт только если буфер меньше колонки, поэтому в цикле
+    // по колонкам память выделяется один раз. Возвращает число записанных байт
+    pub fn decompress_into(&self, buf: &mut Vec<u8>) -> Result<usize> {
+        self.with_access_pattern(AccessPattern::Sequential, || self.fill(buf))
+    }
+
+    fn fill(&self, buf: &mut Vec<u8>) -> Result<usize> {
+        let total = self.uncompressed_len() as usize;
+        buf.clear();
+        buf.resize(total, 0);
+        // Каждый фрейм — самостоятельный поток кодека, поэтому режем строго по записанным
+        // границам, и каждый чанк распаковывается сразу в свою часть буфера
+        let mut parts = Vec::with_capacity(self.chunks.len());
+        let mut rest = buf.as_mut_slice();
+        for chunk in &self.chunks {
+            let (part, tail) = rest.split_at_mut(chunk.uncompressed_len as usize);
+            parts.push(part);
+            rest = tail;
+        }
+        parts.into_par_iter().enumerate().try_for_each(|(idx, part)| {
+            let values = self.chunk_bytes(idx, false)?;
+            if values.len() != part.len() {
+                return Err(corrupt(format!("чанк {} распаковался не в заявленную длину", idx)));
+            }
+            part.copy_from_slice(&values);
+            Ok(())
+        })?;
+        Ok(total)
+    }
+
+    // Распаковывает ровно один чанк
+    pub fn read_chunk(&self, idx: usize) -> Result<Vec<u8>> {
+        if idx >= self.chunks.len() {
+            return Err(invalid_input(format!("чанк {} вне диапазона 0..{}", idx, self.chunks.len())));
+        }
+        Ok(self.chunk_values(idx)?.into_owned())
+    }
+
+    // Значения строк range за один проход; распаковываются только пересекающиеся чанки.
+    // Диапазон за пределами колонки — ошибка InvalidInput, а не молчаливая обрезка.
+    // На месте NULL стоит значение-заполнитель, как в values
+    pub fn get_values(&self, range: Range<usize>) -> Result<Vec<T>> {
+        if range.start > range.end || range.end > self.len() {
+            return Err(invalid_input(format!("диапазон строк {:?} вне колонки из {} строк", range, self.len())));
+        }
+        let mut result = Vec::with_capacity(range.len());
+        if range.is_empty() {
+            return Ok(result);
+        }
+        let first = self.chunk_for_row(range.start as u64).unwrap();
+        for idx in first..self.chunks.len() {
+            let chunk = &self.chunks[idx];
+            if chunk.first_row >= range.end as u64 {
+                break;
+            }
+            let values = self.chunk_bytes(idx, true)?;
+            let from = range.start.saturating_sub(chunk.first_row as usize);
+            let to = (range.end - chunk.first_row as usize).min(chunk.row_count() as usize);
+            result.extend(values[from * T::WIDTH..to * T::WIDTH].chunks_exact(T::WIDTH).map(T::read_le));
         }
-        
         Ok(result)
     }
 
-    pub fn get_value(&self, idx: usize) -> Option<i32> {
-        let offset = idx * 4;
-        if offset + 4 > self.mmap.len() {
-            return None;
+    // Хранимые байты чанка: фрейм кодека или срез исходных значений, за которыми
+    // следует битовая карта валидности, если в чанке есть NULL. У колонки из внешнего
+    // источника — один запрос диапазона чанка
+    fn stored_chunk(&self, idx: usize) -> Result<Cow<'_, [u8]>> {
+        let chunk = &self.chunks[idx];
+        self.backing.read(chunk.offset as usize..chunk.stored_end() as usize)
+    }
+
+    pub(crate) fn checked_chunk(&self, idx: usize) -> Result<Cow<'_, [u8]>> {
+        let bytes = self.stored_chunk(idx)?;
+        if self.verify_checksums && !self.verified_chunks[idx].load(Ordering::Relaxed) {
+            let expected = self.chunks[idx].checksum;
+            let actual = crc32fast::hash(&bytes);
+            if actual != expected {
+                return Err(corrupt(format!(
+                    "контрольная сумма чанка {} не совпадает: ожидалась {:#010x}, получена {:#010x}",
+                    idx, expected, actual
+                )));
+            }
+            self.verified_chunks[idx].store(true, Ordering::Relaxed);
+        }
+        Ok(bytes)
+    }
+
+    // Хранимые значения чанка без битовой карты
+    fn checked_values(&self, idx: usize) -> Result<Cow<'_, [u8]>> {
+        Ok(sub_range(self.checked_chunk(idx)?, 0..self.chunks[idx].compressed_len as usize))
+    }
+
+    fn decode_chunk(&self, idx: usize) -> Result<Vec<u8>> {
+        let frame = self.checked_values(idx)?;
+        self.frames_decoded.fetch_add(1, Ordering::Relaxed);
+        let chunk = &self.chunks[idx];
+        let encoded = self.codec.decompress_frame(&frame, chunk.encoded_len as usize)?;
+        let values = self.encoding.decode(&encoded, &self.dictionary)?;
+        if values.len() as u64 != chunk.uncompressed_len {
+            return Err(corrupt(format!("чанк {} распакован в неверное число байт", idx)));
+        }
+        Ok(values)
+    }
+
+    // Значения одного чанка; для колонки без фреймов — срез mmap без копирования
+    pub(crate) fn chunk_values(&self, idx: usize) -> Result<Cow<'_, [u8]>> {
+        if !self.is_framed() {
+            return self.checked_values(idx);
+        }
+        Ok(Cow::Owned(self.decode_chunk(idx)?))
+    }
+
+    // Значения чанка с учётом кэша чанков: у колонки во фреймах с заданным кэшем
+    // распакованный чанк берётся из него, а при insert ещё и кладётся туда
+    fn chunk_bytes(&self, idx: usize, insert: bool) -> Result<ChunkBytes<'_>> {
+        if let (Some((cache, name)), true) = (&self.chunk_cache, self.is_framed()) {
+            let key = ChunkKey { column: name.clone(), chunk: idx };
+            if insert {
+                return cache.get_or_insert_with(key, || self.decode_chunk(idx).map(Arc::new)).map(ChunkBytes::Shared);
+            }
+            if let Some(values) = cache.get(&key) {
+                return Ok(ChunkBytes::Shared(values));
+            }
+        }
+        Ok(ChunkBytes::Stored(self.chunk_values(idx)?))
+    }
+
+    // Битовая карта валидности чанка, бит 1 — строка заполнена; None, если NULL в чанке нет
+    pub(crate) fn chunk_validity(&self, idx: usize) -> Result<Option<Cow<'_, [u8]>>> {
+        let chunk = &self.chunks[idx];
+        if chunk.validity_len == 0 {
+            return Ok(None);
+        }
+        let stored = self.checked_chunk(idx)?;
+        let stored = sub_range(stored, chunk.compressed_len as usize..chunk.stored_end() as usize - chunk.offset as usize);
+        let bits = if self.is_compressed() {
+            Cow::Owned(self.codec.decompress_frame(&stored, chunk.row_count().div_ceil(8) as usize)?)
+        } else {
+            stored
+        };
+        if bits.len() as u64 != chunk.row_count().div_ceil(8) {
+            return Err(corrupt(format!("битовая карта чанка {} не соответствует числу строк", idx)));
+        }
+        Ok(Some(bits))
+    }
+
+    // Чанк, содержащий строку row
+    pub(crate) fn chunk_for_row(&self, row: u64) -> Option<usize> {
+        let idx = self.chunks.partition_point(|c| c.end_row() <= row);
+        (idx < self.chunks.len()).then_some(idx)
+    }
+
+    pub fn frames_decoded(&self) -> usize {
+        self.frames_decoded.load(Ordering::Relaxed)
+    }
+
+    // Индексы чанков, диапазон значений которых пересекается с [lo, hi].
+    // Чанки из одних NULL не содержат значений и не подходят ни под какой диапазон
+    pub fn chunks_matching_range(&self, lo: T, hi: T) -> Vec<usize> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.min <= hi && c.max >= lo && c.null_count < c.row_count())
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    // Индексы чанков, в которых value может встречаться: общий фильтр как быстрая
+    // предпроверка, затем zone map и bloom-фильтр каждого чанка
+    pub fn chunks_possibly_containing(&self, value: T) -> Vec<usize> {
+        if !self.might_contain(value) {
+            return Vec::new();
+        }
+        self.chunks_matching_range(value, value)
+            .into_iter()
+            .filter(|idx| self.chunk_might_contain(*idx, &value))
+            .collect()
+    }
+
+    // Проверка по bloom-фильтру одного чанка; без фильтров всегда true
+    pub(crate) fn chunk_might_contain(&self, idx: usize, value: &T) -> bool {
+        self.bloom_fp_rate.is_none() || T::bloom_check(&self.chunks[idx].bloom, value)
+    }
+
+    // Номера строк со значениями из [lo, hi]; чанки вне диапазона не распаковываются
+    pub fn scan_range(&self, lo: T, hi: T) -> Result<Vec<usize>> {
+        let candidates = if lo == hi {
+            self.chunks_possibly_containing(lo)
+        } else {
+            self.chunks_matching_range(lo, hi)
+        };
+        let mut rows = Vec::new();
+        for idx in candidates {
+            let values = self.chunk_values(idx)?;
+            let validity = self.chunk_validity(idx)?;
+            let first_row = self.chunks[idx].first_row as usize;
+            for (i, value) in values.chunks_exact(T::WIDTH).map(T::read_le).enumerate() {
+                if (lo..=hi).contains(&value)
+                    && validity.as_ref().is_none_or(|bits| bit_is_set(bits, i))
+                    && !self.is_deleted(first_row + i)
+                {
+                    rows.push(first_row + i);
+                }
+            }
+        }
+        Ok(rows)
+    }
+
+    // Номер первой живой строки со значением value. Бинарный поиск сначала по zone maps
+    // чанков, затем внутри одного чанка; на неотсортированной колонке — ошибка
+    // InvalidInput, а не молчаливый полный просмотр. Удалённые совпадения пропускаются
+    pub fn find(&self, value: T) -> Result<Option<usize>> {
+        let (row, found) = self.partition_rows(|v| v.total_cmp(&value).is_lt())?;
+        if !found.is_some_and(|v| v.total_cmp(&value).is_eq()) {
+            return Ok(None);
+        }
+        if !self.is_deleted(row) {
+            return Ok(Some(row));
+        }
+        // Равные значения идут подряд до первого большего
+        let (end, _) = self.partition_rows(|v| v.total_cmp(&value).is_le())?;
+        Ok((row..end).find(|row| !self.is_deleted(*row)))
+    }
+
+    // Строки со значениями из [lo, hi] на отсортированной колонке; пустой диапазон при lo > hi.
+    // Диапазон физический: удалённые строки внутри него остаются, их отсеивает is_deleted
+    pub fn range_indices(&self, lo: T, hi: T) -> Result<Range<usize>> {
+        let (start, _) = self.partition_rows(|v| v.total_cmp(&lo).is_lt())?;
+        if lo.total_cmp(&hi).is_gt() {
+            return Ok(start..start);
+        }
+        let (end, _) = self.partition_rows(|v| v.total_cmp(&hi).is_le())?;
+        Ok(start..end)
+    }
+
+    // Первая строка, для значения которой pred ложен, и её значение; pred истинен
+    // на префиксе колонки. Распаковывается не больше одного чанка
+    fn partition_rows(&self, pred: impl Fn(&T) -> bool) -> Result<(usize, Option<T>)> {
+        if !self.is_sorted {
+            return Err(invalid_input("бинарный поиск возможен только по отсортированной колонке"));
+        }
+        // Чанк целиком лежит в префиксе, если в нём лежит его максимум
+        let idx = self.chunks.partition_point(|c| pred(&c.max));
+        let Some(chunk) = self.chunks.get(idx) else {
+            return Ok((self.len(), None));
+        };
+        let values = self.chunk_values(idx)?;
+        let read = |row: usize| T::read_le(&values[row * T::WIDTH..(row + 1) * T::WIDTH]);
+        // Граница внутри чанка: его максимум уже не удовлетворяет pred
+        let (mut lo, mut hi) = (0, values.len() / T::WIDTH - 1);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if pred(&read(mid)) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        Ok((chunk.first_row as usize + lo, Some(read(lo))))
+    }
+
+    // Проверка по bloom-фильтру: false означает, что значения в колонке точно нет.
+    // Для типов без фильтра (f64) и колонок с отключённым фильтром всегда true
+    pub fn might_contain(&self, value: T) -> bool {
+        self.bloom_fp_rate.is_none() || T::bloom_check(&self.bloom_filter, &value)
+    }
+
+    // Параметры общего фильтра; None, если колонка записана без bloom-фильтров
+    pub fn bloom_params(&self) -> Option<BloomParams> {
+        self.bloom_fp_rate.map(|fp_rate| BloomParams {
+            fp_rate,
+            capacity: self.bloom_capacity,
+            bits: self.bloom_filter.number_of_bits(),
+            hashes: self.bloom_filter.number_of_hash_functions(),
+        })
+    }
+
+    // Точная проверка: min/max, затем bloom-фильтры колонки и чанков, и только после
+    // этого просмотр уцелевших чанков до первого совпадения. NULL не совпадает ни с чем
+    pub fn contains(&self, value: T) -> Result<bool> {
+        // NaN не входит в zone maps, зато его наличие записано отдельно
+        if value.is_nan() {
+            return Ok(self.has_nan);
+        }
+        if value < self.min || value > self.max {
+            return Ok(false);
+        }
+        for idx in self.chunks_possibly_containing(value) {
+            let values = self.chunk_values(idx)?;
+            let validity = self.chunk_validity(idx)?;
+            let first_row = self.chunks[idx].first_row as usize;
+            let found = values
+                .chunks_exact(T::WIDTH)
+                .enumerate()
+                .any(|(row, raw)| {
+                    T::read_le(raw) == value
+                        && validity.as_ref().is_none_or(|bits| bit_is_set(bits, row))
+                        && !self.is_deleted(first_row + row)
+                });
+            if found {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    // Точечное чтение; для сжатой колонки распаковывается только содержащий строку чанк.
+    // None и для строки вне колонки, и для NULL — различает их get_nullable
+    pub fn get_value(&self, idx: usize) -> Option<T> {
+        self.try_get_value(idx).ok().flatten()
+    }
+
+    // То же, что get_value, но ошибка распаковки возвращается вызывающему
+    pub fn try_get_value(&self, idx: usize) -> Result<Option<T>> {
+        Ok(self.try_get_nullable(idx)?.flatten())
+    }
+
+    // None — строки нет в колонке или она удалена, Some(None) — строка равна NULL
+    pub fn get_nullable(&self, idx: usize) -> Option<Option<T>> {
+        self.try_get_nullable(idx).ok().flatten()
+    }
+
+    pub fn try_get_nullable(&self, idx: usize) -> Result<Option<Option<T>>> {
+        if idx >= self.len() || self.is_deleted(idx) {
+            return Ok(None);
+        }
+        self.stored_row(idx).map(Some)
+    }
+
+    // Хранимое значение строки idx без учёта удалений; None — NULL
+    fn stored_row(&self, idx: usize) -> Result<Option<T>> {
+        let chunk_idx = self
+            .chunk_for_row(idx as u64)
+            .ok_or_else(|| invalid_input(format!("строка {} вне колонки из {} строк", idx, self.len())))?;
+        let row = idx - self.chunks[chunk_idx].first_row as usize;
+        if let Some(bits) = self.chunk_validity(chunk_idx)? {
+            if !bit_is_set(&bits, row) {
+                return Ok(None);
+            }
+        }
+        let offset = row * T::WIDTH;
+        let read = |values: &[u8]| T::read_le(&values[offset..offset + T::WIDTH]);
+
+        if !self.is_framed() {
+            return Ok(Some(read(&self.checked_values(chunk_idx)?)));
+        }
+        if self.chunk_cache.is_some() {
+            return Ok(Some(read(&self.chunk_bytes(chunk_idx, true)?)));
+        }
+        // В кэше лежит целиком записанная пара, поэтому паника другого потока его не портит
+        let mut cached = self.cached_chunk.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((cached_idx, values)) = cached.as_ref() {
+            if *cached_idx == chunk_idx {
+                return Ok(Some(read(values)));
+            }
+        }
+        let values = Arc::new(self.decode_chunk(chunk_idx)?);
+        let value = read(&values);
+        *cached = Some((chunk_idx, values));
+        Ok(Some(value))
+    }
+
+    fn is_framed(&self) -> bool {
+        is_framed(self.codec, self.encoding)
+    }
+}
+
+// Значения чанка: из файла или распакованные, либо общие с кэшем чанков
+enum ChunkBytes<'a> {
+    Stored(Cow<'a, [u8]>),
+    Shared(Arc<Vec<u8>>),
+}
+
+impl Deref for ChunkBytes<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            ChunkBytes::Stored(bytes) => bytes,
+            ChunkBytes::Shared(bytes) => bytes,
+        }
+    }
+}
+
+// Часть байтов чанка: срез заимствованных или обрезка прочитанных у источника
+fn sub_range(bytes: Cow<'_, [u8]>, range: Range<usize>) -> Cow<'_, [u8]> {
+    match bytes {
+        Cow::Borrowed(bytes) => Cow::Borrowed(&bytes[range]),
+        Cow::Owned(mut bytes) => {
+            bytes.truncate(range.end);
+            bytes.drain(..range.start);
+            Cow::Owned(bytes)
         }
-        Some(i32::from_le_bytes(
-            self.mmap[offset..offset+4].try_into().unwrap()
-        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codec::DEFAULT_ZSTD_LEVEL;
+    use crate::error::ColumnarError;
+    use crate::{Agg, Predicate};
     use tempfile::NamedTempFile;
 
+    #[cfg(feature = "zstd")]
     #[test]
     fn test_column_creation() {
         // Подготовка тестовых данных
-        let test_data = vec![10i32, 20, 30];
-        let bytes: Vec<u8> = test_data.iter()
-            .flat_map(|x| x.to_le_bytes())
-            .collect();
+        let test_data = [10i32, 20, 30];
 
         // Создание временного файла
         let tmp_file = NamedTempFile::new().unwrap();
         
         // Тестирование билдера
-        let mut builder = ColumnBuilder::new("test_col".to_string(), bytes.clone());
-        assert!(!builder.is_compressed);
+        let mut builder = ColumnBuilder::from_i32("test_col".to_string(), &test_data);
+        assert_eq!(builder.codec, Codec::None);
         
         // Тестирование сжатия
         builder.compress().unwrap();
-        assert!(builder.is_compressed);
+        assert_eq!(builder.codec, Codec::Zstd { level: DEFAULT_ZSTD_LEVEL });
 
         // Тестирование финализации
         let column = builder.build(tmp_file.path()).unwrap();
@@ -140,10 +1079,9 @@ mod tests {
 
     #[test]
     fn test_value_access() {
-        let data = vec![100i32, 200, 300];
-        let bytes: Vec<u8> = data.iter().flat_map(|x| x.to_le_bytes()).collect();
-        
-        let column = ColumnBuilder::new("test".to_string(), bytes)
+        let data = [100i32, 200, 300];
+
+        let column = ColumnBuilder::from_i32("test".to_string(), &data)
             .build(NamedTempFile::new().unwrap().path())
             .unwrap();
             
@@ -151,4 +1089,1450 @@ mod tests {
         assert_eq!(column.get_value(2), Some(300));
         assert_eq!(column.get_value(3), None); // Проверка выхода за границы
     }
-}
\ No newline at end of file
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_open_roundtrip() {
+        let data = [7i32, -3, 42, 1000];
+        let tmp_file = NamedTempFile::new().unwrap();
+
+        let column = ColumnBuilder::from_i32("plain".to_string(), &data)
+            .build(tmp_file.path())
+            .unwrap();
+        drop(column);
+
+        let reopened = Column::open(tmp_file.path()).unwrap();
+        assert_eq!(reopened.name, "plain");
+        assert_eq!(reopened.min, -3);
+        assert_eq!(reopened.max, 1000);
+        assert!(!reopened.is_compressed());
+        for (i, v) in data.iter().enumerate() {
+            assert_eq!(reopened.get_value(i), Some(*v));
+        }
+        assert_eq!(reopened.get_value(data.len()), None);
+
+        // Сжатая колонка: метаданные и содержимое восстанавливаются без изменений
+        let mut builder = ColumnBuilder::from_i32("packed".to_string(), &data);
+        builder.compress().unwrap();
+        let compressed_file = NamedTempFile::new().unwrap();
+        let built = builder.build(compressed_file.path()).unwrap();
+        let built_data = built.data().to_vec();
+        drop(built);
+
+        let reopened = Column::<i32>::open(compressed_file.path()).unwrap();
+        assert!(reopened.is_compressed());
+        assert_eq!(reopened.min, -3);
+        assert_eq!(reopened.max, 1000);
+        assert_eq!(reopened.data(), &built_data[..]);
+        assert_eq!(reopened.values().unwrap(), data);
+    }
+
+    #[test]
+    fn test_raw_bytes_constructor_validates_length() {
+        for len in [1, 5, 4 * 1000 + 1, 7] {
+            let err = ColumnBuilder::<i32>::new("raw".to_string(), vec![1; len]).err().unwrap();
+            assert!(matches!(err, ColumnarError::InvalidInput(_)), "{}: {:?}", len, err);
+        }
+        let err = ColumnBuilder::<i64>::new("raw".to_string(), vec![1; 12]).err().unwrap();
+        assert!(err.to_string().contains("i64"), "{}", err);
+
+        let empty = ColumnBuilder::<i32>::new("raw".to_string(), Vec::new())
+            .unwrap()
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert!(empty.is_empty());
+        let column = ColumnBuilder::<i32>::new("raw".to_string(), vec![1, 0, 0, 0, 2, 0, 0, 0])
+            .unwrap()
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert_eq!(column.values().unwrap(), vec![1, 2]);
+    }
+
+    // Принимает не больше budget байт, затем отказывает, как переполненный диск
+    struct FailingSink {
+        file: File,
+        budget: usize,
+    }
+
+    impl std::io::Write for FailingSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if buf.len() > self.budget {
+                return Err(std::io::Error::new(std::io::ErrorKind::StorageFull, "нет места"));
+            }
+            self.budget -= buf.len();
+            self.file.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.file.flush()
+        }
+    }
+
+    impl std::io::Seek for FailingSink {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.file.seek(pos)
+        }
+    }
+
+    impl crate::writer::Sink for FailingSink {
+        fn contents(&self) -> Result<Backing> {
+            self.file.contents()
+        }
+
+        fn sync(&self) -> Result<()> {
+            self.file.sync()
+        }
+
+        fn into_backing(self: Box<Self>) -> Result<Backing> {
+            Box::new(self.file).into_backing()
+        }
+    }
+
+    fn dir_entries(dir: &Path) -> Vec<std::ffi::OsString> {
+        let mut names: Vec<_> = std::fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_atomic_build_replaces_file_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("col.bin");
+        let old = ColumnBuilder::from_i32("old".to_string(), &[1, 2, 3]).build(&path).unwrap();
+
+        let values: Vec<i32> = (0..100_000).collect();
+        let mut builder = ColumnBuilder::from_i32("new".to_string(), &values);
+        builder.compress_with(Codec::Lz4).unwrap();
+        let new = builder.build_atomic(&path).unwrap();
+        assert_eq!(new.values().unwrap(), values);
+        assert_eq!(Column::<i32>::open(&path).unwrap().name, "new");
+        // Старый mmap указывает на заменённый файл и видит прежнее содержимое
+        assert_eq!(old.values().unwrap(), vec![1, 2, 3]);
+        assert_eq!(dir_entries(dir.path()), vec!["col.bin", "col.bin.lock"]);
+
+        // Потоковая запись с теми же гарантиями
+        let mut writer = ColumnBuilder::create_atomic("stream".to_string(), &path).unwrap();
+        writer.push_slice(&[7, 8]).unwrap();
+        assert_eq!(Column::<i32>::open(&path).unwrap().name, "new");
+        assert_eq!(writer.finish().unwrap().values().unwrap(), vec![7, 8]);
+        assert_eq!(dir_entries(dir.path()), vec!["col.bin", "col.bin.lock"]);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_synced_build_and_streaming_finish() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = BuildOptions { sync: true };
+        assert_eq!(BuildOptions::default(), BuildOptions { sync: false });
+
+        let values: Vec<i64> = (0..50_000).map(|i| i * 3).collect();
+        let mut builder = ColumnBuilder::from_i64("synced".to_string(), &values);
+        builder.compress().unwrap();
+        let built = builder.build_with(&dir.path().join("a.bin"), options).unwrap();
+        assert_eq!(built.values().unwrap(), values);
+        let atomic = ColumnBuilder::from_i64("atomic".to_string(), &values)
+            .build_atomic_with(&dir.path().join("b.bin"), options)
+            .unwrap();
+        assert_eq!(atomic.stats(), built.stats());
+
+        let mut writer = ColumnBuilder::<i64>::create("stream".to_string(), &dir.path().join("c.bin")).unwrap();
+        writer.push_slice(&values).unwrap();
+        let streamed = writer.finish_with(options).unwrap();
+        assert_eq!(Column::<i64>::open(&dir.path().join("c.bin")).unwrap().values().unwrap(), values);
+        assert_eq!(streamed.len(), values.len());
+    }
+
+    #[test]
+    fn test_atomic_build_cleans_up_after_failed_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("col.bin");
+        ColumnBuilder::from_i32("old".to_string(), &[1, 2, 3]).build(&path).unwrap();
+
+        let values: Vec<i32> = (0..200_000).collect();
+        for budget in [0, 100, 500_000] {
+            let writer = ColumnWriter::create_atomic_with("new".to_string(), &path, |file| {
+                Box::new(FailingSink { file, budget })
+            });
+            let err = match writer {
+                Ok(writer) => ColumnBuilder::from_i32("new".to_string(), &values)
+                    .write_into(writer, BuildOptions::default())
+                    .unwrap_err(),
+                Err(err) => err,
+            };
+            assert!(matches!(&err, ColumnarError::Io(e) if e.kind() == std::io::ErrorKind::StorageFull), "{:?}", err);
+            // Временный файл удалён, прежняя колонка не тронута
+            assert_eq!(dir_entries(dir.path()), vec!["col.bin", "col.bin.lock"], "budget {}", budget);
+            assert_eq!(Column::<i32>::open(&path).unwrap().values().unwrap(), vec![1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn test_open_rejects_data_not_matching_row_count() {
+        let tmp_file = NamedTempFile::new().unwrap();
+        ColumnBuilder::from_i32("rows".to_string(), &[1, 2, 3]).build(tmp_file.path()).unwrap();
+        let raw = std::fs::read(tmp_file.path()).unwrap();
+        let data_end = HEADER_SIZE + 3 * 4;
+
+        // Лишний байт после значений: секция данных больше, чем покрывают чанки
+        let mut dangling = raw.clone();
+        dangling.insert(data_end, 0xaa);
+        std::fs::write(tmp_file.path(), &dangling).unwrap();
+        let err = Column::<i32>::open(tmp_file.path()).unwrap_err();
+        assert!(matches!(err, ColumnarError::Corrupt { .. }), "{:?}", err);
+
+        // Число строк в заголовке не совпадает с чанками
+        let mut wrong_rows = raw.clone();
+        wrong_rows[16..24].copy_from_slice(&4u64.to_le_bytes());
+        std::fs::write(tmp_file.path(), &wrong_rows).unwrap();
+        let err = Column::<i32>::open(tmp_file.path()).unwrap_err();
+        assert!(matches!(err, ColumnarError::Corrupt { .. }), "{:?}", err);
+        assert!(err.to_string().contains("число строк"), "{}", err);
+
+        std::fs::write(tmp_file.path(), &raw).unwrap();
+        assert_eq!(Column::<i32>::open(tmp_file.path()).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_open_rejects_raw_file() {
+        let tmp_file = NamedTempFile::new().unwrap();
+        std::fs::write(tmp_file.path(), [1u8, 0, 0, 0, 2, 0, 0, 0]).unwrap();
+
+        let err = Column::<i32>::open(tmp_file.path()).unwrap_err();
+        assert!(matches!(err, ColumnarError::Corrupt { .. }), "{:?}", err);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_error_variants_for_truncated_file_and_bad_zstd_stream() {
+        let values: Vec<i32> = (0..10_000).collect();
+        let tmp_file = NamedTempFile::new().unwrap();
+        let mut builder = ColumnBuilder::from_i32("errors".to_string(), &values);
+        builder.compress().unwrap();
+        let offset = builder.build(tmp_file.path()).unwrap().chunks()[0].offset as usize;
+        let raw = std::fs::read(tmp_file.path()).unwrap();
+
+        // Обрезанный хвост: footer не найден
+        std::fs::write(tmp_file.path(), &raw[..raw.len() - 3]).unwrap();
+        let err = Column::<i32>::open(tmp_file.path()).unwrap_err();
+        assert!(matches!(err, ColumnarError::Corrupt { .. }), "{:?}", err);
+        let missing = Column::<i32>::open(&tmp_file.path().with_extension("missing")).unwrap_err();
+        assert!(matches!(missing, ColumnarError::Io(_)), "{:?}", missing);
+
+        // Испорченная сигнатура zstd-фрейма; сверка контрольных сумм отключена,
+        // иначе повреждение нашлось бы раньше распаковки
+        let mut broken = raw.clone();
+        broken[offset..offset + 4].copy_from_slice(&[0; 4]);
+        std::fs::write(tmp_file.path(), &broken).unwrap();
+        let mut column = Column::<i32>::open(tmp_file.path()).unwrap();
+        assert!(matches!(column.read_chunk(0), Err(ColumnarError::Corrupt { .. })));
+        column.set_verify_checksums(false);
+        let err = column.read_chunk(0).unwrap_err();
+        assert!(matches!(err, ColumnarError::Decompression { .. }), "{:?}", err);
+        assert_eq!(std::io::Error::from(err).kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_point_lookup_on_compressed_column() {
+        let values: Vec<i32> = (0..2000).map(|i| i * 3 - 1000).collect();
+
+        let plain = ColumnBuilder::from_i32("plain".to_string(), &values)
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        let mut builder = ColumnBuilder::from_i32("packed".to_string(), &values);
+        builder.compress().unwrap();
+        let packed = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+
+        for idx in [0, 1, 999, 1999, 2000, 5000] {
+            assert_eq!(packed.get_value(idx), plain.get_value(idx), "индекс {}", idx);
+        }
+        assert_eq!(packed.try_get_value(1999).unwrap(), Some(values[1999]));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_multi_frame_compression_roundtrip() {
+        // ~6 МиБ псевдослучайных значений — больше десятка фреймов
+        let mut state = 12345u32;
+        let values: Vec<i32> = (0..1_500_000)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 8) as i32 % 1000
+            })
+            .collect();
+
+        let mut builder = ColumnBuilder::from_i32("big".to_string(), &values);
+        builder.compress().unwrap();
+        let tmp_file = NamedTempFile::new().unwrap();
+        let column = builder.build(tmp_file.path()).unwrap();
+        assert!(column.chunks().len() > 10);
+        assert_eq!(column.values().unwrap(), values);
+        drop(column);
+
+        let reopened = Column::<i32>::open(tmp_file.path()).unwrap();
+        assert_eq!(reopened.values().unwrap(), values);
+    }
+
+    #[test]
+    fn test_uncompressed_values_are_not_copied() {
+        let values: Vec<i32> = (0..50_000).collect();
+        let tmp_file = NamedTempFile::new().unwrap();
+        let file = ColumnBuilder::from_i32("plain".to_string(), &values).build(tmp_file.path()).unwrap();
+        let memory = ColumnBuilder::from_i32("plain".to_string(), &values).build_in_memory().unwrap();
+        for column in [&file, &memory] {
+            let data = column.decompress_parallel().unwrap();
+            assert!(data.is_zero_copy());
+            // Срез указывает прямо в отображение, а не в копию
+            assert_eq!(data.as_ptr_range(), column.data().as_ptr_range());
+            assert_eq!(column.values().unwrap(), values);
+        }
+
+        let mut builder = ColumnBuilder::from_i32("lz4".to_string(), &values);
+        builder.compress_with(Codec::Lz4).unwrap();
+        let compressed = builder.build_in_memory().unwrap();
+        let nullable = ColumnBuilder::from_nullable("nulls".to_string(), &[Some(1), None, Some(3)])
+            .build_in_memory()
+            .unwrap();
+        let decompressed = compressed.decompress_parallel().unwrap();
+        assert!(!decompressed.is_zero_copy());
+        assert!(!nullable.decompress_parallel().unwrap().is_zero_copy());
+        assert_eq!(decompressed, file.decompress_parallel().unwrap());
+        assert_eq!(decompressed.into_vec(), file.decompress_parallel().unwrap().into_vec());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_decompress_into_reuses_one_buffer() {
+        let make = |rows: i32, codec: Codec, nulls: bool| {
+            let values: Vec<Option<i32>> = (0..rows).map(|i| (!nulls || i % 3 != 0).then_some(i % 1000)).collect();
+            let mut builder = ColumnBuilder::from_nullable(format!("c{}", rows), &values);
+            builder.compress_with(codec).unwrap();
+            builder.set_chunk_rows(7000);
+            builder.build_in_memory().unwrap()
+        };
+        let columns = [
+            make(60_000, Codec::zstd(), false),
+            make(5, Codec::None, false),
+            make(31_000, Codec::Lz4, true),
+            make(0, Codec::Lz4, false),
+            make(60_000, Codec::None, true),
+        ];
+
+        let mut buf = Vec::new();
+        assert_eq!(columns[0].decompress_into(&mut buf).unwrap(), 240_000);
+        let (ptr, capacity) = (buf.as_ptr(), buf.capacity());
+        for column in &columns {
+            let written = column.decompress_into(&mut buf).unwrap();
+            assert_eq!(written, column.len() * 4);
+            assert_eq!(buf, column.decompress_parallel().unwrap().into_vec(), "{}", column.name);
+            // Буфер самой большой колонки подходит для всех остальных
+            assert_eq!((buf.as_ptr(), buf.capacity()), (ptr, capacity));
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_uncompressed_len_matches_decoded_output() {
+        let values: Vec<i64> = (0..45_000).map(|i| (i / 10) * 1_000_000_007).collect();
+        for (codec, encoding) in [
+            (Codec::zstd(), Encoding::Plain),
+            (Codec::zstd(), Encoding::Delta),
+            (Codec::Lz4, Encoding::Rle),
+            (Codec::None, Encoding::Plain),
+        ] {
+            let mut builder = ColumnBuilder::from_i64("len".to_string(), &values);
+            builder.compress_with(codec).unwrap();
+            builder.set_encoding(encoding);
+            builder.set_chunk_rows(4000);
+            let column = builder.build_in_memory().unwrap();
+            assert_eq!(column.uncompressed_len(), values.len() as u64 * 8);
+            assert_eq!(column.decompress_parallel().unwrap().len() as u64, column.uncompressed_len());
+            for (idx, chunk) in column.chunks().iter().enumerate() {
+                assert_eq!(column.read_chunk(idx).unwrap().len() as u64, chunk.uncompressed_len);
+                // Кодирование сокращает значения ещё до кодека, поэтому encoded_len хранится отдельно
+                if encoding != Encoding::Plain {
+                    assert!(chunk.encoded_len < chunk.uncompressed_len, "{:?}", encoding);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_compression_levels() {
+        let values: Vec<i32> = (0..200_000i32).map(|x| x % 97 * x % 13).collect();
+
+        let mut sizes = Vec::new();
+        for level in [1, 19] {
+            let mut builder = ColumnBuilder::from_i32("lvl".to_string(), &values);
+            builder.compress_with_level(level).unwrap();
+            let tmp_file = NamedTempFile::new().unwrap();
+            let column = builder.build(tmp_file.path()).unwrap();
+            assert_eq!(column.codec, Codec::Zstd { level });
+            assert_eq!(column.values().unwrap(), values);
+            sizes.push(column.data().len());
+
+            let reopened = Column::<i32>::open(tmp_file.path()).unwrap();
+            assert_eq!(reopened.codec, Codec::Zstd { level });
+        }
+        assert!(sizes[1] <= sizes[0], "уровень 19 дал {} байт против {} на уровне 1", sizes[1], sizes[0]);
+
+        let mut builder = ColumnBuilder::from_i32("lvl".to_string(), &values);
+        let err = builder.compress_with_level(1000).unwrap_err();
+        assert!(matches!(err, ColumnarError::InvalidInput(_)), "{:?}", err);
+        assert_eq!(builder.codec, Codec::None);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_roundtrip_through_every_codec() {
+        let values: Vec<i32> = (0..400_000i32).map(|x| x / 7).collect();
+
+        for codec in [Codec::None, Codec::zstd(), Codec::Lz4] {
+            let mut builder = ColumnBuilder::from_i32("codec".to_string(), &values);
+            builder.compress_with(codec).unwrap();
+            let tmp_file = NamedTempFile::new().unwrap();
+            builder.build(tmp_file.path()).unwrap();
+
+            let column = Column::open(tmp_file.path()).unwrap();
+            assert_eq!(column.codec, codec);
+            assert_eq!(column.values().unwrap(), values, "{:?}", codec);
+            assert_eq!(column.get_value(123_456), Some(123_456 / 7), "{:?}", codec);
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_delta_encoding_on_sorted_sequence() {
+        // Монотонные «временные метки» с небольшим разбросом шага
+        let values: Vec<i32> = (0..300_000).map(|i| 1_600_000_000 + i * 10 + (i * 31) % 5).collect();
+
+        let mut plain = ColumnBuilder::from_i32("ts".to_string(), &values);
+        plain.compress().unwrap();
+        let plain = plain.build(NamedTempFile::new().unwrap().path()).unwrap();
+
+        let mut delta = ColumnBuilder::from_i32("ts".to_string(), &values);
+        delta.set_encoding(Encoding::Delta);
+        delta.compress().unwrap();
+        let tmp_file = NamedTempFile::new().unwrap();
+        let delta = delta.build(tmp_file.path()).unwrap();
+
+        assert!(
+            delta.data().len() * 2 < plain.data().len(),
+            "delta: {} байт, без delta: {} байт", delta.data().len(), plain.data().len()
+        );
+        assert_eq!(delta.min, values[0]);
+        assert_eq!(delta.max, *values.last().unwrap());
+        assert!(delta.might_contain(values[1234]));
+        assert_eq!(delta.values().unwrap(), values);
+
+        // Delta без кодека тоже работает и переживает повторное открытие
+        let mut uncompressed = ColumnBuilder::from_i32("ts".to_string(), &values);
+        uncompressed.set_encoding(Encoding::Delta);
+        uncompressed.build(tmp_file.path()).unwrap();
+        let reopened = Column::open(tmp_file.path()).unwrap();
+        assert_eq!(reopened.encoding, Encoding::Delta);
+        assert!(reopened.data().len() < values.len() * 4);
+        assert_eq!(reopened.get_value(299_999), Some(values[299_999]));
+        assert_eq!(reopened.values().unwrap(), values);
+    }
+
+    #[test]
+    fn test_rle_low_cardinality_column() {
+        // 1М значений из трёх статусов длинными сериями
+        let values: Vec<i32> = (0..1_000_000).map(|i| [200, 404, 500][(i / 5000) % 3]).collect();
+
+        let mut builder = ColumnBuilder::from_i32("status".to_string(), &values);
+        builder.set_encoding(Encoding::Rle);
+        let tmp_file = NamedTempFile::new().unwrap();
+        builder.build(tmp_file.path()).unwrap();
+
+        let column = Column::open(tmp_file.path()).unwrap();
+        assert_eq!(column.encoding, Encoding::Rle);
+        assert!(std::fs::metadata(tmp_file.path()).unwrap().len() < (values.len() * 4 / 100) as u64);
+        assert_eq!((column.min, column.max), (200, 500));
+        for idx in [0, 4999, 5000, 14_999, 15_000, 999_999] {
+            assert_eq!(column.get_value(idx), Some(values[idx]), "индекс {}", idx);
+        }
+        assert_eq!(column.get_value(1_000_000), None);
+        assert_eq!(column.values().unwrap(), values);
+    }
+
+    #[test]
+    fn test_dictionary_encoding_and_fallback() {
+        let values: Vec<i32> = (0..100_000).map(|i| (i * 37 % 1000) * 1_000_003).collect();
+
+        let mut builder = ColumnBuilder::from_i32("dict".to_string(), &values);
+        builder.set_encoding(Encoding::Dictionary);
+        let tmp_file = NamedTempFile::new().unwrap();
+        builder.build(tmp_file.path()).unwrap();
+
+        let column = Column::open(tmp_file.path()).unwrap();
+        assert_eq!(column.encoding, Encoding::Dictionary);
+        assert_eq!(column.dictionary.len(), 1000);
+        // Индексы u16 вместо i32
+        assert_eq!(column.data().len(), values.len() * 2);
+        assert_eq!(column.get_value(54_321), Some(values[54_321]));
+        assert!(values.iter().all(|v| column.might_contain(*v)));
+        assert_eq!(column.values().unwrap(), values);
+
+        // Кардинальность больше u16 — словарь не строится
+        let values: Vec<i32> = (0..70_000).collect();
+        let mut builder = ColumnBuilder::from_i32("wide".to_string(), &values);
+        builder.set_encoding(Encoding::Dictionary);
+        let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+        assert_eq!(column.encoding, Encoding::Plain);
+        assert!(column.dictionary.is_empty());
+        assert_eq!(column.get_value(69_999), Some(69_999));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zone_maps_prune_chunks() {
+        let values: Vec<i32> = (0..1_000_000).collect();
+
+        let mut builder = ColumnBuilder::from_i32("sorted".to_string(), &values);
+        builder.compress().unwrap();
+        let tmp_file = NamedTempFile::new().unwrap();
+        builder.build(tmp_file.path()).unwrap();
+        let column = Column::open(tmp_file.path()).unwrap();
+
+        let lo = ROWS_PER_CHUNK as i32 * 3 + 100;
+        let hi = lo + 200;
+        assert_eq!(column.chunks_matching_range(lo, hi), vec![3]);
+        assert!(column.chunks_matching_range(-10, -1).is_empty());
+
+        let rows = column.scan_range(lo, hi).unwrap();
+        assert_eq!(rows, (lo as usize..=hi as usize).collect::<Vec<_>>());
+        assert_eq!(column.frames_decoded(), 1, "распакован лишний чанк");
+
+        // Диапазон на стыке двух чанков
+        let border = ROWS_PER_CHUNK as i32 * 5;
+        assert_eq!(column.chunks_matching_range(border - 1, border), vec![4, 5]);
+        assert_eq!(column.scan_range(border - 1, border).unwrap().len(), 2);
+        assert_eq!(column.frames_decoded(), 3);
+    }
+
+    #[cfg(feature = "bloom")]
+    #[test]
+    fn test_chunk_bloom_filters() {
+        // Значения перемешаны, поэтому zone maps не помогают — работают только bloom-фильтры
+        let values: Vec<i32> = (0..ROWS_PER_CHUNK as i32 * 4).map(|i| i.wrapping_mul(-1_640_531_535) / 2 * 2).collect();
+
+        let mut builder = ColumnBuilder::from_i32("blooms".to_string(), &values);
+        builder.compress_with(Codec::Lz4).unwrap();
+        let tmp_file = NamedTempFile::new().unwrap();
+        builder.build(tmp_file.path()).unwrap();
+        let column = Column::open(tmp_file.path()).unwrap();
+
+        // Ложных отрицаний нет
+        for (row, value) in values.iter().enumerate().step_by(97) {
+            assert!(column.chunks_possibly_containing(*value).contains(&(row / ROWS_PER_CHUNK)));
+        }
+
+        // Нечётных значений в колонке нет — доля ложных срабатываний около 1%
+        let probes = 20_000;
+        let false_positives: usize = (0..probes)
+            .map(|i| {
+                let absent = i * 2 + 1;
+                (0..4).filter(|idx| column.chunks()[*idx].bloom.check(&absent)).count()
+            })
+            .sum();
+        let rate = false_positives as f64 / (probes as f64 * 4.0);
+        assert!(rate < 0.02, "доля ложных срабатываний {}", rate);
+
+        // Точечный поиск распаковывает только чанк-кандидат
+        let target = values[ROWS_PER_CHUNK * 2 + 17];
+        let rows = column.scan_range(target, target).unwrap();
+        assert!(rows.contains(&(ROWS_PER_CHUNK * 2 + 17)));
+        assert!(column.frames_decoded() <= 2);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_checksum_detects_corruption() {
+        let values: Vec<i32> = (0..ROWS_PER_CHUNK as i32 * 3).map(|x| x % 1000).collect();
+
+        for codec in [Codec::zstd(), Codec::None] {
+            let mut builder = ColumnBuilder::from_i32("crc".to_string(), &values);
+            builder.compress_with(codec).unwrap();
+            let tmp_file = NamedTempFile::new().unwrap();
+            let column = builder.build(tmp_file.path()).unwrap();
+
+            // Портим байт внутри второго чанка
+            let target = column.chunks()[1].offset as usize + 5;
+            drop(column);
+            let mut raw = std::fs::read(tmp_file.path()).unwrap();
+            raw[target] ^= 0x10;
+            std::fs::write(tmp_file.path(), &raw).unwrap();
+
+            let column = Column::open(tmp_file.path()).unwrap();
+            let err = column.decompress_parallel().unwrap_err();
+            assert!(err.to_string().contains("чанка 1"), "{:?}: {}", codec, err);
+            assert!(column.try_get_value(ROWS_PER_CHUNK + 1).is_err(), "{:?}", codec);
+            // Первый чанк цел и по-прежнему читается
+            assert_eq!(column.try_get_value(10).unwrap(), Some(10), "{:?}", codec);
+
+            // Без сверки повреждение не обнаруживается контрольной суммой
+            let mut column = Column::<i32>::open(tmp_file.path()).unwrap();
+            column.set_verify_checksums(false);
+            if let Err(err) = column.decompress_parallel() {
+                assert!(!err.to_string().contains("контрольная сумма"));
+            }
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_chunk_index_and_read_chunk() {
+        let values: Vec<i32> = (0..ROWS_PER_CHUNK as i32 * 2 + 10).map(|x| x * 3).collect();
+        let bytes: Vec<u8> = values.iter().flat_map(|x| x.to_le_bytes()).collect();
+
+        let mut builder = ColumnBuilder::from_i32("index".to_string(), &values);
+        builder.compress().unwrap();
+        let tmp_file = NamedTempFile::new().unwrap();
+        builder.build(tmp_file.path()).unwrap();
+        let column = Column::open(tmp_file.path()).unwrap();
+
+        let chunks = column.chunks();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].offset, HEADER_SIZE as u64);
+        assert_eq!(chunks[1].offset, chunks[0].offset + chunks[0].compressed_len);
+        assert_eq!(chunks[2].first_row, ROWS_PER_CHUNK as u64 * 2);
+        assert_eq!(chunks[2].uncompressed_len, 40);
+
+        // Чтение одного чанка распаковывает ровно один фрейм
+        let last = column.read_chunk(2).unwrap();
+        assert_eq!(last, &bytes[ROWS_PER_CHUNK * 8..]);
+        assert_eq!(column.frames_decoded(), 1);
+        assert!(column.read_chunk(3).is_err());
+
+        // Точечные чтения в пределах одного чанка используют кеш
+        assert_eq!(column.get_value(5), Some(15));
+        assert_eq!(column.get_value(ROWS_PER_CHUNK - 1), Some(values[ROWS_PER_CHUNK - 1]));
+        assert_eq!(column.frames_decoded(), 2);
+        assert_eq!(column.get_value(values.len()), None);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_get_values_matches_point_lookups() {
+        let values: Vec<i32> = (0..5000).map(|x| x * 7 - 1000).collect();
+        for codec in [Codec::None, Codec::zstd()] {
+            let mut builder = ColumnBuilder::from_i32("batch".to_string(), &values);
+            builder.compress_with(codec).unwrap();
+            builder.set_chunk_rows(512);
+            let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+
+            let mut state = 7u64;
+            let mut next = |bound: usize| {
+                state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                (state >> 33) as usize % bound
+            };
+            for _ in 0..50 {
+                let start = next(values.len() + 1);
+                let end = start + next(values.len() + 1 - start);
+                let expected: Vec<i32> = (start..end).map(|idx| column.get_value(idx).unwrap()).collect();
+                assert_eq!(column.get_values(start..end).unwrap(), expected, "{}..{}", start, end);
+            }
+            assert_eq!(column.get_values(0..values.len()).unwrap(), values);
+            assert!(column.get_values(10..10).unwrap().is_empty());
+            assert_eq!(column.get_values(5000..5000).unwrap(), Vec::<i32>::new());
+        }
+
+        // Диапазон за концом колонки не обрезается
+        let column = ColumnBuilder::from_i32("short".to_string(), &[1, 2, 3])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        let err = column.get_values(1..4).unwrap_err();
+        assert!(matches!(err, ColumnarError::InvalidInput(_)), "{:?}", err);
+        let (start, end) = (2, 1);
+        assert!(column.get_values(start..end).is_err());
+
+        // Распаковываются только чанки, пересекающиеся с диапазоном
+        let mut builder = ColumnBuilder::from_i32("frames".to_string(), &values);
+        builder.compress().unwrap();
+        builder.set_chunk_rows(1000);
+        let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+        assert_eq!(column.get_values(1990..2010).unwrap(), &values[1990..2010]);
+        assert_eq!(column.frames_decoded(), 2);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_append_across_chunk_boundary() {
+        let initial: Vec<i32> = (0..1000).collect();
+
+        let mut builder = ColumnBuilder::from_i32("append".to_string(), &initial);
+        builder.compress().unwrap();
+        let tmp_file = NamedTempFile::new().unwrap();
+        let mut column = builder.build(tmp_file.path()).unwrap();
+        let old_reader = Column::open(tmp_file.path()).unwrap();
+
+        column.append(&[-50, 5000, 7]).unwrap();
+        column.append(&(2000..2100).collect::<Vec<_>>()).unwrap();
+
+        assert_eq!(column.chunks().len(), 3);
+        assert_eq!(column.get_value(999), Some(999));
+        assert_eq!(column.get_value(1000), Some(-50));
+        assert_eq!(column.get_value(1002), Some(7));
+        assert_eq!(column.get_value(1003), Some(2000));
+        assert_eq!(column.get_value(1102), Some(2099));
+        assert_eq!(column.get_value(1103), None);
+        assert_eq!((column.min, column.max), (-50, 5000));
+        assert!(column.might_contain(2050));
+        assert_eq!(column.chunks_possibly_containing(5000), vec![1]);
+
+        assert_eq!(column.len(), 1103);
+
+        // Старый читатель видит прежнюю длину и целые данные
+        assert_eq!(old_reader.len(), 1000);
+        assert_eq!(old_reader.get_value(999), Some(999));
+        assert_eq!(old_reader.get_value(1000), None);
+
+        let reopened = Column::open(tmp_file.path()).unwrap();
+        assert_eq!(reopened.values().unwrap().len(), 1103);
+        assert_eq!(reopened.len(), 1103);
+        assert_eq!(reopened.get_value(1050), Some(2047));
+    }
+
+    // Колонка в памяти должна отвечать на любые чтения так же, как файловая
+    fn assert_same_reads(file: &Column<i32>, memory: &Column<i32>, probes: &[i32]) {
+        assert!(!file.backing.is_in_memory() && memory.backing.is_in_memory());
+        assert_eq!(memory.data(), file.data());
+        assert_eq!(memory.stats(), file.stats());
+        assert_eq!(memory.bloom_params(), file.bloom_params());
+        assert_eq!(memory.nullable_values().unwrap(), file.nullable_values().unwrap());
+        assert_eq!(memory.decompress_parallel().unwrap(), file.decompress_parallel().unwrap());
+        let iterated = |column: &Column<i32>| column.iter().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(iterated(memory), iterated(file));
+        for idx in 0..memory.chunks().len() {
+            assert_eq!(memory.read_chunk(idx).unwrap(), file.read_chunk(idx).unwrap());
+        }
+        let len = file.len();
+        for idx in [0, 1, len / 3, len / 2, len.saturating_sub(1), len, len + 10] {
+            assert_eq!(memory.get_nullable(idx), file.get_nullable(idx), "строка {}", idx);
+        }
+        assert_eq!(memory.get_values(len / 4..len / 2).unwrap(), file.get_values(len / 4..len / 2).unwrap());
+        for &value in probes {
+            assert_eq!(memory.scan_range(value, value + 50).unwrap(), file.scan_range(value, value + 50).unwrap());
+            assert_eq!(memory.contains(value).unwrap(), file.contains(value).unwrap());
+            assert_eq!(memory.chunks_possibly_containing(value), file.chunks_possibly_containing(value));
+            let selection = file.filter(Predicate::Ge(value)).unwrap();
+            assert_eq!(memory.filter(Predicate::Ge(value)).unwrap(), selection);
+            for agg in [Agg::Sum, Agg::Min, Agg::Max, Agg::Avg, Agg::Count] {
+                assert_eq!(
+                    memory.aggregate(agg, Some(&selection)).unwrap(),
+                    file.aggregate(agg, Some(&selection)).unwrap()
+                );
+            }
+        }
+        assert_eq!(memory.top_k(25, false).unwrap(), file.top_k(25, false).unwrap());
+        if file.is_sorted {
+            for &value in probes {
+                assert_eq!(memory.find(value).unwrap(), file.find(value).unwrap());
+            }
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_in_memory_column_matches_file_across_formats() {
+        let sorted: Vec<i32> = (0..20_000).map(|i| i * 3).collect();
+        let repeated: Vec<i32> = (0..20_000).map(|i| (i / 100) % 7).collect();
+        let nullable: Vec<Option<i32>> = (0..9000).map(|i| (i % 5 != 0).then_some(i - 4000)).collect();
+        let probes = [-1, 0, 3, 6, 3000, 59_997, 100_000];
+
+        let make = |values: &Vec<i32>, codec: Codec, encoding: Encoding| {
+            let mut builder = ColumnBuilder::from_i32("parity".to_string(), values);
+            builder.compress_with(codec).unwrap();
+            builder.set_encoding(encoding);
+            builder.set_chunk_rows(3000);
+            builder
+        };
+        for codec in [Codec::None, Codec::Lz4, Codec::zstd()] {
+            for (values, encoding) in [
+                (&sorted, Encoding::Plain),
+                (&sorted, Encoding::Delta),
+                (&repeated, Encoding::Rle),
+                (&repeated, Encoding::Dictionary),
+            ] {
+                let tmp_file = NamedTempFile::new().unwrap();
+                let file = make(values, codec, encoding).build(tmp_file.path()).unwrap();
+                let memory = make(values, codec, encoding).build_in_memory().unwrap();
+                assert_same_reads(&file, &memory, &probes);
+            }
+
+            let make_nullable = || {
+                let mut builder = ColumnBuilder::from_nullable("nullable".to_string(), &nullable);
+                builder.compress_with(codec).unwrap();
+                builder.set_chunk_rows(1000);
+                builder
+            };
+            let tmp_file = NamedTempFile::new().unwrap();
+            let file = make_nullable().build(tmp_file.path()).unwrap();
+            let memory = make_nullable().build_in_memory().unwrap();
+            assert_same_reads(&file, &memory, &probes);
+        }
+
+        let empty = ColumnBuilder::<i32>::from_values("empty".to_string(), &[]).build_in_memory().unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.values().unwrap(), Vec::<i32>::new());
+        // Подсказки ядру для буфера в памяти ничего не делают, но режим запоминается
+        empty.advise(AccessPattern::Random).unwrap();
+        assert_eq!(empty.access_pattern(), AccessPattern::Random);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_in_memory_twins_of_append_and_streaming() {
+        let initial: Vec<i32> = (0..1000).collect();
+        let mut builder = ColumnBuilder::from_i32("append".to_string(), &initial);
+        builder.compress().unwrap();
+        let mut column = builder.build_in_memory().unwrap();
+        let old_reader = Column::<i32>::from_backing(None, column.backing.clone()).unwrap();
+
+        column.append(&[-50, 5000, 7]).unwrap();
+        column.append(&(2000..2100).collect::<Vec<_>>()).unwrap();
+        assert_eq!(column.chunks().len(), 3);
+        assert_eq!(column.get_value(1000), Some(-50));
+        assert_eq!(column.get_value(1102), Some(2099));
+        assert_eq!(column.get_value(1103), None);
+        assert_eq!((column.min, column.max), (-50, 5000));
+        assert_eq!(column.chunks_possibly_containing(5000), vec![1]);
+        // Прежний буфер не меняется дозаписью
+        assert_eq!(old_reader.len(), 1000);
+        assert_eq!(old_reader.get_value(999), Some(999));
+        assert_eq!(old_reader.get_value(1000), None);
+
+        let mut writer = ColumnBuilder::<i32>::create_in_memory("stream".to_string()).unwrap();
+        writer.set_chunk_rows(4).unwrap();
+        for value in [Some(-5), None, Some(3), None, None, Some(8)] {
+            writer.push_option(value).unwrap();
+        }
+        writer.push_slice(&(0..5000).collect::<Vec<_>>()).unwrap();
+        let mut column = writer.finish().unwrap();
+        assert!(column.backing.is_in_memory());
+        assert_eq!(column.null_count, 3);
+        // Фильтр рассчитан на первый чанк и пересобран по буферу при finish
+        #[cfg(feature = "bloom")]
+        assert!(column.bloom_params().unwrap().capacity >= 5000);
+        assert!((0..5000).all(|v| column.might_contain(v)));
+
+        column.append(&[1, 2]).unwrap();
+        assert_eq!(column.len(), 5008);
+        assert_eq!(column.get_nullable(1), Some(None));
+        assert_eq!(column.get_nullable(5007), Some(Some(2)));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_len_does_not_depend_on_compression() {
+        // Повторяющиеся значения сжимаются во много раз меньше исходных 4 байт на строку
+        let values = vec![42i32; 100_000];
+        for codec in [Codec::None, Codec::zstd(), Codec::Lz4] {
+            let tmp_file = NamedTempFile::new().unwrap();
+            let mut builder = ColumnBuilder::from_i32("len".to_string(), &values);
+            builder.compress_with(codec).unwrap();
+            let column = builder.build(tmp_file.path()).unwrap();
+            assert_eq!(column.len(), 100_000, "{:?}", codec);
+            assert!(!column.is_empty());
+            assert_eq!(column.get_value(99_999), Some(42));
+            assert_eq!(column.get_value(100_000), None);
+            assert_eq!(Column::<i32>::open(tmp_file.path()).unwrap().len(), 100_000);
+        }
+
+        let empty = ColumnBuilder::from_i32("empty".to_string(), &[])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.len(), 0);
+        assert_eq!(empty.get_value(0), None);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_i64_column_outside_i32_range() {
+        let values: Vec<i64> = (0..200_000i64).map(|i| (i - 100_000) * 50_000_000_007).collect();
+
+        for encoding in [Encoding::Plain, Encoding::Delta, Encoding::Rle] {
+            let mut builder = ColumnBuilder::from_i64("keys".to_string(), &values);
+            builder.set_encoding(encoding);
+            builder.compress().unwrap();
+            let tmp_file = NamedTempFile::new().unwrap();
+            builder.build(tmp_file.path()).unwrap();
+
+            let column = Column::<i64>::open(tmp_file.path()).unwrap();
+            assert_eq!((column.min, column.max), (values[0], values[199_999]), "{:?}", encoding);
+            assert_eq!(column.get_value(150_000), Some(values[150_000]), "{:?}", encoding);
+            assert_eq!(column.get_value(200_000), None);
+            assert!(column.might_contain(values[12_345]));
+            assert_eq!(column.scan_range(values[10], values[12]).unwrap(), vec![10, 11, 12]);
+            assert_eq!(column.values().unwrap(), values, "{:?}", encoding);
+        }
+
+        // Несжатая колонка с шагом 8 байт читается прямо из mmap
+        let tmp_file = NamedTempFile::new().unwrap();
+        let column = ColumnBuilder::from_i64("keys".to_string(), &values).build(tmp_file.path()).unwrap();
+        assert_eq!(column.data().len(), values.len() * 8);
+        assert_eq!(column.chunks()[0].row_count(), ROWS_PER_CHUNK as u64);
+        assert_eq!(column.get_value(ROWS_PER_CHUNK), Some(values[ROWS_PER_CHUNK]));
+        assert!(ColumnBuilder::<i64>::new("raw".to_string(), vec![0; 12]).is_err());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_f64_column_with_special_values() {
+        let mut values: Vec<f64> = (0..100_000).map(|i| i as f64 * 0.25 - 1000.0).collect();
+        values[10] = f64::NAN;
+        values[20] = f64::INFINITY;
+        values[30] = f64::NEG_INFINITY;
+        values[40] = -0.0;
+        values[99_999] = f64::NAN;
+        let bits = |v: &[f64]| v.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+
+        for (codec, encoding) in [(Codec::None, Encoding::Plain), (Codec::zstd(), Encoding::Delta), (Codec::Lz4, Encoding::Rle)] {
+            let mut builder = ColumnBuilder::from_f64("metric".to_string(), &values);
+            builder.compress_with(codec).unwrap();
+            builder.set_encoding(encoding);
+            let tmp_file = NamedTempFile::new().unwrap();
+            builder.build(tmp_file.path()).unwrap();
+
+            let column = Column::<f64>::open(tmp_file.path()).unwrap();
+            // NaN не отравляет статистику, но его наличие сохраняется
+            assert_eq!((column.min, column.max), (f64::NEG_INFINITY, f64::INFINITY), "{:?}", codec);
+            assert!(column.has_nan);
+            assert!(column.get_value(10).unwrap().is_nan());
+            assert_eq!(column.get_value(40).unwrap().to_bits(), (-0.0f64).to_bits());
+            assert_eq!(column.get_value(50), Some(50.0 * 0.25 - 1000.0));
+            assert_eq!(bits(&column.values().unwrap()), bits(&values), "{:?}", codec);
+            // Bloom-фильтр для f64 не строится и ничего не отсекает
+            assert!(column.might_contain(12345.678));
+        }
+
+        let column = ColumnBuilder::from_f64("finite".to_string(), &[2.5, -1.5, 0.0])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert_eq!((column.min, column.max), (-1.5, 2.5));
+        assert!(!column.has_nan);
+        assert_eq!(column.scan_range(-2.0, 1.0).unwrap(), vec![1, 2]);
+
+        let column = ColumnBuilder::from_f64("nan".to_string(), &[f64::NAN; 3])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert!(column.has_nan);
+        assert!(column.scan_range(f64::MIN, f64::MAX).unwrap().is_empty());
+    }
+
+    fn reopen_as<T: ColumnType>(path: &Path) -> Result<Vec<T>> {
+        Column::<T>::open(path)?.values()
+    }
+
+    #[test]
+    fn test_type_tag_matrix() {
+        let files: Vec<(u8, NamedTempFile)> = (0..3).map(|tag| (tag, NamedTempFile::new().unwrap())).collect();
+        ColumnBuilder::from_i32("a".to_string(), &[1, -2]).build(files[0].1.path()).unwrap();
+        ColumnBuilder::from_i64("b".to_string(), &[1, -2]).build(files[1].1.path()).unwrap();
+        ColumnBuilder::from_f64("c".to_string(), &[1.0, -2.0]).build(files[2].1.path()).unwrap();
+
+        // Каждый файл открывается только как свой тип, даже при совпадающей ширине i64/f64
+        for (tag, file) in &files {
+            assert_eq!(reopen_as::<i32>(file.path()).is_ok(), *tag == i32::TAG);
+            assert_eq!(reopen_as::<i64>(file.path()).is_ok(), *tag == i64::TAG);
+            assert_eq!(reopen_as::<f64>(file.path()).is_ok(), *tag == f64::TAG);
+        }
+        assert_eq!(Int32Column::open(files[0].1.path()).unwrap().values().unwrap(), vec![1, -2]);
+        assert_eq!(Int64Column::open(files[1].1.path()).unwrap().min, -2);
+        assert_eq!(Float64Column::open(files[2].1.path()).unwrap().max, 1.0);
+    }
+
+    #[test]
+    fn test_open_rejects_mismatched_value_type() {
+        let wide_file = NamedTempFile::new().unwrap();
+        ColumnBuilder::from_i64("wide".to_string(), &[i64::MAX, 1]).build(wide_file.path()).unwrap();
+        let err = Column::<i32>::open(wide_file.path()).unwrap_err();
+        assert!(matches!(err, ColumnarError::Corrupt { .. }), "{:?}", err);
+        assert!(err.to_string().contains("i64"), "{}", err);
+
+        let narrow_file = NamedTempFile::new().unwrap();
+        ColumnBuilder::from_i32("narrow".to_string(), &[1, 2, 3, 4]).build(narrow_file.path()).unwrap();
+        assert!(Column::<i64>::open(narrow_file.path()).is_err());
+        assert_eq!(Column::<i32>::open(narrow_file.path()).unwrap().get_value(3), Some(4));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_nullable_column_with_mixed_values() {
+        // Каждая седьмая строка — NULL; значения положительны, поэтому заполнитель 0 не должен попасть в min
+        let values: Vec<Option<i32>> = (0..5000).map(|i| (i % 7 != 0).then_some(i + 100)).collect();
+        let expected_nulls = values.iter().filter(|v| v.is_none()).count() as u64;
+
+        for codec in [Codec::None, Codec::zstd(), Codec::Lz4] {
+            let tmp_file = NamedTempFile::new().unwrap();
+            let mut builder = ColumnBuilder::from_nullable("nullable".to_string(), &values);
+            builder.compress_with(codec).unwrap();
+            builder.set_chunk_rows(1000);
+            builder.build(tmp_file.path()).unwrap();
+
+            let column = Column::<i32>::open(tmp_file.path()).unwrap();
+            assert_eq!(column.null_count, expected_nulls, "{:?}", codec);
+            assert_eq!((column.min, column.max), (101, 5099));
+            assert_eq!(column.nullable_values().unwrap(), values);
+            assert_eq!(column.get_nullable(0), Some(None));
+            assert_eq!(column.get_nullable(1), Some(Some(101)));
+            assert_eq!(column.get_nullable(5000), None);
+            assert_eq!(column.get_value(7), None);
+            assert!(column.chunks().iter().all(|c| c.null_count > 0 && c.validity_len > 0));
+            assert!(column.chunks_possibly_containing(0).is_empty());
+
+            let expected_rows: Vec<usize> = (0..5000).filter(|i| i % 7 != 0 && *i < 200).collect();
+            assert_eq!(column.scan_range(i32::MIN, 299).unwrap(), expected_rows);
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_all_null_and_no_null_columns() {
+        for codec in [Codec::None, Codec::zstd()] {
+            let tmp_file = NamedTempFile::new().unwrap();
+            let mut builder = ColumnBuilder::<i64>::from_nullable("nulls".to_string(), &[None; 300]);
+            builder.compress_with(codec).unwrap();
+            let column = builder.build(tmp_file.path()).unwrap();
+            assert_eq!(column.null_count, 300);
+            // Статистика пустая, как у колонки без строк
+            assert_eq!((column.min, column.max), (i64::MAX, i64::MIN));
+            assert!(column.chunks_matching_range(i64::MIN, i64::MAX).is_empty());
+            assert!(column.scan_range(i64::MIN, i64::MAX).unwrap().is_empty());
+            assert_eq!(column.nullable_values().unwrap(), vec![None; 300]);
+            assert_eq!(column.get_nullable(299), Some(None));
+
+            // Колонка без NULL не хранит битовую карту, и данные совпадают с обычной колонкой
+            let values: Vec<i64> = (0..300).map(|i| i * 3).collect();
+            let options: Vec<Option<i64>> = values.iter().copied().map(Some).collect();
+            let plain_file = NamedTempFile::new().unwrap();
+            let mut plain = ColumnBuilder::from_i64("plain".to_string(), &values);
+            plain.compress_with(codec).unwrap();
+            let plain = plain.build(plain_file.path()).unwrap();
+            let mut nullable = ColumnBuilder::from_nullable("plain".to_string(), &options);
+            nullable.compress_with(codec).unwrap();
+            let nullable = nullable.build(tmp_file.path()).unwrap();
+            assert_eq!(nullable.null_count, 0);
+            assert_eq!(nullable.chunks()[0].validity_len, 0);
+            assert_eq!(nullable.data(), plain.data());
+            assert_eq!(nullable.get_nullable(10), Some(Some(30)));
+        }
+    }
+
+    #[test]
+    fn test_streaming_nulls_and_append() {
+        let tmp_file = NamedTempFile::new().unwrap();
+        let mut writer = ColumnBuilder::<i32>::create("stream".to_string(), tmp_file.path()).unwrap();
+        writer.set_chunk_rows(4).unwrap();
+        for value in [Some(-5), None, Some(3), None, None, Some(8)] {
+            writer.push_option(value).unwrap();
+        }
+        let mut column = writer.finish().unwrap();
+        assert_eq!(column.null_count, 3);
+        assert_eq!((column.min, column.max), (-5, 8));
+
+        // Дозапись после чанков с битовыми картами продолжает с конца данных
+        column.append(&[1, 2]).unwrap();
+        assert_eq!(column.null_count, 3);
+        assert_eq!(
+            column.nullable_values().unwrap(),
+            vec![Some(-5), None, Some(3), None, None, Some(8), Some(1), Some(2)]
+        );
+        let reopened = Column::<i32>::open(tmp_file.path()).unwrap();
+        assert_eq!(reopened.get_nullable(6), Some(Some(1)));
+        assert_eq!(reopened.values().unwrap().len(), 8);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_column_stats_with_known_cardinality() {
+        // 20 000 различных значений, каждое повторено пять раз, и каждая 97-я строка — NULL
+        let values: Vec<Option<i64>> = (0..100_000i64)
+            .map(|i| (i % 97 != 0).then_some((i % 20_000) * 31 - 7))
+            .collect();
+        let tmp_file = NamedTempFile::new().unwrap();
+        let mut builder = ColumnBuilder::from_nullable("stats".to_string(), &values);
+        builder.set_chunk_rows(10_000);
+        builder.compress().unwrap();
+        builder.set_hll_precision(14).unwrap();
+        builder.build(tmp_file.path()).unwrap();
+
+        let stats = Column::<i64>::open(tmp_file.path()).unwrap().stats();
+        assert_eq!(stats.row_count, 100_000);
+        assert_eq!(stats.null_count, 1031);
+        assert_eq!((stats.min, stats.max), (-7, 19_999 * 31 - 7));
+        // Каждое значение встречается и в строках, отличных от NULL
+        let error = (stats.distinct_count as f64 - 20_000.0).abs() / 20_000.0;
+        assert!(error < 0.03, "оценка {}", stats.distinct_count);
+
+        // Дозапись продолжает оценку по сохранённым регистрам
+        let mut column = Column::<i64>::open(tmp_file.path()).unwrap();
+        column.append(&(1_000_000..1_010_000).collect::<Vec<_>>()).unwrap();
+        let error = (column.stats().distinct_count as f64 - 30_000.0).abs() / 30_000.0;
+        assert!(error < 0.03, "оценка после дозаписи {}", column.stats().distinct_count);
+
+        let empty = ColumnBuilder::from_i32("empty".to_string(), &[])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert_eq!((empty.stats().distinct_count, empty.stats().null_count), (0, 0));
+        assert!(ColumnBuilder::from_i32("p".to_string(), &[1]).set_hll_precision(20).is_err());
+    }
+
+    #[test]
+    fn test_sorted_column_binary_search() {
+        // Отметки времени с повторами, несколько чанков
+        let values: Vec<i64> = (0..10_000i64).map(|i| 1_700_000_000 + (i / 3) * 10).collect();
+        for codec in [Codec::None, Codec::Lz4] {
+            let tmp_file = NamedTempFile::new().unwrap();
+            let mut builder = ColumnBuilder::from_i64("ts".to_string(), &values);
+            builder.compress_with(codec).unwrap();
+            builder.set_chunk_rows(1000);
+            builder.build(tmp_file.path()).unwrap();
+
+            let column = Column::<i64>::open(tmp_file.path()).unwrap();
+            assert!(column.is_sorted && column.stats().is_sorted);
+            assert_eq!(column.find(values[0]).unwrap(), Some(0));
+            assert_eq!(column.find(values[9999]).unwrap(), Some(9999));
+            // Первое вхождение повторяющегося значения, в том числе на границе чанков
+            assert_eq!(column.find(values[1000]).unwrap(), Some(999));
+            assert_eq!(column.find(values[5000] + 1).unwrap(), None);
+            assert_eq!(column.find(0).unwrap(), None);
+            assert_eq!(column.find(i64::MAX).unwrap(), None);
+
+            let (lo, hi) = (values[2500], values[7300]);
+            let expected = column.scan_range(lo, hi).unwrap();
+            let range = column.range_indices(lo, hi).unwrap();
+            assert_eq!(range.clone().collect::<Vec<_>>(), expected);
+            assert_eq!(range, 2499..7302);
+            assert_eq!(column.range_indices(i64::MIN, i64::MAX).unwrap(), 0..10_000);
+            assert!(column.range_indices(hi, lo).unwrap().is_empty());
+            assert!(column.range_indices(values[9999] + 1, i64::MAX).unwrap().is_empty());
+        }
+
+        let empty = ColumnBuilder::from_i32("empty".to_string(), &[])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert!(empty.is_sorted);
+        assert_eq!(empty.find(1).unwrap(), None);
+        assert_eq!(empty.range_indices(i32::MIN, i32::MAX).unwrap(), 0..0);
+    }
+
+    #[test]
+    fn test_unsorted_columns_reject_binary_search() {
+        let tmp_file = NamedTempFile::new().unwrap();
+        let mut column = ColumnBuilder::from_i32("s".to_string(), &[1, 2, 2, 5])
+            .build(tmp_file.path())
+            .unwrap();
+        column.append(&[5, 9]).unwrap();
+        assert!(column.is_sorted);
+        assert_eq!(column.find(9).unwrap(), Some(5));
+        column.append(&[3]).unwrap();
+        assert!(!column.is_sorted);
+        assert!(!Column::<i32>::open(tmp_file.path()).unwrap().is_sorted);
+        let err = column.find(3).unwrap_err();
+        assert!(matches!(err, ColumnarError::InvalidInput(_)), "{:?}", err);
+        assert!(column.range_indices(0, 10).is_err());
+
+        // NULL и NaN снимают признак сортировки
+        let nullable = ColumnBuilder::from_nullable("n".to_string(), &[Some(1), None, Some(2)])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert!(!nullable.is_sorted);
+        let nan = ColumnBuilder::from_f64("f".to_string(), &[1.0, f64::NAN])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert!(!nan.is_sorted);
+    }
+
+    #[test]
+    fn test_bloom_filter_survives_reopen() {
+        let values: Vec<i32> = (0..500).map(|i| i * 7).collect();
+        let tmp_file = NamedTempFile::new().unwrap();
+
+        let built = ColumnBuilder::from_i32("bloom".to_string(), &values)
+            .build(tmp_file.path())
+            .unwrap();
+        let probes: Vec<i32> = (-100..4000).collect();
+        let expected: Vec<bool> = probes.iter().map(|v| built.might_contain(*v)).collect();
+        drop(built);
+
+        let reopened = Column::open(tmp_file.path()).unwrap();
+        let actual: Vec<bool> = probes.iter().map(|v| reopened.might_contain(*v)).collect();
+        assert_eq!(actual, expected);
+        assert!(values.iter().all(|v| reopened.might_contain(*v)));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_contains_confirms_bloom_filter_hits() {
+        // Много значений на фильтр — ложные срабатывания заведомо есть
+        let values: Vec<i32> = (0..20_000).map(|i| i * 2).collect();
+        let mut builder = ColumnBuilder::from_i32("contains".to_string(), &values);
+        builder.compress().unwrap();
+        builder.set_chunk_rows(2000);
+        let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+
+        let odd: Vec<i32> = (0..20_000).map(|i| i * 2 + 1).collect();
+        let false_positives = odd.iter().filter(|v| column.might_contain(**v)).count();
+        assert!(false_positives > 0);
+        for v in &odd {
+            assert!(!column.contains(*v).unwrap(), "{}", v);
+        }
+        for v in [0, 2, 19_998, 39_998] {
+            assert!(column.contains(v).unwrap(), "{}", v);
+        }
+        // Вне min/max фильтр даже не проверяется
+        let frames = column.frames_decoded();
+        assert!(!column.contains(-2).unwrap());
+        assert!(!column.contains(40_000).unwrap());
+        assert_eq!(column.frames_decoded(), frames);
+
+        let nullable = ColumnBuilder::from_nullable("n".to_string(), &[Some(5), None])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert!(nullable.contains(5).unwrap());
+        // Заполнитель NULL не считается значением
+        assert!(!nullable.contains(0).unwrap());
+
+        let floats = ColumnBuilder::from_f64("f".to_string(), &[1.5, f64::NAN])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert!(floats.contains(f64::NAN).unwrap());
+        assert!(floats.contains(1.5).unwrap());
+        assert!(!floats.contains(1.25).unwrap());
+    }
+
+    #[cfg(all(feature = "zstd", feature = "bloom"))]
+    #[test]
+    fn test_bloom_filter_indexes_values_of_compressed_column() {
+        let values: Vec<i32> = (0..300).map(|i| i * 1000).collect();
+
+        let mut builder = ColumnBuilder::from_i32("compressed_bloom".to_string(), &values);
+        builder.compress().unwrap();
+        let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+
+        // Ложных отрицаний быть не должно
+        for v in &values {
+            assert!(column.might_contain(*v), "значение {} потеряно", v);
+        }
+        // Отсутствующие значения в большинстве отсекаются
+        let false_positives = (1..1000).filter(|v| column.might_contain(v * 1000 + 1)).count();
+        assert!(false_positives < 50, "слишком много ложных срабатываний: {}", false_positives);
+    }
+
+    // Доля ложных срабатываний общего фильтра на заведомо отсутствующих значениях
+    fn measured_fp_rate(column: &Column<i32>, probes: impl Iterator<Item = i32>) -> f64 {
+        let (mut hits, mut total) = (0, 0);
+        for probe in probes {
+            hits += column.might_contain(probe) as usize;
+            total += 1;
+        }
+        hits as f64 / total as f64
+    }
+
+    #[cfg(feature = "bloom")]
+    #[test]
+    fn test_bloom_filter_sized_for_million_values() {
+        let values: Vec<i32> = (0..1_000_000).map(|i| i * 2).collect();
+        for fp_rate in [0.01, 0.05] {
+            let mut builder = ColumnBuilder::from_i32("million".to_string(), &values);
+            builder.set_bloom_fp_rate(fp_rate).unwrap();
+            let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+
+            let params = column.bloom_params().unwrap();
+            assert_eq!(params.fp_rate, fp_rate);
+            assert!(params.capacity >= 1_000_000, "{:?}", params);
+            assert!(values.iter().step_by(97).all(|v| column.might_contain(*v)));
+            // Нечётных значений в колонке нет
+            let measured = measured_fp_rate(&column, (0..1_000_000).map(|i| i * 2 + 1));
+            assert!(measured > fp_rate / 4.0 && measured < fp_rate * 1.5, "цель {}, получено {}", fp_rate, measured);
+        }
+
+        let mut builder = ColumnBuilder::from_i32("bad".to_string(), &values);
+        for fp_rate in [0.0, 1.0, -0.5, f64::NAN] {
+            assert!(matches!(builder.set_bloom_fp_rate(fp_rate), Err(ColumnarError::InvalidInput(_))));
+        }
+    }
+
+    #[cfg(feature = "bloom")]
+    #[test]
+    fn test_streaming_bloom_filter_grows_with_running_count() {
+        let tmp_file = NamedTempFile::new().unwrap();
+        let mut writer = ColumnBuilder::create("stream".to_string(), tmp_file.path()).unwrap();
+        writer.compress_with(Codec::Lz4).unwrap();
+        writer.set_bloom_fp_rate(0.02).unwrap();
+        for i in 0..600_000 {
+            writer.push_option((i % 10 != 0).then_some(i * 2)).unwrap();
+        }
+        assert!(writer.set_bloom_fp_rate(0.1).is_err());
+        let mut column = writer.finish().unwrap();
+        // Первоначальный фильтр на один чанк был бы переполнен в несколько раз
+        assert!(column.bloom_params().unwrap().capacity >= 540_000);
+        assert!(column.might_contain(2 * 7) && column.contains(2 * 7).unwrap());
+        // NULL не попадает в фильтр: на месте значения 0 стоит заполнитель
+        assert!(!column.contains(0).unwrap());
+        let measured = measured_fp_rate(&column, (0..500_000).map(|i| i * 2 + 1));
+        assert!(measured < 0.03, "получено {}", measured);
+
+        // Дозапись за пределы ёмкости тоже приводит к перестройке
+        let extra: Vec<i32> = (0..300_000).map(|i| -2 * i - 2).collect();
+        column.append(&extra).unwrap();
+        let reopened = Column::<i32>::open(tmp_file.path()).unwrap();
+        assert_eq!(reopened.bloom_params(), column.bloom_params());
+        assert!(reopened.bloom_params().unwrap().capacity >= 840_000);
+        assert!(extra.iter().step_by(31).all(|v| reopened.might_contain(*v)));
+        assert!(measured_fp_rate(&reopened, (0..500_000).map(|i| -2 * i - 1)) < 0.03);
+    }
+
+    #[test]
+    fn test_disabled_bloom_filter() {
+        let values: Vec<i32> = (0..10_000).map(|i| i * 3).collect();
+        let tmp_file = NamedTempFile::new().unwrap();
+        let mut builder = ColumnBuilder::from_i32("plain".to_string(), &values);
+        builder.disable_bloom_filter();
+        builder.set_chunk_rows(1000);
+        let mut column = builder.build(tmp_file.path()).unwrap();
+
+        assert_eq!(column.bloom_params(), None);
+        assert!(column.might_contain(1));
+        assert!(column.contains(300).unwrap() && !column.contains(301).unwrap());
+        assert_eq!(column.chunks_possibly_containing(301), vec![0]);
+        assert_eq!(column.filter(crate::Predicate::Eq(3)).unwrap().iter_indices().collect::<Vec<_>>(), vec![1]);
+        column.append(&[-7]).unwrap();
+        let reopened = Column::<i32>::open(tmp_file.path()).unwrap();
+        assert_eq!(reopened.bloom_params(), None);
+        assert!(reopened.contains(-7).unwrap());
+
+        // Для f64 фильтры не строятся никогда
+        let mut floats = ColumnBuilder::from_f64("f".to_string(), &[1.0, 2.0]);
+        floats.set_bloom_fp_rate(0.001).unwrap();
+        assert_eq!(floats.build(NamedTempFile::new().unwrap().path()).unwrap().bloom_params(), None);
+    }
+
+    #[cfg(not(feature = "bloom"))]
+    #[test]
+    fn test_builds_without_bloom_feature_write_no_filters() {
+        let values: Vec<i64> = (0..10_000).map(|i| i * 3).collect();
+        let tmp_file = NamedTempFile::new().unwrap();
+        let mut builder = ColumnBuilder::from_values("plain".to_string(), &values);
+        builder.set_bloom_fp_rate(0.001).unwrap();
+        builder.set_chunk_rows(1000);
+        let mut column = builder.build(tmp_file.path()).unwrap();
+
+        assert_eq!(column.bloom_params(), None);
+        assert!(column.might_contain(1));
+        assert!(column.contains(300).unwrap() && !column.contains(301).unwrap());
+        column.append(&[-7]).unwrap();
+        assert!(Column::<i64>::open(tmp_file.path()).unwrap().contains(-7).unwrap());
+
+        // Строковой колонке фильтр нужен по формату: пишется насыщенный
+        let strings = crate::StringColumnBuilder::from_strs("s".to_string(), &["a", "b"]);
+        let strings = strings.build(NamedTempFile::new().unwrap().path()).unwrap();
+        assert!(strings.might_contain("z"));
+    }
+
+    #[test]
+    fn test_header_layout_and_validation() {
+        let tmp_file = NamedTempFile::new().unwrap();
+        let column = ColumnBuilder::from_i32("hdr".to_string(), &[5, 6])
+            .build(tmp_file.path())
+            .unwrap();
+
+        // Значения начинаются сразу после заголовка
+        assert_eq!(&column.backing[..8], crate::format::MAGIC);
+        assert_eq!(&column.backing[HEADER_SIZE..HEADER_SIZE + 4], &5i32.to_le_bytes());
+        assert_eq!(column.get_value(1), Some(6));
+        let raw = std::fs::read(tmp_file.path()).unwrap();
+        drop(column);
+
+        // Неверная сигнатура
+        let mut broken = raw.clone();
+        broken[0] = b'X';
+        std::fs::write(tmp_file.path(), &broken).unwrap();
+        let err = Column::<i32>::open(tmp_file.path()).unwrap_err();
+        assert!(err.to_string().contains("сигнатура"), "{}", err);
+
+        // Неподдерживаемая версия
+        let mut broken = raw.clone();
+        broken[8..10].copy_from_slice(&99u16.to_le_bytes());
+        std::fs::write(tmp_file.path(), &broken).unwrap();
+        let err = Column::<i32>::open(tmp_file.path()).unwrap_err();
+        assert!(err.to_string().contains("версия формата 99"), "{}", err);
+    }
+
+    #[test]
+    fn test_clones_share_data_across_threads() {
+        let file = NamedTempFile::new().unwrap();
+        let values: Vec<i64> = (0..50_000).map(|i| i * 7 % 10_007).collect();
+        let mut builder = ColumnBuilder::from_values("v".to_string(), &values);
+        builder.set_chunk_rows(4096);
+        builder.compress_with(Codec::Lz4).unwrap();
+        let mut column = builder.build(file.path()).unwrap();
+        let copy = column.clone();
+        assert!(Arc::ptr_eq(&column.inner, &copy.inner));
+
+        let values = Arc::new(values);
+        let workers: Vec<_> = (0..8)
+            .map(|worker| {
+                let column = column.clone();
+                let values = values.clone();
+                std::thread::spawn(move || {
+                    for step in 0..500usize {
+                        let idx = (step * 7919 + worker * 104_729) % values.len();
+                        assert_eq!(column.get_value(idx), Some(values[idx]));
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        // Удаление меняет только свою копию
+        assert_eq!(column.delete_rows(&[3]).unwrap(), 1);
+        assert_eq!((column.get_value(3), copy.get_value(3)), (None, Some(values[3])));
+        assert_eq!((column.live_count(), copy.live_count()), (values.len() - 1, values.len()));
+    }
+
+    #[test]
+    fn test_chunk_cache_keeps_only_touched_chunks() {
+        let values: Vec<i32> = (0..20_000).collect();
+        let mut builder = ColumnBuilder::from_i32("scattered".to_string(), &values);
+        builder.set_encoding(Encoding::Delta);
+        builder.set_chunk_rows(1_000);
+        let mut column = builder.build_in_memory().unwrap();
+        let chunk_bytes = 1_000 * 4;
+        let cache = Arc::new(ChunkCache::with_byte_capacity(5 * chunk_bytes, 1).unwrap());
+        column.set_chunk_cache(cache.clone(), "t/scattered");
+
+        let mut row = 7usize;
+        for _ in 0..500 {
+            row = (row * 7_919 + 13) % values.len();
+            assert_eq!(column.get_value(row), Some(row as i32));
+            assert!(cache.current_bytes() <= 5 * chunk_bytes);
+        }
+        assert!(cache.len() <= 5 && cache.current_bytes() < column.uncompressed_len() as usize / 4);
+
+        // Повторные чтения из закэшированного чанка не распаковывают его снова
+        column.get_value(row);
+        let decoded = column.frames_decoded();
+        for offset in 0..50 {
+            assert_eq!(column.get_value(row / 1_000 * 1_000 + offset), Some((row / 1_000 * 1_000 + offset) as i32));
+        }
+        assert_eq!(column.frames_decoded(), decoded);
+        assert_eq!(column.get_values(1_990..2_010).unwrap(), (1_990..2_010).collect::<Vec<_>>());
+
+        // Полное чтение собирается из кэша и распаковки, но кэш не засоряет
+        let insertions = cache.stats().insertions;
+        assert_eq!(column.values().unwrap(), values);
+        assert_eq!(cache.stats().insertions, insertions);
+
+        let resident = cache.len();
+        assert_eq!(column.invalidate_chunk_cache(), resident);
+        assert!(cache.is_empty());
+    }
+}
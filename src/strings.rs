@@ -0,0 +1,309 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::Arc,
+};
+use memmap2::Mmap;
+use crate::bloom::Bloom;
+use crate::codec::Codec;
+use crate::error::{corrupt, Result};
+use crate::encoding::Encoding;
+use crate::format::{
+    decode_bloom, encode_bloom, read_str, split_trailer, validate_sections, write_str, write_trailer,
+    Buffer, ByteReader, Header, Section, HEADER_SIZE,
+};
+use crate::storage::DEFAULT_BLOOM_FP_RATE;
+use crate::types::type_name;
+
+// Тег строковой колонки в заголовке файла
+pub(crate) const STRING_TAG: u8 = 3;
+
+// Строковая колонка хранит два буфера: смещения u64 (на одно больше числа строк)
+// и сплошной UTF-8. Строка idx — это data[offsets[idx]..offsets[idx + 1]].
+pub struct StringColumnBuilder {
+    name: String,
+    offsets: Vec<u8>,
+    data: Vec<u8>,
+    rows: usize,
+    min: String,
+    max: String,
+    bloom: Bloom<str>,
+    codec: Codec,
+}
+
+#[derive(Debug)]
+pub struct StringColumn {
+    pub name: String,
+    pub mmap: Arc<Mmap>,
+    // Лексикографически первая и последняя строки; для пустой колонки — пустые строки
+    pub min: String,
+    pub max: String,
+    pub codec: Codec,
+    pub bloom_filter: Bloom<str>,
+    rows: usize,
+    offsets: Buffer,
+    data: Buffer,
+}
+
+struct StringFooter {
+    name: String,
+    compression_level: i32,
+    min: String,
+    max: String,
+    bloom_filter: Bloom<str>,
+    offsets: Section,
+    data: Section,
+}
+
+impl StringColumnBuilder {
+    pub fn from_strs<S: AsRef<str>>(name: String, values: &[S]) -> Self {
+        let mut offsets = Vec::with_capacity((values.len() + 1) * 8);
+        let mut data = Vec::new();
+        offsets.extend_from_slice(&0u64.to_le_bytes());
+        for value in values {
+            data.extend_from_slice(value.as_ref().as_bytes());
+            offsets.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        }
+
+        let distinct: HashSet<&str> = values.iter().map(AsRef::as_ref).collect();
+        let mut bloom = Bloom::new_for_fp_rate(distinct.len().max(1), DEFAULT_BLOOM_FP_RATE);
+        for value in &distinct {
+            bloom.set(*value);
+        }
+        let min = distinct.iter().min().map_or_else(String::new, |s| s.to_string());
+        let max = distinct.iter().max().map_or_else(String::new, |s| s.to_string());
+
+        Self {
+            name,
+            offsets,
+            data,
+            rows: values.len(),
+            min,
+            max,
+            bloom,
+            codec: Codec::None,
+        }
+    }
+
+    // Кодек применяется к обоим буферам
+    pub fn compress_with(&mut self, codec: Codec) -> Result<()> {
+        codec.validate()?;
+        self.codec = codec;
+        Ok(())
+    }
+
+    pub fn build(self, path: &Path) -> Result<StringColumn> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let header = Header::new(self.codec.tag(), Encoding::Plain.tag(), STRING_TAG, self.rows as u64);
+        file.write_all(&header.encode())?;
+
+        let offsets = Section::write(&mut file, HEADER_SIZE as u64, &self.offsets, self.codec)?;
+        let data = Section::write(&mut file, offsets.offset + offsets.stored_len, &self.data, self.codec)?;
+
+        let footer = StringFooter {
+            name: self.name,
+            compression_level: self.codec.level(),
+            min: self.min,
+            max: self.max,
+            bloom_filter: self.bloom,
+            offsets,
+            data,
+        };
+        file.write_all(&footer.encode())?;
+        file.into_inner().map_err(|e| e.into_error())?;
+
+        StringColumn::open(path)
+    }
+}
+
+impl StringColumn {
+    pub fn open(path: &Path) -> Result<StringColumn> {
+        let file = File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        let header = Header::decode(&mmap)?;
+        if header.type_tag != STRING_TAG {
+            return Err(corrupt(format!(
+                "колонка хранит значения {}, а открывается как {}",
+                type_name(header.type_tag), type_name(STRING_TAG)
+            )));
+        }
+        let (data_end, meta) = split_trailer(&mmap)?;
+        let footer = StringFooter::decode(meta)?;
+        let codec = Codec::from_parts(header.codec_tag, footer.compression_level)?;
+        validate_sections(&[footer.offsets, footer.data], data_end)?;
+
+        let offsets = footer.offsets.load(&mmap, codec)?;
+        let data = footer.data.load(&mmap, codec)?;
+        let column = StringColumn {
+            name: footer.name,
+            mmap: Arc::new(mmap),
+            min: footer.min,
+            max: footer.max,
+            codec,
+            bloom_filter: footer.bloom_filter,
+            rows: header.row_count as usize,
+            offsets,
+            data,
+        };
+        column.validate()?;
+        Ok(column)
+    }
+
+    // Смещения должны быть монотонны, заканчиваться на длине данных и попадать на границы символов
+    fn validate(&self) -> Result<()> {
+        let offsets = self.bytes(&self.offsets);
+        let data = self.bytes(&self.data);
+        if offsets.len() as u64 != (self.rows as u64 + 1) * 8 {
+            return Err(corrupt("размер буфера смещений не соответствует числу строк"));
+        }
+        let text = std::str::from_utf8(data).map_err(|_| corrupt("данные строковой колонки не являются UTF-8"))?;
+        let mut prev = 0;
+        for (idx, raw) in offsets.chunks_exact(8).enumerate() {
+            let offset = u64::from_le_bytes(raw.try_into().unwrap()) as usize;
+            if (idx == 0 && offset != 0) || offset < prev || offset > data.len() || !text.is_char_boundary(offset) {
+                return Err(corrupt(format!("повреждено смещение строки {}", idx)));
+            }
+            prev = offset;
+        }
+        if prev != data.len() {
+            return Err(corrupt("смещения не покрывают буфер данных"));
+        }
+        Ok(())
+    }
+
+    fn bytes<'a>(&'a self, buffer: &'a Buffer) -> &'a [u8] {
+        buffer.bytes(&self.mmap)
+    }
+
+    fn offset(&self, idx: usize) -> usize {
+        let raw = &self.bytes(&self.offsets)[idx * 8..idx * 8 + 8];
+        u64::from_le_bytes(raw.try_into().unwrap()) as usize
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.codec.is_compressed()
+    }
+
+    // Для несжатой колонки строка — срез mmap без копирования
+    pub fn get_str(&self, idx: usize) -> Option<&str> {
+        if idx >= self.rows {
+            return None;
+        }
+        let data = &self.bytes(&self.data)[self.offset(idx)..self.offset(idx + 1)];
+        // Границы символов проверены в open
+        std::str::from_utf8(data).ok()
+    }
+
+    pub fn might_contain(&self, value: &str) -> bool {
+        self.bloom_filter.check(value)
+    }
+
+    // Смещения и байты строк после распаковки
+    pub(crate) fn uncompressed_len(&self) -> u64 {
+        (self.bytes(&self.offsets).len() + self.bytes(&self.data).len()) as u64
+    }
+}
+
+impl StringFooter {
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_str(&mut out, &self.name);
+        out.extend_from_slice(&self.compression_level.to_le_bytes());
+        write_str(&mut out, &self.min);
+        write_str(&mut out, &self.max);
+        encode_bloom(&self.bloom_filter, &mut out);
+        self.offsets.encode(&mut out);
+        self.data.encode(&mut out);
+        write_trailer(&mut out);
+        out
+    }
+
+    fn decode(meta: &[u8]) -> Result<StringFooter> {
+        let mut r = ByteReader::new(meta);
+        Ok(StringFooter {
+            name: read_str(&mut r)?,
+            compression_level: r.i32()?,
+            min: read_str(&mut r)?,
+            max: read_str(&mut r)?,
+            bloom_filter: decode_bloom(&mut r)?,
+            offsets: Section::decode(&mut r)?,
+            data: Section::decode(&mut r)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Column, ColumnBuilder};
+    use tempfile::NamedTempFile;
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_string_column_roundtrip() {
+        let values = ["RU", "", "日本語", "user-agent/1.0 (X11; Linux)", "", "Ελληνικά", "DE"];
+
+        for codec in [Codec::None, Codec::zstd(), Codec::Lz4] {
+            let mut builder = StringColumnBuilder::from_strs("country".to_string(), &values);
+            builder.compress_with(codec).unwrap();
+            let tmp_file = NamedTempFile::new().unwrap();
+            builder.build(tmp_file.path()).unwrap();
+
+            let column = StringColumn::open(tmp_file.path()).unwrap();
+            assert_eq!(column.len(), values.len());
+            for (idx, value) in values.iter().enumerate() {
+                assert_eq!(column.get_str(idx), Some(*value), "{:?}", codec);
+            }
+            assert_eq!(column.get_str(values.len()), None);
+            assert_eq!(column.get_str(usize::MAX), None);
+            assert_eq!((column.min.as_str(), column.max.as_str()), ("", "日本語"));
+            assert!(column.might_contain("日本語"));
+            assert!(column.might_contain(""));
+        }
+    }
+
+    #[test]
+    fn test_uncompressed_strings_are_zero_copy() {
+        let tmp_file = NamedTempFile::new().unwrap();
+        let column = StringColumnBuilder::from_strs("s".to_string(), &["abc", "defg"])
+            .build(tmp_file.path())
+            .unwrap();
+        let value = column.get_str(1).unwrap();
+        let mapped = column.mmap.as_ptr_range();
+        assert!(mapped.contains(&value.as_ptr()));
+
+        let empty = StringColumnBuilder::from_strs::<&str>("e".to_string(), &[])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.get_str(0), None);
+    }
+
+    #[test]
+    fn test_string_column_type_checks() {
+        let strings = NamedTempFile::new().unwrap();
+        StringColumnBuilder::from_strs("s".to_string(), &["x"]).build(strings.path()).unwrap();
+        assert!(Column::<i32>::open(strings.path()).is_err());
+
+        let ints = NamedTempFile::new().unwrap();
+        ColumnBuilder::from_i32("i".to_string(), &[1]).build(ints.path()).unwrap();
+        let err = StringColumn::open(ints.path()).unwrap_err();
+        assert!(err.to_string().contains("i32"), "{}", err);
+
+        // Повреждённый буфер данных обнаруживается при открытии
+        let mut raw = std::fs::read(strings.path()).unwrap();
+        raw[HEADER_SIZE + 16] ^= 0xff;
+        std::fs::write(strings.path(), &raw).unwrap();
+        assert!(StringColumn::open(strings.path()).is_err());
+    }
+}
@@ -0,0 +1,321 @@
+use std::{cmp::Ordering, fmt};
+use crate::bools::BoolColumn;
+use crate::codec::Codec;
+use crate::compute::Selection;
+use crate::storage::Column;
+use crate::strings::StringColumn;
+use crate::table::{Table, TableColumn};
+use crate::types::{ColumnType, DataType};
+
+// Сводка колонки для логов и отладки: то, что обычно ищут в {:?} колонки, без
+// байтов файла и внутренних буферов. min и max уже отформатированы, поэтому сводки
+// колонок разных типов можно сложить в одну таблицу
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSummary {
+    pub name: String,
+    pub data_type: DataType,
+    pub rows: usize,
+    // Строки, удалённые через .del; входят в rows
+    pub deleted: usize,
+    pub codec: Codec,
+    // Размер файла колонки вместе с заголовком и метаданными
+    pub disk_bytes: u64,
+    // Размер распакованных значений
+    pub uncompressed_bytes: u64,
+    // None — в колонке нет ни одного значения, кроме NULL и NaN
+    pub min: Option<String>,
+    pub max: Option<String>,
+    pub null_count: u64,
+    pub has_bloom: bool,
+}
+
+impl<T: ColumnType> Column<T> {
+    pub fn describe(&self) -> ColumnSummary {
+        // У пустой колонки min и max остаются T::MAX и T::MIN
+        let bounds = self.min.total_cmp(&self.max) != Ordering::Greater;
+        ColumnSummary {
+            name: self.name.clone(),
+            data_type: T::DATA_TYPE,
+            rows: self.len(),
+            deleted: self.deleted.as_deref().map_or(0, Selection::count),
+            codec: self.codec,
+            disk_bytes: self.backing.len() as u64,
+            uncompressed_bytes: self.uncompressed_len(),
+            min: bounds.then(|| format!("{:?}", self.min)),
+            max: bounds.then(|| format!("{:?}", self.max)),
+            null_count: self.null_count,
+            has_bloom: self.bloom_params().is_some(),
+        }
+    }
+}
+
+impl StringColumn {
+    pub fn describe(&self) -> ColumnSummary {
+        let bounds = !self.is_empty();
+        ColumnSummary {
+            name: self.name.clone(),
+            data_type: DataType::Utf8,
+            rows: self.len(),
+            deleted: 0,
+            codec: self.codec,
+            disk_bytes: self.mmap.len() as u64,
+            uncompressed_bytes: self.uncompressed_len(),
+            min: bounds.then(|| format!("{:?}", self.min)),
+            max: bounds.then(|| format!("{:?}", self.max)),
+            null_count: 0,
+            has_bloom: true,
+        }
+    }
+}
+
+impl BoolColumn {
+    pub fn describe(&self) -> ColumnSummary {
+        let bounds = !self.is_empty();
+        ColumnSummary {
+            name: self.name.clone(),
+            data_type: DataType::Bool,
+            rows: self.len(),
+            deleted: 0,
+            codec: self.codec,
+            disk_bytes: self.mmap.len() as u64,
+            uncompressed_bytes: self.uncompressed_len(),
+            min: bounds.then(|| (self.false_count == 0).to_string()),
+            max: bounds.then(|| (self.true_count > 0).to_string()),
+            null_count: 0,
+            has_bloom: false,
+        }
+    }
+}
+
+impl TableColumn {
+    pub fn describe(&self) -> ColumnSummary {
+        match self {
+            TableColumn::Int32(column) => column.describe(),
+            TableColumn::Int64(column) => column.describe(),
+            TableColumn::Float64(column) => column.describe(),
+            TableColumn::Utf8(column) => column.describe(),
+            TableColumn::Bool(column) => column.describe(),
+        }
+    }
+}
+
+// Двоичные единицы с одним знаком после запятой; байты — без дробной части
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["КиБ", "МиБ", "ГиБ", "ТиБ"];
+    if bytes < 1024 {
+        return format!("{} Б", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+fn bound(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("—")
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "да" } else { "нет" }
+}
+
+// Одна строка: name (Int64): строк 1000, кодек none, ... Удалённые строки
+// упоминаются, только если они есть
+impl fmt::Display for ColumnSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:?}): строк {}", self.name, self.data_type, self.rows)?;
+        if self.deleted > 0 {
+            write!(f, ", удалено {}", self.deleted)?;
+        }
+        write!(
+            f,
+            ", кодек {}, на диске {}, без сжатия {}, min {}, max {}, NULL {}, bloom {}",
+            self.codec,
+            format_bytes(self.disk_bytes),
+            format_bytes(self.uncompressed_bytes),
+            bound(&self.min),
+            bound(&self.max),
+            self.null_count,
+            yes_no(self.has_bloom)
+        )
+    }
+}
+
+impl<T: ColumnType> fmt::Display for Column<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.describe().fmt(f)
+    }
+}
+
+// Вместо всех полей — сводка и путь файла: байты отображения, фильтры и регистры
+// HyperLogLog в отладочном выводе только мешают
+impl<T: ColumnType> fmt::Debug for Column<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let summary = self.describe();
+        f.debug_struct("Column")
+            .field("name", &summary.name)
+            .field("data_type", &summary.data_type)
+            .field("rows", &summary.rows)
+            .field("deleted", &summary.deleted)
+            .field("codec", &summary.codec)
+            .field("encoding", &self.encoding)
+            .field("chunks", &self.chunks.len())
+            .field("disk_bytes", &summary.disk_bytes)
+            .field("uncompressed_bytes", &summary.uncompressed_bytes)
+            .field("min", &self.min)
+            .field("max", &self.max)
+            .field("null_count", &summary.null_count)
+            .field("bloom", &self.bloom_params())
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+// Заголовок таблицы и по строке на колонку, столбцы выровнены по ширине.
+// Числовые столбцы выровнены вправо
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const HEADER: [&str; 10] = ["колонка", "тип", "строк", "кодек", "на диске", "без сжатия", "min", "max", "NULL", "bloom"];
+        const RIGHT: [bool; 10] = [false, false, true, false, true, true, false, false, true, false];
+        writeln!(f, "таблица {}: колонок {}, строк {}", self.name, self.columns().len(), self.row_count())?;
+        let rows: Vec<[String; 10]> = self
+            .columns()
+            .iter()
+            .map(|column| {
+                let summary = column.describe();
+                [
+                    summary.name.clone(),
+                    format!("{:?}", summary.data_type),
+                    summary.rows.to_string(),
+                    summary.codec.to_string(),
+                    format_bytes(summary.disk_bytes),
+                    format_bytes(summary.uncompressed_bytes),
+                    bound(&summary.min).to_string(),
+                    bound(&summary.max).to_string(),
+                    summary.null_count.to_string(),
+                    yes_no(summary.has_bloom).to_string(),
+                ]
+            })
+            .collect();
+        let mut widths = HEADER.map(|title| title.chars().count());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let header = HEADER.map(str::to_string);
+        for row in std::iter::once(&header).chain(&rows) {
+            let mut line = String::new();
+            for (i, cell) in row.iter().enumerate() {
+                if i > 0 {
+                    line.push_str("  ");
+                }
+                // Ширину {:>w} считает в символах, а не в байтах, поэтому кириллица
+                // выравнивается так же, как латиница
+                if RIGHT[i] {
+                    line.push_str(&format!("{:>1$}", cell, widths[i]));
+                } else {
+                    line.push_str(&format!("{:<1$}", cell, widths[i]));
+                }
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::{BoolColumnBuilder, ColumnBuilder, StringColumnBuilder};
+
+    #[test]
+    fn test_format_bytes_units() {
+        assert_eq!(format_bytes(0), "0 Б");
+        assert_eq!(format_bytes(1023), "1023 Б");
+        assert_eq!(format_bytes(1536), "1.5 КиБ");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 МиБ");
+        assert_eq!(format_bytes(3 << 30), "3.0 ГиБ");
+    }
+
+    #[test]
+    fn test_column_display_and_debug_snapshots() {
+        let values: Vec<Option<i64>> = (0..1000).map(|i| (i % 10 != 0).then_some(i)).collect();
+        let mut builder = ColumnBuilder::from_nullable("v".to_string(), &values);
+        builder.disable_bloom_filter();
+        let column = builder.build_in_memory().unwrap();
+        let summary = column.describe();
+        assert_eq!((summary.rows, summary.null_count, summary.uncompressed_bytes), (1000, 100, 8000));
+        assert_eq!(
+            column.to_string(),
+            format!(
+                "v (Int64): строк 1000, кодек none, на диске {}, без сжатия 7.8 КиБ, min 1, max 999, NULL 100, bloom нет",
+                format_bytes(summary.disk_bytes)
+            )
+        );
+        assert_eq!(
+            format!("{:?}", column),
+            format!(
+                "Column {{ name: \"v\", data_type: Int64, rows: 1000, deleted: 0, codec: None, encoding: Plain, chunks: 1, \
+                 disk_bytes: {}, uncompressed_bytes: 8000, min: 1, max: 999, null_count: 100, bloom: None, path: None }}",
+                summary.disk_bytes
+            )
+        );
+
+        let empty = ColumnBuilder::<f64>::from_values("e".to_string(), &[]).build_in_memory().unwrap();
+        assert!(empty.to_string().contains("строк 0, кодек none"));
+        assert!(empty.to_string().contains("min —, max —"));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_display_shows_zstd_level_and_bloom() {
+        let values: Vec<i32> = (0..10_000).map(|i| i % 7).collect();
+        let mut builder = ColumnBuilder::from_i32("z".to_string(), &values);
+        builder.compress_with_level(5).unwrap();
+        let column = builder.build_in_memory().unwrap();
+        let shown = column.to_string();
+        assert!(shown.starts_with("z (Int32): строк 10000, кодек zstd(5), на диске "));
+        assert!(shown.contains(", без сжатия 39.1 КиБ, min 0, max 6, NULL 0, bloom "));
+        assert_eq!(shown.ends_with("bloom да"), cfg!(feature = "bloom"));
+        assert!(column.describe().disk_bytes < column.describe().uncompressed_bytes);
+    }
+
+    #[test]
+    fn test_table_display_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut table = Table::new("metrics".to_string());
+        let mut id = ColumnBuilder::from_i32("id".to_string(), &[1, 2, 3]);
+        id.disable_bloom_filter();
+        table.add_column(Arc::new(id.build_in_memory().unwrap())).unwrap();
+        let score = ColumnBuilder::from_nullable("score".to_string(), &[Some(0.5), None, Some(2.25)]);
+        table.add_column(Arc::new(score.build_in_memory().unwrap())).unwrap();
+        let tag = StringColumnBuilder::from_strs("tag".to_string(), &["b", "a", "c"]);
+        let tag = tag.build(&dir.path().join("tag.col")).unwrap();
+        let flag = BoolColumnBuilder::from_bools("flag".to_string(), &[true, true, true]);
+        let flag = flag.build(&dir.path().join("flag.col")).unwrap();
+        let disk: Vec<String> = table
+            .columns()
+            .iter()
+            .map(|c| format_bytes(c.describe().disk_bytes))
+            .chain([format_bytes(tag.mmap.len() as u64), format_bytes(flag.mmap.len() as u64)])
+            .collect();
+        table.add_column(Arc::new(tag)).unwrap();
+        table.add_column(Arc::new(flag)).unwrap();
+
+        let expected = format!(
+            "таблица metrics: колонок 4, строк 3\n\
+             колонка  тип      строк  кодек  на диске  без сжатия  min   max   NULL  bloom\n\
+             id       Int32        3  none   {:>8}        12 Б  1     3        0  нет\n\
+             score    Float64      3  none   {:>8}        24 Б  0.5   2.25     1  нет\n\
+             tag      Utf8         3  none   {:>8}        35 Б  \"a\"   \"c\"      0  да\n\
+             flag     Bool         3  none   {:>8}         1 Б  true  true     0  нет\n",
+            disk[0], disk[1], disk[2], disk[3]
+        );
+        assert_eq!(table.to_string(), expected);
+    }
+}
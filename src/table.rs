@@ -0,0 +1,565 @@
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{Read, Write},
+    path::Path,
+    sync::Arc,
+};
+use crate::backing::ColumnData;
+use crate::bools::BoolColumn;
+use crate::error::{corrupt, invalid_input, Result};
+use crate::format::{read_str, write_str, ByteReader, Header, HEADER_SIZE};
+use crate::schema::{Field, Schema};
+use crate::storage::Column;
+use crate::strings::StringColumn;
+use crate::tombstone::write_deletions;
+use crate::types::{type_name, ColumnType, DataType};
+
+// Каталог таблицы: файлы колонок и манифест, который пишется последним
+const MANIFEST_FILE: &str = "table.manifest";
+// magic [8] | имя таблицы | число строк u64 | число колонок u32 |
+// по колонке: имя, тег типа u8, допускает ли NULL u8, имя файла | CRC32 предыдущих байтов
+const MANIFEST_MAGIC: &[u8; 8] = b"COLTABLE";
+
+// Колонка таблицы с любым типом значений
+#[derive(Debug, Clone)]
+pub enum TableColumn {
+    Int32(Arc<Column<i32>>),
+    Int64(Arc<Column<i64>>),
+    Float64(Arc<Column<f64>>),
+    Utf8(Arc<StringColumn>),
+    Bool(Arc<BoolColumn>),
+}
+
+impl TableColumn {
+    pub fn name(&self) -> &str {
+        match self {
+            TableColumn::Int32(column) => &column.name,
+            TableColumn::Int64(column) => &column.name,
+            TableColumn::Float64(column) => &column.name,
+            TableColumn::Utf8(column) => &column.name,
+            TableColumn::Bool(column) => &column.name,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            TableColumn::Int32(column) => column.len(),
+            TableColumn::Int64(column) => column.len(),
+            TableColumn::Float64(column) => column.len(),
+            TableColumn::Utf8(column) => column.len(),
+            TableColumn::Bool(column) => column.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Числовая колонка с значениями T; None для другого типа
+    pub fn as_column<T: ColumnType>(&self) -> Option<&Arc<Column<T>>> {
+        let column: &dyn Any = match self {
+            TableColumn::Int32(column) => column,
+            TableColumn::Int64(column) => column,
+            TableColumn::Float64(column) => column,
+            TableColumn::Utf8(_) | TableColumn::Bool(_) => return None,
+        };
+        column.downcast_ref()
+    }
+
+    pub fn data_type(&self) -> DataType {
+        match self {
+            TableColumn::Int32(_) => DataType::Int32,
+            TableColumn::Int64(_) => DataType::Int64,
+            TableColumn::Float64(_) => DataType::Float64,
+            TableColumn::Utf8(_) => DataType::Utf8,
+            TableColumn::Bool(_) => DataType::Bool,
+        }
+    }
+
+    // Строковые и булевы колонки NULL не хранят
+    pub fn has_nulls(&self) -> bool {
+        match self {
+            TableColumn::Int32(column) => column.null_count > 0,
+            TableColumn::Int64(column) => column.null_count > 0,
+            TableColumn::Float64(column) => column.null_count > 0,
+            TableColumn::Utf8(_) | TableColumn::Bool(_) => false,
+        }
+    }
+
+    // Описание колонки по её содержимому: NULL допускается, если они в ней есть
+    pub fn field(&self) -> Field {
+        Field::new(self.name(), self.data_type(), self.has_nulls())
+    }
+
+    // Байты файла колонки целиком
+    fn file_bytes(&self) -> &[u8] {
+        match self {
+            TableColumn::Int32(column) => &column.backing,
+            TableColumn::Int64(column) => &column.backing,
+            TableColumn::Float64(column) => &column.backing,
+            TableColumn::Utf8(column) => &column.mmap,
+            TableColumn::Bool(column) => &column.mmap,
+        }
+    }
+
+    fn open(path: &Path, data_type: DataType) -> Result<TableColumn> {
+        Ok(match data_type {
+            DataType::Int32 => Column::<i32>::open(path)?.into(),
+            DataType::Int64 => Column::<i64>::open(path)?.into(),
+            DataType::Float64 => Column::<f64>::open(path)?.into(),
+            DataType::Utf8 => StringColumn::open(path)?.into(),
+            DataType::Bool => BoolColumn::open(path)?.into(),
+        })
+    }
+
+    // Распакованные значения числовой колонки. Строковые и булевы колонки
+    // распаковываются целиком уже при открытии, поэтому для них None
+    pub fn decompress_parallel(&self) -> Result<Option<ColumnData>> {
+        match self {
+            TableColumn::Int32(column) => column.decompress_parallel().map(Some),
+            TableColumn::Int64(column) => column.decompress_parallel().map(Some),
+            TableColumn::Float64(column) => column.decompress_parallel().map(Some),
+            TableColumn::Utf8(_) | TableColumn::Bool(_) => Ok(None),
+        }
+    }
+}
+
+impl<T: ColumnType> From<Column<T>> for TableColumn
+where
+    TableColumn: From<Arc<Column<T>>>,
+{
+    fn from(column: Column<T>) -> Self {
+        Arc::new(column).into()
+    }
+}
+
+impl From<Arc<Column<i32>>> for TableColumn {
+    fn from(column: Arc<Column<i32>>) -> Self {
+        TableColumn::Int32(column)
+    }
+}
+
+impl From<Arc<Column<i64>>> for TableColumn {
+    fn from(column: Arc<Column<i64>>) -> Self {
+        TableColumn::Int64(column)
+    }
+}
+
+impl From<Arc<Column<f64>>> for TableColumn {
+    fn from(column: Arc<Column<f64>>) -> Self {
+        TableColumn::Float64(column)
+    }
+}
+
+impl From<StringColumn> for TableColumn {
+    fn from(column: StringColumn) -> Self {
+        TableColumn::Utf8(Arc::new(column))
+    }
+}
+
+impl From<Arc<StringColumn>> for TableColumn {
+    fn from(column: Arc<StringColumn>) -> Self {
+        TableColumn::Utf8(column)
+    }
+}
+
+impl From<BoolColumn> for TableColumn {
+    fn from(column: BoolColumn) -> Self {
+        TableColumn::Bool(Arc::new(column))
+    }
+}
+
+impl From<Arc<BoolColumn>> for TableColumn {
+    fn from(column: Arc<BoolColumn>) -> Self {
+        TableColumn::Bool(column)
+    }
+}
+
+// Именованный набор колонок с общим числом строк. Колонки ищутся по имени
+// и перечисляются в порядке добавления
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub name: String,
+    columns: Vec<TableColumn>,
+    index: HashMap<String, usize>,
+    // Схема, заданная заранее; без неё схема выводится из добавленных колонок
+    declared: Option<Schema>,
+}
+
+impl Table {
+    pub fn new(name: String) -> Self {
+        Self { name, columns: Vec::new(), index: HashMap::new(), declared: None }
+    }
+
+    // Таблица, которая принимает только колонки из schema с объявленными типами
+    pub fn with_schema(name: String, schema: Schema) -> Self {
+        Self { declared: Some(schema), ..Self::new(name) }
+    }
+
+    // Первая колонка задаёт число строк таблицы; имена колонок не повторяются
+    pub fn add_column(&mut self, column: impl Into<TableColumn>) -> Result<()> {
+        let column = column.into();
+        if self.index.contains_key(column.name()) {
+            return Err(invalid_input(format!("колонка {} уже есть в таблице {}", column.name(), self.name)));
+        }
+        if let Some(schema) = &self.declared {
+            let field = schema
+                .field(column.name())
+                .ok_or_else(|| invalid_input(format!("колонки {} нет в схеме таблицы {}", column.name(), self.name)))?;
+            if field.data_type != column.data_type() {
+                return Err(invalid_input(format!(
+                    "колонка {} объявлена как {:?}, а передана {:?}",
+                    field.name, field.data_type, column.data_type()
+                )));
+            }
+            if !field.nullable && column.has_nulls() {
+                return Err(invalid_input(format!("колонка {} объявлена без NULL, но содержит их", field.name)));
+            }
+        }
+        if !self.columns.is_empty() && column.len() != self.row_count() {
+            return Err(invalid_input(format!(
+                "в колонке {} {} строк, а в таблице {} — {}",
+                column.name(),
+                column.len(),
+                self.name,
+                self.row_count()
+            )));
+        }
+        self.index.insert(column.name().to_string(), self.columns.len());
+        self.columns.push(column);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TableColumn> {
+        self.index.get(name).map(|idx| &self.columns[*idx])
+    }
+
+    // То же, что get, но отсутствие колонки — ошибка InvalidInput
+    pub(crate) fn require(&self, name: &str) -> Result<&TableColumn> {
+        self.get(name).ok_or_else(|| invalid_input(format!("колонки {} нет в таблице {}", name, self.name)))
+    }
+
+    // Числовая колонка name; None, если её нет или значения другого типа
+    pub fn column<T: ColumnType>(&self, name: &str) -> Option<&Arc<Column<T>>> {
+        self.get(name)?.as_column()
+    }
+
+    pub fn columns(&self) -> &[TableColumn] {
+        &self.columns
+    }
+
+    pub fn column_names(&self) -> Vec<&str> {
+        self.columns.iter().map(TableColumn::name).collect()
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.columns.first().map_or(0, TableColumn::len)
+    }
+
+    // Объявленная схема или, если её нет, описания добавленных колонок
+    pub fn schema(&self) -> Schema {
+        match &self.declared {
+            Some(schema) => schema.clone(),
+            None => Schema::new(self.columns.iter().map(TableColumn::field).collect()),
+        }
+    }
+
+    // Записывает каждую колонку в свой файл внутри dir и затем манифест. Каждый файл
+    // заменяется атомарно, а файлы колонок прежнего сохранения, которых нет в новом
+    // манифесте, удаляются после его записи
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        let mut manifest = Vec::new();
+        manifest.extend_from_slice(MANIFEST_MAGIC);
+        write_str(&mut manifest, &self.name);
+        manifest.extend_from_slice(&(self.row_count() as u64).to_le_bytes());
+        manifest.extend_from_slice(&(self.columns.len() as u32).to_le_bytes());
+        let schema = self.schema();
+        for (idx, column) in self.columns.iter().enumerate() {
+            // Имя колонки может содержать что угодно, поэтому файл называется по номеру
+            let file_name = format!("{}.col", idx);
+            write_atomic(dir, &file_name, column.file_bytes())?;
+            write_deletions(&dir.join(&file_name), column.deleted_rows())?;
+            let field = schema.field(column.name()).cloned().unwrap_or_else(|| column.field());
+            write_str(&mut manifest, &field.name);
+            manifest.push(field.data_type.tag());
+            manifest.push(field.nullable as u8);
+            write_str(&mut manifest, &file_name);
+        }
+        let checksum = crc32fast::hash(&manifest);
+        manifest.extend_from_slice(&checksum.to_le_bytes());
+        write_atomic(dir, MANIFEST_FILE, &manifest)?;
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let column_file = match path.extension() {
+                Some(ext) if ext == "del" || ext == "lock" => path.with_extension(""),
+                _ => path.clone(),
+            };
+            let stale = column_file.extension().is_some_and(|ext| ext == "col")
+                && column_file.file_stem().and_then(|stem| stem.to_str()?.parse::<usize>().ok())
+                    .is_some_and(|idx| idx >= self.columns.len());
+            if stale {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Открывает таблицу, сохранённую save. Файлы колонок должны в точности
+    // соответствовать манифесту: пропавший или лишний файл — ошибка
+    pub fn load(dir: &Path) -> Result<Table> {
+        let manifest = fs::read(dir.join(MANIFEST_FILE))?;
+        if manifest.len() < MANIFEST_MAGIC.len() + 4 || &manifest[..MANIFEST_MAGIC.len()] != MANIFEST_MAGIC {
+            return Err(corrupt(format!("{} не является манифестом таблицы", dir.join(MANIFEST_FILE).display())));
+        }
+        let (body, checksum) = manifest.split_at(manifest.len() - 4);
+        if crc32fast::hash(body) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+            return Err(corrupt("контрольная сумма манифеста таблицы не совпадает"));
+        }
+        let mut r = ByteReader::new(&body[MANIFEST_MAGIC.len()..]);
+        let name = read_str(&mut r)?;
+        let row_count = r.u64()?;
+        let column_count = r.u32()?;
+        let mut fields = Vec::new();
+        let mut files = Vec::new();
+        for _ in 0..column_count {
+            let field_name = read_str(&mut r)?;
+            let tag = r.bytes(1)?[0];
+            let data_type = DataType::from_tag(tag)
+                .ok_or_else(|| corrupt(format!("неизвестный тип {} колонки {} в манифесте", tag, field_name)))?;
+            let nullable = r.bytes(1)?[0] != 0;
+            fields.push(Field::new(field_name, data_type, nullable));
+            files.push(read_str(&mut r)?);
+        }
+        let mut table = Table::with_schema(name, Schema::new(fields.clone()));
+
+        let listed: HashSet<&str> = files.iter().map(String::as_str).collect();
+        for entry in fs::read_dir(dir)? {
+            let file_name = entry?.file_name();
+            let file_name = file_name.to_string_lossy();
+            // Точкой начинаются временные файлы прерванного сохранения
+            let column_file = file_name
+                .strip_suffix(".del")
+                .or_else(|| file_name.strip_suffix(".lock"))
+                .unwrap_or(&file_name);
+            if file_name != MANIFEST_FILE && !file_name.starts_with('.') && !listed.contains(column_file) {
+                return Err(corrupt(format!("в каталоге таблицы {} лишний файл {}", table.name, file_name)));
+            }
+        }
+        for (field, file_name) in fields.into_iter().zip(files) {
+            let path = dir.join(&file_name);
+            if !path.exists() {
+                return Err(corrupt(format!(
+                    "нет файла {} колонки {} ({:?}) таблицы {}",
+                    file_name, field.name, field.data_type, table.name
+                )));
+            }
+            let stored_tag = read_header(&path)?.type_tag;
+            if stored_tag != field.data_type.tag() {
+                return Err(corrupt(format!(
+                    "файл {} хранит значения {}, а манифест объявляет колонку {} как {:?}",
+                    file_name, type_name(stored_tag), field.name, field.data_type
+                )));
+            }
+            let column = TableColumn::open(&path, field.data_type)?;
+            if column.name() != field.name || column.len() as u64 != row_count {
+                return Err(corrupt(format!(
+                    "файл {} не соответствует манифесту: колонка {} из {} строк вместо {} из {}",
+                    file_name, column.name(), column.len(), field.name, row_count
+                )));
+            }
+            table.add_column(column).map_err(|e| corrupt(format!("файл {} не соответствует манифесту: {}", file_name, e)))?;
+        }
+        Ok(table)
+    }
+
+    // Ключ кэша для колонки таблицы: одинаковые имена колонок в разных таблицах не пересекаются
+    pub fn cache_key(&self, column: &str) -> String {
+        format!("{}/{}", self.name, column)
+    }
+}
+
+fn read_header(path: &Path) -> Result<Header> {
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    File::open(path)?.take(HEADER_SIZE as u64).read_to_end(&mut header)?;
+    Header::decode(&header)
+}
+
+fn write_atomic(dir: &Path, file_name: &str, bytes: &[u8]) -> Result<()> {
+    let mut file = tempfile::Builder::new().prefix(".table-").tempfile_in(dir)?;
+    file.write_all(bytes)?;
+    file.as_file().sync_all()?;
+    file.persist(dir.join(file_name)).map_err(|e| e.error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ColumnarError;
+    use crate::{BoolColumnBuilder, Codec, ColumnBuilder, Encoding, StringColumnBuilder};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_table_lookup_and_row_count_checks() {
+        let mut table = Table::new("events".to_string());
+        assert_eq!(table.row_count(), 0);
+        table.add_column(ColumnBuilder::from_i32("id".to_string(), &[1, 2, 3]).build_in_memory().unwrap()).unwrap();
+        table.add_column(ColumnBuilder::from_i64("ts".to_string(), &[10, 20, 30]).build_in_memory().unwrap()).unwrap();
+        let tmp_file = NamedTempFile::new().unwrap();
+        let flags = BoolColumnBuilder::from_bools("ok".to_string(), &[true, false, true]).build(tmp_file.path()).unwrap();
+        table.add_column(flags).unwrap();
+
+        assert_eq!(table.row_count(), 3);
+        assert_eq!(table.column_names(), vec!["id", "ts", "ok"]);
+        assert_eq!(table.column::<i64>("ts").unwrap().get_value(2), Some(30));
+        assert!(table.column::<i32>("ts").is_none());
+        assert!(table.column::<i32>("ok").is_none());
+        assert!(table.get("missing").is_none());
+        assert_eq!(table.cache_key("id"), "events/id");
+
+        let short = ColumnBuilder::from_f64("value".to_string(), &[1.0, 2.0]).build_in_memory().unwrap();
+        assert!(matches!(table.add_column(short), Err(ColumnarError::InvalidInput(_))));
+        let tmp_file = NamedTempFile::new().unwrap();
+        let long = StringColumnBuilder::from_strs("label".to_string(), &["a", "b", "c", "d"])
+            .build(tmp_file.path())
+            .unwrap();
+        assert!(matches!(table.add_column(long), Err(ColumnarError::InvalidInput(_))));
+        let duplicate = ColumnBuilder::from_i32("id".to_string(), &[4, 5, 6]).build_in_memory().unwrap();
+        assert!(matches!(table.add_column(duplicate), Err(ColumnarError::InvalidInput(_))));
+        // Отклонённые колонки не меняют таблицу
+        assert_eq!(table.column_names(), vec!["id", "ts", "ok"]);
+    }
+
+    #[test]
+    fn test_declared_schema_checks_added_columns() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("score", DataType::Float64, true),
+        ]);
+        let mut table = Table::with_schema("scores".to_string(), schema.clone());
+        let wrong_type = ColumnBuilder::from_i64("id".to_string(), &[1, 2]).build_in_memory().unwrap();
+        assert!(matches!(table.add_column(wrong_type), Err(ColumnarError::InvalidInput(_))));
+        let undeclared = ColumnBuilder::from_i32("extra".to_string(), &[1, 2]).build_in_memory().unwrap();
+        assert!(matches!(table.add_column(undeclared), Err(ColumnarError::InvalidInput(_))));
+        let with_nulls = ColumnBuilder::<i32>::from_nullable("id".to_string(), &[Some(1), None]).build_in_memory().unwrap();
+        assert!(matches!(table.add_column(with_nulls), Err(ColumnarError::InvalidInput(_))));
+
+        table.add_column(ColumnBuilder::from_i32("id".to_string(), &[1, 2]).build_in_memory().unwrap()).unwrap();
+        let score = ColumnBuilder::<f64>::from_nullable("score".to_string(), &[None, Some(0.5)]).build_in_memory().unwrap();
+        table.add_column(score).unwrap();
+        assert_eq!(table.schema(), schema);
+
+        // Без объявленной схемы она выводится из колонок
+        let mut derived = Table::new("derived".to_string());
+        derived.add_column(table.get("score").unwrap().clone()).unwrap();
+        let tmp_file = NamedTempFile::new().unwrap();
+        derived.add_column(StringColumnBuilder::from_strs("tag".to_string(), &["a", "b"]).build(tmp_file.path()).unwrap()).unwrap();
+        assert_eq!(
+            derived.schema(),
+            Schema::new(vec![Field::new("score", DataType::Float64, true), Field::new("tag", DataType::Utf8, false)])
+        );
+        assert_eq!(derived.schema().index_of("tag"), Some(1));
+    }
+
+    fn sample_table() -> Table {
+        let ids: Vec<i32> = (0..5000).collect();
+        let mut id = ColumnBuilder::from_i32("id".to_string(), &ids);
+        id.set_encoding(Encoding::Delta);
+        id.compress_with(Codec::zstd()).unwrap();
+        let mut bucket = ColumnBuilder::from_i64("bucket".to_string(), &ids.iter().map(|i| (i / 100) as i64).collect::<Vec<_>>());
+        bucket.set_encoding(Encoding::Rle);
+        bucket.compress_with(Codec::Lz4).unwrap();
+        let value = ColumnBuilder::from_f64("value".to_string(), &ids.iter().map(|i| *i as f64 * 0.5).collect::<Vec<_>>());
+
+        let mut table = Table::new("readings".to_string());
+        table.add_column(id.build_in_memory().unwrap()).unwrap();
+        table.add_column(bucket.build_in_memory().unwrap()).unwrap();
+        table.add_column(value.build_in_memory().unwrap()).unwrap();
+        table
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let table = sample_table();
+        table.save(dir.path()).unwrap();
+        // Повторное сохранение поверх открытой таблицы заменяет файлы целиком
+        let loaded = Table::load(dir.path()).unwrap();
+        loaded.save(dir.path()).unwrap();
+        let loaded = Table::load(dir.path()).unwrap();
+
+        assert_eq!(loaded.name, "readings");
+        assert_eq!(loaded.row_count(), 5000);
+        assert_eq!(loaded.column_names(), vec!["id", "bucket", "value"]);
+        let id = loaded.column::<i32>("id").unwrap();
+        assert_eq!((id.codec, id.encoding), (Codec::zstd(), Encoding::Delta));
+        assert_eq!(id.values().unwrap(), table.column::<i32>("id").unwrap().values().unwrap());
+        let bucket = loaded.column::<i64>("bucket").unwrap();
+        assert_eq!((bucket.codec, bucket.encoding), (Codec::Lz4, Encoding::Rle));
+        assert_eq!(bucket.get_value(4321), Some(43));
+        assert_eq!(loaded.column::<f64>("value").unwrap().get_value(3), Some(1.5));
+        assert_eq!(loaded.schema(), table.schema());
+
+        // Таблица с меньшим числом колонок не оставляет файлов прежнего сохранения
+        let mut narrow = Table::new("readings".to_string());
+        narrow.add_column(loaded.get("value").unwrap().clone()).unwrap();
+        narrow.save(dir.path()).unwrap();
+        assert_eq!(Table::load(dir.path()).unwrap().column_names(), vec!["value"]);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_load_reports_missing_extra_and_damaged_files() {
+        let dir = tempfile::tempdir().unwrap();
+        sample_table().save(dir.path()).unwrap();
+        let error = |dir: &Path| match Table::load(dir) {
+            Err(ColumnarError::Corrupt { detail }) => detail,
+            other => panic!("ожидалась ошибка Corrupt, получено {:?}", other.map(|t| t.column_names().len())),
+        };
+
+        fs::write(dir.path().join("notes.txt"), b"x").unwrap();
+        assert!(error(dir.path()).contains("лишний файл notes.txt"));
+        fs::remove_file(dir.path().join("notes.txt")).unwrap();
+
+        fs::remove_file(dir.path().join("1.col")).unwrap();
+        let detail = error(dir.path());
+        assert!(detail.contains("1.col") && detail.contains("bucket"), "{}", detail);
+
+        let manifest = dir.path().join(MANIFEST_FILE);
+        let mut bytes = fs::read(&manifest).unwrap();
+        bytes[12] ^= 1;
+        fs::write(&manifest, bytes).unwrap();
+        assert!(error(dir.path()).contains("контрольная сумма"));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_load_rejects_manifest_type_that_disagrees_with_file() {
+        let dir = tempfile::tempdir().unwrap();
+        sample_table().save(dir.path()).unwrap();
+        // Манифест объявляет id как Int64, а в 0.col лежат значения i32
+        let manifest = dir.path().join(MANIFEST_FILE);
+        let bytes = fs::read(&manifest).unwrap();
+        let mut body = bytes[..bytes.len() - 4].to_vec();
+        let mut name = Vec::new();
+        write_str(&mut name, "id");
+        let at = body.windows(name.len()).position(|w| w == name.as_slice()).unwrap() + name.len();
+        assert_eq!(body[at], DataType::Int32.tag());
+        body[at] = DataType::Int64.tag();
+        let crc = crc32fast::hash(&body);
+        body.extend_from_slice(&crc.to_le_bytes());
+        fs::write(&manifest, body).unwrap();
+
+        match Table::load(dir.path()) {
+            Err(ColumnarError::Corrupt { detail }) => {
+                assert!(detail.contains("0.col") && detail.contains("Int64") && detail.contains("i32"), "{}", detail)
+            }
+            other => panic!("ожидалась ошибка Corrupt, получено {:?}", other.map(|t| t.column_names().len())),
+        }
+    }
+}
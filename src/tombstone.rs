@@ -0,0 +1,361 @@
+use std::{
+    borrow::Cow,
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use crate::compute::Selection;
+use crate::error::{corrupt, invalid_input, Result};
+use crate::lock::WriteLock;
+use crate::storage::Column;
+use crate::table::{Table, TableColumn};
+use crate::types::ColumnType;
+use crate::writer::sync_parent_dir;
+
+// Удалённые строки хранятся рядом с файлом колонки в <файл>.del, поэтому удаление
+// не трогает сам файл колонки. Файл только дописывается записями
+// [число строк: u32][номера строк: u64...][crc32 номеров: u32], по одной на вызов delete_rows
+pub(crate) fn deletions_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".del");
+    PathBuf::from(name)
+}
+
+// Удалённые строки колонки из row_count строк; None — удалений нет
+pub(crate) fn load_deletions(path: &Path, row_count: u64) -> Result<Option<Selection>> {
+    let bytes = match fs::read(deletions_path(path)) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut deleted = Selection::new(row_count as usize);
+    parse_records(&bytes, row_count, |row| deleted.set(row as usize))?;
+    Ok((deleted.count() > 0).then_some(deleted))
+}
+
+// Разбирает записи файла удалений и возвращает длину целых записей. Недописанная
+// последняя запись осталась от сбоя посреди delete_rows, который не завершился,
+// и отбрасывается; испорченная целая запись — ошибка
+fn parse_records(bytes: &[u8], row_count: u64, mut f: impl FnMut(u64)) -> Result<usize> {
+    let mut pos = 0;
+    while let Some(count) = bytes.get(pos..pos + 4) {
+        let rows_len = u32::from_le_bytes(count.try_into().unwrap()) as usize * 8;
+        let Some(record) = bytes.get(pos + 4..pos + 4 + rows_len + 4) else {
+            break;
+        };
+        let (rows, checksum) = record.split_at(rows_len);
+        if crc32fast::hash(rows) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+            return Err(corrupt(format!("контрольная сумма записи удалений со смещения {} не совпадает", pos)));
+        }
+        for row in rows.chunks_exact(8).map(|raw| u64::from_le_bytes(raw.try_into().unwrap())) {
+            if row >= row_count {
+                return Err(corrupt(format!("удалённая строка {} вне колонки из {} строк", row, row_count)));
+            }
+            f(row);
+        }
+        pos += 4 + record.len();
+    }
+    Ok(pos)
+}
+
+fn encode_records(rows: &[usize], out: &mut Vec<u8>) {
+    for part in rows.chunks(u32::MAX as usize) {
+        out.extend_from_slice(&(part.len() as u32).to_le_bytes());
+        let start = out.len();
+        for row in part {
+            out.extend_from_slice(&(*row as u64).to_le_bytes());
+        }
+        let checksum = crc32fast::hash(&out[start..]);
+        out.extend_from_slice(&checksum.to_le_bytes());
+    }
+}
+
+// Дописывает удаления и сбрасывает их на диск. Хвост недописанной записи сначала
+// отрезается, иначе новая запись читалась бы с неверного смещения
+fn append_deletions(path: &Path, rows: &[usize], row_count: u64) -> Result<()> {
+    let path = deletions_path(path);
+    let created = !path.exists();
+    let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)?;
+    let mut existing = Vec::new();
+    file.read_to_end(&mut existing)?;
+    let valid = parse_records(&existing, row_count, |_| ())?;
+    file.set_len(valid as u64)?;
+    file.seek(SeekFrom::Start(valid as u64))?;
+    let mut record = Vec::new();
+    encode_records(rows, &mut record);
+    file.write_all(&record)?;
+    file.sync_data()?;
+    if created {
+        sync_parent_dir(&path)?;
+    }
+    Ok(())
+}
+
+// Заменяет файл удалений колонки path целиком; None — удаляет его
+pub(crate) fn write_deletions(path: &Path, deleted: Option<&Selection>) -> Result<()> {
+    let Some(deleted) = deleted else {
+        return remove_deletions(path);
+    };
+    let target = deletions_path(path);
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut file = tempfile::Builder::new().prefix(".del-").tempfile_in(dir)?;
+    let rows: Vec<usize> = deleted.iter_indices().collect();
+    let mut bytes = Vec::new();
+    encode_records(&rows, &mut bytes);
+    file.write_all(&bytes)?;
+    file.as_file().sync_all()?;
+    file.persist(&target).map_err(|e| e.error)?;
+    Ok(())
+}
+
+// Удаления относятся к прежнему содержимому файла и после его перезаписи неверны
+pub(crate) fn remove_deletions(path: &Path) -> Result<()> {
+    match fs::remove_file(deletions_path(path)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+impl<T: ColumnType> Column<T> {
+    // Помечает строки удалёнными без перезаписи колонки: у колонки в файле номера
+    // дописываются в .del и сбрасываются на диск до возврата. iter, filter, aggregate
+    // и Table::scan пропускают удалённые строки, get_value и get_nullable возвращают
+    // для них None, а values, nullable_values и decompress_* по-прежнему отдают все
+    // строки по номерам. Строка вне колонки — InvalidInput, и тогда не удаляется
+    // ничего. Возвращает число впервые удалённых строк
+    pub fn delete_rows(&mut self, rows: &[usize]) -> Result<usize> {
+        if let Some(row) = rows.iter().find(|row| **row >= self.len()) {
+            return Err(invalid_input(format!("строка {} вне колонки из {} строк", row, self.len())));
+        }
+        let mut fresh: Vec<usize> = rows.iter().copied().filter(|row| !self.is_deleted(*row)).collect();
+        fresh.sort_unstable();
+        fresh.dedup();
+        if fresh.is_empty() {
+            return Ok(0);
+        }
+        if let Some(path) = &self.path {
+            let _lock = WriteLock::acquire(path)?;
+            append_deletions(path, &fresh, self.len() as u64)?;
+        }
+        let len = self.len();
+        let deleted = Arc::make_mut(self.deleted.get_or_insert_with(|| Arc::new(Selection::new(len))));
+        for row in &fresh {
+            deleted.set(*row);
+        }
+        Ok(fresh.len())
+    }
+
+    pub fn is_deleted(&self, row: usize) -> bool {
+        self.deleted.as_ref().is_some_and(|deleted| deleted.get(row))
+    }
+
+    // Число строк без удалённых; NULL считаются
+    pub fn live_count(&self) -> usize {
+        self.len() - self.deleted.as_deref().map_or(0, Selection::count)
+    }
+
+    pub(crate) fn deleted_rows(&self) -> Option<&Selection> {
+        self.deleted.as_deref()
+    }
+
+    // selection без удалённых строк; None — отобраны все строки и удалений нет
+    pub(crate) fn live_selection<'a>(&self, selection: Option<&'a Selection>) -> Option<Cow<'a, Selection>> {
+        match (selection, &self.deleted) {
+            (selection, None) => selection.map(Cow::Borrowed),
+            (None, Some(deleted)) => Some(Cow::Owned(deleted.not())),
+            (Some(selection), Some(deleted)) => {
+                let mut live = selection.clone();
+                live.difference_with(deleted);
+                Some(Cow::Owned(live))
+            }
+        }
+    }
+}
+
+impl Table {
+    // Строки, удалённые хотя бы в одной колонке; None — удалений нет
+    pub(crate) fn deleted_rows(&self) -> Option<Selection> {
+        let mut deleted = self.columns().iter().filter_map(TableColumn::deleted_rows);
+        let mut union = deleted.next()?.clone();
+        for other in deleted {
+            union.union_with(other);
+        }
+        Some(union)
+    }
+}
+
+impl TableColumn {
+    // Строковые и логические колонки удалений не поддерживают
+    pub(crate) fn deleted_rows(&self) -> Option<&Selection> {
+        match self {
+            TableColumn::Int32(column) => column.deleted_rows(),
+            TableColumn::Int64(column) => column.deleted_rows(),
+            TableColumn::Float64(column) => column.deleted_rows(),
+            TableColumn::Utf8(_) | TableColumn::Bool(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ColumnarError;
+    use crate::{Agg, AggValue, Codec, ColumnBuilder, ColumnPredicate, Expr, GroupKey, Predicate, Table};
+
+    fn values() -> Vec<Option<i64>> {
+        (0..1000).map(|i| (i % 9 != 4).then_some(i)).collect()
+    }
+
+    fn build(path: &Path) -> Column<i64> {
+        let mut builder = ColumnBuilder::from_nullable("v".to_string(), &values());
+        builder.set_chunk_rows(128);
+        builder.compress_with(Codec::Lz4).unwrap();
+        builder.build(path).unwrap()
+    }
+
+    #[test]
+    fn test_deleted_rows_are_skipped_by_reads_and_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let mut column = build(&path);
+        let doomed = [3, 500, 999, 130, 131, 4, 0];
+        assert_eq!(column.delete_rows(&doomed).unwrap(), 7);
+        assert_eq!(column.delete_rows(&[3, 3, 500]).unwrap(), 0);
+        assert!(deletions_path(&path).exists());
+
+        let live = |row: usize| !doomed.contains(&row);
+        let expected: Vec<Option<i64>> = values().into_iter().enumerate().filter(|(row, _)| live(*row)).map(|(_, v)| v).collect();
+        let present: Vec<i64> = expected.iter().flatten().copied().collect();
+        let check = |column: &Column<i64>| {
+            assert_eq!(column.len(), 1000);
+            assert_eq!(column.live_count(), 993);
+            assert_eq!(column.get_value(500), None);
+            assert_eq!(column.get_nullable(999), None);
+            assert_eq!(column.get_nullable(12), Some(Some(12)));
+            assert!(column.is_deleted(4) && !column.is_deleted(5));
+
+            let iterated: Vec<i64> = column.iter().map(Result::unwrap).collect();
+            assert_eq!(iterated.len(), 993);
+            assert!(expected.iter().zip(&iterated).all(|(e, got)| e.is_none_or(|v| v == *got)));
+
+            let selection = column.filter(Predicate::Lt(600)).unwrap();
+            assert!(!selection.get(500) && !selection.get(0) && selection.get(1));
+            assert_eq!(selection.count(), (0..600).filter(|row| live(*row) && row % 9 != 4).count());
+
+            assert_eq!(column.aggregate(Agg::Count, None).unwrap(), AggValue::Count(present.len() as u64));
+            assert_eq!(column.aggregate(Agg::Sum, None).unwrap(), AggValue::Sum(present.iter().map(|v| *v as i128).sum()));
+            assert_eq!(column.aggregate(Agg::Min, None).unwrap(), AggValue::Min(Some(1)));
+            assert_eq!(column.aggregate(Agg::Max, None).unwrap(), AggValue::Max(Some(998)));
+            let small: i128 = present.iter().filter(|v| **v < 600).map(|v| *v as i128).sum();
+            assert_eq!(column.sum(Some(&Selection::full(1000).and(&selection))).unwrap(), small);
+            // Отбор с удалённой строкой её всё равно не учитывает
+            assert_eq!(column.count(Some(&Selection::from_indices(1000, &[500, 501]).unwrap())).unwrap(), 1);
+        };
+        check(&column);
+        check(&Column::open(&path).unwrap());
+
+        // Дозапись сохраняет удаления, а перезапись файла их сбрасывает
+        column.append(&[5000, 5001]).unwrap();
+        assert_eq!(column.live_count(), 995);
+        assert_eq!(column.aggregate(Agg::Max, None).unwrap(), AggValue::Max(Some(5001)));
+        drop(column);
+        let rebuilt = build(&path);
+        assert!(!deletions_path(&path).exists());
+        assert_eq!(rebuilt.live_count(), 1000);
+    }
+
+    #[test]
+    fn test_in_memory_deletions_and_table_scan() {
+        let mut builder = ColumnBuilder::from_nullable("v".to_string(), &values());
+        builder.set_chunk_rows(100);
+        let mut column = builder.build_in_memory().unwrap();
+        assert!(matches!(column.delete_rows(&[1, 1000]), Err(ColumnarError::InvalidInput(_))));
+        assert_eq!(column.live_count(), 1000);
+        column.delete_rows(&(0..1000).filter(|row| row % 2 == 0).collect::<Vec<_>>()).unwrap();
+        assert_eq!(column.live_count(), 500);
+        column.append(&[-1]).unwrap();
+        assert_eq!(column.live_count(), 501);
+        assert_eq!(column.aggregate(Agg::Min, None).unwrap(), AggValue::Min(Some(-1)));
+
+        let mut table = Table::new("t".to_string());
+        table.add_column(column).unwrap();
+        // Not отбирает и NULL, но не удалённые строки
+        let large = Expr::from(ColumnPredicate::Int64("v".to_string(), Predicate::Gt(20)));
+        let rows: Vec<usize> = table
+            .scan(Expr::Not(Box::new(large)), &["v"])
+            .unwrap()
+            .flat_map(|batch| batch.unwrap().rows)
+            .collect();
+        let mut expected: Vec<usize> = (0..1000).filter(|row| row % 2 == 1 && (*row <= 20 || row % 9 == 4)).collect();
+        expected.push(1000);
+        assert_eq!(rows, expected);
+        let dir = tempfile::tempdir().unwrap();
+        table.save(dir.path()).unwrap();
+        let loaded = Table::load(dir.path()).unwrap();
+        assert_eq!(loaded.column::<i64>("v").unwrap().live_count(), 501);
+    }
+
+    #[test]
+    fn test_operators_skip_deleted_rows() {
+        let mut column = ColumnBuilder::from_i32("k".to_string(), &[1, 1, 2, 100]).build_in_memory().unwrap();
+        column.delete_rows(&[3]).unwrap();
+        assert_eq!(column.top_k(1, false).unwrap(), [(2, 2)]);
+        assert_eq!(column.distinct(None).unwrap(), [1, 2]);
+        assert!(!column.contains(100).unwrap());
+        assert!(column.contains(2).unwrap());
+        assert_eq!(column.scan_range(2, 200).unwrap(), [2]);
+
+        let mut table = Table::new("t".to_string());
+        table.add_column(column).unwrap();
+        let groups = table.group_by("k", &[("k", Agg::Count)], None).unwrap();
+        assert_eq!(groups.iter().map(|g| g.key.clone()).collect::<Vec<_>>(), [GroupKey::Int32(1), GroupKey::Int32(2)]);
+        let mut other = Table::new("o".to_string());
+        other.add_column(ColumnBuilder::from_i32("id".to_string(), &[100, 2]).build_in_memory().unwrap()).unwrap();
+        let pairs: Vec<(usize, usize)> = table
+            .join(&other, "k", "id", &[])
+            .unwrap()
+            .flat_map(|batch| {
+                let batch = batch.unwrap();
+                batch.left_rows.into_iter().zip(batch.right_rows).collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(pairs, [(2, 1)]);
+        // Удаление на стороне, по которой строится хэш-таблица, тоже учитывается
+        let mut ids = ColumnBuilder::from_i32("id".to_string(), &[100, 2]).build_in_memory().unwrap();
+        ids.delete_rows(&[1]).unwrap();
+        let mut other = Table::new("o".to_string());
+        other.add_column(ids).unwrap();
+        assert_eq!(table.join(&other, "k", "id", &[]).unwrap().count(), 0);
+
+        // find пропускает удалённое совпадение, range_indices остаётся физическим
+        let mut sorted = ColumnBuilder::from_i32("s".to_string(), &[1, 2, 2, 3, 4]).build_in_memory().unwrap();
+        sorted.delete_rows(&[1]).unwrap();
+        assert_eq!(sorted.find(2).unwrap(), Some(2));
+        sorted.delete_rows(&[2]).unwrap();
+        assert_eq!(sorted.find(2).unwrap(), None);
+        assert_eq!(sorted.find(3).unwrap(), Some(3));
+        assert_eq!(sorted.range_indices(2, 3).unwrap(), 1..4);
+    }
+
+    #[test]
+    fn test_append_after_deleting_last_row_keeps_sort_boundary() {
+        let mut column = ColumnBuilder::from_i32("s".to_string(), &[1, 2, 3, 10]).build_in_memory().unwrap();
+        assert!(column.is_sorted);
+        column.delete_rows(&[3]).unwrap();
+        column.append(&[5]).unwrap();
+        assert!(!column.is_sorted, "5 записано после хранимого 10");
+        assert!(column.find(5).is_err());
+        assert_eq!(column.values().unwrap(), [1, 2, 3, 10, 5]);
+
+        let mut larger = ColumnBuilder::from_i32("s".to_string(), &[1, 2, 3, 10]).build_in_memory().unwrap();
+        larger.delete_rows(&[3]).unwrap();
+        larger.append(&[11]).unwrap();
+        assert!(larger.is_sorted);
+        assert_eq!(larger.find(11).unwrap(), Some(4));
+    }
+}
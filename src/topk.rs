@@ -0,0 +1,156 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+use crate::bools::bit_is_set;
+use crate::error::Result;
+use crate::storage::Column;
+use crate::types::ColumnType;
+
+// Элемент кучи; на вершине — худший из отобранных, его и вытесняют
+struct Candidate<T: ColumnType> {
+    value: T,
+    row: usize,
+    ascending: bool,
+}
+
+impl<T: ColumnType> Candidate<T> {
+    // Greater — хуже: дальше от начала результата; при равных значениях хуже больший номер строки
+    fn rank(&self, other: &Self) -> Ordering {
+        let by_value = if self.ascending {
+            self.value.total_cmp(&other.value)
+        } else {
+            other.value.total_cmp(&self.value)
+        };
+        by_value.then(self.row.cmp(&other.row))
+    }
+}
+
+impl<T: ColumnType> PartialEq for Candidate<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank(other).is_eq()
+    }
+}
+
+impl<T: ColumnType> Eq for Candidate<T> {}
+
+impl<T: ColumnType> PartialOrd for Candidate<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: ColumnType> Ord for Candidate<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank(other)
+    }
+}
+
+impl<T: ColumnType> Column<T> {
+    // k наибольших (ascending = false) или наименьших значений с номерами строк, от лучшего
+    // к худшему; при равных значениях раньше идёт меньший номер строки. NULL, NaN
+    // и удалённые строки пропускаются. Чанки обходятся от лучшей границы zone map, и чанк, граница которого
+    // не может вытеснить худший элемент заполненной кучи, не распаковывается
+    pub fn top_k(&self, k: usize, ascending: bool) -> Result<Vec<(usize, T)>> {
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+        let chunks = self.chunks();
+        let bound = |idx: usize| if ascending { chunks[idx].min } else { chunks[idx].max };
+        // У чанка без значений min остаётся больше max
+        let mut order: Vec<usize> = (0..chunks.len())
+            .filter(|idx| chunks[*idx].min.total_cmp(&chunks[*idx].max).is_le())
+            .collect();
+        order.sort_by(|a, b| {
+            let ordering = bound(*a).total_cmp(&bound(*b));
+            if ascending { ordering } else { ordering.reverse() }
+        });
+
+        let mut heap: BinaryHeap<Candidate<T>> = BinaryHeap::with_capacity(k.min(self.len()) + 1);
+        for idx in order {
+            let chunk = &chunks[idx];
+            if heap.len() == k {
+                let best_possible = Candidate { value: bound(idx), row: chunk.first_row as usize, ascending };
+                if best_possible >= *heap.peek().unwrap() {
+                    continue;
+                }
+            }
+            let values = self.chunk_values(idx)?;
+            let validity = self.chunk_validity(idx)?;
+            for (row, value) in values.chunks_exact(T::WIDTH).enumerate() {
+                if !validity.as_ref().is_none_or(|bits| bit_is_set(bits, row)) || self.is_deleted(chunk.first_row as usize + row) {
+                    continue;
+                }
+                let value = T::read_le(value);
+                if value.is_nan() {
+                    continue;
+                }
+                let candidate = Candidate { value, row: chunk.first_row as usize + row, ascending };
+                if heap.len() < k {
+                    heap.push(candidate);
+                } else if candidate < *heap.peek().unwrap() {
+                    heap.pop();
+                    heap.push(candidate);
+                }
+            }
+        }
+        Ok(heap.into_sorted_vec().into_iter().map(|c| (c.row, c.value)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Codec, ColumnBuilder};
+    use tempfile::NamedTempFile;
+
+    fn naive(values: &[i32], k: usize, ascending: bool) -> Vec<(usize, i32)> {
+        let mut rows: Vec<(usize, i32)> = values.iter().copied().enumerate().collect();
+        rows.sort_by(|a, b| {
+            let by_value = if ascending { a.1.cmp(&b.1) } else { b.1.cmp(&a.1) };
+            by_value.then(a.0.cmp(&b.0))
+        });
+        rows.truncate(k);
+        rows
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_top_k_matches_sorting_with_ties() {
+        // Много повторов, чтобы проверить порядок при равных значениях
+        let values: Vec<i32> = (0..50_000).map(|i| (i * 7919) % 1000).collect();
+        let mut builder = ColumnBuilder::from_i32("top".to_string(), &values);
+        builder.compress_with(Codec::zstd()).unwrap();
+        builder.set_chunk_rows(5000);
+        let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+
+        for k in [1, 7, 100, 1234] {
+            assert_eq!(column.top_k(k, false).unwrap(), naive(&values, k, false), "k={}", k);
+            assert_eq!(column.top_k(k, true).unwrap(), naive(&values, k, true), "k={}", k);
+        }
+        assert!(column.top_k(0, false).unwrap().is_empty());
+        let all = column.top_k(1_000_000, true).unwrap();
+        assert_eq!(all.len(), values.len());
+        assert_eq!(all, naive(&values, values.len(), true));
+    }
+
+    #[test]
+    fn test_top_k_skips_chunks_by_zone_map() {
+        // Значения растут, поэтому наибольшие лежат в последнем чанке
+        let values: Vec<i32> = (0..100_000).collect();
+        let mut builder = ColumnBuilder::from_i32("sorted".to_string(), &values);
+        builder.compress_with(Codec::Lz4).unwrap();
+        builder.set_chunk_rows(10_000);
+        let column = builder.build(NamedTempFile::new().unwrap().path()).unwrap();
+
+        let top = column.top_k(100, false).unwrap();
+        assert_eq!(top[0], (99_999, 99_999));
+        assert_eq!(top[99], (99_900, 99_900));
+        assert_eq!(column.frames_decoded(), 1);
+
+        let nullable = ColumnBuilder::from_nullable("n".to_string(), &[Some(3), None, Some(9), Some(3)])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert_eq!(nullable.top_k(10, true).unwrap(), vec![(0, 3), (3, 3), (2, 9)]);
+        let floats = ColumnBuilder::from_f64("f".to_string(), &[f64::NAN, 2.5, -1.0])
+            .build(NamedTempFile::new().unwrap().path())
+            .unwrap();
+        assert_eq!(floats.top_k(2, false).unwrap(), vec![(1, 2.5), (2, -1.0)]);
+    }
+}
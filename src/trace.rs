@@ -0,0 +1,186 @@
+// Диагностика через крейт log (функция log): события уровня debug с полями
+// key=value. Цели и имена полей стабильны, на них можно строить дашборды; в
+// tracing события переводит tracing-log. Поле event — имя события, у span-ов это
+// пара событий с phase = enter и phase = exit, у выхода есть elapsed_us.
+//   columnar::build      span build: column, rows, codec
+//                        span compress: column, rows, chunks, bytes
+//   columnar::decompress span decompress_parallel: column, chunks, bytes
+//   columnar::cache      get: key, hit, tier (lfu, lru, negative или none)
+//                        insert: key, tier
+//   columnar::prefetch   item: column, queue_depth, latency_us, outcome
+//                        (hit, loaded, skipped или error)
+#[cfg(feature = "log")]
+use std::time::Instant;
+
+#[cfg(feature = "log")]
+macro_rules! event {
+    ($target:literal, $name:literal $(, $key:ident = $value:expr)* $(,)?) => {
+        log::debug!(target: $target, event = $name $(, $key = $value)*; $name)
+    };
+}
+
+// Без функции log значения не вычисляются, но остаются использованными
+#[cfg(not(feature = "log"))]
+macro_rules! event {
+    ($target:literal, $name:literal $(, $key:ident = $value:expr)* $(,)?) => {
+        if false {
+            $(let _ = &$value;)*
+        }
+    };
+}
+
+// Событие входа сразу, событие выхода при удалении возвращённого Span. Поле column
+// есть у обоих событий, чтобы выход можно было сопоставить со входом
+macro_rules! span {
+    ($target:literal, $name:literal, column = $column:expr $(, $key:ident = $value:expr)* $(,)?) => {{
+        $crate::trace::event!($target, $name, phase = "enter", column = $column $(, $key = $value)*);
+        $crate::trace::Span::enter($target, $name, &$column)
+    }};
+}
+
+pub(crate) use {event, span};
+
+// Поле события по Display, для значений без своего представления в log
+#[cfg(feature = "log")]
+pub(crate) fn display<T: std::fmt::Display>(value: &T) -> log::kv::Value<'_> {
+    log::kv::Value::from_display(value)
+}
+
+#[cfg(not(feature = "log"))]
+pub(crate) fn display<T: std::fmt::Display>(value: &T) -> &T {
+    value
+}
+
+#[cfg(feature = "log")]
+pub(crate) struct Span {
+    target: &'static str,
+    name: &'static str,
+    column: String,
+    start: Instant,
+}
+
+#[cfg(not(feature = "log"))]
+pub(crate) struct Span;
+
+impl Span {
+    #[cfg(feature = "log")]
+    pub(crate) fn enter(target: &'static str, name: &'static str, column: &str) -> Self {
+        Self { target, name, column: column.to_string(), start: Instant::now() }
+    }
+
+    #[cfg(not(feature = "log"))]
+    pub(crate) fn enter(_: &'static str, _: &'static str, _: &str) -> Self {
+        Span
+    }
+}
+
+#[cfg(feature = "log")]
+impl Drop for Span {
+    fn drop(&mut self) {
+        let elapsed_us = self.start.elapsed().as_micros() as u64;
+        log::debug!(
+            target: self.target,
+            event = self.name,
+            phase = "exit",
+            column = self.column.as_str(),
+            elapsed_us = elapsed_us;
+            "{}", self.name
+        );
+    }
+}
+
+#[cfg(all(test, feature = "log"))]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex, OnceLock},
+        thread,
+        time::Duration,
+    };
+    use log::kv::{Error, Key, Value, VisitSource};
+    use crate::{ColumnBuilder, ConcurrentHybridCache, Prefetcher};
+
+    type Fields = HashMap<String, String>;
+
+    // Логгер процесса, собирающий события крейта; тесты выбирают свои по полям
+    struct Collector(Mutex<Vec<(String, Fields)>>);
+
+    struct Visitor<'a>(&'a mut Fields);
+
+    impl<'kvs> VisitSource<'kvs> for Visitor<'_> {
+        fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), Error> {
+            self.0.insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+    }
+
+    impl log::Log for Collector {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.target().starts_with("columnar::")
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                let mut fields = Fields::new();
+                record.key_values().visit(&mut Visitor(&mut fields)).unwrap();
+                self.0.lock().unwrap().push((record.target().to_string(), fields));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn collector() -> &'static Collector {
+        static COLLECTOR: OnceLock<&'static Collector> = OnceLock::new();
+        COLLECTOR.get_or_init(|| {
+            let collector = Box::leak(Box::new(Collector(Mutex::new(Vec::new()))));
+            log::set_logger(collector).unwrap();
+            log::set_max_level(log::LevelFilter::Debug);
+            collector
+        })
+    }
+
+    fn matching(target: &str, event: &str, field: &str, value: &str) -> Vec<Fields> {
+        let events = collector().0.lock().unwrap();
+        events
+            .iter()
+            .filter(|(t, fields)| {
+                t == target && fields.get("event").is_some_and(|e| e == event) && fields.get(field).is_some_and(|v| v == value)
+            })
+            .map(|(_, fields)| fields.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_prefetch_through_cache_emits_spans_and_events() {
+        collector();
+        let values: Vec<i32> = (0..50_000).collect();
+        let mut builder = ColumnBuilder::from_i32("traced".to_string(), &values);
+        builder.set_chunk_rows(10_000);
+        let column = builder.build_in_memory().unwrap();
+        let build = matching("columnar::build", "build", "column", "traced");
+        assert_eq!(build.iter().map(|f| f["phase"].as_str()).collect::<Vec<_>>(), ["enter", "exit"]);
+        assert_eq!((build[0]["rows"].as_str(), build[0]["codec"].as_str()), ("50000", "none"));
+        assert!(build[1].contains_key("elapsed_us"));
+        let compress = matching("columnar::build", "compress", "column", "traced");
+        assert_eq!((compress[0]["chunks"].as_str(), compress[0]["bytes"].as_str()), ("5", "200000"));
+
+        let cache = Arc::new(ConcurrentHybridCache::new(10, 1).unwrap());
+        let prefetcher = Prefetcher::new(column, cache.clone());
+        prefetcher.schedule_prefetch("traced".to_string());
+        thread::sleep(Duration::from_millis(100));
+        prefetcher.schedule_prefetch("traced".to_string());
+        thread::sleep(Duration::from_millis(100));
+
+        let decompress = matching("columnar::decompress", "decompress_parallel", "column", "traced");
+        assert_eq!(decompress.len(), 2);
+        assert_eq!((decompress[0]["chunks"].as_str(), decompress[0]["bytes"].as_str()), ("5", "200000"));
+        let gets = matching("columnar::cache", "get", "key", "traced");
+        assert_eq!(gets.iter().map(|f| (f["hit"].as_str(), f["tier"].as_str())).collect::<Vec<_>>(), [("false", "none")]);
+        let inserts = matching("columnar::cache", "insert", "key", "traced");
+        assert_eq!((inserts.len(), inserts[0]["tier"].as_str()), (1, "lru"));
+        let items = matching("columnar::prefetch", "item", "column", "traced");
+        assert_eq!(items.iter().map(|f| f["outcome"].as_str()).collect::<Vec<_>>(), ["loaded", "hit"]);
+        assert!(items.iter().all(|f| f.contains_key("queue_depth") && f.contains_key("latency_us")));
+    }
+}
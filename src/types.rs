@@ -0,0 +1,265 @@
+use std::{cmp::Ordering, fmt::Debug};
+use crate::bloom::Bloom;
+
+// Тип значений колонки фиксированной ширины. Тег записывается в заголовок файла,
+// поэтому колонку нельзя открыть как значения другого типа.
+pub trait ColumnType: Copy + PartialOrd + Debug + Send + Sync + 'static {
+    const TAG: u8;
+    const DATA_TYPE: DataType;
+    const WIDTH: usize;
+    // Начальные значения статистики пустой колонки
+    const MIN: Self;
+    const MAX: Self;
+    // Строятся ли для типа bloom-фильтры; для чисел с плавающей точкой точное
+    // совпадение почти не используется, и фильтры не пишутся. Без функции bloom
+    // фильтры не пишутся ни для какого типа
+    const HAS_BLOOM: bool;
+    // Тип суммы: для целых шире самого типа, чтобы сумма не переполнялась
+    type Sum: Copy + Default + PartialEq + Debug + Send + std::ops::Add<Output = Self::Sum>;
+
+    fn write_le(self, out: &mut Vec<u8>);
+    // bytes — ровно WIDTH байт
+    fn read_le(bytes: &[u8]) -> Self;
+    // Значение как i64 для delta-кодирования; обратное преобразование может обрезать старшие биты
+    fn to_bits(self) -> i64;
+    fn from_bits(bits: i64) -> Self;
+    // Полный порядок для словаря и статистики
+    fn total_cmp(&self, other: &Self) -> Ordering;
+    fn widen(self) -> Self::Sum;
+    fn sum_to_f64(sum: Self::Sum) -> f64;
+
+    // Значения, которые не участвуют в min/max
+    fn is_nan(&self) -> bool {
+        false
+    }
+
+    fn bloom_set(bloom: &mut Bloom<Self>, value: &Self);
+    // Для типов без bloom-фильтров всегда true
+    fn bloom_check(bloom: &Bloom<Self>, value: &Self) -> bool;
+}
+
+impl ColumnType for i32 {
+    type Sum = i64;
+    const TAG: u8 = 0;
+    const DATA_TYPE: DataType = DataType::Int32;
+    const WIDTH: usize = 4;
+    const MIN: Self = i32::MIN;
+    const MAX: Self = i32::MAX;
+    const HAS_BLOOM: bool = cfg!(feature = "bloom");
+
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        i32::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    fn to_bits(self) -> i64 {
+        self as i64
+    }
+
+    fn from_bits(bits: i64) -> Self {
+        bits as i32
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+
+    fn widen(self) -> i64 {
+        self as i64
+    }
+
+    fn sum_to_f64(sum: i64) -> f64 {
+        sum as f64
+    }
+
+    fn bloom_set(bloom: &mut Bloom<Self>, value: &Self) {
+        bloom.set(value)
+    }
+
+    fn bloom_check(bloom: &Bloom<Self>, value: &Self) -> bool {
+        bloom.check(value)
+    }
+}
+
+impl ColumnType for i64 {
+    type Sum = i128;
+    const TAG: u8 = 1;
+    const DATA_TYPE: DataType = DataType::Int64;
+    const WIDTH: usize = 8;
+    const MIN: Self = i64::MIN;
+    const MAX: Self = i64::MAX;
+    const HAS_BLOOM: bool = cfg!(feature = "bloom");
+
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        i64::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    fn to_bits(self) -> i64 {
+        self
+    }
+
+    fn from_bits(bits: i64) -> Self {
+        bits
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+
+    fn widen(self) -> i128 {
+        self as i128
+    }
+
+    fn sum_to_f64(sum: i128) -> f64 {
+        sum as f64
+    }
+
+    fn bloom_set(bloom: &mut Bloom<Self>, value: &Self) {
+        bloom.set(value)
+    }
+
+    fn bloom_check(bloom: &Bloom<Self>, value: &Self) -> bool {
+        bloom.check(value)
+    }
+}
+
+// Пустая колонка и колонка из одних NaN имеют min = +inf, max = -inf
+impl ColumnType for f64 {
+    type Sum = f64;
+    const TAG: u8 = 2;
+    const DATA_TYPE: DataType = DataType::Float64;
+    const WIDTH: usize = 8;
+    const MIN: Self = f64::NEG_INFINITY;
+    const MAX: Self = f64::INFINITY;
+    const HAS_BLOOM: bool = false;
+
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        f64::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    fn to_bits(self) -> i64 {
+        f64::to_bits(self) as i64
+    }
+
+    fn from_bits(bits: i64) -> Self {
+        f64::from_bits(bits as u64)
+    }
+
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f64::total_cmp(self, other)
+    }
+
+    fn widen(self) -> f64 {
+        self
+    }
+
+    fn sum_to_f64(sum: f64) -> f64 {
+        sum
+    }
+
+    fn is_nan(&self) -> bool {
+        f64::is_nan(*self)
+    }
+
+    fn bloom_set(_: &mut Bloom<Self>, _: &Self) {}
+
+    fn bloom_check(_: &Bloom<Self>, _: &Self) -> bool {
+        true
+    }
+}
+
+// Тип значений колонки любого вида: числовой Column<T>, StringColumn или BoolColumn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataType {
+    Int32,
+    Int64,
+    Float64,
+    Utf8,
+    Bool,
+}
+
+impl DataType {
+    // Тег типа в заголовке файла колонки
+    pub fn tag(self) -> u8 {
+        match self {
+            DataType::Int32 => 0,
+            DataType::Int64 => 1,
+            DataType::Float64 => 2,
+            DataType::Utf8 => 3,
+            DataType::Bool => 4,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        Some(match tag {
+            0 => DataType::Int32,
+            1 => DataType::Int64,
+            2 => DataType::Float64,
+            3 => DataType::Utf8,
+            4 => DataType::Bool,
+            _ => return None,
+        })
+    }
+}
+
+// Имя типа по тегу из заголовка — для сообщений об ошибках
+pub(crate) fn type_name(tag: u8) -> &'static str {
+    match tag {
+        0 => "i32",
+        1 => "i64",
+        2 => "f64",
+        3 => "str",
+        4 => "bool",
+        _ => "неизвестный тип",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<T: ColumnType>(value: T) -> T {
+        let mut out = Vec::new();
+        value.write_le(&mut out);
+        assert_eq!(out.len(), T::WIDTH);
+        let decoded = T::read_le(&out);
+        assert!(T::from_bits(decoded.to_bits()).total_cmp(&decoded).is_eq());
+        decoded
+    }
+
+    #[test]
+    fn test_value_roundtrip_and_order() {
+        for value in [0i32, -1, i32::MIN, i32::MAX] {
+            assert_eq!(roundtrip(value), value);
+        }
+        for value in [0i64, -1, i64::MIN, i64::MAX] {
+            assert_eq!(roundtrip(value), value);
+        }
+        for value in [0.0f64, -0.0, f64::INFINITY, f64::MIN_POSITIVE] {
+            assert_eq!(roundtrip(value).to_bits(), value.to_bits());
+        }
+        assert!(roundtrip(f64::NAN).is_nan());
+
+        // Полный порядок различает нули и ставит NaN после +inf
+        assert!(ColumnType::total_cmp(&-0.0f64, &0.0).is_lt());
+        assert!(ColumnType::total_cmp(&f64::NAN, &f64::INFINITY).is_gt());
+        assert!(!5i64.is_nan());
+        assert_ne!(type_name(i64::TAG), type_name(f64::TAG));
+        for data_type in [DataType::Int32, DataType::Int64, DataType::Float64, DataType::Utf8, DataType::Bool] {
+            assert_eq!(DataType::from_tag(data_type.tag()), Some(data_type));
+        }
+        assert_eq!(i64::DATA_TYPE.tag(), i64::TAG);
+        assert_eq!(DataType::from_tag(5), None);
+    }
+}
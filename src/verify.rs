@@ -0,0 +1,305 @@
+use std::borrow::Cow;
+use crate::bools::bit_is_set;
+use crate::codec::Codec;
+use crate::encoding::Encoding;
+use crate::error::Result;
+use crate::format::{ChunkMeta, Footer, Header};
+use crate::par::*;
+use crate::storage::Column;
+use crate::types::ColumnType;
+use crate::writer::is_framed;
+
+// Значения чанка и его битовая карта валидности, если в чанке есть NULL
+type DecodedChunk<'a> = (Cow<'a, [u8]>, Option<Cow<'a, [u8]>>);
+
+// Параметры проверки целостности колонки
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VerifyOptions {
+    // Только структура файла и контрольные суммы чанков, без распаковки
+    pub quick: bool,
+}
+
+// Найденное при проверке расхождение
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyProblem {
+    // Заголовок, footer или индекс чанков не согласованы между собой
+    Structure(String),
+    Checksum { chunk: usize, expected: u32, actual: u32 },
+    // Чанк не распаковывается или распаковывается не в заявленный размер
+    Decode { chunk: usize, message: String },
+    // Сводка в метаданных не совпадает с пересчитанной по данным; chunk None — сводка колонки
+    Metadata { chunk: Option<usize>, message: String },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub chunks_checked: usize,
+    // Строки распакованных чанков; в быстром режиме 0
+    pub rows_checked: u64,
+    // По порядку чанков, сначала расхождения всей колонки
+    pub problems: Vec<VerifyProblem>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl<T: ColumnType> Column<T> {
+    // Полная проверка колонки, например скопированной по ненадёжному каналу: каждый
+    // чанк сверяется с контрольной суммой, распаковывается, и его число строк, NULL и
+    // min/max пересчитываются и сравниваются с метаданными. Проверка не
+    // останавливается на первом расхождении, а собирает все в отчёт
+    pub fn verify(&self) -> Result<VerifyReport> {
+        self.verify_with(VerifyOptions::default())
+    }
+
+    // Чанки проверяются параллельно; контрольные суммы считаются заново, даже если
+    // чтение уже сверяло их или сверка отключена set_verify_checksums
+    pub fn verify_with(&self, options: VerifyOptions) -> Result<VerifyReport> {
+        // Колонка из внешнего источника проверяется по файлу, скачанному целиком
+        let file = self.backing.whole()?;
+        let mut problems = self.verify_structure(file);
+        let per_chunk: Vec<Vec<VerifyProblem>> =
+            (0..self.chunks.len()).into_par_iter().map(|idx| self.verify_chunk(file, idx, options.quick)).collect();
+        problems.extend(per_chunk.into_iter().flatten());
+        Ok(VerifyReport {
+            chunks_checked: self.chunks.len(),
+            rows_checked: if options.quick { 0 } else { self.len() as u64 },
+            problems,
+        })
+    }
+
+    fn verify_structure(&self, file: &[u8]) -> Vec<VerifyProblem> {
+        let mut problems = Vec::new();
+        match Header::decode(file) {
+            Ok(header) if header.type_tag != T::TAG => {
+                problems.push(VerifyProblem::Structure(format!("тег типа {} в заголовке", header.type_tag)));
+            }
+            Ok(header) if header.row_count != self.len() as u64 => {
+                problems.push(VerifyProblem::Structure(format!(
+                    "в заголовке {} строк, а в колонке {}",
+                    header.row_count, self.len()
+                )));
+            }
+            Ok(_) => {}
+            Err(e) => problems.push(VerifyProblem::Structure(e.to_string())),
+        }
+        // Footer разбирается заново: он проверяет, что чанки вплотную покрывают секцию данных
+        if let Err(e) = Footer::<T>::decode(file) {
+            problems.push(VerifyProblem::Structure(e.to_string()));
+        }
+        let rows_in_chunks = self.chunks.last().map_or(0, ChunkMeta::end_row);
+        if rows_in_chunks != self.len() as u64 {
+            problems.push(VerifyProblem::Structure(format!(
+                "чанки покрывают {} строк из {}",
+                rows_in_chunks, self.len()
+            )));
+        }
+
+        let null_count: u64 = self.chunks.iter().map(|c| c.null_count).sum();
+        if null_count != self.null_count {
+            problems.push(VerifyProblem::Metadata {
+                chunk: None,
+                message: format!("в чанках {} NULL, а в сводке колонки {}", null_count, self.null_count),
+            });
+        }
+        let (min, max) = self.chunks.iter().fold((T::MAX, T::MIN), |(min, max), c| {
+            (if c.min.total_cmp(&min).is_lt() { c.min } else { min }, if c.max.total_cmp(&max).is_gt() { c.max } else { max })
+        });
+        if min.total_cmp(&self.min).is_ne() || max.total_cmp(&self.max).is_ne() {
+            problems.push(VerifyProblem::Metadata {
+                chunk: None,
+                message: format!(
+                    "min/max колонки {:?}/{:?}, а по чанкам {:?}/{:?}",
+                    self.min, self.max, min, max
+                ),
+            });
+        }
+        problems
+    }
+
+    fn verify_chunk(&self, file: &[u8], idx: usize, quick: bool) -> Vec<VerifyProblem> {
+        let chunk = &self.chunks[idx];
+        let mut problems = Vec::new();
+        let Some(stored) = file.get(chunk.offset as usize..chunk.stored_end() as usize) else {
+            problems.push(VerifyProblem::Structure(format!("чанк {} выходит за пределы файла", idx)));
+            return problems;
+        };
+        let actual = crc32fast::hash(stored);
+        if actual != chunk.checksum {
+            problems.push(VerifyProblem::Checksum { chunk: idx, expected: chunk.checksum, actual });
+        }
+        if quick {
+            return problems;
+        }
+
+        let (values, validity) = match decode_stored(self.codec, self.encoding, &self.dictionary, chunk, stored) {
+            Ok(decoded) => decoded,
+            Err(message) => {
+                problems.push(VerifyProblem::Decode { chunk: idx, message });
+                return problems;
+            }
+        };
+        let rows = chunk.row_count() as usize;
+        let is_valid = |row: usize| validity.as_ref().is_none_or(|bits| bit_is_set(bits, row));
+        let null_count = (0..rows).filter(|row| !is_valid(*row)).count() as u64;
+        if null_count != chunk.null_count {
+            problems.push(VerifyProblem::Metadata {
+                chunk: Some(idx),
+                message: format!("в данных {} NULL, а в метаданных {}", null_count, chunk.null_count),
+            });
+        }
+        // NaN и NULL в min/max не входят, как и при записи
+        let (mut min, mut max) = (T::MAX, T::MIN);
+        for (row, raw) in values.chunks_exact(T::WIDTH).enumerate() {
+            let value = T::read_le(raw);
+            if !is_valid(row) || value.is_nan() {
+                continue;
+            }
+            if value.total_cmp(&min).is_lt() {
+                min = value;
+            }
+            if value.total_cmp(&max).is_gt() {
+                max = value;
+            }
+        }
+        if min.total_cmp(&chunk.min).is_ne() {
+            problems.push(VerifyProblem::Metadata {
+                chunk: Some(idx),
+                message: format!("min в метаданных {:?}, а в данных {:?}", chunk.min, min),
+            });
+        }
+        if max.total_cmp(&chunk.max).is_ne() {
+            problems.push(VerifyProblem::Metadata {
+                chunk: Some(idx),
+                message: format!("max в метаданных {:?}, а в данных {:?}", chunk.max, max),
+            });
+        }
+        problems
+    }
+}
+
+// Значения и битовая карта хранимых байтов чанка в обход кэша проверенных чанков;
+// ошибка — описание того, что не распаковалось
+pub(crate) fn decode_stored<'a, T: ColumnType>(
+    codec: Codec,
+    encoding: Encoding,
+    dictionary: &[T],
+    chunk: &ChunkMeta<T>,
+    stored: &'a [u8],
+) -> std::result::Result<DecodedChunk<'a>, String> {
+    let (values, validity) = stored.split_at(chunk.compressed_len as usize);
+    let values = if is_framed(codec, encoding) {
+        let encoded = codec.decompress_frame(values, chunk.encoded_len as usize).map_err(|e| e.to_string())?;
+        Cow::Owned(encoding.decode(&encoded, dictionary).map_err(|e| e.to_string())?)
+    } else {
+        Cow::Borrowed(values)
+    };
+    if values.len() as u64 != chunk.uncompressed_len {
+        return Err(format!("значения распакованы в {} байт вместо {}", values.len(), chunk.uncompressed_len));
+    }
+    if validity.is_empty() {
+        return Ok((values, None));
+    }
+    let bitmap_len = chunk.row_count().div_ceil(8) as usize;
+    let bits = if codec.is_compressed() {
+        Cow::Owned(codec.decompress_frame(validity, bitmap_len).map_err(|e| e.to_string())?)
+    } else {
+        Cow::Borrowed(validity)
+    };
+    if bits.len() != bitmap_len {
+        return Err(format!("битовая карта валидности из {} байт вместо {}", bits.len(), bitmap_len));
+    }
+    Ok((values, Some(bits)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::Path};
+    use crate::ColumnBuilder;
+
+    fn build(path: &Path) -> Column<i64> {
+        let values: Vec<Option<i64>> = (0..5000).map(|i| (i % 13 != 0).then_some(i)).collect();
+        let mut builder = ColumnBuilder::from_nullable("v".to_string(), &values);
+        builder.set_chunk_rows(1000);
+        builder.compress_with(Codec::Lz4).unwrap();
+        builder.build(path).unwrap()
+    }
+
+    // Запись индекса чанка 2 в footer: min и max подряд, каждый встречается в файле один раз
+    fn corrupt_min(path: &Path, column: &Column<i64>) {
+        let chunk = &column.chunks[2];
+        let mut pattern = chunk.min.to_le_bytes().to_vec();
+        pattern.extend_from_slice(&chunk.max.to_le_bytes());
+        let mut bytes = fs::read(path).unwrap();
+        let at = bytes.windows(16).rposition(|w| w == pattern).unwrap();
+        bytes[at..at + 8].copy_from_slice(&(chunk.min - 1).to_le_bytes());
+        fs::write(path, bytes).unwrap();
+    }
+
+    fn flip_data_byte(path: &Path, column: &Column<i64>) {
+        let mut bytes = fs::read(path).unwrap();
+        bytes[column.chunks[3].offset as usize + 10] ^= 0xFF;
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn test_verify_reports_wrong_min_and_flipped_byte() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let column = build(&path);
+        let report = column.verify().unwrap();
+        assert!(report.is_ok(), "{:?}", report);
+        assert_eq!((report.chunks_checked, report.rows_checked), (5, 5000));
+
+        // Метаданные: контрольная сумма footer не покрывает, находит только полная проверка
+        corrupt_min(&path, &column);
+        let reopened = Column::<i64>::open(&path).unwrap();
+        assert!(reopened.verify_with(VerifyOptions { quick: true }).unwrap().is_ok());
+        let problems = reopened.verify().unwrap().problems;
+        assert!(matches!(problems.as_slice(), [VerifyProblem::Metadata { chunk: Some(2), .. }]), "{:?}", problems);
+        drop(reopened);
+
+        // Данные: расходится контрольная сумма, проверка продолжается за чанком 3
+        let path = dir.path().join("w.col");
+        let column = build(&path);
+        flip_data_byte(&path, &column);
+        let reopened = Column::<i64>::open(&path).unwrap();
+        let quick = reopened.verify_with(VerifyOptions { quick: true }).unwrap();
+        assert!(matches!(quick.problems.as_slice(), [VerifyProblem::Checksum { chunk: 3, .. }]), "{:?}", quick);
+        assert_eq!(quick.rows_checked, 0);
+        let full = reopened.verify().unwrap();
+        assert!(matches!(full.problems[0], VerifyProblem::Checksum { chunk: 3, expected, actual } if expected != actual));
+        assert!(full.problems.iter().all(|p| matches!(p,
+            VerifyProblem::Checksum { chunk: 3, .. } | VerifyProblem::Decode { chunk: 3, .. } | VerifyProblem::Metadata { chunk: Some(3), .. })));
+
+        // Оба повреждения в одном файле попадают в один отчёт
+        corrupt_min(&path, &column);
+        let problems = Column::<i64>::open(&path).unwrap().verify().unwrap().problems;
+        assert!(problems.iter().any(|p| matches!(p, VerifyProblem::Metadata { chunk: Some(2), .. })));
+        assert!(problems.iter().any(|p| matches!(p, VerifyProblem::Checksum { chunk: 3, .. })));
+    }
+
+    #[test]
+    fn test_verify_clean_columns_and_column_summary() {
+        let values: Vec<f64> = (0..3000).map(|i| if i % 100 == 7 { f64::NAN } else { (i % 40) as f64 }).collect();
+        let mut builder = ColumnBuilder::from_values("d".to_string(), &values);
+        builder.set_chunk_rows(700);
+        builder.set_encoding(Encoding::Dictionary);
+        let column = builder.build_in_memory().unwrap();
+        assert!(column.verify().unwrap().is_ok());
+        let empty = ColumnBuilder::<i32>::from_values("e".to_string(), &[]).build_in_memory().unwrap();
+        assert_eq!(empty.verify().unwrap(), VerifyReport::default());
+
+        let mut column = ColumnBuilder::from_values("v".to_string(), &[5i32, 1, 9]).build_in_memory().unwrap();
+        let inner = column.inner_mut();
+        inner.max = 10;
+        inner.null_count = 1;
+        let problems = column.verify().unwrap().problems;
+        assert_eq!(problems.len(), 2);
+        assert!(problems.iter().all(|p| matches!(p, VerifyProblem::Metadata { chunk: None, .. })));
+    }
+}
@@ -0,0 +1,185 @@
+use std::{ops::Range, sync::Arc};
+use crate::aggregate::{Agg, AggValue};
+use crate::compute::Selection;
+use crate::error::{invalid_input, Result};
+use crate::filter::Predicate;
+use crate::iter::ColumnIter;
+use crate::storage::Column;
+use crate::types::ColumnType;
+
+// Окно из строк колонки без копирования данных: чтения ограничены окном, а строки
+// нумеруются от его начала. Клонирование стоит одного Arc
+#[derive(Debug, Clone)]
+pub struct ColumnView<T: ColumnType = i32> {
+    column: Arc<Column<T>>,
+    offset: usize,
+    len: usize,
+}
+
+fn check_window(offset: usize, len: usize, total: usize) -> Result<()> {
+    if offset.checked_add(len).is_none_or(|end| end > total) {
+        return Err(invalid_input(format!("окно из {} строк со строки {} вне {} строк", len, offset, total)));
+    }
+    Ok(())
+}
+
+impl<T: ColumnType> Column<T> {
+    // Строки offset..offset + len; окно за концом колонки — ошибка InvalidInput
+    pub fn slice(self: &Arc<Self>, offset: usize, len: usize) -> Result<ColumnView<T>> {
+        check_window(offset, len, self.len())?;
+        Ok(ColumnView { column: Arc::clone(self), offset, len })
+    }
+}
+
+impl<T: ColumnType> ColumnView<T> {
+    pub fn column(&self) -> &Arc<Column<T>> {
+        &self.column
+    }
+
+    // Первая строка окна в колонке
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn window(&self) -> Range<usize> {
+        self.offset..self.offset + self.len
+    }
+
+    // Окно внутри окна; offset считается от начала этого окна
+    pub fn slice(&self, offset: usize, len: usize) -> Result<ColumnView<T>> {
+        check_window(offset, len, self.len)?;
+        Ok(ColumnView { column: Arc::clone(&self.column), offset: self.offset + offset, len })
+    }
+
+    // None и для строки вне окна, и для NULL, как в Column::get_value
+    pub fn get_value(&self, idx: usize) -> Option<T> {
+        self.get_nullable(idx).flatten()
+    }
+
+    pub fn get_nullable(&self, idx: usize) -> Option<Option<T>> {
+        if idx >= self.len {
+            return None;
+        }
+        self.column.get_nullable(self.offset + idx)
+    }
+
+    pub fn get_values(&self, range: Range<usize>) -> Result<Vec<T>> {
+        if range.start > range.end || range.end > self.len {
+            return Err(invalid_input(format!("диапазон строк {:?} вне окна из {} строк", range, self.len)));
+        }
+        self.column.get_values(self.offset + range.start..self.offset + range.end)
+    }
+
+    pub fn iter(&self) -> ColumnIter<'_, T> {
+        self.column.iter_range(self.window())
+    }
+
+    // Отбор длины окна; чанки вне окна не читаются
+    pub fn filter(&self, predicate: Predicate<T>) -> Result<Selection> {
+        let window = self.window();
+        let selection = self
+            .column
+            .filter_chunks(&predicate, |rows| rows.end <= window.start || rows.start >= window.end)?;
+        Ok(selection.slice(window))
+    }
+
+    // selection, если задан, имеет длину окна. Чанки целиком внутри окна по-прежнему
+    // отвечают на min/max из zone map
+    pub fn aggregate(&self, agg: Agg, selection: Option<&Selection>) -> Result<AggValue<T>> {
+        let rows = match selection {
+            Some(selection) if selection.len() != self.len => {
+                return Err(invalid_input(format!(
+                    "битовая карта из {} строк для окна из {} строк",
+                    selection.len(), self.len
+                )));
+            }
+            Some(selection) => selection.embed(self.offset, self.column.len()),
+            None => {
+                let mut rows = Selection::new(self.column.len());
+                rows.set_range(self.window());
+                rows
+            }
+        };
+        self.column.aggregate(agg, Some(&rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ColumnarError;
+    use crate::{Codec, ColumnBuilder};
+
+    fn column() -> (Vec<Option<i64>>, Arc<Column<i64>>) {
+        let values: Vec<Option<i64>> = (0..1000).map(|i| (i % 10 != 3).then_some((i * 37) % 101)).collect();
+        let mut builder = ColumnBuilder::from_nullable("v".to_string(), &values);
+        builder.set_chunk_rows(100);
+        builder.compress_with(Codec::Lz4).unwrap();
+        (values, Arc::new(builder.build_in_memory().unwrap()))
+    }
+
+    #[test]
+    fn test_slice_across_chunk_boundary_rebases_reads() {
+        let (values, column) = column();
+        let view = column.slice(150, 200).unwrap();
+        let window = &values[150..350];
+        assert_eq!(view.len(), 200);
+        assert_eq!(view.get_nullable(0), Some(window[0]));
+        assert_eq!(view.get_value(199), window[199]);
+        assert_eq!(view.get_nullable(200), None);
+        let iterated: Vec<i64> = view.iter().map(Result::unwrap).collect();
+        assert_eq!(iterated.len(), 200);
+        assert_eq!(view.get_values(0..200).unwrap(), iterated);
+        assert!(window.iter().zip(&iterated).all(|(expected, got)| expected.is_none_or(|v| v == *got)));
+
+        let before = column.frames_decoded();
+        let selection = view.filter(Predicate::Lt(20)).unwrap();
+        // Окно задевает чанки 1, 2 и 3
+        assert_eq!(column.frames_decoded() - before, 3);
+        let expected: Vec<usize> = (0..200).filter(|row| window[*row].is_some_and(|v| v < 20)).collect();
+        assert_eq!(selection.iter_indices().collect::<Vec<_>>(), expected);
+
+        let present: Vec<i64> = window.iter().flatten().copied().collect();
+        assert_eq!(view.aggregate(Agg::Count, None).unwrap(), AggValue::Count(present.len() as u64));
+        assert_eq!(view.aggregate(Agg::Sum, None).unwrap(), AggValue::Sum(present.iter().map(|v| *v as i128).sum()));
+        assert_eq!(view.aggregate(Agg::Max, None).unwrap(), AggValue::Max(present.iter().max().copied()));
+        let small: i128 = window.iter().flatten().filter(|v| **v < 20).map(|v| *v as i128).sum();
+        assert_eq!(view.aggregate(Agg::Sum, Some(&selection)).unwrap(), AggValue::Sum(small));
+
+        // Окно окна
+        let inner = view.slice(40, 30).unwrap();
+        assert_eq!((inner.offset(), inner.len()), (190, 30));
+        assert_eq!(inner.get_values(0..30).unwrap(), iterated[40..70]);
+        let min = window[40..70].iter().flatten().min().copied();
+        assert_eq!(inner.clone().aggregate(Agg::Min, None).unwrap(), AggValue::Min(min));
+    }
+
+    #[test]
+    fn test_empty_and_out_of_range_slices() {
+        let (_, column) = column();
+        let empty = column.slice(1000, 0).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.iter().count(), 0);
+        assert_eq!(empty.get_value(0), None);
+        assert!(empty.filter(Predicate::Ge(0)).unwrap().is_empty());
+        assert_eq!(empty.aggregate(Agg::Count, None).unwrap(), AggValue::Count(0));
+        assert_eq!(empty.aggregate(Agg::Min, None).unwrap(), AggValue::Min(None));
+
+        let invalid = |result: Result<ColumnView<i64>>| matches!(result, Err(ColumnarError::InvalidInput(_)));
+        assert!(invalid(column.slice(1001, 0)));
+        assert!(invalid(column.slice(900, 101)));
+        assert!(invalid(column.slice(1, usize::MAX)));
+        let view = column.slice(100, 100).unwrap();
+        assert!(invalid(view.slice(50, 51)));
+        assert!(matches!(view.get_values(90..101), Err(ColumnarError::InvalidInput(_))));
+        assert!(matches!(view.aggregate(Agg::Sum, Some(&Selection::new(1000))), Err(ColumnarError::InvalidInput(_))));
+    }
+}
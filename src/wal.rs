@@ -0,0 +1,276 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+use crate::error::{corrupt, invalid_input, ColumnarError, Result};
+use crate::format::{ByteReader, ChunkMeta, HEADER_SIZE};
+use crate::lock::WriteLock;
+use crate::storage::Column;
+use crate::types::ColumnType;
+use crate::writer::{sync_parent_dir, BuildOptions};
+
+// Журнал дозаписи лежит рядом с колонкой в <файл>.wal. Запись журнала —
+// [длина данных: u64][crc32 данных: u32][данные] и описывает одну дозапись: число строк
+// до неё, байты файла, которые она затрёт (заголовок и всё после последнего чанка),
+// и сами значения. По затираемым байтам обрезанный сбоем файл возвращается к
+// состоянию до дозаписи, после чего она повторяется
+pub(crate) fn wal_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".wal");
+    PathBuf::from(name)
+}
+
+struct WalEntry<T: ColumnType> {
+    base_rows: u64,
+    // Где заканчивается последний чанк: отсюда дозапись пишет новые чанки
+    offset: u64,
+    header: Vec<u8>,
+    tail: Vec<u8>,
+    values: Vec<T>,
+}
+
+impl<T: ColumnType> WalEntry<T> {
+    fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(32 + self.header.len() + self.tail.len() + self.values.len() * T::WIDTH);
+        payload.extend_from_slice(&self.base_rows.to_le_bytes());
+        payload.extend_from_slice(&self.offset.to_le_bytes());
+        payload.extend_from_slice(&self.header);
+        payload.extend_from_slice(&(self.tail.len() as u64).to_le_bytes());
+        payload.extend_from_slice(&self.tail);
+        payload.extend_from_slice(&(self.values.len() as u64).to_le_bytes());
+        for value in &self.values {
+            value.write_le(&mut payload);
+        }
+        let mut record = Vec::with_capacity(12 + payload.len());
+        record.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        record.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        record.extend_from_slice(&payload);
+        record
+    }
+
+    fn decode(payload: &[u8]) -> Result<Self> {
+        let mut r = ByteReader::new(payload);
+        let base_rows = r.u64()?;
+        let offset = r.u64()?;
+        let header = r.bytes(HEADER_SIZE)?.to_vec();
+        let tail_len = r.u64()? as usize;
+        let tail = r.bytes(tail_len)?.to_vec();
+        let count = r.u64()? as usize;
+        let values = (0..count).map(|_| r.value::<T>()).collect::<Result<_>>()?;
+        if r.remaining() != 0 {
+            return Err(corrupt("лишние байты в записи журнала дозаписи"));
+        }
+        Ok(Self { base_rows, offset, header, tail, values })
+    }
+
+    // Повторяет дозапись, если она не дошла до конца. Колонка, уже содержащая
+    // значения записи, не меняется
+    fn apply(&self, path: &Path, lock: WriteLock) -> Result<()> {
+        let mut column = match Column::<T>::open_mapped(path) {
+            Ok(column) if column.len() as u64 == self.base_rows + self.values.len() as u64 => return Ok(()),
+            Ok(column) if column.len() as u64 == self.base_rows => column,
+            Ok(column) => {
+                return Err(corrupt(format!(
+                    "журнал дозаписи начинается со строки {}, а в колонке {} строк",
+                    self.base_rows,
+                    column.len()
+                )));
+            }
+            // Файл обрезан или его footer затёрт посреди дозаписи
+            Err(ColumnarError::Corrupt { .. }) => {
+                self.restore(path)?;
+                Column::<T>::open_mapped(path)?
+            }
+            Err(e) => return Err(e),
+        };
+        column.append_with(&self.values, BuildOptions { sync: true }, Some(lock))
+    }
+
+    fn restore(&self, path: &Path) -> Result<()> {
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        file.set_len(self.offset)?;
+        file.seek(SeekFrom::Start(self.offset))?;
+        file.write_all(&self.tail)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&self.header)?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+// Записи журнала по порядку. Недописанная или испорченная запись получена при сбое
+// во время записи журнала, её дозапись не была подтверждена; на ней разбор кончается
+fn read_records(bytes: &[u8]) -> Vec<&[u8]> {
+    let mut records = Vec::new();
+    let mut pos = 0;
+    while let Some(frame) = bytes.get(pos..pos + 12) {
+        let len = u64::from_le_bytes(frame[..8].try_into().unwrap()) as usize;
+        let checksum = u32::from_le_bytes(frame[8..].try_into().unwrap());
+        let Some(payload) = bytes.get(pos + 12..).and_then(|rest| rest.get(..len)) else {
+            break;
+        };
+        if crc32fast::hash(payload) != checksum {
+            break;
+        }
+        records.push(payload);
+        pos += 12 + len;
+    }
+    records
+}
+
+pub(crate) fn wal_is_empty(path: &Path) -> Result<bool> {
+    match fs::metadata(wal_path(path)) {
+        Ok(metadata) => Ok(metadata.len() == 0),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(true),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Журнал очищается, а не удаляется: файл и запись о нём в каталоге уже на диске
+pub(crate) fn clear_wal(path: &Path) -> Result<()> {
+    let file = OpenOptions::new().write(true).open(wal_path(path))?;
+    file.set_len(0)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+// Повторяет записи непустого журнала колонки path и очищает его; вызывается до
+// отображения файла. Журнал читается под блокировкой писателя: два open одной
+// колонки не повторят одну дозапись дважды
+pub(crate) fn replay_wal<T: ColumnType>(path: &Path) -> Result<()> {
+    if wal_is_empty(path)? {
+        return Ok(());
+    }
+    let lock = WriteLock::acquire(path)?;
+    if wal_is_empty(path)? {
+        return Ok(());
+    }
+    let bytes = fs::read(wal_path(path))?;
+    for payload in read_records(&bytes) {
+        WalEntry::<T>::decode(payload)?.apply(path, lock.clone())?;
+    }
+    clear_wal(path)
+}
+
+impl<T: ColumnType> Column<T> {
+    // Дозапись через журнал: значения и затираемые ею байты файла сначала сбрасываются
+    // в .wal, затем дописываются в колонку с fsync, и журнал очищается. После сбоя
+    // посреди дозаписи open восстанавливает файл по журналу и повторяет её, так что
+    // строки успешного append не теряются. Только для колонок в файле
+    pub fn set_write_ahead_log(&mut self, enabled: bool) -> Result<()> {
+        if enabled && self.path.is_none() {
+            return Err(invalid_input("журнал дозаписи нужен только колонке в файле"));
+        }
+        self.write_ahead_log = enabled;
+        Ok(())
+    }
+
+    // Журнал очищается под той же блокировкой, что и дозапись
+    pub(crate) fn append_logged(&mut self, values: &[T], lock: WriteLock) -> Result<()> {
+        let path = self.path.clone().unwrap();
+        self.log_append(&path, values)?;
+        self.append_with(values, BuildOptions { sync: true }, Some(lock.clone()))?;
+        clear_wal(&path)
+    }
+
+    fn log_append(&self, path: &Path, values: &[T]) -> Result<()> {
+        // Неприменённая запись осталась от неудачной дозаписи, после которой файл мог
+        // измениться под этим экземпляром; её повторяет только open
+        if !wal_is_empty(path)? {
+            return Err(invalid_input("в журнале дозаписи есть неприменённая запись: откройте колонку заново"));
+        }
+        let offset = self.chunks.last().map_or(HEADER_SIZE as u64, ChunkMeta::stored_end);
+        let entry = WalEntry {
+            base_rows: self.len() as u64,
+            offset,
+            header: self.backing[..HEADER_SIZE].to_vec(),
+            tail: self.backing[offset as usize..].to_vec(),
+            values: values.to_vec(),
+        };
+        let wal = wal_path(path);
+        let created = !wal.exists();
+        let mut file = File::create(&wal)?;
+        file.write_all(&entry.encode())?;
+        file.sync_all()?;
+        if created {
+            sync_parent_dir(&wal)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Codec, ColumnBuilder};
+
+    fn build(path: &Path) -> Column<i64> {
+        let values: Vec<i64> = (0..5000).collect();
+        let mut builder = ColumnBuilder::from_values("v".to_string(), &values);
+        builder.set_chunk_rows(1000);
+        builder.compress_with(Codec::Lz4).unwrap();
+        let mut column = builder.build(path).unwrap();
+        column.set_write_ahead_log(true).unwrap();
+        column
+    }
+
+    #[test]
+    fn test_open_replays_append_torn_by_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let mut column = build(&path);
+        column.append(&(5000..5500).collect::<Vec<_>>()).unwrap();
+        assert!(wal_is_empty(&path).unwrap());
+
+        // Журнал записан, а дозапись оборвалась: footer затёрт, а файл обрезан
+        let pending: Vec<i64> = (5500..5800).collect();
+        let offset = column.chunks.last().unwrap().stored_end();
+        column.log_append(&path, &pending).unwrap();
+        drop(column);
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(offset)).unwrap();
+        file.write_all(&[0xAB; 100]).unwrap();
+        file.set_len(offset + 60).unwrap();
+        drop(file);
+        assert!(matches!(Column::<i64>::open_mapped(&path), Err(ColumnarError::Corrupt { .. })));
+
+        let recovered = Column::<i64>::open(&path).unwrap();
+        assert_eq!(recovered.values().unwrap(), (0..5800).collect::<Vec<_>>());
+        assert!(recovered.is_sorted);
+        assert!(wal_is_empty(&path).unwrap());
+        // Повторное открытие ничего не повторяет
+        assert_eq!(Column::<i64>::open(&path).unwrap().len(), 5800);
+    }
+
+    #[test]
+    fn test_replay_skips_applied_and_torn_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("v.col");
+        let mut column = build(&path);
+
+        // Сбой после дозаписи, но до очистки журнала
+        let applied: Vec<i64> = (5000..5100).collect();
+        column.log_append(&path, &applied).unwrap();
+        column.append_with(&applied, BuildOptions::default(), Some(WriteLock::acquire(&path).unwrap())).unwrap();
+        assert!(matches!(column.append(&[1]), Err(ColumnarError::InvalidInput(_))));
+        drop(column);
+        let mut column = Column::<i64>::open(&path).unwrap();
+        assert_eq!(column.len(), 5100);
+
+        // Сбой посреди записи журнала: дозапись не подтверждена и не применяется
+        column.set_write_ahead_log(true).unwrap();
+        column.log_append(&path, &[7, 8, 9]).unwrap();
+        let wal = wal_path(&path);
+        let len = fs::metadata(&wal).unwrap().len();
+        OpenOptions::new().write(true).open(&wal).unwrap().set_len(len - 5).unwrap();
+        drop(column);
+        let column = Column::<i64>::open(&path).unwrap();
+        assert_eq!(column.len(), 5100);
+        assert!(wal_is_empty(&path).unwrap());
+
+        let mut memory = ColumnBuilder::from_values("m".to_string(), &[1i64]).build_in_memory().unwrap();
+        assert!(matches!(memory.set_write_ahead_log(true), Err(ColumnarError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,983 @@
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{BufWriter, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tempfile::TempPath;
+use crate::backing::Backing;
+use crate::bloom::Bloom;
+use crate::bools::{bit_is_set, pack_bits};
+use crate::codec::Codec;
+use crate::encoding::{dictionary_index, Encoding};
+use crate::error::{invalid_input, Result};
+use crate::format::{placeholder_bloom, ChunkMeta, Footer, Header, CHUNK_FRAME_SIZE, FLAG_CHUNK_FRAMES, HEADER_SIZE, MAX_CHUNK_BYTES};
+use crate::histogram::{check_histogram_buckets, Histogram, Sampler};
+use crate::hll::{HyperLogLog, DEFAULT_HLL_PRECISION};
+use crate::lock::WriteLock;
+use crate::par::*;
+use crate::storage::{Column, DEFAULT_BLOOM_FP_RATE, ROWS_PER_CHUNK};
+use crate::tombstone::remove_deletions;
+use crate::trace::span;
+use crate::types::ColumnType;
+
+// Параметры завершения записи колонки
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BuildOptions {
+    // fsync файла и его каталога до возврата: колонка переживает отключение питания
+    // сразу после успешного build. Заметно замедляет запись, поэтому выключено по умолчанию
+    pub sync: bool,
+}
+
+// Потоковая запись колонки: значения копятся до полного чанка, который сразу
+// кодируется, сжимается и сбрасывается на диск. В памяти держится не больше одного чанка.
+pub struct ColumnWriter<T: ColumnType = i32> {
+    // Путь, по которому колонка окажется после finish; None — колонка собирается в памяти
+    path: Option<PathBuf>,
+    file: BufWriter<Box<dyn Sink>>,
+    // При атомарной записи данные пишутся во временный файл рядом с path и
+    // переименовываются в finish; при ошибке временный файл удаляется вместе с writer
+    temp_path: Option<TempPath>,
+    name: String,
+    codec: Codec,
+    encoding: Encoding,
+    dictionary: Vec<T>,
+    chunk_rows: usize,
+    // Исходные значения текущего незаполненного чанка и признаки их заполненности
+    pending: Vec<u8>,
+    pending_validity: Vec<bool>,
+    chunks: Vec<ChunkMeta<T>>,
+    offset: u64,
+    row_count: u64,
+    min: T,
+    max: T,
+    has_nan: bool,
+    // Значения пока не убывают; NULL и NaN снимают признак
+    is_sorted: bool,
+    last_value: Option<T>,
+    null_count: u64,
+    distinct: HyperLogLog,
+    // None — bloom-фильтры отключены. Общий фильтр создаётся при первой записи,
+    // когда известно, на сколько значений его рассчитывать
+    bloom_fp_rate: Option<f64>,
+    bloom: Option<Bloom<T>>,
+    bloom_capacity: u64,
+    // Some — при finish строится гистограмма с таким числом корзин
+    histogram_buckets: Option<usize>,
+    // При дозаписи — гистограмма прежних строк: с ней сливается выборка только
+    // из чанков начиная с sampled_from, прежние чанки не перечитываются
+    base_histogram: Option<Histogram<T>>,
+    sampled_from: usize,
+    // Перед каждым чанком пишется его кадр (FLAG_CHUNK_FRAMES)
+    chunk_frames: bool,
+    // Дозапись в существующий файл; иначе его прежний файл удалений устарел
+    appending: bool,
+    // Держится до конца finish; None у колонки в памяти
+    _lock: Option<WriteLock>,
+}
+
+impl<T: ColumnType> ColumnWriter<T> {
+    pub(crate) fn create(name: String, path: &Path) -> Result<Self> {
+        Self::create_locked(name, path, WriteLock::acquire(path)?)
+    }
+
+    // Файл обрезается только под блокировкой, иначе он затёр бы данные другого писателя
+    pub(crate) fn create_locked(name: String, path: &Path, lock: WriteLock) -> Result<Self> {
+        // Один дескриптор на чтение и запись: через него же finish отображает файл в память
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        Self::with_sink(name, Some(path), Box::new(file), None, Some(lock))
+    }
+
+    // Тот же формат, что и в файле, но в буфере памяти
+    pub(crate) fn create_in_memory(name: String) -> Result<Self> {
+        Self::with_sink(name, None, Box::new(Cursor::new(Vec::new())), None, None)
+    }
+
+    // Запись во временный файл в каталоге path с переименованием после fsync:
+    // читатели видят либо прежний файл, либо полностью записанную колонку
+    pub(crate) fn create_atomic(name: String, path: &Path) -> Result<Self> {
+        Self::create_atomic_with(name, path, |file| Box::new(file))
+    }
+
+    pub(crate) fn create_atomic_with(
+        name: String,
+        path: &Path,
+        wrap: impl FnOnce(File) -> Box<dyn Sink>,
+    ) -> Result<Self> {
+        Self::create_atomic_locked(name, path, WriteLock::acquire(path)?, wrap)
+    }
+
+    // Блокировку path вызывающий уже держит
+    pub(crate) fn create_atomic_locked(
+        name: String,
+        path: &Path,
+        lock: WriteLock,
+        wrap: impl FnOnce(File) -> Box<dyn Sink>,
+    ) -> Result<Self> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let (file, temp_path) = tempfile::Builder::new().prefix(".column-").tempfile_in(dir)?.into_parts();
+        Self::with_sink(name, Some(path), wrap(file), Some(temp_path), Some(lock))
+    }
+
+    fn with_sink(
+        name: String,
+        path: Option<&Path>,
+        sink: Box<dyn Sink>,
+        temp_path: Option<TempPath>,
+        lock: Option<WriteLock>,
+    ) -> Result<Self> {
+        let mut file = BufWriter::new(sink);
+        // Заголовок перезаписывается в finish, когда известно число строк
+        file.write_all(&[0u8; HEADER_SIZE])?;
+        Ok(Self {
+            path: path.map(Path::to_path_buf),
+            file,
+            temp_path,
+            name,
+            codec: Codec::None,
+            encoding: Encoding::Plain,
+            dictionary: Vec::new(),
+            chunk_rows: ROWS_PER_CHUNK,
+            pending: Vec::new(),
+            pending_validity: Vec::new(),
+            chunks: Vec::new(),
+            offset: HEADER_SIZE as u64,
+            row_count: 0,
+            min: T::MAX,
+            max: T::MIN,
+            has_nan: false,
+            is_sorted: true,
+            last_value: None,
+            null_count: 0,
+            distinct: HyperLogLog::new(DEFAULT_HLL_PRECISION)?,
+            bloom_fp_rate: T::HAS_BLOOM.then_some(DEFAULT_BLOOM_FP_RATE),
+            bloom: None,
+            bloom_capacity: 0,
+            histogram_buckets: None,
+            base_histogram: None,
+            sampled_from: 0,
+            chunk_frames: false,
+            appending: false,
+            _lock: lock,
+        })
+    }
+
+    pub fn compress_with(&mut self, codec: Codec) -> Result<()> {
+        self.ensure_nothing_written()?;
+        codec.validate()?;
+        self.codec = codec;
+        Ok(())
+    }
+
+    // Словарное кодирование требует знать все значения заранее и потоково недоступно
+    pub fn set_encoding(&mut self, encoding: Encoding) -> Result<()> {
+        self.ensure_nothing_written()?;
+        if encoding == Encoding::Dictionary {
+            return Err(invalid_input("словарное кодирование недоступно при потоковой записи"));
+        }
+        self.encoding = encoding;
+        Ok(())
+    }
+
+    // Точность оценки числа различных значений: 2^precision регистров по байту в footer
+    pub fn set_hll_precision(&mut self, precision: u8) -> Result<()> {
+        self.ensure_nothing_written()?;
+        self.distinct = HyperLogLog::new(precision)?;
+        Ok(())
+    }
+
+    // Целевая доля ложных срабатываний общего фильтра и фильтров чанков.
+    // Для типов без bloom-фильтров (f64) ни на что не влияет
+    pub fn set_bloom_fp_rate(&mut self, fp_rate: f64) -> Result<()> {
+        self.ensure_nothing_written()?;
+        check_fp_rate(fp_rate)?;
+        self.bloom_fp_rate = T::HAS_BLOOM.then_some(fp_rate);
+        Ok(())
+    }
+
+    // Колонка без bloom-фильтров: меньше метаданных там, где точечные запросы не нужны
+    pub fn disable_bloom_filter(&mut self) -> Result<()> {
+        self.ensure_nothing_written()?;
+        self.bloom_fp_rate = None;
+        Ok(())
+    }
+
+    // Равноглубинная гистограмма в footer: finish строит её по выборке записанных
+    // значений за один дополнительный проход по чанкам. При дозаписи проходятся
+    // только новые чанки, см. Histogram::merge
+    pub fn enable_histogram(&mut self, buckets: usize) -> Result<()> {
+        self.ensure_nothing_written()?;
+        check_histogram_buckets(buckets)?;
+        self.histogram_buckets = Some(buckets);
+        Ok(())
+    }
+
+    // Кадр перед каждым чанком: 44 байта на чанк, зато при потере footer, например
+    // если диск переполнился посреди дозаписи, Column::open_recover находит целые чанки.
+    // Значения несжатой колонки тогда не лежат в файле подряд, и decompress_parallel копирует их
+    pub fn enable_chunk_frames(&mut self) -> Result<()> {
+        self.ensure_nothing_written()?;
+        self.chunk_frames = true;
+        Ok(())
+    }
+
+    pub fn set_chunk_rows(&mut self, rows: usize) -> Result<()> {
+        self.ensure_nothing_written()?;
+        if rows == 0 {
+            return Err(invalid_input("размер чанка должен быть положительным"));
+        }
+        if (rows as u64).saturating_mul(T::WIDTH as u64) > MAX_CHUNK_BYTES / 2 {
+            return Err(invalid_input(format!("чанк из {} строк больше допустимого", rows)));
+        }
+        self.chunk_rows = rows;
+        Ok(())
+    }
+
+    // Дозапись в существующий файл: новые чанки пишутся поверх старого footer,
+    // поэтому уже записанные байты данных не меняются и файл только растёт.
+    // Колонка в памяти копирует данные в новый буфер, а прежний остаётся у открытых
+    // экземпляров. last_value — последнее значение отсортированной колонки; lock
+    // обязателен для колонки в файле
+    // last_value — хранимое значение последней строки, Some(None) — она NULL, и признак
+    // сортировки продолжить нельзя
+    pub(crate) fn append_to(column: &Column<T>, last_value: Option<Option<T>>, lock: Option<WriteLock>) -> Result<Self> {
+        let offset = column.chunks.last().map_or(HEADER_SIZE as u64, ChunkMeta::stored_end);
+        let mut sink: Box<dyn Sink> = match &column.path {
+            Some(path) => {
+                debug_assert!(lock.is_some());
+                let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+                // Писатель, дописавший файл после открытия колонки, уже затёр её footer
+                let mut header = [0u8; HEADER_SIZE];
+                file.read_exact(&mut header)?;
+                let rows = Header::decode(&header)?.row_count;
+                if rows != column.len() as u64 {
+                    return Err(invalid_input(format!(
+                        "файл колонки изменился после открытия: в нём {} строк, а в колонке {}",
+                        rows,
+                        column.len()
+                    )));
+                }
+                Box::new(file)
+            }
+            None => Box::new(Cursor::new(column.backing.read(0..offset as usize)?.into_owned())),
+        };
+        sink.seek(SeekFrom::Start(offset))?;
+        Ok(Self {
+            path: column.path.clone(),
+            file: BufWriter::new(sink),
+            temp_path: None,
+            name: column.name.clone(),
+            codec: column.codec,
+            encoding: column.encoding,
+            dictionary: column.dictionary.clone(),
+            chunk_rows: ROWS_PER_CHUNK,
+            pending: Vec::new(),
+            pending_validity: Vec::new(),
+            chunks: column.chunks.clone(),
+            offset,
+            row_count: column.len() as u64,
+            min: column.min,
+            max: column.max,
+            has_nan: column.has_nan,
+            is_sorted: column.is_sorted && !matches!(last_value, Some(None)),
+            last_value: last_value.flatten(),
+            null_count: column.null_count,
+            distinct: column.distinct.clone(),
+            // Фильтры файла, записанного с функцией bloom, собранный без неё писатель
+            // дополнить не может и пишет колонку без них
+            bloom_fp_rate: column.bloom_fp_rate.filter(|_| T::HAS_BLOOM),
+            bloom: column.bloom_fp_rate.filter(|_| T::HAS_BLOOM).map(|_| column.bloom_filter.clone()),
+            bloom_capacity: column.bloom_capacity,
+            histogram_buckets: column.histogram.as_ref().map(Histogram::target_buckets),
+            base_histogram: column.histogram.clone(),
+            sampled_from: column.chunks.len(),
+            chunk_frames: column.chunk_frames,
+            appending: true,
+            _lock: lock,
+        })
+    }
+
+    // Новая колонка с кодеком, кодированием, словарём и настройками метаданных column;
+    // пишется атомарно, поэтому path может совпадать с файлом одной из исходных колонок
+    pub(crate) fn create_like(column: &Column<T>, path: &Path) -> Result<Self> {
+        let mut writer = Self::create_atomic(column.name.clone(), path)?;
+        writer.codec = column.codec;
+        writer.encoding = column.encoding;
+        writer.dictionary = column.dictionary.clone();
+        writer.distinct = column.distinct.empty_like();
+        writer.bloom_fp_rate = column.bloom_fp_rate.filter(|_| T::HAS_BLOOM);
+        writer.histogram_buckets = column.histogram.as_ref().map(Histogram::target_buckets);
+        writer.chunk_frames = column.chunk_frames;
+        Ok(writer)
+    }
+
+    // Чанки column переносятся без перекодирования: хранимые байты копируются вместе
+    // с контрольной суммой, а сводка собирается из метаданных чанков и колонки.
+    // Кодек, кодирование и словарь должны совпадать с писателем — это проверяет вызывающий
+    pub(crate) fn copy_chunks(&mut self, column: &Column<T>) -> Result<()> {
+        self.flush_pending()?;
+        self.distinct.merge(&column.distinct);
+        self.has_nan |= column.has_nan;
+        if let (Some(first), Some(last)) = (column.chunks.first(), column.chunks.last()) {
+            // Отсортированная колонка без NULL и NaN начинается со своего минимума
+            // и заканчивается максимумом
+            if self.is_sorted {
+                self.is_sorted = column.is_sorted && self.last_value.is_none_or(|prev| first.min.total_cmp(&prev).is_ge());
+                self.last_value = Some(last.max);
+            }
+        }
+        for idx in 0..column.chunks.len() {
+            self.store_chunk(&column.checked_chunk(idx)?, column.chunks[idx].clone())?;
+        }
+        // Фильтры разных колонок построены с разными ключами хеширования и не
+        // объединяются, поэтому finish построит общий фильтр заново по всем чанкам
+        self.bloom = None;
+        Ok(())
+    }
+
+    // Словарь заранее построен ColumnBuilder по всем значениям колонки
+    pub(crate) fn set_dictionary(&mut self, dictionary: Vec<T>) {
+        self.create_bloom(dictionary.len() as u64);
+        if let Some(bloom) = &mut self.bloom {
+            for value in &dictionary {
+                T::bloom_set(bloom, value);
+            }
+        }
+        self.encoding = Encoding::Dictionary;
+        self.dictionary = dictionary;
+    }
+
+    pub fn push(&mut self, value: T) -> Result<()> {
+        if self.encoding == Encoding::Dictionary && dictionary_index(&self.dictionary, &value).is_none() {
+            return Err(invalid_input(format!("значение {:?} отсутствует в словаре колонки", value)));
+        }
+        self.push_slot(value, true)
+    }
+
+    // Ячейка NULL заполняется значением, которое кодируется любым способом,
+    // в том числе словарём; при чтении оно не видно
+    pub fn push_null(&mut self) -> Result<()> {
+        let placeholder = self.dictionary.first().copied().unwrap_or(T::from_bits(0));
+        self.push_slot(placeholder, false)
+    }
+
+    pub fn push_option(&mut self, value: Option<T>) -> Result<()> {
+        match value {
+            Some(value) => self.push(value),
+            None => self.push_null(),
+        }
+    }
+
+    fn push_slot(&mut self, value: T, valid: bool) -> Result<()> {
+        value.write_le(&mut self.pending);
+        self.pending_validity.push(valid);
+        if self.pending.len() == self.chunk_rows * T::WIDTH {
+            self.flush_pending()?;
+        }
+        Ok(())
+    }
+
+    pub fn push_slice(&mut self, values: &[T]) -> Result<()> {
+        for value in values {
+            self.push(*value)?;
+        }
+        Ok(())
+    }
+
+    // Значения, ещё не сброшенные на диск
+    pub fn buffered_rows(&self) -> usize {
+        self.pending.len() / T::WIDTH
+    }
+
+    pub fn row_count(&self) -> u64 {
+        self.row_count + self.buffered_rows() as u64
+    }
+
+    // Запись уже собранных в памяти значений: чанки кодируются параллельно.
+    // validity — по признаку на строку, None означает колонку без NULL
+    pub(crate) fn write_all_parallel(&mut self, data: &[u8], validity: Option<&[bool]>) -> Result<()> {
+        self.flush_pending()?;
+        let validity: Vec<Option<&[bool]>> = match validity {
+            Some(validity) => validity.chunks(self.chunk_rows).map(nulls_present).collect(),
+            None => vec![None; data.len().div_ceil(self.chunk_rows * T::WIDTH)],
+        };
+        let granules: Vec<(&[u8], Option<&[bool]>)> =
+            data.chunks(self.chunk_rows * T::WIDTH).zip(validity.iter().copied()).collect();
+        let _span = span!(
+            "columnar::build",
+            "compress",
+            column = self.name.as_str(),
+            rows = data.len() / T::WIDTH,
+            chunks = granules.len(),
+            bytes = data.len()
+        );
+
+        // Сводка по значениям собирается по чанкам параллельно и сливается; результат
+        // тот же, что при добавлении значений по одному в write_chunk. Чанки делятся
+        // на группы по числу потоков, как в fill_bloom_parallel
+        let group = granules.len().div_ceil(current_num_threads()).max(1);
+        let partials: Vec<HyperLogLog> = granules
+            .par_chunks(group)
+            .map(|granules| {
+                let mut hll = self.distinct.empty_like();
+                for (chunk, validity) in granules {
+                    valid_values::<T>(chunk, *validity).for_each(|value| hll.insert::<T>(&value));
+                }
+                hll
+            })
+            .collect();
+        for partial in &partials {
+            self.distinct.merge(partial);
+        }
+        // Все значения уже в памяти, поэтому фильтр сразу рассчитывается на итоговое
+        // число различных значений
+        if self.bloom.is_none() && self.bloom_fp_rate.is_some() {
+            self.create_bloom(bloom_capacity(self.distinct.estimate()));
+        }
+        // Значения словарной колонки уже внесены в фильтр вместе со словарём
+        if let Some(bloom) = self.bloom.as_mut().filter(|_| self.dictionary.is_empty()) {
+            *bloom = fill_bloom_parallel(bloom, &granules);
+        }
+        if !self.has_nan {
+            self.has_nan = granules
+                .par_iter()
+                .any(|(chunk, validity)| valid_values(chunk, *validity).any(|value: T| value.is_nan()));
+        }
+
+        let (codec, encoding, dictionary, fp_rate) = (self.codec, self.encoding, &self.dictionary, self.bloom_fp_rate);
+        let encoded: Vec<(Cow<[u8]>, ChunkMeta<T>)> = granules
+            .par_iter()
+            .map(|(chunk, validity)| encode_chunk(codec, encoding, dictionary, chunk, *validity, fp_rate))
+            .collect::<Result<_>>()?;
+        for ((chunk, validity), (stored, meta)) in granules.into_iter().zip(encoded) {
+            self.place_chunk(chunk, validity, &stored, meta)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<Column<T>> {
+        self.finish_with(BuildOptions::default())
+    }
+
+    pub fn finish_with(mut self, options: BuildOptions) -> Result<Column<T>> {
+        self.flush_pending()?;
+        let bloom_filter = self.finish_bloom()?;
+        let histogram = match self.histogram_buckets {
+            Some(buckets) => {
+                let mut sampler = Sampler::new(buckets);
+                self.for_each_written_value(self.sampled_from, |value| sampler.push(value))?;
+                let sampled = sampler.finish(buckets);
+                Some(match &self.base_histogram {
+                    Some(base) => base.merge(&sampled),
+                    None => sampled,
+                })
+            }
+            None => None,
+        };
+
+        // Метаданные дописываются после данных, чтобы Column::open не пересчитывал их
+        let footer = Footer {
+            name: self.name,
+            min: self.min,
+            max: self.max,
+            compression_level: self.codec.level(),
+            has_nan: self.has_nan,
+            is_sorted: self.is_sorted,
+            null_count: self.null_count,
+            distinct: self.distinct,
+            histogram,
+            bloom_fp_rate: self.bloom_fp_rate,
+            bloom_capacity: self.bloom_capacity,
+            bloom_filter,
+            dictionary: self.dictionary,
+            chunks: self.chunks,
+        };
+        self.file.write_all(&footer.encode())?;
+        let mut header = Header::new(self.codec.tag(), self.encoding.tag(), T::TAG, self.row_count);
+        if self.chunk_frames {
+            header.flags |= FLAG_CHUNK_FRAMES;
+        }
+        let mut file = self.file.into_inner().map_err(|e| e.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&header.encode())?;
+        file.flush()?;
+        // При атомарной записи данные должны оказаться на диске раньше, чем их станет
+        // видно под новым именем
+        if options.sync || self.temp_path.is_some() {
+            file.sync()?;
+        }
+        if let (Some(temp_path), Some(path)) = (self.temp_path, &self.path) {
+            temp_path.persist(path).map_err(|e| e.error)?;
+        }
+        if let (false, Some(path)) = (self.appending, &self.path) {
+            remove_deletions(path)?;
+        }
+        // Запись о файле (и переименование) в каталоге тоже нужно сбросить
+        if let (true, Some(path)) = (options.sync, &self.path) {
+            sync_parent_dir(path)?;
+        }
+
+        Column::from_backing(self.path, file.into_backing()?)
+    }
+
+    fn create_bloom(&mut self, capacity: u64) {
+        if let Some(fp_rate) = self.bloom_fp_rate {
+            self.bloom_capacity = capacity.max(1);
+            self.bloom = Some(Bloom::new_for_fp_rate(self.bloom_capacity as usize, fp_rate));
+        }
+    }
+
+    // При потоковой записи и дозаписи итоговое число значений заранее неизвестно.
+    // Если оно превысило расчётное, переполненный фильтр дал бы почти сплошные ложные
+    // срабатывания, поэтому он строится заново по записанным чанкам
+    fn finish_bloom(&mut self) -> Result<Bloom<T>> {
+        if self.bloom_fp_rate.is_none() {
+            return Ok(placeholder_bloom());
+        }
+        let estimate = self.distinct.estimate();
+        if self.bloom.is_none() || (self.dictionary.is_empty() && estimate > self.bloom_capacity) {
+            self.rebuild_bloom(bloom_capacity(estimate))?;
+        }
+        Ok(self.bloom.take().unwrap())
+    }
+
+    fn rebuild_bloom(&mut self, capacity: u64) -> Result<()> {
+        self.create_bloom(capacity);
+        let mut bloom = self.bloom.take().unwrap();
+        self.for_each_written_value(0, |value| T::bloom_set(&mut bloom, &value))?;
+        self.bloom = Some(bloom);
+        Ok(())
+    }
+
+    // Записанные значения без NULL в порядке строк, начиная с чанка from_chunk
+    fn for_each_written_value(&mut self, from_chunk: usize, mut f: impl FnMut(T)) -> Result<()> {
+        self.file.flush()?;
+        let contents = self.file.get_ref().contents()?;
+        // Чанки читаются по одному, чтобы не держать в памяти всю колонку
+        for meta in &self.chunks[from_chunk..] {
+            let stored = &contents[meta.offset as usize..meta.stored_end() as usize];
+            let (values, bits) = stored.split_at(meta.compressed_len as usize);
+            let values = if is_framed(self.codec, self.encoding) {
+                let encoded = self.codec.decompress_frame(values, meta.encoded_len as usize)?;
+                Cow::Owned(self.encoding.decode(&encoded, &self.dictionary)?)
+            } else {
+                Cow::Borrowed(values)
+            };
+            let validity: Option<Vec<bool>> = if meta.validity_len == 0 {
+                None
+            } else {
+                let bits_len = meta.row_count().div_ceil(8) as usize;
+                let bits = if self.codec.is_compressed() {
+                    self.codec.decompress_frame(bits, bits_len)?
+                } else {
+                    bits.to_vec()
+                };
+                Some((0..meta.row_count() as usize).map(|row| bit_is_set(&bits, row)).collect())
+            };
+            for value in valid_values::<T>(&values, validity.as_deref()) {
+                f(value);
+            }
+        }
+        Ok(())
+    }
+
+    fn flush_pending(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::take(&mut self.pending);
+        let validity = std::mem::take(&mut self.pending_validity);
+        let (stored, meta) = encode_chunk(
+            self.codec,
+            self.encoding,
+            &self.dictionary,
+            &chunk,
+            nulls_present(&validity),
+            self.bloom_fp_rate,
+        )?;
+        self.write_chunk(&chunk, nulls_present(&validity), &stored, meta)?;
+        // Буферы переиспользуются для следующего чанка
+        self.pending = chunk;
+        self.pending.clear();
+        self.pending_validity = validity;
+        self.pending_validity.clear();
+        Ok(())
+    }
+
+    fn write_chunk(&mut self, raw: &[u8], validity: Option<&[bool]>, stored: &[u8], meta: ChunkMeta<T>) -> Result<()> {
+        for value in valid_values(raw, validity) {
+            self.distinct.insert::<T>(&value);
+        }
+        // Потоковая запись начинает с фильтра на один чанк; если значений окажется
+        // больше, finish построит его заново
+        if self.bloom.is_none() {
+            self.create_bloom(self.chunk_rows as u64);
+        }
+        // Значения словарной колонки уже внесены в фильтр вместе со словарём
+        if let Some(bloom) = self.bloom.as_mut().filter(|_| self.dictionary.is_empty()) {
+            for value in valid_values(raw, validity) {
+                T::bloom_set(bloom, &value);
+            }
+        }
+        // NaN не попадает в min/max, поэтому его наличие отмечается отдельно
+        if !self.has_nan {
+            self.has_nan = valid_values(raw, validity).any(|value: T| value.is_nan());
+        }
+        self.place_chunk(raw, validity, stored, meta)
+    }
+
+    // Запись чанка и его метаданных без сводки по значениям: её ведёт вызывающий
+    fn place_chunk(
+        &mut self,
+        raw: &[u8],
+        validity: Option<&[bool]>,
+        stored: &[u8],
+        meta: ChunkMeta<T>,
+    ) -> Result<()> {
+        self.track_sortedness(raw, validity.is_some());
+        self.store_chunk(stored, meta)
+    }
+
+    fn store_chunk(&mut self, stored: &[u8], mut meta: ChunkMeta<T>) -> Result<()> {
+        if self.chunk_frames {
+            self.file.write_all(&meta.encode_frame())?;
+            self.offset += CHUNK_FRAME_SIZE as u64;
+        }
+        self.file.write_all(stored)?;
+        meta.offset = self.offset;
+        meta.first_row = self.row_count;
+        self.offset += stored.len() as u64;
+        self.row_count += meta.row_count();
+        self.null_count += meta.null_count;
+        if meta.min.total_cmp(&self.min).is_lt() {
+            self.min = meta.min;
+        }
+        if meta.max.total_cmp(&self.max).is_gt() {
+            self.max = meta.max;
+        }
+        self.chunks.push(meta);
+        Ok(())
+    }
+
+    // Бинарный поиск по колонке опирается на zone maps чанков, которые не видят
+    // NULL и NaN, поэтому колонка с ними не считается отсортированной
+    fn track_sortedness(&mut self, raw: &[u8], has_nulls: bool) {
+        if !self.is_sorted {
+            return;
+        }
+        if has_nulls || self.has_nan {
+            self.is_sorted = false;
+            return;
+        }
+        for value in raw.chunks_exact(T::WIDTH).map(T::read_le) {
+            if self.last_value.is_some_and(|last| value.total_cmp(&last).is_lt()) {
+                self.is_sorted = false;
+                return;
+            }
+            self.last_value = Some(value);
+        }
+    }
+
+    fn ensure_nothing_written(&self) -> Result<()> {
+        if self.row_count() > 0 {
+            return Err(invalid_input("параметры колонки нельзя менять после записи значений"));
+        }
+        Ok(())
+    }
+}
+
+// Куда пишет ColumnWriter: файл или буфер в памяти. Тесты подставляют обёртку,
+// которая отказывает на записи
+pub(crate) trait Sink: Write + Seek + Send {
+    // Уже записанные байты; пересборка bloom-фильтра читает по ним чанки до finish
+    fn contents(&self) -> Result<Backing>;
+    fn sync(&self) -> Result<()>;
+    // Байты готовой колонки
+    fn into_backing(self: Box<Self>) -> Result<Backing>;
+}
+
+impl Sink for File {
+    fn contents(&self) -> Result<Backing> {
+        Backing::map(self)
+    }
+
+    fn sync(&self) -> Result<()> {
+        self.sync_all()?;
+        Ok(())
+    }
+
+    fn into_backing(self: Box<Self>) -> Result<Backing> {
+        Backing::map(&self)
+    }
+}
+
+impl Sink for Cursor<Vec<u8>> {
+    fn contents(&self) -> Result<Backing> {
+        Ok(Backing::Memory(Arc::new(self.get_ref().clone())))
+    }
+
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn into_backing(self: Box<Self>) -> Result<Backing> {
+        Ok(Backing::Memory(Arc::new(self.into_inner())))
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn sync_parent_dir(path: &Path) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+// Каталог нельзя открыть как файл для fsync; NTFS сохраняет метаданные сам
+#[cfg(not(unix))]
+pub(crate) fn sync_parent_dir(_: &Path) -> Result<()> {
+    Ok(())
+}
+
+// Без кодека и кодирования значения лежат в файле как есть и читаются напрямую из mmap
+pub(crate) fn is_framed(codec: Codec, encoding: Encoding) -> bool {
+    codec.is_compressed() || encoding != Encoding::Plain
+}
+
+pub(crate) fn check_fp_rate(fp_rate: f64) -> Result<()> {
+    if fp_rate.is_nan() || fp_rate <= 0.0 || fp_rate >= 1.0 {
+        return Err(invalid_input(format!(
+            "доля ложных срабатываний должна лежать в (0, 1), получено {}",
+            fp_rate
+        )));
+    }
+    Ok(())
+}
+
+// Запас на ошибку оценки HyperLogLog, чтобы фильтр не оказался переполнен
+fn bloom_capacity(estimate: u64) -> u64 {
+    estimate + estimate / 8
+}
+
+// Битовая карта нужна только чанкам, в которых есть хотя бы один NULL
+fn nulls_present(validity: &[bool]) -> Option<&[bool]> {
+    validity.contains(&false).then_some(validity)
+}
+
+// Значения чанка без ячеек NULL
+fn valid_values<'a, T: ColumnType>(chunk: &'a [u8], validity: Option<&'a [bool]>) -> impl Iterator<Item = T> + 'a {
+    chunk
+        .chunks_exact(T::WIDTH)
+        .enumerate()
+        .filter(move |(row, _)| validity.is_none_or(|validity| validity[*row]))
+        .map(|(_, value)| T::read_le(value))
+}
+
+// Кодирует один чанк; offset и first_row заполняются при записи.
+// Битовая карта валидности сжимается тем же кодеком и пишется сразу за значениями
+fn encode_chunk<'a, T: ColumnType>(
+    codec: Codec,
+    encoding: Encoding,
+    dictionary: &[T],
+    chunk: &'a [u8],
+    validity: Option<&[bool]>,
+    bloom_fp_rate: Option<f64>,
+) -> Result<(Cow<'a, [u8]>, ChunkMeta<T>)> {
+    let (mut stored, encoded_len) = if is_framed(codec, encoding) {
+        let encoded = encoding.encode(chunk, dictionary);
+        (Cow::Owned(codec.compress_frame(&encoded)?), encoded.len())
+    } else {
+        (Cow::Borrowed(chunk), chunk.len())
+    };
+    let compressed_len = stored.len() as u64;
+    let null_count = validity.map_or(0, |validity| validity.iter().filter(|valid| !**valid).count() as u64);
+    if let Some(validity) = validity {
+        stored.to_mut().extend_from_slice(&codec.compress_frame(&pack_bits(validity))?);
+    }
+    let (min, max) = compute_stats(chunk, validity);
+    let meta = ChunkMeta {
+        offset: 0,
+        compressed_len,
+        uncompressed_len: chunk.len() as u64,
+        encoded_len: encoded_len as u64,
+        first_row: 0,
+        min,
+        max,
+        null_count,
+        validity_len: stored.len() as u64 - compressed_len,
+        checksum: crc32fast::hash(&stored),
+        bloom: compute_chunk_bloom(chunk, validity, bloom_fp_rate),
+    };
+    Ok((stored, meta))
+}
+
+// Частичные фильтры по группам чанков с теми же ключами и размером, что у bloom,
+// объединяются по битам: это тот же фильтр, что при последовательном добавлении.
+// Каждому потоку нужна своя битовая карта размера общего фильтра
+fn fill_bloom_parallel<T: ColumnType>(bloom: &Bloom<T>, granules: &[(&[u8], Option<&[bool]>)]) -> Bloom<T> {
+    let empty = || {
+        let bytes = vec![0u8; bloom.number_of_bits().div_ceil(8) as usize];
+        Bloom::from_existing(&bytes, bloom.number_of_bits(), bloom.number_of_hash_functions(), bloom.sip_keys())
+    };
+    // По группе подряд идущих чанков на поток, чтобы частичных карт было не больше потоков
+    let group = granules.len().div_ceil(current_num_threads()).max(1);
+    let partials: Vec<Bloom<T>> = granules
+        .par_chunks(group)
+        .map(|granules| {
+            let mut partial = empty();
+            for (chunk, validity) in granules {
+                valid_values(chunk, *validity).for_each(|value: T| T::bloom_set(&mut partial, &value));
+            }
+            partial
+        })
+        .collect();
+    partials.iter().fold(bloom.clone(), |merged, partial| bloom_union(&merged, partial))
+}
+
+fn bloom_union<T: ColumnType>(a: &Bloom<T>, b: &Bloom<T>) -> Bloom<T> {
+    let mut bytes = a.bitmap();
+    for (byte, other) in bytes.iter_mut().zip(b.bitmap()) {
+        *byte |= other;
+    }
+    Bloom::from_existing(&bytes, a.number_of_bits(), a.number_of_hash_functions(), a.sip_keys())
+}
+
+// NaN и NULL пропускаются, чтобы не отравлять сравнения
+fn compute_stats<T: ColumnType>(data: &[u8], validity: Option<&[bool]>) -> (T, T) {
+    let mut min = T::MAX;
+    let mut max = T::MIN;
+    for value in valid_values::<T>(data, validity) {
+        if value.is_nan() {
+            continue;
+        }
+        if value.total_cmp(&min).is_lt() {
+            min = value;
+        }
+        if value.total_cmp(&max).is_gt() {
+            max = value;
+        }
+    }
+    (min, max)
+}
+
+// Размер по числу различных значений: для колонок с низкой кардинальностью
+// фильтр по числу строк занимал бы больше самих данных
+fn compute_chunk_bloom<T: ColumnType>(chunk: &[u8], validity: Option<&[bool]>, fp_rate: Option<f64>) -> Bloom<T> {
+    let Some(fp_rate) = fp_rate else {
+        return placeholder_bloom();
+    };
+    let distinct: HashSet<&[u8]> = chunk
+        .chunks_exact(T::WIDTH)
+        .enumerate()
+        .filter(|(row, _)| validity.is_none_or(|validity| validity[*row]))
+        .map(|(_, value)| value)
+        .collect();
+    // Чанк из одних NULL получает пустой фильтр минимального размера
+    let mut bloom = Bloom::new_for_fp_rate(distinct.len().max(1), fp_rate);
+    for value in distinct {
+        T::bloom_set(&mut bloom, &T::read_le(value));
+    }
+    bloom
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnBuilder, ColumnarError};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_streaming_build_with_small_chunks() {
+        let tmp_file = NamedTempFile::new().unwrap();
+        let mut writer = ColumnBuilder::create("stream".to_string(), tmp_file.path()).unwrap();
+        writer.compress_with(Codec::Lz4).unwrap();
+        writer.set_chunk_rows(1000).unwrap();
+
+        let batch: Vec<i32> = (0..777).collect();
+        for round in 0..1300 {
+            let values: Vec<i32> = batch.iter().map(|v| v + round).collect();
+            writer.push_slice(&values).unwrap();
+            // Буфер никогда не превышает один чанк
+            assert!(writer.buffered_rows() < 1000);
+        }
+        writer.push(-5).unwrap();
+        assert!(writer.compress_with(Codec::None).is_err());
+
+        let column = writer.finish().unwrap();
+        let total: usize = 777 * 1300 + 1;
+        assert_eq!(column.chunks().len(), total.div_ceil(1000));
+        assert_eq!(column.chunks()[0].row_count(), 1000);
+        assert_eq!((column.min, column.max), (-5, 776 + 1299));
+        assert!(column.might_contain(1500));
+        assert_eq!(column.get_value(777 * 3 + 10), Some(10 + 3));
+        assert_eq!(column.get_value(total - 1), Some(-5));
+        assert_eq!(column.get_value(total), None);
+
+        let reopened = Column::<i32>::open(tmp_file.path()).unwrap();
+        assert_eq!(reopened.codec, Codec::Lz4);
+        assert_eq!(reopened.decompress_parallel().unwrap().len(), total * 4);
+    }
+
+    #[test]
+    fn test_streaming_rejects_dictionary() {
+        let tmp_file = NamedTempFile::new().unwrap();
+        let mut writer = ColumnBuilder::<i32>::create("dict".to_string(), tmp_file.path()).unwrap();
+        let err = writer.set_encoding(Encoding::Dictionary).unwrap_err();
+        assert!(matches!(err, ColumnarError::InvalidInput(_)), "{:?}", err);
+
+        // Пустая колонка тоже корректно закрывается
+        let column = writer.finish().unwrap();
+        assert!(column.chunks().is_empty());
+        assert_eq!(column.get_value(0), None);
+    }
+
+    #[test]
+    fn test_parallel_summary_matches_sequential_writes() {
+        // Линейный конгруэнтный генератор: случайные, но воспроизводимые данные
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            state >> 33
+        };
+        let values: Vec<Option<f64>> = (0..120_000)
+            .map(|i| match next() % 1000 {
+                0 => None,
+                1 if i > 60_000 => Some(f64::NAN),
+                r => Some((r as f64 - 500.0) * (next() % 97) as f64),
+            })
+            .collect();
+
+        let mut builder = ColumnBuilder::from_nullable("parallel".to_string(), &values);
+        builder.set_chunk_rows(7000);
+        let parallel = builder.build_in_memory().unwrap();
+        let mut writer = ColumnBuilder::<f64>::create_in_memory("sequential".to_string()).unwrap();
+        writer.set_chunk_rows(7000).unwrap();
+        for value in &values {
+            writer.push_option(*value).unwrap();
+        }
+        let sequential = writer.finish().unwrap();
+
+        assert_eq!(parallel.stats(), sequential.stats());
+        assert_eq!(parallel.bloom_params(), sequential.bloom_params());
+        for (a, b) in parallel.chunks().iter().zip(sequential.chunks()) {
+            assert_eq!((a.min, a.max, a.null_count), (b.min, b.max, b.null_count));
+        }
+
+        // Ключи фильтра случайны, поэтому эталон заполняется по одному значению с теми же ключами
+        let bloom = &parallel.bloom_filter;
+        let zeros = vec![0u8; bloom.number_of_bits().div_ceil(8) as usize];
+        let mut expected =
+            Bloom::from_existing(&zeros, bloom.number_of_bits(), bloom.number_of_hash_functions(), bloom.sip_keys());
+        for value in values.iter().flatten() {
+            f64::bloom_set(&mut expected, value);
+        }
+        assert_eq!(bloom.bitmap(), expected.bitmap());
+    }
+}